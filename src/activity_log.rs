@@ -0,0 +1,221 @@
+use std::{path::{PathBuf, Path}, fs::OpenOptions, io::{Write, Read, Seek, SeekFrom}};
+
+use anyhow::Result;
+use serde::{Serialize, Deserialize};
+
+use crate::{library::Song, youtube::unix_time_now};
+
+/// The name of the file inside a library where its activity log is kept. Like the `.originals`
+/// folder, the leading dot keeps it out of the way, and it never looks like a song.
+const ACTIVITY_LOG_FILE: &str = ".activity.jsonl";
+
+/// The number of entries kept for each song. Older entries are dropped when the log is compacted.
+const MAX_ENTRIES_PER_SONG: usize = 50;
+
+/// How many entries a song can have over [`MAX_ENTRIES_PER_SONG`] before the log is compacted, so
+/// that the whole file isn't rewritten every time something happens to a busy song.
+const COMPACTION_SLACK: usize = 10;
+
+/// Something which happened to a song.
+#[derive(Debug, Serialize, Deserialize, Clone, Copy, PartialEq, Eq)]
+pub enum Activity {
+    Downloaded,
+    Cropped,
+    MetadataEdited,
+    PostProcessed,
+    Restored,
+    Hidden,
+    Unhidden,
+    Imported,
+    Redownloaded,
+    Approved,
+    PostDownloadCommandRan,
+}
+
+/// One line of the activity log.
+#[derive(Debug, Serialize, Deserialize, Clone, PartialEq, Eq)]
+pub struct ActivityEntry {
+    pub unix_time: u64,
+
+    /// The song this happened to. Songs are matched by their video ID rather than their path, so
+    /// that their history follows them when they're hidden or unhidden.
+    pub youtube_id: String,
+
+    /// If the song is one chapter of a video which was split up, which chapter it is, so that the
+    /// chapters' histories are kept apart despite sharing a video ID.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub chapter: Option<u32>,
+
+    /// The song's file name when this happened.
+    pub file_name: String,
+
+    pub activity: Activity,
+
+    /// Details of what happened, like the segments kept by a crop. This can be empty.
+    pub summary: String,
+}
+
+/// What tells one song's entries apart from another's - see [`ActivityEntry::key`].
+type SongKey = (String, Option<u32>, String);
+
+impl ActivityEntry {
+    /// Identifies the song this entry is for. Songs are told apart by their video ID and chapter,
+    /// but imported songs have no ID, so those are told apart by their file name instead, ignoring
+    /// whether they're hidden.
+    fn key(&self) -> SongKey {
+        let file_name = if self.youtube_id.is_empty() {
+            self.file_name.trim_end_matches(".hidden").to_string()
+        } else {
+            String::new()
+        };
+        (self.youtube_id.clone(), self.chapter, file_name)
+    }
+}
+
+/// The key of the entries for the given song - see [`ActivityEntry::key`].
+fn key_for(song: &Song) -> SongKey {
+    let file_name = if song.metadata.youtube_id.is_empty() {
+        song.root_path().file_name().unwrap_or_default().to_string_lossy().into_owned()
+    } else {
+        String::new()
+    };
+    (song.metadata.youtube_id.clone(), song.metadata.chapter, file_name)
+}
+
+/// The path to the activity log of the library which the given song is in.
+pub fn path(song: &Song) -> PathBuf {
+    song.path
+        .parent()
+        .expect("song is not in a folder")
+        .join(ACTIVITY_LOG_FILE)
+}
+
+/// Records something which happened to a song in its library's activity log.
+///
+/// This is best-effort - the log is only there to help explain things later, so if it can't be
+/// written, the failure is printed and otherwise ignored.
+pub fn record(song: &Song, activity: Activity, summary: impl Into<String>) {
+    let entry = ActivityEntry {
+        unix_time: unix_time_now(),
+        youtube_id: song.metadata.youtube_id.clone(),
+        chapter: song.metadata.chapter,
+        file_name: song.path.file_name().unwrap_or_default().to_string_lossy().into_owned(),
+        activity,
+        summary: summary.into(),
+    };
+
+    if let Err(e) = append(&path(song), &entry) {
+        println!("[Activity] Could not record {:?} for {}: {}", activity, entry.youtube_id, e);
+    }
+}
+
+/// The entries recorded for a song, newest first.
+///
+/// If the log can't be read, this is empty - like [`record`], failures are printed and otherwise
+/// ignored.
+pub fn entries_for(song: &Song) -> Vec<ActivityEntry> {
+    match read_entries(&path(song)) {
+        Ok(entries) => {
+            let key = key_for(song);
+            let mut entries: Vec<_> = entries.into_iter()
+                .filter(|e| e.key() == key)
+                .collect();
+            entries.reverse();
+            entries.truncate(MAX_ENTRIES_PER_SONG);
+            entries
+        }
+
+        Err(e) => {
+            println!("[Activity] Could not read activity log: {}", e);
+            vec![]
+        }
+    }
+}
+
+/// Adds an entry to the end of a log, compacting it if the entry's song now has too many.
+fn append(path: &Path, entry: &ActivityEntry) -> Result<()> {
+    let mut file = OpenOptions::new().read(true).append(true).create(true).open(path)?;
+
+    // If CrossPlay was killed part-way through writing the last entry, start on a new line, so that
+    // only the broken entry is lost rather than this one too
+    let length = file.metadata()?.len();
+    if length > 0 {
+        let mut last_byte = [0];
+        file.seek(SeekFrom::Start(length - 1))?;
+        file.read_exact(&mut last_byte)?;
+        if last_byte[0] != b'\n' {
+            file.write_all(b"\n")?;
+        }
+    }
+
+    let mut line = serde_json::to_string(entry)?;
+    line.push('\n');
+    file.write_all(line.as_bytes())?;
+    drop(file);
+
+    let entries = read_entries(path)?;
+    let song_entry_count = entries.iter().filter(|e| e.key() == entry.key()).count();
+    if song_entry_count > MAX_ENTRIES_PER_SONG + COMPACTION_SLACK {
+        compact(path, entries)?;
+    }
+
+    Ok(())
+}
+
+/// Reads every entry in a log, oldest first. Lines which can't be parsed, for example because
+/// writing them was interrupted, are skipped without affecting the others.
+fn read_entries(path: &Path) -> Result<Vec<ActivityEntry>> {
+    if !path.exists() {
+        return Ok(vec![]);
+    }
+
+    let contents = std::fs::read(path)?;
+    let mut entries = vec![];
+    let mut skipped = 0;
+    for line in contents.split(|b| *b == b'\n') {
+        if line.iter().all(u8::is_ascii_whitespace) {
+            continue;
+        }
+
+        match serde_json::from_slice(line) {
+            Ok(entry) => entries.push(entry),
+            Err(_) => skipped += 1,
+        }
+    }
+
+    if skipped > 0 {
+        println!("[Activity] Skipped {} unreadable lines in {}", skipped, path.to_string_lossy());
+    }
+
+    Ok(entries)
+}
+
+/// Rewrites a log to keep only the newest [`MAX_ENTRIES_PER_SONG`] entries of each song. Unreadable
+/// lines are dropped too.
+///
+/// The new log is written alongside the old one and then renamed over it, so the log is never left
+/// half-written.
+fn compact(path: &Path, entries: Vec<ActivityEntry>) -> Result<()> {
+    let mut kept = vec![];
+    let mut counts = std::collections::HashMap::new();
+    for entry in entries.into_iter().rev() {
+        let count = counts.entry(entry.key()).or_insert(0);
+        if *count < MAX_ENTRIES_PER_SONG {
+            *count += 1;
+            kept.push(entry);
+        }
+    }
+    kept.reverse();
+
+    let mut contents = String::new();
+    for entry in &kept {
+        contents.push_str(&serde_json::to_string(entry)?);
+        contents.push('\n');
+    }
+
+    let temp_path = path.with_extension("jsonl.crossplay-tmp");
+    std::fs::write(&temp_path, contents)?;
+    std::fs::rename(&temp_path, path)?;
+
+    Ok(())
+}
@@ -23,3 +23,4 @@ image!(RESTORE, restore);
 image!(RESTORE_DISABLED, restore_disabled);
 image!(HIDDEN, hidden);
 image!(NOT_HIDDEN, not_hidden);
+image!(NO_ALBUM_ART, no_album_art);
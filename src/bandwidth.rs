@@ -0,0 +1,77 @@
+use std::path::PathBuf;
+
+use anyhow::Result;
+use serde::{Serialize, Deserialize};
+
+use crate::{settings::Settings, youtube::unix_time_now, ui_util::date_from_unix_days};
+
+/// A running total of how much has been downloaded this month, for users on metered connections.
+#[derive(Debug, Serialize, Deserialize, Clone, PartialEq, Eq)]
+pub struct BandwidthUsage {
+    /// The month which `bytes` was counted in, like "2022-07". Months are in UTC.
+    pub month: String,
+    pub bytes: u64,
+}
+
+impl BandwidthUsage {
+    pub fn path() -> PathBuf {
+        Settings::settings_dir().join("bandwidth.json")
+    }
+
+    /// Loads the usage for the current month. If the saved usage is from an earlier month, or
+    /// nothing has been saved yet, this starts again from zero. The total is only a guide, so
+    /// that also happens if the saved usage can't be parsed.
+    pub fn load() -> Result<Self> {
+        let path = Self::path();
+        if !path.exists() {
+            return Ok(Self::empty());
+        }
+
+        let usage: Self = match serde_json::from_str(&std::fs::read_to_string(path)?) {
+            Ok(usage) => usage,
+            Err(e) => {
+                println!("[Bandwidth] Saved usage is corrupt, starting again: {}", e);
+                return Ok(Self::empty());
+            }
+        };
+        if usage.month == current_month() {
+            Ok(usage)
+        } else {
+            Ok(Self::empty())
+        }
+    }
+
+    pub fn save(&self) -> Result<()> {
+        std::fs::create_dir_all(Settings::settings_dir())?;
+
+        // Write to a temporary file first, so that a crash part-way through can't leave a
+        // truncated file behind
+        let path = Self::path();
+        let temp_path = path.with_extension("json.crossplay-tmp");
+        std::fs::write(&temp_path, serde_json::to_string(self)?)?;
+        std::fs::rename(&temp_path, path)?;
+        Ok(())
+    }
+
+    /// Adds a finished download to the saved total for this month.
+    pub fn record_download(bytes: u64) -> Result<()> {
+        let mut usage = Self::load()?;
+        usage.bytes += bytes;
+        usage.save()
+    }
+
+    /// Clears the saved total for this month.
+    pub fn reset() -> Result<()> {
+        Self::empty().save()
+    }
+
+    pub fn empty() -> Self {
+        Self { month: current_month(), bytes: 0 }
+    }
+}
+
+/// The current month in UTC, formatted like "2022-07".
+fn current_month() -> String {
+    let (year, month, _) = date_from_unix_days((unix_time_now() / 86400) as i64);
+    format!("{:04}-{:02}", year, month)
+}
@@ -0,0 +1,75 @@
+use std::path::PathBuf;
+
+use anyhow::Result;
+use serde::{Serialize, Deserialize};
+
+use crate::{settings::Settings, youtube::unix_time_now};
+
+/// How many downloads are remembered. Once there are more, the oldest are forgotten.
+const MAX_ENTRIES: usize = 1000;
+
+/// A download which was attempted, whether or not it succeeded.
+#[derive(Debug, Serialize, Deserialize, Clone, PartialEq, Eq)]
+pub struct HistoryEntry {
+    /// What the download was known by - see [`crate::youtube::MediaDownload::id`]. This can be
+    /// downloaded again, even if the song has since been deleted.
+    pub id: String,
+
+    /// The song's title, or its ID if the title was never found.
+    pub title: String,
+    pub unix_time: u64,
+    pub succeeded: bool,
+}
+
+/// Every download made from CrossPlay, kept so that songs can be found and downloaded again after
+/// they've been deleted from the library.
+#[derive(Debug, Serialize, Deserialize, Clone, PartialEq, Eq, Default)]
+pub struct DownloadHistory {
+    /// Oldest first.
+    pub entries: Vec<HistoryEntry>,
+}
+
+impl DownloadHistory {
+    pub fn path() -> PathBuf {
+        Settings::settings_dir().join("download_history.json")
+    }
+
+    /// Loads the saved history. If it can't be understood, it's started again from empty, since
+    /// losing it isn't worth refusing to download over.
+    pub fn load() -> Result<Self> {
+        let path = Self::path();
+        if !path.exists() {
+            return Ok(Self::default());
+        }
+
+        match serde_json::from_str(&std::fs::read_to_string(path)?) {
+            Ok(history) => Ok(history),
+            Err(e) => {
+                println!("[History] Download history is corrupt, starting again: {}", e);
+                Ok(Self::default())
+            }
+        }
+    }
+
+    pub fn save(&self) -> Result<()> {
+        std::fs::create_dir_all(Settings::settings_dir())?;
+        std::fs::write(Self::path(), serde_json::to_string(self)?)?;
+        Ok(())
+    }
+
+    /// Adds a finished download to the saved history, forgetting the oldest if it's full.
+    pub fn record(id: &str, title: &str, succeeded: bool) -> Result<()> {
+        let mut history = Self::load()?;
+        history.entries.push(HistoryEntry {
+            id: id.to_string(),
+            title: title.to_string(),
+            unix_time: unix_time_now(),
+            succeeded,
+        });
+
+        let excess = history.entries.len().saturating_sub(MAX_ENTRIES);
+        history.entries.drain(..excess);
+
+        history.save()
+    }
+}
@@ -0,0 +1,103 @@
+use std::path::PathBuf;
+
+use anyhow::Result;
+use serde::{Serialize, Deserialize};
+
+use crate::{settings::{Settings, AudioFormat, AudioQuality}, youtube::{MediaDownload, MetadataOverride}};
+
+/// A download waiting for a free slot, along with the settings it was requested with, so that
+/// changing them while it waits doesn't change what it's downloaded as.
+#[derive(Debug, Serialize, Deserialize, Clone, PartialEq, Eq)]
+pub struct QueuedDownload {
+    pub id: String,
+    pub format: AudioFormat,
+    pub quality: AudioQuality,
+
+    /// Whether to split the video by its chapters, if it has any.
+    #[serde(default)]
+    pub split_chapters: bool,
+
+    /// Whether to go without lyrics rather than use subtitles YouTube generated itself.
+    #[serde(default)]
+    pub skip_auto_subtitles: bool,
+
+    /// Metadata chosen before the download was requested, which replaces what's found.
+    #[serde(default)]
+    pub metadata_override: MetadataOverride,
+
+    /// The song this is downloaded over, if it's being downloaded again rather than added to the
+    /// library.
+    #[serde(default)]
+    pub replacing: Option<PathBuf>,
+
+    /// Arguments passed to youtube-dl for this download only, after those from the settings.
+    #[serde(default)]
+    pub extra_args: Vec<String>,
+}
+
+impl QueuedDownload {
+    pub fn download(&self) -> MediaDownload {
+        MediaDownload::new(self.id.clone())
+    }
+}
+
+/// The downloads waiting to start, in the order they'll be started. This is saved whenever it
+/// changes, so that closing CrossPlay doesn't lose them.
+#[derive(Debug, Serialize, Deserialize, Clone, PartialEq, Eq, Default)]
+pub struct DownloadQueue {
+    pub downloads: Vec<QueuedDownload>,
+
+    /// The downloads which were running as of the last save. If CrossPlay crashes or is closed
+    /// part-way through these, they can be resumed when it's next opened.
+    #[serde(default)]
+    pub in_progress: Vec<QueuedDownload>,
+}
+
+impl DownloadQueue {
+    pub fn path() -> PathBuf {
+        Settings::settings_dir().join("download_queue.json")
+    }
+
+    /// Loads the saved queue, or an empty one if nothing has been saved yet.
+    pub fn load() -> Result<Self> {
+        let path = Self::path();
+        if !path.exists() {
+            return Ok(Self::default());
+        }
+
+        Ok(serde_json::from_str(&std::fs::read_to_string(path)?)?)
+    }
+
+    pub fn save(&self) -> Result<()> {
+        std::fs::create_dir_all(Settings::settings_dir())?;
+        std::fs::write(Self::path(), serde_json::to_string(self)?)?;
+        Ok(())
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.downloads.is_empty()
+    }
+
+    pub fn contains(&self, dl: &MediaDownload) -> bool {
+        self.downloads.iter().any(|q| q.id == dl.id)
+    }
+
+    pub fn remove(&mut self, dl: &MediaDownload) {
+        self.downloads.retain(|q| q.id != dl.id);
+    }
+
+    /// Records that a download has finished, whether or not it succeeded, so that it isn't resumed.
+    pub fn finish(&mut self, dl: &MediaDownload) {
+        self.in_progress.retain(|q| q.id != dl.id);
+    }
+
+    /// Moves a download by `offset` places towards the back of the queue, or towards the front if
+    /// it's negative, stopping at either end.
+    pub fn move_by(&mut self, dl: &MediaDownload, offset: isize) {
+        if let Some(index) = self.downloads.iter().position(|q| q.id == dl.id) {
+            let new_index = (index as isize).saturating_add(offset).clamp(0, self.downloads.len() as isize - 1) as usize;
+            let queued = self.downloads.remove(index);
+            self.downloads.insert(new_index, queued);
+        }
+    }
+}
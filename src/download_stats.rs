@@ -0,0 +1,79 @@
+use std::{path::PathBuf, time::Duration};
+
+use anyhow::Result;
+use serde::{Serialize, Deserialize};
+
+use crate::settings::Settings;
+
+/// Running totals of how downloads have gone. One of these is kept for the current session, and
+/// another is saved with totals for all time.
+///
+/// Failed downloads are only counted, so that they don't add to the other totals.
+#[derive(Debug, Serialize, Deserialize, Clone, PartialEq, Eq, Default)]
+pub struct DownloadStats {
+    pub succeeded: u64,
+    pub failed: u64,
+    pub bytes: u64,
+
+    /// The total length of the songs downloaded, in seconds. Songs whose length couldn't be found
+    /// aren't counted.
+    pub audio_secs: u64,
+
+    /// How long downloads have been running for, in seconds. Downloads which run at the same time
+    /// only count once, so this is how long the user was kept waiting.
+    pub downloading_secs: u64,
+}
+
+impl DownloadStats {
+    pub fn path() -> PathBuf {
+        Settings::settings_dir().join("download_stats.json")
+    }
+
+    /// Loads the all-time totals, or empty totals if nothing has been saved yet or the saved totals
+    /// are corrupt.
+    pub fn load() -> Result<Self> {
+        let path = Self::path();
+        if !path.exists() {
+            return Ok(Self::default());
+        }
+
+        match serde_json::from_str(&std::fs::read_to_string(path)?) {
+            Ok(stats) => Ok(stats),
+            Err(e) => {
+                println!("[Stats] Download stats are corrupt, starting again: {}", e);
+                Ok(Self::default())
+            }
+        }
+    }
+
+    pub fn save(&self) -> Result<()> {
+        std::fs::create_dir_all(Settings::settings_dir())?;
+        std::fs::write(Self::path(), serde_json::to_string(self)?)?;
+        Ok(())
+    }
+
+    /// Applies a change to the saved all-time totals.
+    pub fn update_saved(change: impl FnOnce(&mut Self)) -> Result<()> {
+        let mut stats = Self::load()?;
+        change(&mut stats);
+        stats.save()
+    }
+
+    pub fn record_success(&mut self, bytes: u64, audio: Option<Duration>) {
+        self.succeeded += 1;
+        self.bytes += bytes;
+        self.audio_secs += audio.map_or(0, |d| d.as_secs());
+    }
+
+    pub fn record_failure(&mut self) {
+        self.failed += 1;
+    }
+
+    pub fn record_downloading_time(&mut self, time: Duration) {
+        self.downloading_secs += time.as_secs();
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.succeeded == 0 && self.failed == 0
+    }
+}
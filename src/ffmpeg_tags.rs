@@ -0,0 +1,286 @@
+use std::{path::{Path, PathBuf}, process::Command};
+
+use id3::{Tag, TagLike, frame::{Comment, Lyrics, Picture, PictureType}};
+use image::ImageFormat;
+use serde_json::Value;
+
+use crate::{library::LibraryError, settings::AudioFormat, tag_interface::CUSTOM_TAG_NAMES};
+
+type Result<T> = std::result::Result<T, LibraryError>;
+
+/// Reads the tags of an audio file which can't hold ID3 tags, by shelling out to ffprobe.
+///
+/// The rest of CrossPlay works with ID3 tags, so the tags are converted into one. Only the tags
+/// which CrossPlay uses are carried over - title, artist, album, genre, year, track number,
+/// duration, lyrics, cover art and CrossPlay's own custom tags.
+pub fn read_tag(path: &Path) -> Result<Tag> {
+    let (mut tag, has_cover) = read_tag_without_cover(path)?;
+    if has_cover {
+        tag.add_frame(read_cover(path)?);
+    }
+
+    Ok(tag)
+}
+
+/// Like [`read_tag`], but leaves out the cover art, which takes another run of ffmpeg to extract.
+/// Whether the file has any is returned alongside the tag instead, so that it can be read later
+/// only if it's needed.
+pub fn read_tag_without_cover(path: &Path) -> Result<(Tag, bool)> {
+    let output = run(Command::new("ffprobe")
+        .arg("-v")
+        .arg("error")
+        .arg("-show_entries")
+        .arg("format_tags:stream_tags:stream_disposition=attached_pic")
+        .arg("-of")
+        .arg("json")
+        .arg(path))?;
+    let json: Value = serde_json::from_slice(&output)
+        .map_err(|e| LibraryError::TagParse { path: path.to_path_buf(), message: e.to_string() })?;
+
+    Ok(tag_from_probe(&json))
+}
+
+/// Converts ffprobe's JSON description of a file's tags into an ID3 tag, and whether the file has
+/// cover art.
+fn tag_from_probe(json: &Value) -> (Tag, bool) {
+    let empty = vec![];
+    let streams = json["streams"].as_array().unwrap_or(&empty);
+
+    // Ogg keeps its tags on the audio stream, while other containers keep them on the whole file
+    let mut tag = Tag::new();
+    let all_tags = std::iter::once(&json["format"]["tags"]).chain(streams.iter().map(|s| &s["tags"]));
+    for (key, value) in all_tags.filter_map(|t| t.as_object()).flatten() {
+        let value = match value.as_str() {
+            Some(value) => value,
+            None => continue,
+        };
+
+        // Some containers change the case of tag names, so match them case-insensitively
+        match key.to_lowercase().as_str() {
+            "title" => tag.set_title(value),
+            "artist" => tag.set_artist(value),
+            "album" => tag.set_album(value),
+            "genre" => tag.set_genre(value),
+            // Dates might be a full date, and track numbers might include the total, like "3/12"
+            "date" | "year" => if let Some(year) = value.get(..4).and_then(|y| y.parse().ok()) {
+                tag.set_year(year);
+            },
+            "track" => if let Some(track) = value.split('/').next().and_then(|t| t.trim().parse().ok()) {
+                tag.set_track(track);
+            },
+            // Like ID3's TLEN frame, which it's named after, this is in milliseconds
+            "tlen" => if let Ok(millis) = value.parse() {
+                tag.set_duration(millis);
+            },
+            "lyrics" => {
+                tag.add_frame(Lyrics { lang: "eng".to_string(), description: String::new(), text: value.to_string() });
+            },
+            _ => if let Some(name) = CUSTOM_TAG_NAMES.iter().find(|n| n.eq_ignore_ascii_case(key)) {
+                tag.add_frame(Comment {
+                    lang: "eng".to_string(),
+                    description: name.to_string(),
+                    text: value.to_string(),
+                });
+            },
+        }
+    }
+
+    let has_cover = streams.iter().any(|s| s["disposition"]["attached_pic"].as_i64() == Some(1));
+    (tag, has_cover)
+}
+
+/// Replaces the tags of an audio file which can't hold ID3 tags with those in an ID3 tag, by
+/// shelling out to ffmpeg. This is the reverse of [`read_tag`].
+///
+/// The audio isn't re-encoded, but ffmpeg can't write into the file it's reading from, so the
+/// whole file is copied.
+pub fn write_tag(tag: &Tag, path: &Path, format: AudioFormat) -> Result<()> {
+    let retagged_path = with_suffix(path, "retag");
+    let cover_path = with_suffix(path, "cover");
+
+    let mut command = Command::new("ffmpeg");
+    command
+        .arg("-v")
+        .arg("error")
+        .arg("-y")
+        .arg("-i")
+        .arg(path);
+
+    let cover = tag.pictures()
+        .find(|p| p.picture_type == PictureType::CoverFront)
+        .filter(|_| format.supports_embedded_art());
+    if let Some(cover) = cover {
+        std::fs::write(&cover_path, &cover.data).map_err(|e| LibraryError::from_io(&cover_path, e))?;
+        command.arg("-i").arg(&cover_path);
+    }
+
+    // Drop the existing tags, so that ones which have been removed from `tag` don't survive
+    command
+        .arg("-map")
+        .arg("0:a")
+        .arg("-c")
+        .arg("copy")
+        .arg("-map_metadata")
+        .arg("-1");
+    if cover.is_some() {
+        command
+            .arg("-map")
+            .arg("1:v")
+            .arg("-disposition:v")
+            .arg("attached_pic");
+    }
+
+    let metadata_flag = if format == AudioFormat::Opus { "-metadata:s:a:0" } else { "-metadata" };
+    for (key, value) in tag_entries(tag) {
+        command.arg(metadata_flag).arg(format!("{}={}", key, value));
+    }
+
+    // Without this, MP4 containers only keep the tags they have a dedicated field for
+    if format == AudioFormat::M4a {
+        command.arg("-movflags").arg("use_metadata_tags");
+    }
+
+    // The paths don't have an extension ffmpeg recognises
+    command.arg("-f").arg(format.ffmpeg_muxer()).arg(&retagged_path);
+
+    let result = run(&mut command);
+    if cover.is_some() {
+        let _ = std::fs::remove_file(&cover_path);
+    }
+    if let Err(e) = result {
+        let _ = std::fs::remove_file(&retagged_path);
+        return Err(e);
+    }
+
+    std::fs::rename(&retagged_path, path).map_err(|e| LibraryError::from_io(path, e))
+}
+
+/// The tags from an ID3 tag which [`write_tag`] carries over, as names and values.
+fn tag_entries(tag: &Tag) -> Vec<(String, String)> {
+    let mut entries = vec![];
+    if let Some(title) = tag.title() { entries.push(("title".to_string(), title.to_string())) }
+    if let Some(artist) = tag.artist() { entries.push(("artist".to_string(), artist.to_string())) }
+    if let Some(album) = tag.album() { entries.push(("album".to_string(), album.to_string())) }
+    if let Some(genre) = tag.genre() { entries.push(("genre".to_string(), genre.to_string())) }
+    if let Some(year) = tag.year() { entries.push(("date".to_string(), year.to_string())) }
+    if let Some(track) = tag.track() { entries.push(("track".to_string(), track.to_string())) }
+    if let Some(duration) = tag.duration() { entries.push(("TLEN".to_string(), duration.to_string())) }
+    if let Some(lyrics) = tag.lyrics().next() { entries.push(("lyrics".to_string(), lyrics.text.clone())) }
+
+    for comment in tag.comments().filter(|c| CUSTOM_TAG_NAMES.contains(&c.description.as_str())) {
+        // Flags are empty comments, but ffmpeg deletes a tag when it's given an empty value, so
+        // give them a placeholder - flags don't look at their value anyway
+        let text = if comment.text.is_empty() { "1".to_string() } else { comment.text.clone() };
+        entries.push((comment.description.clone(), text));
+    }
+
+    entries
+}
+
+/// Extracts the cover art attached to an audio file.
+fn read_cover(path: &Path) -> Result<Picture> {
+    let data = run(Command::new("ffmpeg")
+        .arg("-v")
+        .arg("error")
+        .arg("-i")
+        .arg(path)
+        .arg("-map")
+        .arg("0:v:0")
+        .arg("-c")
+        .arg("copy")
+        .arg("-f")
+        .arg("image2pipe")
+        .arg("-"))?;
+
+    let mime_type = match image::guess_format(&data) {
+        Ok(ImageFormat::Png) => "image/png",
+        _ => "image/jpeg",
+    };
+
+    Ok(Picture {
+        mime_type: mime_type.to_string(),
+        picture_type: PictureType::CoverFront,
+        description: "Cover".to_string(),
+        data,
+    })
+}
+
+/// Runs an ffmpeg or ffprobe command to completion, returning its standard output.
+pub(crate) fn run(command: &mut Command) -> Result<Vec<u8>> {
+    let program = command.get_program().to_string_lossy().into_owned();
+    let output = command
+        .output()
+        .map_err(|e| LibraryError::FfmpegFailed { stderr: format!("could not run {}: {}", program, e) })?;
+
+    if !output.status.success() {
+        return Err(LibraryError::FfmpegFailed { stderr: String::from_utf8_lossy(&output.stderr).into_owned() });
+    }
+
+    Ok(output.stdout)
+}
+
+/// Whether ffmpeg is installed and can be run. youtube-dl needs it to convert downloads, as does
+/// anything CrossPlay does to songs beyond reading and writing MP3 tags.
+pub fn ffmpeg_available() -> bool {
+    Command::new("ffmpeg")
+        .arg("-version")
+        .output()
+        .map(|output| output.status.success())
+        .unwrap_or(false)
+}
+
+/// Appends a suffix to a path, keeping it in the same folder - for example, `song.opus` with the
+/// suffix `retag` becomes `song.opus.retag`.
+pub(crate) fn with_suffix(path: &Path, suffix: &str) -> PathBuf {
+    format!("{}.{}", path.to_string_lossy(), suffix).into()
+}
+
+#[cfg(test)]
+mod tests {
+    use serde_json::{json, Map};
+
+    use super::*;
+
+    /// A tag with lyrics and a duration, which only ID3 has dedicated frames for.
+    fn tag_with_lyrics_and_duration() -> Tag {
+        let mut tag = Tag::new();
+        tag.set_title("Title");
+        tag.set_duration(215_000);
+        tag.add_frame(Lyrics { lang: "eng".to_string(), description: String::new(), text: "First line\nSecond line".to_string() });
+        tag
+    }
+
+    /// The tags which ffprobe would describe after [`write_tag`] wrote `tag`, as a JSON object.
+    fn written_tags(tag: &Tag) -> Value {
+        Value::Object(tag_entries(tag).into_iter().map(|(key, value)| (key, value.into())).collect::<Map<_, _>>())
+    }
+
+    #[test]
+    fn test_lyrics_and_duration_round_trip() {
+        let tag = tag_with_lyrics_and_duration();
+        let (read, has_cover) = tag_from_probe(&json!({ "format": { "tags": written_tags(&tag) } }));
+
+        assert_eq!(read.title(), Some("Title"));
+        assert_eq!(read.duration(), Some(215_000));
+        assert_eq!(read.lyrics().next().map(|l| l.text.as_str()), Some("First line\nSecond line"));
+        assert!(!has_cover);
+    }
+
+    #[test]
+    fn test_lyrics_and_duration_round_trip_on_stream() {
+        // Ogg keeps its tags on the audio stream, and Vorbis comments are usually upper case
+        let tags = written_tags(&tag_with_lyrics_and_duration());
+        let tags = tags.as_object().unwrap().iter().map(|(key, value)| (key.to_uppercase(), value.clone())).collect::<Map<_, _>>();
+        let (read, has_cover) = tag_from_probe(&json!({
+            "format": {},
+            "streams": [
+                { "tags": tags, "disposition": { "attached_pic": 0 } },
+                { "disposition": { "attached_pic": 1 } },
+            ],
+        }));
+
+        assert_eq!(read.duration(), Some(215_000));
+        assert_eq!(read.lyrics().next().map(|l| l.text.as_str()), Some("First line\nSecond line"));
+        assert!(has_cover);
+    }
+}
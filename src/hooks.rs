@@ -0,0 +1,108 @@
+use std::{path::Path, time::Duration, sync::Arc, io, fmt::Display};
+
+use async_io::Timer;
+use async_process::{Command, Child, Stdio};
+use iced::futures::{AsyncReadExt, future::{join, select, Either}};
+
+use crate::{youtube::{split_args, ExtraArgsError}, i18n::{Key, tr, tr_args, tr_count}};
+
+/// How long the post-download command is given to finish before it's killed.
+pub const POST_DOWNLOAD_COMMAND_TIMEOUT: Duration = Duration::from_secs(5 * 60);
+
+/// How much of what the command printed is kept, from the end, since that's where it explains
+/// what went wrong.
+const MAX_OUTPUT_CHARS: usize = 2000;
+
+/// Why the post-download command didn't succeed.
+#[derive(Debug, Clone)]
+pub enum HookError {
+    /// The command couldn't be split into arguments.
+    Unparseable(ExtraArgsError),
+
+    /// The command is empty.
+    Empty,
+
+    /// The command's program couldn't be started.
+    SpawnFailed(Arc<io::Error>),
+
+    /// The command didn't finish within [`POST_DOWNLOAD_COMMAND_TIMEOUT`], so it was killed.
+    TimedOut,
+
+    /// The command ran, but reported failure. The end of what it printed is kept.
+    NonZeroExit { code: Option<i32>, output: String },
+}
+
+impl Display for HookError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            // Splitting the command only fails if a quote isn't closed
+            HookError::Unparseable(_) => f.write_str(tr(Key::HookErrorUnclosedQuote)),
+            HookError::Empty => f.write_str(tr(Key::HookErrorEmpty)),
+            HookError::SpawnFailed(e) => f.write_str(&tr_args(Key::HookErrorSpawnFailed, &[e])),
+            HookError::TimedOut => f.write_str(&tr_count(Key::HookErrorTimedOut, POST_DOWNLOAD_COMMAND_TIMEOUT.as_secs() as usize / 60, &[])),
+            HookError::NonZeroExit { code, output } => {
+                match code {
+                    Some(code) => f.write_str(&tr_args(Key::HookErrorExitCode, &[code]))?,
+                    None => f.write_str(tr(Key::HookErrorTerminated))?,
+                }
+                if !output.is_empty() {
+                    write!(f, ":\n{}", output)?;
+                }
+                Ok(())
+            }
+        }
+    }
+}
+
+impl std::error::Error for HookError {}
+
+/// Runs the user's post-download command on a downloaded song, with `{path}` in its arguments
+/// replaced by the song's path and `{id}` by its video ID. The command is run directly rather than
+/// through a shell, so paths with spaces in them don't need quoting.
+///
+/// Returns the end of what the command printed, if it succeeded.
+pub async fn run_post_download_command(command: &str, path: &Path, id: &str) -> Result<String, HookError> {
+    let path = path.to_string_lossy();
+    let mut args = split_args(command)
+        .map_err(HookError::Unparseable)?
+        .into_iter()
+        .map(|arg| arg.replace("{path}", &path).replace("{id}", id));
+    let program = args.next().ok_or(HookError::Empty)?;
+
+    println!("[Hook] Running post-download command for {}", path);
+    let child = Command::new(program)
+        .args(args)
+        .stdin(Stdio::null())
+        .stdout(Stdio::piped())
+        .stderr(Stdio::piped())
+        // Dropping the child when the timeout is up is what stops it
+        .kill_on_drop(true)
+        .spawn()
+        .map_err(|e| HookError::SpawnFailed(Arc::new(e)))?;
+
+    match select(Box::pin(wait_for_output(child)), Timer::after(POST_DOWNLOAD_COMMAND_TIMEOUT)).await {
+        Either::Left((result, _)) => result,
+        Either::Right(_) => Err(HookError::TimedOut),
+    }
+}
+
+/// Waits for the command to finish, collecting what it printed to stdout and stderr.
+async fn wait_for_output(mut child: Child) -> Result<String, HookError> {
+    let mut stdout = vec![];
+    let mut stderr = vec![];
+    let mut stdout_pipe = child.stdout.take().unwrap();
+    let mut stderr_pipe = child.stderr.take().unwrap();
+    // Both are read at once, so that neither pipe can fill up and stall the command
+    let _ = join(stdout_pipe.read_to_end(&mut stdout), stderr_pipe.read_to_end(&mut stderr)).await;
+
+    let status = child.status().await.map_err(|e| HookError::SpawnFailed(Arc::new(e)))?;
+    let output = format!("{}{}", String::from_utf8_lossy(&stdout), String::from_utf8_lossy(&stderr));
+    let skip = output.chars().count().saturating_sub(MAX_OUTPUT_CHARS);
+    let output = output.chars().skip(skip).collect::<String>().trim().to_string();
+
+    if status.success() {
+        Ok(output)
+    } else {
+        Err(HookError::NonZeroExit { code: status.code(), output })
+    }
+}
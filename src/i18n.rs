@@ -0,0 +1,1720 @@
+//! Translations of user-facing text.
+//!
+//! Every string shown in the UI has a [`Key`], which is looked up in the table for the current
+//! [`Language`]. Strings can contain numbered placeholders like `{0}`, which are filled in by
+//! [`tr_args`] and [`tr_count`].
+//!
+//! Values written into song tags, like "Unknown Artist", are deliberately not translated, so that
+//! files stay the same regardless of who downloaded them.
+
+use std::{fmt::Display, sync::atomic::{AtomicU8, Ordering}};
+
+use serde::{Serialize, Deserialize};
+
+#[derive(Debug, Serialize, Deserialize, Copy, Clone, PartialEq, Eq, Default)]
+#[repr(u8)]
+pub enum Language {
+    #[default]
+    English,
+    French,
+}
+
+impl Language {
+    pub const ALL: [Language; 2] = [Language::English, Language::French];
+
+    /// Which plural form should be used for the given count.
+    fn plural(self, count: usize) -> Plural {
+        match self {
+            Language::English => if count == 1 { Plural::One } else { Plural::Other },
+            Language::French => if count <= 1 { Plural::One } else { Plural::Other },
+        }
+    }
+}
+
+impl Display for Language {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        // Always shown in the language itself, so that it can be found by someone who can't read
+        // the current one
+        f.write_str(match self {
+            Language::English => "English",
+            Language::French => "Français",
+        })
+    }
+}
+
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+enum Plural {
+    One,
+    Other,
+}
+
+static LANGUAGE: AtomicU8 = AtomicU8::new(Language::English as u8);
+
+/// Changes the language which all text is translated into.
+pub fn set_language(language: Language) {
+    LANGUAGE.store(language as u8, Ordering::Relaxed);
+}
+
+pub fn language() -> Language {
+    Language::ALL
+        .into_iter()
+        .find(|l| *l as u8 == LANGUAGE.load(Ordering::Relaxed))
+        .unwrap_or_default()
+}
+
+/// Translates a string.
+pub fn tr(key: Key) -> &'static str {
+    lookup(language(), key, Plural::Other)
+}
+
+/// Translates a string, filling in its placeholders with the given arguments.
+pub fn tr_args(key: Key, args: &[&dyn Display]) -> String {
+    fill(tr(key), args)
+}
+
+/// Translates a string which describes some number of things, picking the correct plural form.
+/// The count fills the `{0}` placeholder, and any other arguments fill `{1}` onwards.
+pub fn tr_count(key: Key, count: usize, args: &[&dyn Display]) -> String {
+    let template = lookup(language(), key, language().plural(count));
+
+    let mut all_args: Vec<&dyn Display> = vec![&count];
+    all_args.extend_from_slice(args);
+    fill(template, &all_args)
+}
+
+/// Replaces each placeholder in the template with its argument. This is done in one pass, so an
+/// argument which itself looks like a placeholder, like a song called "{1}", is left as it is.
+/// Anything in braces which isn't the number of an argument is left alone too.
+fn fill(template: &str, args: &[&dyn Display]) -> String {
+    let mut result = String::with_capacity(template.len());
+    let mut rest = template;
+    while let Some(start) = rest.find('{') {
+        result.push_str(&rest[..start]);
+        rest = &rest[start..];
+
+        let arg = rest.find('}')
+            .and_then(|end| Some((end, rest[1..end].parse::<usize>().ok()?)))
+            .and_then(|(end, index)| Some((end, args.get(index)?)));
+        match arg {
+            Some((end, arg)) => {
+                result.push_str(&arg.to_string());
+                rest = &rest[end + 1..];
+            }
+            None => {
+                result.push('{');
+                rest = &rest[1..];
+            }
+        }
+    }
+    result.push_str(rest);
+    result
+}
+
+fn lookup(language: Language, key: Key, plural: Plural) -> &'static str {
+    match language {
+        Language::English => english(key, plural),
+        Language::French => french(key, plural),
+    }
+}
+
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub enum Key {
+    // Shared
+    Cancel,
+    ApplyAndSave,
+    Play,
+    Pause,
+    Edit,
+    Crop,
+    Stop,
+    Delete,
+    Ok,
+    Title,
+    Artist,
+    Album,
+    Genre,
+    Year,
+    Track,
+    ChangedOnDisk,
+    ErrorNotFound,
+    ErrorPermissionDenied,
+    ErrorTagParse,
+    ErrorFfmpegFailed,
+    ErrorAlreadyExists,
+    ErrorBusy,
+    ErrorIo,
+
+    // Main window
+    CloudSyncTitle,
+    CloudSyncBody,
+    CloudSyncAsk,
+    CloudSyncInform,
+    CancelDownloadsTitle,
+    CancelDownloadsBody,
+    PickLibraryTitle,
+    PickLibraryBody,
+    StatusSongCount,
+    StatusHiddenCount,
+
+    // Content view
+    SongDeletedTitle,
+    SongDeletedBody,
+    SongChangedTitle,
+    SongChangedBody,
+    ResumePlaybackTitle,
+    ResumePlaybackBody,
+    PlaybackFailed,
+    SeekFailed,
+    ToastLibraryChanged,
+    LibraryDiffAdded,
+    LibraryDiffRemoved,
+    LibraryDiffChanged,
+    LibraryDiffAddedHeading,
+    LibraryDiffRemovedHeading,
+    LibraryDiffChangedHeading,
+    LibraryDiffMoved,
+    LibraryDiffMovedHeading,
+    LibraryDiffSong,
+
+    // Download view
+    SettingsMenu,
+    ChangeLibrary,
+    OpenLibraryFolder,
+    RefreshLibrary,
+    RestoreTagSnapshotMenu,
+    ExportPlaylistMenu,
+    ImportFilesMenu,
+    Mp3Filter,
+    ToastImportedFiles,
+    ToastImportFilesFailed,
+    M3uPlaylistFilter,
+    PlsPlaylistFilter,
+    ExportHiddenTitle,
+    ExportHiddenBody,
+    ToastPlaylistExported,
+    MoreSettings,
+    SortTitleAscending,
+    SortTitleDescending,
+    SortArtistAscending,
+    SortArtistDescending,
+    SortAlbumAscending,
+    SortAlbumDescending,
+    SortNewestFirst,
+    SortOldestFirst,
+    GroupSongs,
+    UngroupSongs,
+    GroupNoArtist,
+    GroupNoAlbum,
+    GroupSongCount,
+    PasteLinkPlaceholder,
+    Download,
+    PasteAndDownload,
+    ImportLinkList,
+    MoreDownloadOptions,
+    ArtistAndAlbumHint,
+    ExtraArgsLabel,
+    ExtraArgsPlaceholder,
+    ExtraArgsUnclosedQuote,
+    ExtraArgsForbidden,
+    LinkListFilter,
+    ToastLinkListReadFailed,
+    ToastLinkListEmpty,
+    ToastLinkListStarted,
+    LinkListSkippedSuffix,
+    LinkListUnparsedSuffix,
+    LinkListUnparsedLine,
+    DownloadBatchProgress,
+    QueuedNotShown,
+    SessionStatsSummary,
+    SessionStatsDetail,
+    ResetSessionStats,
+    ClipboardNotALink,
+    NotAYouTubeLink,
+    DownloadsInProgress,
+    DownloadLookingUp,
+    DownloadQueued,
+    DownloadQueuedDetail,
+    QueueMoveToTop,
+    QueueMoveUp,
+    QueueMoveDown,
+    DownloadTitleWithId,
+    DownloadFailedDetail,
+    ToastDownloaded,
+    ToastRedownloaded,
+    ToastDownloadedChapters,
+    ToastDownloadFailed,
+    DownloadErrorDownloaderNotFound,
+    DownloaderNotFoundTitle,
+    DownloaderNotFoundBody,
+    DownloaderUnusableTitle,
+    DownloaderUnusableBody,
+    DownloaderPathMissingTitle,
+    DownloaderPathMissingBody,
+    MissingToolsTitle,
+    SettingsSaveFailed,
+    MissingToolsBody,
+    MissingToolDownloader,
+    MissingToolFfmpeg,
+    MissingToolsAfter,
+    MissingToolsBanner,
+    CheckAgain,
+    ToastToolsFound,
+    LibraryLoadFailed,
+    LibraryRefreshFailed,
+    HistoryMenu,
+    HistoryTitle,
+    HistoryEmpty,
+    HistoryLoadFailed,
+    HistoryDownloaded,
+    HistoryFailed,
+    DownloadAgain,
+    Back,
+    ChooseCookiesFileMenu,
+    ClearCookiesFileMenu,
+    CookiesFileFilter,
+    ToastCookiesFileChosen,
+    DownloaderOutdated,
+    DownloaderUpdateHelp,
+    DownloaderUpdateNow,
+    DownloaderUpdating,
+    ToastDownloaderUpdated,
+    ToastDownloaderUpdateFailed,
+    DownloadErrorSpawnFailed,
+    DownloadErrorNonZeroExit,
+    DownloadErrorNonZeroExitReason,
+    DownloadErrorVideoUnavailable,
+    DownloadErrorVideoPrivate,
+    DownloadErrorAgeRestricted,
+    DownloadErrorRegionBlocked,
+    DownloadErrorRateLimited,
+    DownloadErrorDiskFull,
+    DownloadErrorConversionFailed,
+    DownloadErrorNetwork,
+    DownloadErrorAudioNotFound,
+    DownloadErrorThumbnail,
+    DownloadErrorIo,
+    DownloadMayWorkOnRetry,
+    DownloadErrorPostProcess,
+    DownloadErrorChapterSplit,
+    DownloadErrorReplace,
+    DownloadPostProcessing,
+    DownloadSplittingChapter,
+    DownloadStageFetchingMetadata,
+    DownloadStageDownloadingAudio,
+    DownloadStageExtractingAudio,
+    DownloadStageProcessingThumbnail,
+    DownloadStageWritingTags,
+    DownloadsStripSummary,
+    SplitIntoChapters,
+    SkipAutoSubtitles,
+    Retry,
+    RetryAll,
+    DownloadSizeProgress,
+    DownloadErrorPlaylistEntryUnavailable,
+    PlaylistLookingUp,
+    ToastPlaylistFailed,
+    AlreadyDownloadingTitle,
+    AlreadyDownloadingBody,
+    AlreadyDownloadedTitle,
+    AlreadyDownloadedBody,
+    AlreadyDownloadedEditedNote,
+    AlreadyDownloadedCroppedBody,
+    PreviewLoading,
+    PreviewFailed,
+    PreviewLongVideo,
+    PreviewEstimatedSize,
+    DownloadErrorInfoUnreadable,
+    DownloadErrorFfmpegNotFound,
+    DownloadErrorCookiesFileMissing,
+    FfmpegMissingTitle,
+    FfmpegMissingBody,
+    ResumeDownloadsTitle,
+    ResumeDownloadsBody,
+    PartialFilesTitle,
+    PartialFilesBody,
+    ToastDeletedPartialFiles,
+
+    // Song list
+    FilterPinned,
+    FilterNeedsMetadata,
+    FilterNeedsAttention,
+    FilterUploader,
+    FilterHidden,
+    FilterByUploader,
+    SongFromUploader,
+    SongFromVideoTitle,
+    ReviewChecking,
+    ReviewNothingFound,
+    ReviewMarkFine,
+    ReviewRuleTitleIsVideoId,
+    ReviewRuleMissingAlbumArt,
+    ReviewRuleUnknownArtist,
+    ReviewRuleTooShort,
+    ReviewRuleTooLong,
+    PinnedBadge,
+    NeedsMetadataBadge,
+    MenuMore,
+    MenuPin,
+    MenuUnpin,
+    MenuCropWith,
+    MenuCopyYouTubeId,
+    MenuCopyYouTubeUrl,
+    MenuCopyFilePath,
+    MenuRedownload,
+    MenuSelectAlbum,
+    MenuSelectAlbumUnavailable,
+    ToastCopied,
+    ConflictedCopiesBanner,
+    OpenFolder,
+    DeleteConflictedTitle,
+    DeleteConflictedBody,
+    DeleteConflictedFailedTitle,
+    DeleteConflictedFailedBody,
+    DeleteConflictedFailure,
+    TagSnapshotFilter,
+    SnapshotUnreadableTitle,
+    SnapshotUnreadableBody,
+    SnapshotFailedTitle,
+    SnapshotFailedBody,
+    OpenFailedTitle,
+    OpenFailedBody,
+    ToastSnapshotFailed,
+    NothingToRestoreTitle,
+    NothingToRestoreBody,
+    RestoreTagsTitle,
+    RestoreTagsBody,
+    TagChange,
+    AndMore,
+    RestoreTagsFailedTitle,
+    RestoreTagsFailedBody,
+    ToastRestoredTags,
+    RestoreOriginalTitle,
+    RestoreOriginalBody,
+    RestoreOriginalMissing,
+    RestoreDurations,
+    RestoreDurationRegained,
+    RestoreOriginalDuration,
+    RestoreOriginalUnreadable,
+    RestoreRevertsMetadata,
+    RestoreKeepsMetadata,
+    RestoreRevertsPostProcessing,
+    ToastRestoredOriginal,
+    StagingTitle,
+    Approve,
+    ApproveAll,
+    ToastApproved,
+    ToastApprovedAll,
+    ToastApproveFailed,
+    RedownloadTitle,
+    RedownloadModifiedBody,
+    DeleteSongTitle,
+    DeleteSongBody,
+    ToastDeleted,
+    ToastUndone,
+    Undo,
+    UnhideSongTitle,
+    UnhideSongBody,
+    ToastUnhid,
+    HideSongTitle,
+    HideSongBody,
+    ToastHid,
+    ToastCroppedWithTemplate,
+    ToastPostProcessed,
+    TemplateDoesntFitTitle,
+    TemplateDoesntFitBody,
+    TemplateMissingTitle,
+    TemplateMissingBody,
+    ToastInlineEditConflict,
+    SelectionCount,
+    EditSelected,
+    Hide,
+    ClearSelection,
+    HideSelectedTitle,
+    HideSelectedBody,
+    ToastHidSelected,
+    DeleteSelectedTitle,
+    DeleteSelectedBody,
+    ToastDeletedSelected,
+    ToastDeleteSelectedFailed,
+    ShownCount,
+    HideAllShown,
+    UnhideAllShown,
+    HidingShownProgress,
+    UnhidingShownProgress,
+    UnhideShownTitle,
+    UnhideShownBody,
+    ToastUnhidShown,
+    ToastVisibilityChangeFailed,
+
+    // Crop view
+    CropTitle,
+    MediaPlayer,
+    StartPoint,
+    EndPoint,
+    FadeInSeconds,
+    FadeOutSeconds,
+    KeepSegment,
+    KeptSegmentsHeading,
+    KeptSegment,
+    RemoveSegment,
+    CropShortcutsHint,
+    NotSet,
+    Set,
+    Jump,
+    TemplateNamePlaceholder,
+    SaveAsTemplate,
+    ReplaceTemplateTitle,
+    ReplaceTemplateBody,
+    ToastSavedTemplate,
+    ToastCropped,
+
+    // Edit metadata view
+    EditMetadataTitle,
+    ToastEdited,
+    BatchEditTitle,
+    BatchEditMoreSongs,
+    BatchEditHint,
+    ToastBatchEdited,
+    ToastBatchEditFailed,
+    SquareAlbumArt,
+    BrokenAlbumArt,
+    AlbumArtUnreadable,
+    RemoveBrokenAlbumArt,
+    ChangeCover,
+    LyricsHeading,
+    LyricsHint,
+    ClearLyrics,
+    ImageFilter,
+    ToastCoverUnreadable,
+    ToastRemovedBrokenAlbumArt,
+    MusicBrainzLookUp,
+    MusicBrainzSearching,
+    MusicBrainzNoMatches,
+    MusicBrainzMatch,
+    MusicBrainzUse,
+    MusicBrainzErrorNetwork,
+    MusicBrainzErrorRateLimited,
+    MusicBrainzErrorBadResponse,
+    ActivityHeading,
+    ActivityNone,
+    ActivityDownloaded,
+    ActivityCropped,
+    ActivityMetadataEdited,
+    ActivityPostProcessed,
+    ActivityRestored,
+    ActivityHidden,
+    ActivityUnhidden,
+    ActivityImported,
+    ActivityRedownloaded,
+    ActivityApproved,
+    ActivityPostDownloadCommandRan,
+    ToastPostDownloadCommandFailed,
+    LoadingLibrary,
+
+    // Settings view
+    LanguageLabel,
+    CropTemplateNameTakenTitle,
+    CropTemplateNameTakenBody,
+    DeleteCropTemplateTitle,
+    DeleteCropTemplateBody,
+    CropTemplatesHeading,
+    CropTemplatesEmpty,
+    Rename,
+    CropTemplateRange,
+    CropTemplateRangeEnd,
+    CropNudgeLabel,
+    SongListHeading,
+    DoubleClickLabel,
+    MiddleClickLabel,
+    SplitLayoutLabel,
+    ReviewRulesLabel,
+    DownloadsHeading,
+    AudioFormatLabel,
+    OpusNoAlbumArtHint,
+    AudioQualityLabel,
+    LosslessQualityHint,
+    AlbumArtSizeLabel,
+    SplitChaptersSetting,
+    EmbedLyricsSetting,
+    MissingFfmpegLabel,
+    DownloadUnconvertedHint,
+    MaxConcurrentDownloadsLabel,
+    MetadataFailureLabel,
+    ParseArtistSetting,
+    DownloaderProgramLabel,
+    Apply,
+    DownloaderBinaryHint,
+    ChooseDownloaderButton,
+    RateLimitLabel,
+    RateLimitPlaceholder,
+    PerSecond,
+    RateLimitHint,
+    RateLimitInvalid,
+    ExtraArgsHint,
+    PipelineLabel,
+    PipelineHint,
+    LoudnessTargetLabel,
+    Earlier,
+    Later,
+    StorageHeading,
+    DeduplicateOriginalsSetting,
+    DeduplicateOriginalsHint,
+    ScanRecursivelySetting,
+    LibraryCheckLabel,
+    RowActionPlayInCrossPlay,
+    RowActionEditMetadata,
+    RowActionOpenOnYouTube,
+    RowActionNothing,
+    SplitLayoutNever,
+    SplitLayoutWideWindows,
+    SplitLayoutAlways,
+    MetadataFailureKeepFallback,
+    MetadataFailureOpenEditor,
+    MetadataFailureFlag,
+    MissingFfmpegRefuse,
+    MissingFfmpegDownloadUnconverted,
+    LibraryCheckOnFocus,
+    LibraryCheckEveryMinutes,
+    AudioQualityBest,
+    AudioQualityKbps,
+    AlbumArtSizeOriginal,
+    AlbumArtSizeMax,
+    ReviewRuleTooShortSetting,
+    ReviewRuleTooLongSetting,
+    PostProcessTrimSilence,
+    PostProcessSquareAlbumArt,
+    PostProcessShrinkAlbumArt,
+    PostProcessNormalizeLoudness,
+    ResetBandwidthTitle,
+    ResetBandwidthBody,
+    ResetBandwidthFailedTitle,
+    ResetBandwidthFailedBody,
+    StatisticsHeading,
+    BandwidthThisMonth,
+    Reset,
+    DownloadStatsAllTime,
+    ApproveStagedTitle,
+    ApproveStagedBody,
+    ApproveStagedFailedTitle,
+    ApproveStagedFailedBody,
+    ApproveStagedFailure,
+    StagingSetting,
+    StagingHint,
+    TestCommandTitle,
+    TestCommandBody,
+    CommandSucceededTitle,
+    CommandSucceededSilent,
+    CommandSucceededOutput,
+    CommandFailedTitle,
+    CommandFailedBody,
+    PostDownloadCommandSetting,
+    TestRun,
+    TestRunning,
+    PostDownloadCommandHint,
+    PostDownloadCommandUnclosedQuote,
+    HookErrorUnclosedQuote,
+    HookErrorEmpty,
+    HookErrorSpawnFailed,
+    HookErrorTimedOut,
+    HookErrorExitCode,
+    HookErrorTerminated,
+}
+
+fn english(key: Key, plural: Plural) -> &'static str {
+    match (key, plural) {
+        (Key::Cancel, _) => "Cancel",
+        (Key::ApplyAndSave, _) => "Apply and save",
+        (Key::Play, _) => "Play",
+        (Key::Pause, _) => "Pause",
+        (Key::Edit, _) => "Edit",
+        (Key::Crop, _) => "Crop",
+        (Key::Stop, _) => "Stop",
+        (Key::Delete, _) => "Delete",
+        (Key::Ok, _) => "OK",
+        (Key::Title, _) => "Title",
+        (Key::Artist, _) => "Artist",
+        (Key::Album, _) => "Album",
+        (Key::Genre, _) => "Genre",
+        (Key::Year, _) => "Year",
+        (Key::Track, _) => "Track",
+        (Key::ChangedOnDisk, _) => "This song has been changed or deleted since you opened it.",
+        (Key::ErrorNotFound, _) => "'{0}' doesn't exist any more - it may have been moved or deleted outside of CrossPlay",
+        (Key::ErrorPermissionDenied, _) => "CrossPlay doesn't have permission to change '{0}'",
+        (Key::ErrorTagParse, _) => "The tags of '{0}' couldn't be read",
+        (Key::ErrorFfmpegFailed, _) => "ffmpeg couldn't process the song",
+        (Key::ErrorAlreadyExists, _) => "'{0}' already exists",
+        (Key::ErrorBusy, _) => "'{0}' is in use by another program - close it and try again",
+        (Key::ErrorIo, _) => "'{0}' couldn't be accessed",
+
+        (Key::CloudSyncTitle, _) => "Library is in a cloud-synced folder",
+        (Key::CloudSyncBody, _) => "Your library folder is inside a folder synced by {0}.\n\nCrossPlay frequently rewrites song files while downloading, cropping and editing them. If the same library is synced to another computer, these changes can conflict, leaving behind duplicate \"conflicted copy\" files or corrupted songs.\n\nThe library path is: {1}",
+        (Key::CloudSyncAsk, _) => "Would you like to use this folder anyway?",
+        (Key::CloudSyncInform, _) => "You can pick a different library folder from the settings menu.",
+        (Key::CancelDownloadsTitle, _) => "Cancel downloads?",
+        (Key::CancelDownloadsBody, _) => "There are currently downloads in progress. Exiting now will stop them, but you'll be offered to resume them next time CrossPlay opens. Are you sure you would like to exit?",
+        (Key::PickLibraryTitle, _) => "Pick new library?",
+        (Key::PickLibraryBody, _) => "Would you like to pick a new library folder? Your songs will not be copied to the new location, but will be preserved in the old location so you can switch back to it later.\n\nThe current library path is: {0}",
+        (Key::StatusSongCount, Plural::One) => "{0} song",
+        (Key::StatusSongCount, Plural::Other) => "{0} songs",
+        (Key::StatusHiddenCount, _) => "{0} hidden",
+
+        (Key::SongDeletedTitle, _) => "Song deleted",
+        (Key::SongDeletedBody, _) => "'{0}' has been deleted since you started changing it, so your changes can't be saved.",
+        (Key::SongChangedTitle, _) => "Song changed",
+        (Key::SongChangedBody, _) => "'{0}' has been changed by something else since you started changing it.\n\nWould you like to reload its current state? Your changes here will be lost. Otherwise, your changes will be abandoned.",
+        (Key::ResumePlaybackTitle, _) => "Resume playback?",
+        (Key::ResumePlaybackBody, _) => "Would you like to resume playing '{0}' from {1}?",
+        (Key::PlaybackFailed, _) => "Couldn't play this song: {0}",
+        (Key::SeekFailed, _) => "Couldn't skip to where the song was: {0}",
+        (Key::ToastLibraryChanged, _) => "Library refreshed: {0}",
+        (Key::LibraryDiffAdded, _) => "+{0} added",
+        (Key::LibraryDiffRemoved, _) => "{0} removed",
+        (Key::LibraryDiffChanged, _) => "{0} changed",
+        (Key::LibraryDiffAddedHeading, _) => "Added:",
+        (Key::LibraryDiffRemovedHeading, _) => "Removed:",
+        (Key::LibraryDiffChangedHeading, _) => "Changed:",
+        (Key::LibraryDiffMoved, _) => "{0} renamed",
+        (Key::LibraryDiffMovedHeading, _) => "Renamed:",
+        (Key::LibraryDiffSong, _) => "  {0} ({1})",
+
+        (Key::SettingsMenu, _) => "Settings",
+        (Key::ChangeLibrary, _) => "Change library",
+        (Key::OpenLibraryFolder, _) => "Open library folder",
+        (Key::RefreshLibrary, _) => "Refresh library",
+        (Key::RestoreTagSnapshotMenu, _) => "Restore tags from snapshot...",
+        (Key::ExportPlaylistMenu, _) => "Export library as playlist...",
+        (Key::ImportFilesMenu, _) => "Import MP3 files...",
+        (Key::Mp3Filter, _) => "MP3 file",
+        (Key::ToastImportedFiles, Plural::One) => "Imported {0} song",
+        (Key::ToastImportedFiles, Plural::Other) => "Imported {0} songs",
+        (Key::ToastImportFilesFailed, Plural::One) => "{0} file couldn't be imported ({1} others were)",
+        (Key::ToastImportFilesFailed, Plural::Other) => "{0} files couldn't be imported ({1} others were)",
+        (Key::M3uPlaylistFilter, _) => "M3U playlist",
+        (Key::PlsPlaylistFilter, _) => "PLS playlist",
+        (Key::ExportHiddenTitle, _) => "Include hidden songs?",
+        (Key::ExportHiddenBody, Plural::One) => "{0} song in your library is hidden. Do you want to include it in the playlist?",
+        (Key::ExportHiddenBody, Plural::Other) => "{0} songs in your library are hidden. Do you want to include them in the playlist?",
+        (Key::ToastPlaylistExported, Plural::One) => "Exported {0} song to {1}",
+        (Key::ToastPlaylistExported, Plural::Other) => "Exported {0} songs to {1}",
+        (Key::MoreSettings, _) => "More settings...",
+        (Key::SortTitleAscending, _) => "Title A→Z",
+        (Key::SortTitleDescending, _) => "Title Z→A",
+        (Key::SortArtistAscending, _) => "Artist A→Z",
+        (Key::SortArtistDescending, _) => "Artist Z→A",
+        (Key::SortAlbumAscending, _) => "Album A→Z",
+        (Key::SortAlbumDescending, _) => "Album Z→A",
+        (Key::SortNewestFirst, _) => "Newest first",
+        (Key::SortOldestFirst, _) => "Oldest first",
+        (Key::GroupSongs, _) => "Group under headings",
+        (Key::UngroupSongs, _) => "Stop grouping",
+        (Key::GroupNoArtist, _) => "No artist",
+        (Key::GroupNoAlbum, _) => "No album",
+        (Key::GroupSongCount, Plural::One) => "{0} song",
+        (Key::GroupSongCount, Plural::Other) => "{0} songs",
+        (Key::PasteLinkPlaceholder, _) => "Paste a link to a song on YouTube, SoundCloud or Bandcamp...",
+        (Key::Download, _) => "Download",
+        (Key::PasteAndDownload, _) => "Paste & Download",
+        (Key::ImportLinkList, _) => "Import List...",
+        (Key::MoreDownloadOptions, _) => "Options...",
+        (Key::ArtistAndAlbumHint, _) => "Save the next download as:",
+        (Key::ExtraArgsLabel, _) => "Extra youtube-dl arguments:",
+        (Key::ExtraArgsPlaceholder, _) => "None, like --force-ipv4",
+        (Key::ExtraArgsUnclosedQuote, _) => "A quote in the extra arguments is never closed",
+        (Key::ExtraArgsForbidden, _) => "'{0}' can't be used, since CrossPlay needs to control where downloads are saved",
+        (Key::LinkListFilter, _) => "List of links",
+        (Key::ToastLinkListReadFailed, _) => "Couldn't read {0}",
+        (Key::ToastLinkListEmpty, _) => "There were no links to download",
+        (Key::ToastLinkListStarted, Plural::One) => "Downloading {0} link",
+        (Key::ToastLinkListStarted, Plural::Other) => "Downloading {0} links",
+        (Key::LinkListSkippedSuffix, Plural::One) => ", skipped {0} already downloaded",
+        (Key::LinkListSkippedSuffix, Plural::Other) => ", skipped {0} already downloaded",
+        (Key::LinkListUnparsedSuffix, Plural::One) => ", but {0} line wasn't a link",
+        (Key::LinkListUnparsedSuffix, Plural::Other) => ", but {0} lines weren't links",
+        (Key::LinkListUnparsedLine, _) => "Line {0}: {1}",
+        (Key::DownloadBatchProgress, _) => " ({0} of {1} complete)",
+        (Key::QueuedNotShown, Plural::One) => "...and {0} more queued",
+        (Key::QueuedNotShown, Plural::Other) => "...and {0} more queued",
+        (Key::SessionStatsSummary, _) => "This session: {0} downloaded, {1} failed",
+        (Key::SessionStatsDetail, _) => "{0} downloaded, {1} of audio, {2} spent downloading",
+        (Key::ResetSessionStats, _) => "Reset",
+        (Key::ClipboardNotALink, _) => "The clipboard doesn't contain a link to a video, so nothing was downloaded.",
+        (Key::NotAYouTubeLink, _) => "That doesn't look like a YouTube link.",
+        (Key::DownloadsInProgress, Plural::One) => "{0} download in progress...",
+        (Key::DownloadsInProgress, Plural::Other) => "{0} downloads in progress...",
+        (Key::DownloadLookingUp, _) => "Looking up video info... (ID {0})",
+        (Key::DownloadQueued, _) => "Queued",
+        (Key::DownloadQueuedDetail, _) => "{0} ({1}, {2})",
+        (Key::QueueMoveToTop, _) => "Move to top",
+        (Key::QueueMoveUp, _) => "Move up",
+        (Key::QueueMoveDown, _) => "Move down",
+        (Key::DownloadTitleWithId, _) => "{0} (ID {1})",
+        (Key::DownloadFailedDetail, _) => "Download {0} failed: {1}",
+        (Key::ToastDownloaded, _) => "Downloaded '{0}'",
+        (Key::ToastRedownloaded, _) => "Downloaded '{0}' again",
+        (Key::ToastDownloadedChapters, Plural::One) => "Downloaded '{1}' as {0} song",
+        (Key::ToastDownloadedChapters, Plural::Other) => "Downloaded '{1}' as {0} songs, one per chapter",
+        (Key::ToastDownloadFailed, _) => "Download of '{0}' failed",
+        (Key::DownloadErrorDownloaderNotFound, _) => "Neither yt-dlp nor youtube-dl is installed - install one of them, then try again",
+        (Key::DownloaderNotFoundTitle, _) => "No downloader found",
+        (Key::DownloaderUnusableTitle, _) => "Downloader can't be run",
+        (Key::DownloaderUnusableBody, _) => "'{0}' couldn't be run with --version. Choose the yt-dlp or youtube-dl executable.",
+        (Key::DownloaderPathMissingTitle, _) => "Chosen downloader unavailable",
+        (Key::DownloaderPathMissingBody, _) => "The downloader you chose, '{0}', can't be run any more, so one on the PATH will be used instead.",
+        (Key::MissingToolsTitle, _) => "Programs missing",
+        (Key::SettingsSaveFailed, _) => "Settings can't be saved ({0}) - changes will be lost when CrossPlay closes. To keep them, start CrossPlay with --settings-dir pointing to a folder which can be written to.",
+        (Key::MissingToolsBody, _) => "CrossPlay couldn't find some programs it needs:",
+        (Key::MissingToolDownloader, _) => "• {0} (or yt-dlp or youtube-dl), to download songs. Install yt-dlp from https://github.com/yt-dlp/yt-dlp, or choose where it is in Settings.",
+        (Key::MissingToolFfmpeg, _) => "• ffmpeg, to convert downloads and to crop songs. Install it from https://ffmpeg.org.",
+        (Key::MissingToolsAfter, _) => "Until they're installed, the buttons which need them are turned off. Once they are, press 'Check again' above the song list, or restart CrossPlay.",
+        (Key::MissingToolsBanner, _) => "Downloading is turned off, because CrossPlay couldn't find: {0}",
+        (Key::CheckAgain, _) => "Check again",
+        (Key::ToastToolsFound, _) => "Everything CrossPlay needs is installed",
+        (Key::LibraryLoadFailed, _) => "Couldn't load the library at '{0}': {1}",
+        (Key::LibraryRefreshFailed, _) => "Couldn't refresh the library: {0} - showing the songs as they were last loaded",
+        (Key::HistoryMenu, _) => "Download history",
+        (Key::HistoryTitle, _) => "Download history",
+        (Key::HistoryEmpty, _) => "Nothing has been downloaded yet.",
+        (Key::HistoryLoadFailed, _) => "The download history couldn't be loaded: {0}",
+        (Key::HistoryDownloaded, _) => "Downloaded",
+        (Key::HistoryFailed, _) => "Failed",
+        (Key::DownloadAgain, _) => "Download again",
+        (Key::Back, _) => "Back",
+        (Key::ChooseCookiesFileMenu, _) => "Choose cookies file...",
+        (Key::ClearCookiesFileMenu, _) => "Clear cookies file",
+        (Key::CookiesFileFilter, _) => "Cookies file",
+        (Key::ToastCookiesFileChosen, _) => "Downloads will sign in with the chosen cookies file",
+        (Key::DownloaderOutdated, _) => "Your downloader, '{0}', is version {1}, which is out of date. YouTube changes often, so downloads may fail until it's updated.",
+        (Key::DownloaderUpdateHelp, _) => "How to update",
+        (Key::DownloaderUpdateNow, _) => "Update now",
+        (Key::DownloaderUpdating, _) => "Updating...",
+        (Key::ToastDownloaderUpdated, _) => "Downloader updated",
+        (Key::ToastDownloaderUpdateFailed, _) => "Couldn't update the downloader",
+        (Key::DownloaderNotFoundBody, _) => "CrossPlay couldn't run '{0}', yt-dlp or youtube-dl. Install yt-dlp, or set the downloader in Settings, then try again.",
+        (Key::DownloadErrorSpawnFailed, _) => "The downloader couldn't be started - is it installed?",
+        (Key::DownloadErrorNonZeroExit, _) => "youtube-dl failed - the video may be unavailable, or the connection may have dropped",
+        (Key::DownloadErrorNonZeroExitReason, _) => "youtube-dl failed: {0}",
+        (Key::DownloadErrorVideoUnavailable, _) => "The video is unavailable - it may have been deleted",
+        (Key::DownloadErrorVideoPrivate, _) => "The video is private",
+        (Key::DownloadErrorAgeRestricted, _) => "The video is age-restricted, so YouTube needs you to sign in to watch it - choose a cookies file from the settings menu to sign in",
+        (Key::DownloadErrorRegionBlocked, _) => "The video isn't available in your country",
+        (Key::DownloadErrorRateLimited, _) => "The site is refusing to download any more for now - wait a while before trying again",
+        (Key::DownloadErrorDiskFull, _) => "The disk is full - free up some space, then try again",
+        (Key::DownloadErrorConversionFailed, _) => "ffmpeg couldn't convert the download - updating ffmpeg or choosing another audio format might help",
+        (Key::DownloadErrorNetwork, _) => "The site couldn't be reached - check your internet connection, then try again",
+        (Key::DownloadErrorAudioNotFound, _) => "The download finished, but no audio file was produced - is ffmpeg installed?",
+        (Key::DownloadErrorThumbnail, _) => "The video's thumbnail couldn't be saved",
+        (Key::DownloadErrorIo, _) => "The download couldn't be saved to the library folder",
+        (Key::DownloadMayWorkOnRetry, _) => " (downloading it again may work)",
+        (Key::DownloadErrorPostProcess, _) => "The song downloaded, but the '{0}' step failed: {1}",
+        (Key::DownloadErrorChapterSplit, _) => "The song downloaded, but couldn't be split into chapters, so it was kept whole: {0}",
+        (Key::DownloadErrorReplace, _) => "The song downloaded again, but couldn't replace the copy in the library, so that was kept as it was: {0}",
+        (Key::DownloadPostProcessing, _) => " - {0}...",
+        (Key::DownloadSplittingChapter, _) => " - splitting chapter {0}/{1}...",
+        (Key::DownloadStageFetchingMetadata, _) => "Fetching video info...",
+        (Key::DownloadStageDownloadingAudio, _) => "Downloading audio...",
+        (Key::DownloadStageExtractingAudio, _) => "Extracting audio...",
+        (Key::DownloadStageProcessingThumbnail, _) => "Processing thumbnail...",
+        (Key::DownloadStageWritingTags, _) => "Writing tags...",
+        (Key::DownloadsStripSummary, Plural::One) => "{0} download · {1}%",
+        (Key::DownloadsStripSummary, Plural::Other) => "{0} downloads · {1}%",
+        (Key::SplitIntoChapters, Plural::One) => "Split into {0} song, one per chapter",
+        (Key::SplitIntoChapters, Plural::Other) => "Split into {0} songs, one per chapter",
+        (Key::SkipAutoSubtitles, _) => "Skip lyrics from auto-generated subtitles",
+        (Key::Retry, _) => "Retry",
+        (Key::RetryAll, _) => "Retry all",
+        (Key::DownloadSizeProgress, _) => "{0} of ~{1}",
+        (Key::DownloadErrorPlaylistEntryUnavailable, _) => "This video in the playlist is private, deleted, or unavailable, so it was skipped",
+        (Key::PlaylistLookingUp, _) => "Looking up the videos in playlist {0}...",
+        (Key::ToastPlaylistFailed, _) => "Couldn't look up playlist {0}",
+        (Key::AlreadyDownloadingTitle, _) => "Already downloading",
+        (Key::AlreadyDownloadingBody, _) => "'{0}' is already being downloaded.",
+        (Key::AlreadyDownloadedTitle, _) => "Already downloaded",
+        (Key::AlreadyDownloadedBody, _) => "'{0}' is already in your library. Would you like to download it again? The existing song will be overwritten.",
+        (Key::AlreadyDownloadedEditedNote, _) => "You've edited this song's metadata, so those edits will be lost - but a snapshot of its tags will be saved first, which you can restore from the settings menu.",
+        (Key::AlreadyDownloadedCroppedBody, _) => "'{0}' is already in your library, and has been cropped. Downloading it again would lose the crop, so it won't be downloaded. Restore the song's original or delete it first if you'd like to download it again.",
+        (Key::PreviewLoading, _) => "Looking up video...",
+        (Key::PreviewFailed, _) => "Couldn't fetch info for this link: {0}",
+        (Key::PreviewLongVideo, _) => "This video is over an hour long, so it'll take a while to download and use a lot of space.",
+        (Key::PreviewEstimatedSize, _) => "about {0}",
+        (Key::DownloadErrorInfoUnreadable, _) => "youtube-dl's info about the video couldn't be understood - it may need updating",
+        (Key::DownloadErrorFfmpegNotFound, _) => "youtube-dl needs ffmpeg to convert the download, but couldn't find it - install ffmpeg, then try again",
+        (Key::DownloadErrorCookiesFileMissing, _) => "The cookies file chosen in the settings menu doesn't exist any more - choose it again, or clear it",
+        (Key::FfmpegMissingTitle, _) => "ffmpeg not found",
+        (Key::FfmpegMissingBody, _) => "youtube-dl needs ffmpeg to convert downloads, but it isn't installed. Install ffmpeg, then try again.\n\nIf you'd rather download songs without converting them, you can choose to in Settings.",
+        (Key::ResumeDownloadsTitle, _) => "Resume downloads?",
+        (Key::ResumeDownloadsBody, Plural::One) => "{0} download was still running when CrossPlay was last closed. Resume it from where it got to?\n\n{1}",
+        (Key::ResumeDownloadsBody, Plural::Other) => "{0} downloads were still running when CrossPlay was last closed. Resume them from where they got to?\n\n{1}",
+        (Key::PartialFilesTitle, _) => "Delete unfinished downloads?",
+        (Key::PartialFilesBody, Plural::One) => "{0} file from a download which never finished was left in {1}. Delete it?",
+        (Key::PartialFilesBody, Plural::Other) => "{0} files from downloads which never finished were left in {1}. Delete them?",
+        (Key::ToastDeletedPartialFiles, Plural::One) => "Deleted {0} unfinished download file",
+        (Key::ToastDeletedPartialFiles, Plural::Other) => "Deleted {0} unfinished download files",
+
+        (Key::FilterPinned, _) => "Pinned",
+        (Key::FilterNeedsMetadata, _) => "Needs metadata",
+        (Key::FilterNeedsAttention, _) => "Needs attention",
+        (Key::FilterUploader, _) => "Channel: {0}",
+        (Key::FilterHidden, _) => "Hidden",
+        (Key::FilterByUploader, _) => "Channel...",
+        (Key::SongFromUploader, _) => "From {0}",
+        (Key::SongFromVideoTitle, _) => "Video title: {0}",
+        (Key::ReviewChecking, _) => "Checking songs...",
+        (Key::ReviewNothingFound, _) => "Nothing needs attention.",
+        (Key::ReviewMarkFine, _) => "Mark as fine",
+        (Key::ReviewRuleTitleIsVideoId, _) => "Title is the video ID",
+        (Key::ReviewRuleMissingAlbumArt, _) => "No album art",
+        (Key::ReviewRuleUnknownArtist, _) => "Unknown artist",
+        (Key::ReviewRuleTooShort, _) => "Very short",
+        (Key::ReviewRuleTooLong, _) => "Very long",
+        (Key::PinnedBadge, _) => "PINNED",
+        (Key::NeedsMetadataBadge, _) => "NEEDS METADATA",
+        (Key::MenuMore, _) => "More",
+        (Key::MenuPin, _) => "Pin to top",
+        (Key::MenuUnpin, _) => "Unpin",
+        (Key::MenuCropWith, _) => "Crop with '{0}'",
+        (Key::MenuCopyYouTubeId, _) => "Copy YouTube ID",
+        (Key::MenuCopyYouTubeUrl, _) => "Copy video URL",
+        (Key::MenuCopyFilePath, _) => "Copy file path",
+        (Key::MenuRedownload, _) => "Download again",
+        (Key::MenuSelectAlbum, _) => "Select all from '{0}'",
+        (Key::MenuSelectAlbumUnavailable, _) => "Select album (this song has no album)",
+        (Key::ToastCopied, _) => "Copied {0}",
+        (Key::ConflictedCopiesBanner, Plural::One) => "{0} conflicted copy of a library file, left behind by a cloud sync service, is in the library folder.",
+        (Key::ConflictedCopiesBanner, Plural::Other) => "{0} conflicted copies of library files, left behind by a cloud sync service, are in the library folder.",
+        (Key::OpenFolder, _) => "Open folder",
+        (Key::DeleteConflictedTitle, _) => "Delete conflicted copies?",
+        (Key::DeleteConflictedBody, _) => "The following files will be permanently deleted. If you made changes on another computer which only exist in these copies, those changes will be lost.\n\n{0}",
+        (Key::DeleteConflictedFailedTitle, _) => "Some conflicted copies could not be deleted",
+        (Key::DeleteConflictedFailedBody, _) => "These files are still in the library folder:\n\n{0}",
+        (Key::DeleteConflictedFailure, _) => "{0}: {1}",
+        (Key::TagSnapshotFilter, _) => "Tag snapshot",
+        (Key::SnapshotUnreadableTitle, _) => "Couldn't read snapshot",
+        (Key::SnapshotUnreadableBody, _) => "The snapshot could not be read: {0}",
+        (Key::SnapshotFailedTitle, _) => "Couldn't take snapshot",
+        (Key::SnapshotFailedBody, _) => "A snapshot of these songs' tags couldn't be saved, so this change can't be undone with \"Restore tags from snapshot\":\n\n{0}\n\nDo you want to go ahead anyway?",
+        (Key::OpenFailedTitle, _) => "Couldn't open",
+        (Key::OpenFailedBody, _) => "{0} couldn't be opened: {1}\n\nIt has been copied to the clipboard, so that you can open it yourself.",
+        (Key::ToastSnapshotFailed, _) => "Couldn't take a snapshot of the songs' tags",
+        (Key::NothingToRestoreTitle, _) => "Nothing to restore",
+        (Key::NothingToRestoreBody, _) => "All songs in this snapshot already match their tags in the library, or are no longer in the library.",
+        (Key::RestoreTagsTitle, _) => "Restore tags?",
+        (Key::RestoreTagsBody, Plural::One) => "The tags of {0} song will be restored from this snapshot:\n\n{1}",
+        (Key::RestoreTagsBody, Plural::Other) => "The tags of {0} songs will be restored from this snapshot:\n\n{1}",
+        (Key::TagChange, _) => "'{0}' by {1} ({2})  =>  '{3}' by {4} ({5})",
+        (Key::AndMore, _) => "...and {0} more",
+        (Key::RestoreTagsFailedTitle, _) => "Some tags couldn't be restored",
+        (Key::RestoreTagsFailedBody, _) => "The following songs could not be restored:\n\n{0}",
+        (Key::ToastRestoredTags, Plural::One) => "Restored tags of {0} song",
+        (Key::ToastRestoredTags, Plural::Other) => "Restored tags of {0} songs",
+        (Key::RestoreOriginalTitle, _) => "Restore original?",
+        (Key::RestoreOriginalBody, _) => "Are you sure you would like to restore '{0}'?",
+        (Key::RestoreOriginalMissing, _) => "The original copy of '{0}' is missing, so it can't be restored.",
+        (Key::RestoreDurations, _) => "This copy is {0} long, and the original is {1}.",
+        (Key::RestoreDurationRegained, _) => "Restoring brings back {0} which the crop removed.",
+        (Key::RestoreOriginalDuration, _) => "The original is {0} long.",
+        (Key::RestoreOriginalUnreadable, _) => "The original's length couldn't be read: {0}",
+        (Key::RestoreRevertsMetadata, _) => "Metadata edits will also be undone.",
+        (Key::RestoreKeepsMetadata, _) => "The metadata hasn't been edited, so it won't change.",
+        (Key::RestoreRevertsPostProcessing, _) => "Post-processing will also be undone.",
+        (Key::ToastRestoredOriginal, _) => "Restored original of '{0}'",
+        (Key::StagingTitle, _) => "Staging ({0})",
+        (Key::Approve, _) => "Approve",
+        (Key::ApproveAll, _) => "Approve all",
+        (Key::ToastApproved, _) => "Moved '{0}' into the library",
+        (Key::ToastApprovedAll, Plural::One) => "Moved {0} song into the library",
+        (Key::ToastApprovedAll, Plural::Other) => "Moved {0} songs into the library",
+        (Key::ToastApproveFailed, Plural::One) => "{0} song couldn't be moved into the library ({1} others were)",
+        (Key::ToastApproveFailed, Plural::Other) => "{0} songs couldn't be moved into the library ({1} others were)",
+        (Key::RedownloadTitle, _) => "Download again?",
+        (Key::RedownloadModifiedBody, _) => "'{0}' has been cropped or post-processed. Downloading it again keeps its metadata, but the new download won't have these changes. Are you sure?",
+        (Key::DeleteSongTitle, _) => "Delete song?",
+        (Key::DeleteSongBody, _) => "This will permanently delete the song and any modifications made to it. Are you sure you would like to delete '{0}'?",
+        (Key::ToastDeleted, _) => "Deleted '{0}'",
+        (Key::ToastUndone, _) => "Undone: {0}",
+        (Key::Undo, _) => "Undo",
+        (Key::UnhideSongTitle, _) => "Unhide song?",
+        (Key::UnhideSongBody, _) => "The song '{0}' will re-appear in media players.",
+        (Key::ToastUnhid, _) => "Unhid '{0}'",
+        (Key::HideSongTitle, _) => "Hide song?",
+        (Key::HideSongBody, _) => "The song '{0}' will remain downloaded and visible in CrossPlay, but will stop showing in media players.",
+        (Key::ToastHid, _) => "Hid '{0}'",
+        (Key::ToastCroppedWithTemplate, _) => "Cropped '{0}' with template '{1}'",
+        (Key::ToastPostProcessed, _) => "{1}: done for '{0}'",
+        (Key::TemplateDoesntFitTitle, _) => "Crop template doesn't fit",
+        (Key::TemplateDoesntFitBody, _) => "The crop template '{0}' can't be applied to '{1}', because the song is too short.",
+        (Key::TemplateMissingTitle, _) => "Crop template not found",
+        (Key::TemplateMissingBody, _) => "The crop template '{0}' no longer exists.",
+        (Key::ToastInlineEditConflict, _) => "'{0}' was changed outside of CrossPlay, so your edit wasn't saved",
+        (Key::SelectionCount, Plural::One) => "{0} song selected",
+        (Key::SelectionCount, Plural::Other) => "{0} songs selected",
+        (Key::EditSelected, _) => "Edit",
+        (Key::Hide, _) => "Hide",
+        (Key::ClearSelection, _) => "Clear selection",
+        (Key::HideSelectedTitle, _) => "Hide songs?",
+        (Key::HideSelectedBody, Plural::One) => "{0} song will remain downloaded and visible in CrossPlay, but will stop showing in media players.",
+        (Key::HideSelectedBody, Plural::Other) => "{0} songs will remain downloaded and visible in CrossPlay, but will stop showing in media players.",
+        (Key::ToastHidSelected, Plural::One) => "Hid {0} song",
+        (Key::ToastHidSelected, Plural::Other) => "Hid {0} songs",
+        (Key::DeleteSelectedTitle, _) => "Delete songs?",
+        (Key::DeleteSelectedBody, Plural::One) => "This will permanently delete {0} song and any modifications made to it. Are you sure?",
+        (Key::DeleteSelectedBody, Plural::Other) => "This will permanently delete {0} songs and any modifications made to them. Are you sure?",
+        (Key::ToastDeletedSelected, Plural::One) => "Deleted {0} song",
+        (Key::ToastDeletedSelected, Plural::Other) => "Deleted {0} songs",
+        (Key::ToastDeleteSelectedFailed, Plural::One) => "{0} song couldn't be deleted ({1} others were)",
+        (Key::ToastDeleteSelectedFailed, Plural::Other) => "{0} songs couldn't be deleted ({1} others were)",
+        (Key::ShownCount, Plural::One) => "{0} song shown",
+        (Key::ShownCount, Plural::Other) => "{0} songs shown",
+        (Key::HideAllShown, _) => "Hide all shown",
+        (Key::UnhideAllShown, _) => "Unhide all shown",
+        (Key::HidingShownProgress, _) => "Hiding songs... ({0} of {1})",
+        (Key::UnhidingShownProgress, _) => "Unhiding songs... ({0} of {1})",
+        (Key::UnhideShownTitle, _) => "Unhide songs?",
+        (Key::UnhideShownBody, Plural::One) => "{0} song will start showing in media players again.",
+        (Key::UnhideShownBody, Plural::Other) => "{0} songs will start showing in media players again.",
+        (Key::ToastUnhidShown, Plural::One) => "Unhid {0} song",
+        (Key::ToastUnhidShown, Plural::Other) => "Unhid {0} songs",
+        (Key::ToastVisibilityChangeFailed, Plural::One) => "{0} song couldn't be changed ({1} others were)",
+        (Key::ToastVisibilityChangeFailed, Plural::Other) => "{0} songs couldn't be changed ({1} others were)",
+
+        (Key::CropTitle, _) => "Crop: {0}",
+        (Key::MediaPlayer, _) => "Media player",
+        (Key::StartPoint, _) => "Start point",
+        (Key::EndPoint, _) => "End point",
+        (Key::FadeInSeconds, _) => "Fade in (seconds):",
+        (Key::FadeOutSeconds, _) => "Fade out (seconds):",
+        (Key::KeepSegment, _) => "Keep this segment and add another",
+        (Key::KeptSegmentsHeading, _) => "Segments to keep, joined in order:",
+        (Key::KeptSegment, _) => "{0} to {1}",
+        (Key::RemoveSegment, _) => "Remove",
+        (Key::CropShortcutsHint, _) => "Space plays or pauses, the arrow keys move the playhead (hold Shift to move further), and [ and ] set the start and end points.",
+        (Key::NotSet, _) => "Not set",
+        (Key::Set, _) => "Set",
+        (Key::Jump, _) => "Jump",
+        (Key::TemplateNamePlaceholder, _) => "Template name...",
+        (Key::SaveAsTemplate, _) => "Save as template",
+        (Key::ReplaceTemplateTitle, _) => "Replace crop template?",
+        (Key::ReplaceTemplateBody, _) => "There is already a crop template named '{0}'. Would you like to replace it?",
+        (Key::ToastSavedTemplate, _) => "Saved crop template '{0}'",
+        (Key::ToastCropped, _) => "Cropped '{0}'",
+
+        (Key::EditMetadataTitle, _) => "Edit Metadata",
+        (Key::ToastEdited, _) => "Edited '{0}'",
+        (Key::BatchEditTitle, Plural::One) => "Edit {0} Song",
+        (Key::BatchEditTitle, Plural::Other) => "Edit {0} Songs",
+        (Key::BatchEditMoreSongs, Plural::One) => "...and {0} more",
+        (Key::BatchEditMoreSongs, Plural::Other) => "...and {0} more",
+        (Key::BatchEditHint, _) => "Fields left blank won't be changed.",
+        (Key::ToastBatchEdited, Plural::One) => "Edited {0} song",
+        (Key::ToastBatchEdited, Plural::Other) => "Edited {0} songs",
+        (Key::ToastBatchEditFailed, Plural::One) => "{0} song couldn't be edited ({1} others were)",
+        (Key::ToastBatchEditFailed, Plural::Other) => "{0} songs couldn't be edited ({1} others were)",
+        (Key::SquareAlbumArt, _) => "Crop to a square",
+        (Key::BrokenAlbumArt, _) => "This song's album art is damaged and can't be shown.",
+        (Key::AlbumArtUnreadable, _) => "This song's album art couldn't be read: {0}",
+        (Key::RemoveBrokenAlbumArt, _) => "Remove broken art",
+        (Key::ChangeCover, _) => "Change cover...",
+        (Key::LyricsHeading, _) => "Lyrics",
+        (Key::LyricsHint, _) => "Press Enter to start a new line.",
+        (Key::ClearLyrics, _) => "Clear lyrics",
+        (Key::ImageFilter, _) => "Image",
+        (Key::ToastCoverUnreadable, _) => "Couldn't read '{0}' as an image",
+        (Key::ToastRemovedBrokenAlbumArt, _) => "Removed the broken album art from '{0}'",
+        (Key::MusicBrainzLookUp, _) => "Look up on MusicBrainz",
+        (Key::MusicBrainzSearching, _) => "Searching MusicBrainz...",
+        (Key::MusicBrainzNoMatches, _) => "MusicBrainz has nothing matching this title and artist.",
+        (Key::MusicBrainzMatch, _) => "'{0}' by {1}",
+        (Key::MusicBrainzUse, _) => "Use",
+        (Key::MusicBrainzErrorNetwork, _) => "MusicBrainz couldn't be reached - check your internet connection, then try again",
+        (Key::MusicBrainzErrorRateLimited, _) => "MusicBrainz is busy - wait a few seconds, then try again",
+        (Key::MusicBrainzErrorBadResponse, _) => "MusicBrainz sent something CrossPlay couldn't understand",
+        (Key::ActivityHeading, _) => "History",
+        (Key::ActivityNone, _) => "Nothing has been recorded for this song yet.",
+        (Key::ActivityDownloaded, _) => "Downloaded",
+        (Key::ActivityCropped, _) => "Cropped",
+        (Key::ActivityMetadataEdited, _) => "Metadata edited",
+        (Key::ActivityPostProcessed, _) => "Post-processed",
+        (Key::ActivityRestored, _) => "Restored original",
+        (Key::ActivityHidden, _) => "Hidden",
+        (Key::ActivityUnhidden, _) => "Unhidden",
+        (Key::ActivityImported, _) => "Imported",
+        (Key::ActivityRedownloaded, _) => "Downloaded again",
+        (Key::ActivityApproved, _) => "Approved from staging",
+        (Key::ActivityPostDownloadCommandRan, _) => "Post-download command run",
+        (Key::ToastPostDownloadCommandFailed, _) => "Downloaded '{0}', but the post-download command failed",
+        (Key::LoadingLibrary, _) => "Loading library…",
+
+        (Key::LanguageLabel, _) => "Language:",
+        (Key::CropTemplateNameTakenTitle, _) => "Name already used",
+        (Key::CropTemplateNameTakenBody, _) => "There is already a crop template named '{0}'.",
+        (Key::DeleteCropTemplateTitle, _) => "Delete crop template?",
+        (Key::DeleteCropTemplateBody, _) => "Are you sure you would like to delete the crop template '{0}'? Songs which have already been cropped with it will not be affected.",
+        (Key::CropTemplatesHeading, _) => "Crop templates",
+        (Key::CropTemplatesEmpty, _) => "Save a crop as a template from the crop view to reuse it on other songs.",
+        (Key::Rename, _) => "Rename",
+        (Key::CropTemplateRange, _) => "From {0} to {1}",
+        (Key::CropTemplateRangeEnd, _) => "end",
+        (Key::CropNudgeLabel, _) => "Arrow keys move the playhead by (ms):",
+        (Key::SongListHeading, _) => "Song list",
+        (Key::DoubleClickLabel, _) => "When a song is double-clicked:",
+        (Key::MiddleClickLabel, _) => "When a song is middle-clicked:",
+        (Key::SplitLayoutLabel, _) => "Show song details beside the list:",
+        (Key::ReviewRulesLabel, _) => "The \"Needs attention\" filter lists songs with any of these problems:",
+        (Key::DownloadsHeading, _) => "Downloads",
+        (Key::AudioFormatLabel, _) => "Audio format:",
+        (Key::OpusNoAlbumArtHint, _) => "Album art can't be stored in Opus files, so songs in this format won't have any.",
+        (Key::AudioQualityLabel, _) => "Audio quality:",
+        (Key::LosslessQualityHint, _) => "FLAC is lossless, so the audio quality setting doesn't apply to it.",
+        (Key::AlbumArtSizeLabel, _) => "Album art size:",
+        (Key::SplitChaptersSetting, _) => "Split videos with chapters, like full albums, into one song per chapter",
+        (Key::EmbedLyricsSetting, _) => "Embed videos' English subtitles as lyrics, if they have any",
+        (Key::MissingFfmpegLabel, _) => "If ffmpeg isn't installed:",
+        (Key::DownloadUnconvertedHint, _) => "Without ffmpeg, songs are saved as MP3 or M4A, whichever YouTube has, and post-processing steps are skipped. Only MP3 songs can be tagged without ffmpeg, so M4A songs won't appear in the library until it's installed.",
+        (Key::MaxConcurrentDownloadsLabel, _) => "Downloads at once:",
+        (Key::MetadataFailureLabel, _) => "If a video's info can't be found:",
+        (Key::ParseArtistSetting, _) => "Take the artist from video titles like \"Artist - Song\", and remove \"(Official Video)\" and similar",
+        (Key::DownloaderProgramLabel, _) => "Downloader program:",
+        (Key::Apply, _) => "Apply",
+        (Key::DownloaderBinaryHint, _) => "A name on your PATH, or a full path. If it can't be run, yt-dlp and then youtube-dl are used instead.",
+        (Key::ChooseDownloaderButton, _) => "Browse...",
+        (Key::RateLimitLabel, _) => "Limit each download to:",
+        (Key::RateLimitPlaceholder, _) => "No limit",
+        (Key::PerSecond, _) => "per second",
+        (Key::RateLimitHint, _) => "Bytes per second, like 500K or 2M. Leave empty for no limit. Applies to downloads started afterwards.",
+        (Key::RateLimitInvalid, _) => "Not a valid limit - use a number, optionally followed by K, M or G, like 500K or 2M.",
+        (Key::ExtraArgsHint, _) => "Passed to youtube-dl for every download, like --force-ipv4. Quote arguments with spaces in them.",
+        (Key::PipelineLabel, _) => "After downloading a song, run these steps, in this order:",
+        (Key::PipelineHint, _) => "Any step can also be run on a song from its \"More\" menu. Restoring the original undoes them.",
+        (Key::LoudnessTargetLabel, _) => "Normalize loudness to (LUFS):",
+        (Key::Earlier, _) => "Earlier",
+        (Key::Later, _) => "Later",
+        (Key::StorageHeading, _) => "Storage",
+        (Key::DeduplicateOriginalsSetting, _) => "Store originals by checksum, so identical originals are only kept once",
+        (Key::DeduplicateOriginalsHint, _) => "Existing originals are moved over the next time each song is modified.",
+        (Key::ScanRecursivelySetting, _) => "Look for songs in subfolders of the library",
+        (Key::LibraryCheckLabel, _) => "Check for changes made outside CrossPlay:",
+        (Key::RowActionPlayInCrossPlay, _) => "Play in CrossPlay",
+        (Key::RowActionEditMetadata, _) => "Edit metadata",
+        (Key::RowActionOpenOnYouTube, _) => "Open the video's page",
+        (Key::RowActionNothing, _) => "Do nothing",
+        (Key::SplitLayoutNever, _) => "Never",
+        (Key::SplitLayoutWideWindows, _) => "When the window is wide",
+        (Key::SplitLayoutAlways, _) => "Always",
+        (Key::MetadataFailureKeepFallback, _) => "Keep the video ID as the title",
+        (Key::MetadataFailureOpenEditor, _) => "Open the metadata editor",
+        (Key::MetadataFailureFlag, _) => "Flag as needing metadata",
+        (Key::MissingFfmpegRefuse, _) => "Don't download",
+        (Key::MissingFfmpegDownloadUnconverted, _) => "Download without converting",
+        (Key::LibraryCheckOnFocus, _) => "Only when the window is focused",
+        (Key::LibraryCheckEveryMinutes, Plural::One) => "Every minute",
+        (Key::LibraryCheckEveryMinutes, Plural::Other) => "Every {0} minutes",
+        (Key::AudioQualityBest, _) => "Best available",
+        (Key::AudioQualityKbps, _) => "{0} kbps",
+        (Key::AlbumArtSizeOriginal, _) => "Largest available",
+        (Key::AlbumArtSizeMax, _) => "At most {0}px",
+        (Key::ReviewRuleTooShortSetting, _) => "Shorter than 30 seconds",
+        (Key::ReviewRuleTooLongSetting, _) => "Longer than 20 minutes",
+        (Key::PostProcessTrimSilence, _) => "Trim silence",
+        (Key::PostProcessSquareAlbumArt, _) => "Crop album art to a square",
+        (Key::PostProcessShrinkAlbumArt, _) => "Shrink large album art",
+        (Key::PostProcessNormalizeLoudness, _) => "Normalize loudness",
+        (Key::ResetBandwidthTitle, _) => "Reset bandwidth usage?",
+        (Key::ResetBandwidthBody, _) => "This month's total of downloaded data will go back to zero.",
+        (Key::ResetBandwidthFailedTitle, _) => "Couldn't reset bandwidth usage",
+        (Key::ResetBandwidthFailedBody, _) => "The saved usage couldn't be cleared: {0}",
+        (Key::StatisticsHeading, _) => "Statistics",
+        (Key::BandwidthThisMonth, _) => "Downloaded this month ({0}): {1}",
+        (Key::Reset, _) => "Reset",
+        (Key::DownloadStatsAllTime, _) => "All time: {0} downloaded, {1} failed, {2} of audio ({3}), {4} spent downloading",
+        (Key::ApproveStagedTitle, _) => "Approve staged songs?",
+        (Key::ApproveStagedBody, Plural::One) => "There is {0} song waiting in staging. Would you like to approve it now, moving it into the library?",
+        (Key::ApproveStagedBody, Plural::Other) => "There are {0} songs waiting in staging. Would you like to approve them all now, moving them into the library?",
+        (Key::ApproveStagedFailedTitle, _) => "Some songs could not be approved",
+        (Key::ApproveStagedFailedBody, _) => "These songs are still in staging:\n\n{0}",
+        (Key::ApproveStagedFailure, _) => "'{0}': {1}",
+        (Key::StagingSetting, _) => "Keep new downloads in staging until they're approved",
+        (Key::StagingHint, _) => "Staged songs are listed separately, and left out of exported playlists, until they're moved into the library.",
+        (Key::TestCommandTitle, _) => "Test the command?",
+        (Key::TestCommandBody, _) => "The command will be run on '{0}', the most recently downloaded song, just as if it had been downloaded again.",
+        (Key::CommandSucceededTitle, _) => "Command succeeded",
+        (Key::CommandSucceededSilent, _) => "The command finished without printing anything.",
+        (Key::CommandSucceededOutput, _) => "The command printed:\n\n{0}",
+        (Key::CommandFailedTitle, _) => "Command failed",
+        (Key::CommandFailedBody, _) => "The test run didn't succeed - {0}",
+        (Key::PostDownloadCommandSetting, _) => "After each successful download, run this command:",
+        (Key::TestRun, _) => "Test run",
+        (Key::TestRunning, _) => "Running...",
+        (Key::PostDownloadCommandHint, Plural::One) => "{path} is replaced with the downloaded song's file, and {id} with its video ID. The command runs after the steps above, only for downloads which succeed, and is stopped if it takes longer than {0} minute. What it prints is kept in the song's history.",
+        (Key::PostDownloadCommandHint, Plural::Other) => "{path} is replaced with the downloaded song's file, and {id} with its video ID. The command runs after the steps above, only for downloads which succeed, and is stopped if it takes longer than {0} minutes. What it prints is kept in the song's history.",
+        (Key::PostDownloadCommandUnclosedQuote, _) => "This can't be run - a quote in it is never closed.",
+        (Key::HookErrorUnclosedQuote, _) => "a quote in the command is never closed",
+        (Key::HookErrorEmpty, _) => "the command is empty",
+        (Key::HookErrorSpawnFailed, _) => "the command could not be started: {0}",
+        (Key::HookErrorTimedOut, Plural::One) => "the command took longer than {0} minute, so was stopped",
+        (Key::HookErrorTimedOut, Plural::Other) => "the command took longer than {0} minutes, so was stopped",
+        (Key::HookErrorExitCode, _) => "the command failed with exit code {0}",
+        (Key::HookErrorTerminated, _) => "the command was terminated",
+    }
+}
+
+fn french(key: Key, plural: Plural) -> &'static str {
+    match (key, plural) {
+        (Key::Cancel, _) => "Annuler",
+        (Key::ApplyAndSave, _) => "Appliquer et enregistrer",
+        (Key::Play, _) => "Lire",
+        (Key::Pause, _) => "Pause",
+        (Key::Edit, _) => "Modifier",
+        (Key::Crop, _) => "Rogner",
+        (Key::Stop, _) => "Arrêter",
+        (Key::Delete, _) => "Supprimer",
+        (Key::Ok, _) => "OK",
+        (Key::Title, _) => "Titre",
+        (Key::Artist, _) => "Artiste",
+        (Key::Album, _) => "Album",
+        (Key::Genre, _) => "Genre",
+        (Key::Year, _) => "Année",
+        (Key::Track, _) => "Piste",
+        (Key::ChangedOnDisk, _) => "Ce morceau a été modifié ou supprimé depuis que vous l'avez ouvert.",
+        (Key::ErrorNotFound, _) => "« {0} » n'existe plus - il a peut-être été déplacé ou supprimé en dehors de CrossPlay",
+        (Key::ErrorPermissionDenied, _) => "CrossPlay n'a pas la permission de modifier « {0} »",
+        (Key::ErrorTagParse, _) => "Les tags de « {0} » n'ont pas pu être lus",
+        (Key::ErrorFfmpegFailed, _) => "ffmpeg n'a pas pu traiter le morceau",
+        (Key::ErrorAlreadyExists, _) => "« {0} » existe déjà",
+        (Key::ErrorBusy, _) => "« {0} » est utilisé par un autre programme - fermez-le et réessayez",
+        (Key::ErrorIo, _) => "« {0} » n'a pas pu être ouvert",
+
+        (Key::CloudSyncTitle, _) => "La bibliothèque est dans un dossier synchronisé",
+        (Key::CloudSyncBody, _) => "Votre dossier de bibliothèque se trouve dans un dossier synchronisé par {0}.\n\nCrossPlay réécrit souvent les fichiers des morceaux lors du téléchargement, du rognage et de la modification. Si la même bibliothèque est synchronisée avec un autre ordinateur, ces modifications peuvent entrer en conflit, laissant des fichiers « copie en conflit » en double ou des morceaux corrompus.\n\nLe chemin de la bibliothèque est : {1}",
+        (Key::CloudSyncAsk, _) => "Voulez-vous quand même utiliser ce dossier ?",
+        (Key::CloudSyncInform, _) => "Vous pouvez choisir un autre dossier de bibliothèque depuis le menu des paramètres.",
+        (Key::CancelDownloadsTitle, _) => "Annuler les téléchargements ?",
+        (Key::CancelDownloadsBody, _) => "Des téléchargements sont en cours. Quitter maintenant les interrompra, mais il vous sera proposé de les reprendre à la prochaine ouverture de CrossPlay. Voulez-vous vraiment quitter ?",
+        (Key::PickLibraryTitle, _) => "Choisir une nouvelle bibliothèque ?",
+        (Key::PickLibraryBody, _) => "Voulez-vous choisir un nouveau dossier de bibliothèque ? Vos morceaux ne seront pas copiés vers le nouvel emplacement, mais seront conservés dans l'ancien afin que vous puissiez y revenir plus tard.\n\nLe chemin actuel de la bibliothèque est : {0}",
+        (Key::StatusSongCount, Plural::One) => "{0} morceau",
+        (Key::StatusSongCount, Plural::Other) => "{0} morceaux",
+        (Key::StatusHiddenCount, _) => "{0} masqué(s)",
+
+        (Key::SongDeletedTitle, _) => "Morceau supprimé",
+        (Key::SongDeletedBody, _) => "« {0} » a été supprimé pendant que vous le modifiiez, vos modifications ne peuvent donc pas être enregistrées.",
+        (Key::SongChangedTitle, _) => "Morceau modifié",
+        (Key::SongChangedBody, _) => "« {0} » a été modifié par autre chose pendant que vous le modifiiez.\n\nVoulez-vous recharger son état actuel ? Vos modifications ici seront perdues. Sinon, vos modifications seront abandonnées.",
+        (Key::ResumePlaybackTitle, _) => "Reprendre la lecture ?",
+        (Key::ResumePlaybackBody, _) => "Voulez-vous reprendre la lecture de « {0} » à partir de {1} ?",
+        (Key::PlaybackFailed, _) => "Impossible de lire ce morceau : {0}",
+        (Key::SeekFailed, _) => "Impossible de reprendre le morceau là où il en était : {0}",
+        (Key::ToastLibraryChanged, _) => "Bibliothèque actualisée : {0}",
+        (Key::LibraryDiffAdded, Plural::One) => "+{0} ajouté",
+        (Key::LibraryDiffAdded, Plural::Other) => "+{0} ajoutés",
+        (Key::LibraryDiffRemoved, Plural::One) => "{0} supprimé",
+        (Key::LibraryDiffRemoved, Plural::Other) => "{0} supprimés",
+        (Key::LibraryDiffChanged, Plural::One) => "{0} modifié",
+        (Key::LibraryDiffChanged, Plural::Other) => "{0} modifiés",
+        (Key::LibraryDiffAddedHeading, _) => "Ajoutés :",
+        (Key::LibraryDiffRemovedHeading, _) => "Supprimés :",
+        (Key::LibraryDiffChangedHeading, _) => "Modifiés :",
+        (Key::LibraryDiffMoved, Plural::One) => "{0} renommé",
+        (Key::LibraryDiffMoved, Plural::Other) => "{0} renommés",
+        (Key::LibraryDiffMovedHeading, _) => "Renommés :",
+        (Key::LibraryDiffSong, _) => "  {0} ({1})",
+
+        (Key::SettingsMenu, _) => "Paramètres",
+        (Key::ChangeLibrary, _) => "Changer de bibliothèque",
+        (Key::OpenLibraryFolder, _) => "Ouvrir le dossier de la bibliothèque",
+        (Key::RefreshLibrary, _) => "Actualiser la bibliothèque",
+        (Key::RestoreTagSnapshotMenu, _) => "Restaurer les tags depuis un instantané...",
+        (Key::ExportPlaylistMenu, _) => "Exporter la bibliothèque en playlist...",
+        (Key::ImportFilesMenu, _) => "Importer des fichiers MP3...",
+        (Key::Mp3Filter, _) => "Fichier MP3",
+        (Key::ToastImportedFiles, Plural::One) => "{0} morceau importé",
+        (Key::ToastImportedFiles, Plural::Other) => "{0} morceaux importés",
+        (Key::ToastImportFilesFailed, Plural::One) => "{0} fichier n'a pas pu être importé (les {1} autres l'ont été)",
+        (Key::ToastImportFilesFailed, Plural::Other) => "{0} fichiers n'ont pas pu être importés (les {1} autres l'ont été)",
+        (Key::M3uPlaylistFilter, _) => "Playlist M3U",
+        (Key::PlsPlaylistFilter, _) => "Playlist PLS",
+        (Key::ExportHiddenTitle, _) => "Inclure les morceaux masqués ?",
+        (Key::ExportHiddenBody, Plural::One) => "{0} morceau de votre bibliothèque est masqué. Voulez-vous l'inclure dans la playlist ?",
+        (Key::ExportHiddenBody, Plural::Other) => "{0} morceaux de votre bibliothèque sont masqués. Voulez-vous les inclure dans la playlist ?",
+        (Key::ToastPlaylistExported, Plural::One) => "{0} morceau exporté vers {1}",
+        (Key::ToastPlaylistExported, Plural::Other) => "{0} morceaux exportés vers {1}",
+        (Key::MoreSettings, _) => "Plus de paramètres...",
+        (Key::SortTitleAscending, _) => "Titre A→Z",
+        (Key::SortTitleDescending, _) => "Titre Z→A",
+        (Key::SortArtistAscending, _) => "Artiste A→Z",
+        (Key::SortArtistDescending, _) => "Artiste Z→A",
+        (Key::SortAlbumAscending, _) => "Album A→Z",
+        (Key::SortAlbumDescending, _) => "Album Z→A",
+        (Key::SortNewestFirst, _) => "Plus récents d'abord",
+        (Key::SortOldestFirst, _) => "Plus anciens d'abord",
+        (Key::GroupSongs, _) => "Regrouper sous des titres",
+        (Key::UngroupSongs, _) => "Ne plus regrouper",
+        (Key::GroupNoArtist, _) => "Sans artiste",
+        (Key::GroupNoAlbum, _) => "Sans album",
+        (Key::GroupSongCount, Plural::One) => "{0} morceau",
+        (Key::GroupSongCount, Plural::Other) => "{0} morceaux",
+        (Key::PasteLinkPlaceholder, _) => "Collez un lien YouTube, SoundCloud ou Bandcamp...",
+        (Key::Download, _) => "Télécharger",
+        (Key::PasteAndDownload, _) => "Coller et télécharger",
+        (Key::ImportLinkList, _) => "Importer une liste...",
+        (Key::MoreDownloadOptions, _) => "Options...",
+        (Key::ArtistAndAlbumHint, _) => "Enregistrer le prochain téléchargement avec :",
+        (Key::ExtraArgsLabel, _) => "Arguments youtube-dl supplémentaires :",
+        (Key::ExtraArgsPlaceholder, _) => "Aucun, par exemple --force-ipv4",
+        (Key::ExtraArgsUnclosedQuote, _) => "Un guillemet des arguments supplémentaires n'est jamais fermé",
+        (Key::ExtraArgsForbidden, _) => "« {0} » ne peut pas être utilisé, car CrossPlay doit choisir où les téléchargements sont enregistrés",
+        (Key::LinkListFilter, _) => "Liste de liens",
+        (Key::ToastLinkListReadFailed, _) => "Impossible de lire {0}",
+        (Key::ToastLinkListEmpty, _) => "Aucun lien à télécharger",
+        (Key::ToastLinkListStarted, Plural::One) => "Téléchargement de {0} lien",
+        (Key::ToastLinkListStarted, Plural::Other) => "Téléchargement de {0} liens",
+        (Key::LinkListSkippedSuffix, Plural::One) => ", {0} déjà téléchargé ignoré",
+        (Key::LinkListSkippedSuffix, Plural::Other) => ", {0} déjà téléchargés ignorés",
+        (Key::LinkListUnparsedSuffix, Plural::One) => ", mais {0} ligne n'était pas un lien",
+        (Key::LinkListUnparsedSuffix, Plural::Other) => ", mais {0} lignes n'étaient pas des liens",
+        (Key::LinkListUnparsedLine, _) => "Ligne {0} : {1}",
+        (Key::DownloadBatchProgress, _) => " ({0} sur {1} terminés)",
+        (Key::QueuedNotShown, Plural::One) => "...et {0} de plus en attente",
+        (Key::QueuedNotShown, Plural::Other) => "...et {0} de plus en attente",
+        (Key::SessionStatsSummary, _) => "Cette session : {0} téléchargés, {1} en échec",
+        (Key::SessionStatsDetail, _) => "{0} téléchargés, {1} d'audio, {2} passées à télécharger",
+        (Key::ResetSessionStats, _) => "Réinitialiser",
+        (Key::ClipboardNotALink, _) => "Le presse-papiers ne contient pas de lien vers une vidéo, rien n'a donc été téléchargé.",
+        (Key::NotAYouTubeLink, _) => "Cela ne ressemble pas à un lien YouTube.",
+        (Key::DownloadsInProgress, Plural::One) => "{0} téléchargement en cours...",
+        (Key::DownloadsInProgress, Plural::Other) => "{0} téléchargements en cours...",
+        (Key::DownloadLookingUp, _) => "Recherche des infos de la vidéo... (ID {0})",
+        (Key::DownloadQueued, _) => "En attente",
+        (Key::DownloadQueuedDetail, _) => "{0} ({1}, {2})",
+        (Key::QueueMoveToTop, _) => "Mettre en premier",
+        (Key::QueueMoveUp, _) => "Monter",
+        (Key::QueueMoveDown, _) => "Descendre",
+        (Key::DownloadTitleWithId, _) => "{0} (ID {1})",
+        (Key::DownloadFailedDetail, _) => "Le téléchargement {0} a échoué : {1}",
+        (Key::ToastDownloaded, _) => "« {0} » téléchargé",
+        (Key::ToastRedownloaded, _) => "« {0} » téléchargé à nouveau",
+        (Key::ToastDownloadedChapters, Plural::One) => "« {1} » téléchargé en {0} morceau",
+        (Key::ToastDownloadedChapters, Plural::Other) => "« {1} » téléchargé en {0} morceaux, un par chapitre",
+        (Key::ToastDownloadFailed, _) => "Le téléchargement de « {0} » a échoué",
+        (Key::DownloadErrorDownloaderNotFound, _) => "Ni yt-dlp ni youtube-dl n'est installé - installez l'un des deux, puis réessayez",
+        (Key::DownloaderNotFoundTitle, _) => "Aucun outil de téléchargement trouvé",
+        (Key::DownloaderUnusableTitle, _) => "Impossible de lancer l'outil de téléchargement",
+        (Key::DownloaderUnusableBody, _) => "'{0}' n'a pas pu être lancé avec --version. Choisissez l'exécutable de yt-dlp ou de youtube-dl.",
+        (Key::DownloaderPathMissingTitle, _) => "Outil de téléchargement choisi indisponible",
+        (Key::DownloaderPathMissingBody, _) => "L'outil de téléchargement choisi, '{0}', ne peut plus être lancé, donc celui du PATH sera utilisé à la place.",
+        (Key::MissingToolsTitle, _) => "Programmes manquants",
+        (Key::SettingsSaveFailed, _) => "Les paramètres ne peuvent pas être enregistrés ({0}) - les modifications seront perdues à la fermeture de CrossPlay. Pour les conserver, lancez CrossPlay avec --settings-dir vers un dossier accessible en écriture.",
+        (Key::MissingToolsBody, _) => "CrossPlay n'a pas trouvé certains programmes dont il a besoin :",
+        (Key::MissingToolDownloader, _) => "• {0} (ou yt-dlp ou youtube-dl), pour télécharger les morceaux. Installez yt-dlp depuis https://github.com/yt-dlp/yt-dlp, ou indiquez où il se trouve dans les paramètres.",
+        (Key::MissingToolFfmpeg, _) => "• ffmpeg, pour convertir les téléchargements et rogner les morceaux. Installez-le depuis https://ffmpeg.org.",
+        (Key::MissingToolsAfter, _) => "Tant qu'ils ne sont pas installés, les boutons qui en ont besoin sont désactivés. Une fois installés, appuyez sur « Vérifier à nouveau » au-dessus de la liste des morceaux, ou redémarrez CrossPlay.",
+        (Key::MissingToolsBanner, _) => "Le téléchargement est désactivé, car CrossPlay n'a pas trouvé : {0}",
+        (Key::CheckAgain, _) => "Vérifier à nouveau",
+        (Key::ToastToolsFound, _) => "Tout ce dont CrossPlay a besoin est installé",
+        (Key::LibraryLoadFailed, _) => "Impossible de charger la bibliothèque dans '{0}' : {1}",
+        (Key::LibraryRefreshFailed, _) => "Impossible d'actualiser la bibliothèque : {0} - les morceaux sont affichés tels qu'ils étaient au dernier chargement",
+        (Key::HistoryMenu, _) => "Historique des téléchargements",
+        (Key::HistoryTitle, _) => "Historique des téléchargements",
+        (Key::HistoryEmpty, _) => "Rien n'a encore été téléchargé.",
+        (Key::HistoryLoadFailed, _) => "L'historique des téléchargements n'a pas pu être chargé : {0}",
+        (Key::HistoryDownloaded, _) => "Téléchargé",
+        (Key::HistoryFailed, _) => "Échec",
+        (Key::DownloadAgain, _) => "Télécharger à nouveau",
+        (Key::Back, _) => "Retour",
+        (Key::ChooseCookiesFileMenu, _) => "Choisir un fichier de cookies...",
+        (Key::ClearCookiesFileMenu, _) => "Retirer le fichier de cookies",
+        (Key::CookiesFileFilter, _) => "Fichier de cookies",
+        (Key::ToastCookiesFileChosen, _) => "Les téléchargements se connecteront avec le fichier de cookies choisi",
+        (Key::DownloaderOutdated, _) => "Votre outil de téléchargement, '{0}', est en version {1}, qui est obsolète. YouTube change souvent, donc les téléchargements risquent d'échouer jusqu'à sa mise à jour.",
+        (Key::DownloaderUpdateHelp, _) => "Comment le mettre à jour",
+        (Key::DownloaderUpdateNow, _) => "Mettre à jour",
+        (Key::DownloaderUpdating, _) => "Mise à jour...",
+        (Key::ToastDownloaderUpdated, _) => "Outil de téléchargement mis à jour",
+        (Key::ToastDownloaderUpdateFailed, _) => "Impossible de mettre à jour l'outil de téléchargement",
+        (Key::DownloaderNotFoundBody, _) => "CrossPlay n'a pas pu lancer '{0}', yt-dlp ni youtube-dl. Installez yt-dlp, ou choisissez l'outil de téléchargement dans les paramètres, puis réessayez.",
+        (Key::DownloadErrorSpawnFailed, _) => "L'outil de téléchargement n'a pas pu être lancé - est-il installé ?",
+        (Key::DownloadErrorNonZeroExit, _) => "youtube-dl a échoué - la vidéo est peut-être indisponible, ou la connexion a été interrompue",
+        (Key::DownloadErrorNonZeroExitReason, _) => "youtube-dl a échoué : {0}",
+        (Key::DownloadErrorVideoUnavailable, _) => "La vidéo est indisponible - elle a peut-être été supprimée",
+        (Key::DownloadErrorVideoPrivate, _) => "La vidéo est privée",
+        (Key::DownloadErrorAgeRestricted, _) => "La vidéo est soumise à une limite d'âge, YouTube demande donc de se connecter pour la regarder - choisissez un fichier de cookies dans le menu des paramètres pour vous connecter",
+        (Key::DownloadErrorRegionBlocked, _) => "La vidéo n'est pas disponible dans votre pays",
+        (Key::DownloadErrorRateLimited, _) => "Le site refuse d'autres téléchargements pour le moment - attendez un peu avant de réessayer",
+        (Key::DownloadErrorDiskFull, _) => "Le disque est plein - libérez de l'espace, puis réessayez",
+        (Key::DownloadErrorConversionFailed, _) => "ffmpeg n'a pas pu convertir le téléchargement - mettre ffmpeg à jour ou choisir un autre format audio peut aider",
+        (Key::DownloadErrorNetwork, _) => "Le site est injoignable - vérifiez votre connexion internet, puis réessayez",
+        (Key::DownloadErrorAudioNotFound, _) => "Le téléchargement s'est terminé, mais aucun fichier audio n'a été produit - ffmpeg est-il installé ?",
+        (Key::DownloadErrorThumbnail, _) => "La miniature de la vidéo n'a pas pu être enregistrée",
+        (Key::DownloadErrorIo, _) => "Le téléchargement n'a pas pu être enregistré dans le dossier de la bibliothèque",
+        (Key::DownloadMayWorkOnRetry, _) => " (un nouveau téléchargement peut fonctionner)",
+        (Key::DownloadErrorPostProcess, _) => "Le morceau a été téléchargé, mais l'étape « {0} » a échoué : {1}",
+        (Key::DownloadErrorChapterSplit, _) => "Le morceau a été téléchargé, mais n'a pas pu être découpé en chapitres, il a donc été gardé entier : {0}",
+        (Key::DownloadErrorReplace, _) => "Le morceau a été téléchargé à nouveau, mais n'a pas pu remplacer celui de la bibliothèque, qui a donc été gardé tel quel : {0}",
+        (Key::DownloadPostProcessing, _) => " - {0}...",
+        (Key::DownloadSplittingChapter, _) => " - découpage du chapitre {0}/{1}...",
+        (Key::DownloadStageFetchingMetadata, _) => "Récupération des infos de la vidéo...",
+        (Key::DownloadStageDownloadingAudio, _) => "Téléchargement de l'audio...",
+        (Key::DownloadStageExtractingAudio, _) => "Extraction de l'audio...",
+        (Key::DownloadStageProcessingThumbnail, _) => "Traitement de la miniature...",
+        (Key::DownloadStageWritingTags, _) => "Écriture des tags...",
+        (Key::DownloadsStripSummary, Plural::One) => "{0} téléchargement · {1} %",
+        (Key::DownloadsStripSummary, Plural::Other) => "{0} téléchargements · {1} %",
+        (Key::SplitIntoChapters, Plural::One) => "Découper en {0} morceau, un par chapitre",
+        (Key::SplitIntoChapters, Plural::Other) => "Découper en {0} morceaux, un par chapitre",
+        (Key::SkipAutoSubtitles, _) => "Ignorer les paroles des sous-titres générés automatiquement",
+        (Key::Retry, _) => "Réessayer",
+        (Key::RetryAll, _) => "Tout réessayer",
+        (Key::DownloadSizeProgress, _) => "{0} sur ~{1}",
+        (Key::DownloadErrorPlaylistEntryUnavailable, _) => "Cette vidéo de la playlist est privée, supprimée ou indisponible, elle a donc été ignorée",
+        (Key::PlaylistLookingUp, _) => "Recherche des vidéos de la playlist {0}...",
+        (Key::ToastPlaylistFailed, _) => "Impossible de trouver la playlist {0}",
+        (Key::AlreadyDownloadingTitle, _) => "Téléchargement déjà en cours",
+        (Key::AlreadyDownloadingBody, _) => "« {0} » est déjà en cours de téléchargement.",
+        (Key::AlreadyDownloadedTitle, _) => "Déjà téléchargé",
+        (Key::AlreadyDownloadedBody, _) => "« {0} » est déjà dans votre bibliothèque. Voulez-vous le télécharger à nouveau ? Le morceau existant sera remplacé.",
+        (Key::AlreadyDownloadedEditedNote, _) => "Vous avez modifié les métadonnées de ce morceau, ces modifications seront donc perdues - mais un instantané de ses tags sera d'abord enregistré, que vous pourrez restaurer depuis le menu des paramètres.",
+        (Key::AlreadyDownloadedCroppedBody, _) => "« {0} » est déjà dans votre bibliothèque et a été rogné. Le télécharger à nouveau ferait perdre le rognage, il ne sera donc pas téléchargé. Restaurez l'original du morceau ou supprimez-le d'abord si vous souhaitez le télécharger à nouveau.",
+        (Key::PreviewLoading, _) => "Recherche de la vidéo...",
+        (Key::PreviewFailed, _) => "Impossible de récupérer les informations de ce lien : {0}",
+        (Key::PreviewLongVideo, _) => "Cette vidéo dure plus d'une heure : son téléchargement prendra du temps et beaucoup d'espace.",
+        (Key::PreviewEstimatedSize, _) => "environ {0}",
+        (Key::DownloadErrorInfoUnreadable, _) => "Les infos de youtube-dl sur la vidéo n'ont pas pu être comprises - il doit peut-être être mis à jour",
+        (Key::DownloadErrorFfmpegNotFound, _) => "youtube-dl a besoin de ffmpeg pour convertir le téléchargement, mais ne l'a pas trouvé - installez ffmpeg, puis réessayez",
+        (Key::DownloadErrorCookiesFileMissing, _) => "Le fichier de cookies choisi dans le menu des paramètres n'existe plus - choisissez-le à nouveau, ou retirez-le",
+        (Key::FfmpegMissingTitle, _) => "ffmpeg introuvable",
+        (Key::FfmpegMissingBody, _) => "youtube-dl a besoin de ffmpeg pour convertir les téléchargements, mais il n'est pas installé. Installez ffmpeg, puis réessayez.\n\nSi vous préférez télécharger les morceaux sans les convertir, vous pouvez le choisir dans les paramètres.",
+        (Key::ResumeDownloadsTitle, _) => "Reprendre les téléchargements ?",
+        (Key::ResumeDownloadsBody, Plural::One) => "{0} téléchargement était encore en cours à la dernière fermeture de CrossPlay. Le reprendre là où il s'était arrêté ?\n\n{1}",
+        (Key::ResumeDownloadsBody, Plural::Other) => "{0} téléchargements étaient encore en cours à la dernière fermeture de CrossPlay. Les reprendre là où ils s'étaient arrêtés ?\n\n{1}",
+        (Key::PartialFilesTitle, _) => "Supprimer les téléchargements inachevés ?",
+        (Key::PartialFilesBody, Plural::One) => "{0} fichier d'un téléchargement jamais terminé est resté dans {1}. Le supprimer ?",
+        (Key::PartialFilesBody, Plural::Other) => "{0} fichiers de téléchargements jamais terminés sont restés dans {1}. Les supprimer ?",
+        (Key::ToastDeletedPartialFiles, Plural::One) => "{0} fichier de téléchargement inachevé supprimé",
+        (Key::ToastDeletedPartialFiles, Plural::Other) => "{0} fichiers de téléchargement inachevés supprimés",
+
+        (Key::FilterPinned, _) => "Épinglés",
+        (Key::FilterNeedsMetadata, _) => "Métadonnées manquantes",
+        (Key::FilterNeedsAttention, _) => "À vérifier",
+        (Key::FilterUploader, _) => "Chaîne : {0}",
+        (Key::FilterHidden, _) => "Masqués",
+        (Key::FilterByUploader, _) => "Chaîne...",
+        (Key::SongFromUploader, _) => "De {0}",
+        (Key::SongFromVideoTitle, _) => "Titre de la vidéo : {0}",
+        (Key::ReviewChecking, _) => "Vérification des chansons...",
+        (Key::ReviewNothingFound, _) => "Rien à vérifier.",
+        (Key::ReviewMarkFine, _) => "Marquer comme correcte",
+        (Key::ReviewRuleTitleIsVideoId, _) => "Le titre est l'identifiant de la vidéo",
+        (Key::ReviewRuleMissingAlbumArt, _) => "Pas de pochette",
+        (Key::ReviewRuleUnknownArtist, _) => "Artiste inconnu",
+        (Key::ReviewRuleTooShort, _) => "Très courte",
+        (Key::ReviewRuleTooLong, _) => "Très longue",
+        (Key::PinnedBadge, _) => "ÉPINGLÉ",
+        (Key::NeedsMetadataBadge, _) => "MÉTADONNÉES MANQUANTES",
+        (Key::MenuMore, _) => "Plus",
+        (Key::MenuPin, _) => "Épingler en haut",
+        (Key::MenuUnpin, _) => "Désépingler",
+        (Key::MenuCropWith, _) => "Rogner avec « {0} »",
+        (Key::MenuCopyYouTubeId, _) => "Copier l'identifiant YouTube",
+        (Key::MenuCopyYouTubeUrl, _) => "Copier l'URL de la vidéo",
+        (Key::MenuCopyFilePath, _) => "Copier le chemin du fichier",
+        (Key::MenuRedownload, _) => "Télécharger à nouveau",
+        (Key::MenuSelectAlbum, _) => "Sélectionner tout « {0} »",
+        (Key::MenuSelectAlbumUnavailable, _) => "Sélectionner l'album (ce morceau n'a pas d'album)",
+        (Key::ToastCopied, _) => "{0} copié",
+        (Key::ConflictedCopiesBanner, Plural::One) => "{0} copie en conflit d'un fichier de la bibliothèque, laissée par un service de synchronisation, se trouve dans le dossier de la bibliothèque.",
+        (Key::ConflictedCopiesBanner, Plural::Other) => "{0} copies en conflit de fichiers de la bibliothèque, laissées par un service de synchronisation, se trouvent dans le dossier de la bibliothèque.",
+        (Key::OpenFolder, _) => "Ouvrir le dossier",
+        (Key::DeleteConflictedTitle, _) => "Supprimer les copies en conflit ?",
+        (Key::DeleteConflictedBody, _) => "Les fichiers suivants seront définitivement supprimés. Si vous avez fait sur un autre ordinateur des modifications qui n'existent que dans ces copies, elles seront perdues.\n\n{0}",
+        (Key::DeleteConflictedFailedTitle, _) => "Certaines copies en conflit n'ont pas pu être supprimées",
+        (Key::DeleteConflictedFailedBody, _) => "Ces fichiers sont toujours dans le dossier de la bibliothèque :\n\n{0}",
+        (Key::DeleteConflictedFailure, _) => "{0} : {1}",
+        (Key::TagSnapshotFilter, _) => "Instantané de tags",
+        (Key::SnapshotUnreadableTitle, _) => "Impossible de lire l'instantané",
+        (Key::SnapshotUnreadableBody, _) => "L'instantané n'a pas pu être lu : {0}",
+        (Key::SnapshotFailedTitle, _) => "Impossible de prendre un instantané",
+        (Key::SnapshotFailedBody, _) => "Un instantané des tags de ces chansons n'a pas pu être enregistré, donc cette modification ne pourra pas être annulée avec « Restaurer les tags depuis un instantané » :\n\n{0}\n\nVoulez-vous continuer quand même ?",
+        (Key::OpenFailedTitle, _) => "Impossible d'ouvrir",
+        (Key::OpenFailedBody, _) => "{0} n'a pas pu être ouvert : {1}\n\nIl a été copié dans le presse-papiers, pour que vous puissiez l'ouvrir vous-même.",
+        (Key::ToastSnapshotFailed, _) => "Impossible de prendre un instantané des tags des chansons",
+        (Key::NothingToRestoreTitle, _) => "Rien à restaurer",
+        (Key::NothingToRestoreBody, _) => "Tous les morceaux de cet instantané correspondent déjà à leurs tags dans la bibliothèque, ou n'y sont plus.",
+        (Key::RestoreTagsTitle, _) => "Restaurer les tags ?",
+        (Key::RestoreTagsBody, Plural::One) => "Les tags de {0} morceau seront restaurés depuis cet instantané :\n\n{1}",
+        (Key::RestoreTagsBody, Plural::Other) => "Les tags de {0} morceaux seront restaurés depuis cet instantané :\n\n{1}",
+        (Key::TagChange, _) => "« {0} » par {1} ({2})  =>  « {3} » par {4} ({5})",
+        (Key::AndMore, _) => "...et {0} de plus",
+        (Key::RestoreTagsFailedTitle, _) => "Certains tags n'ont pas pu être restaurés",
+        (Key::RestoreTagsFailedBody, _) => "Les morceaux suivants n'ont pas pu être restaurés :\n\n{0}",
+        (Key::ToastRestoredTags, Plural::One) => "Tags de {0} morceau restaurés",
+        (Key::ToastRestoredTags, Plural::Other) => "Tags de {0} morceaux restaurés",
+        (Key::RestoreOriginalTitle, _) => "Restaurer l'original ?",
+        (Key::RestoreOriginalBody, _) => "Voulez-vous vraiment restaurer « {0} » ?",
+        (Key::RestoreOriginalMissing, _) => "La copie originale de « {0} » est introuvable, elle ne peut donc pas être restaurée.",
+        (Key::RestoreDurations, _) => "Cette copie dure {0}, et l'original {1}.",
+        (Key::RestoreDurationRegained, _) => "La restauration rétablit les {0} retirées par le rognage.",
+        (Key::RestoreOriginalDuration, _) => "L'original dure {0}.",
+        (Key::RestoreOriginalUnreadable, _) => "La durée de l'original n'a pas pu être lue : {0}",
+        (Key::RestoreRevertsMetadata, _) => "Les modifications des métadonnées seront également annulées.",
+        (Key::RestoreKeepsMetadata, _) => "Les métadonnées n'ont pas été modifiées, elles ne changeront donc pas.",
+        (Key::RestoreRevertsPostProcessing, _) => "Le post-traitement sera également annulé.",
+        (Key::ToastRestoredOriginal, _) => "Original de « {0} » restauré",
+        (Key::StagingTitle, _) => "En attente ({0})",
+        (Key::Approve, _) => "Approuver",
+        (Key::ApproveAll, _) => "Tout approuver",
+        (Key::ToastApproved, _) => "« {0} » déplacé dans la bibliothèque",
+        (Key::ToastApprovedAll, Plural::One) => "{0} morceau déplacé dans la bibliothèque",
+        (Key::ToastApprovedAll, Plural::Other) => "{0} morceaux déplacés dans la bibliothèque",
+        (Key::ToastApproveFailed, Plural::One) => "{0} morceau n'a pas pu être déplacé dans la bibliothèque (les {1} autres l'ont été)",
+        (Key::ToastApproveFailed, Plural::Other) => "{0} morceaux n'ont pas pu être déplacés dans la bibliothèque (les {1} autres l'ont été)",
+        (Key::RedownloadTitle, _) => "Télécharger à nouveau ?",
+        (Key::RedownloadModifiedBody, _) => "« {0} » a été rogné ou post-traité. Le télécharger à nouveau conserve ses métadonnées, mais le nouveau téléchargement n'aura pas ces modifications. Voulez-vous continuer ?",
+        (Key::DeleteSongTitle, _) => "Supprimer le morceau ?",
+        (Key::DeleteSongBody, _) => "Cela supprimera définitivement le morceau et toutes ses modifications. Voulez-vous vraiment supprimer « {0} » ?",
+        (Key::ToastDeleted, _) => "« {0} » supprimé",
+        (Key::ToastUndone, _) => "Annulé : {0}",
+        (Key::Undo, _) => "Annuler",
+        (Key::UnhideSongTitle, _) => "Afficher le morceau ?",
+        (Key::UnhideSongBody, _) => "Le morceau « {0} » réapparaîtra dans les lecteurs multimédias.",
+        (Key::ToastUnhid, _) => "« {0} » affiché",
+        (Key::HideSongTitle, _) => "Masquer le morceau ?",
+        (Key::HideSongBody, _) => "Le morceau « {0} » restera téléchargé et visible dans CrossPlay, mais n'apparaîtra plus dans les lecteurs multimédias.",
+        (Key::ToastHid, _) => "« {0} » masqué",
+        (Key::ToastCroppedWithTemplate, _) => "« {0} » rogné avec le modèle « {1} »",
+        (Key::ToastPostProcessed, _) => "{1} : terminé pour « {0} »",
+        (Key::TemplateDoesntFitTitle, _) => "Le modèle de rognage ne convient pas",
+        (Key::TemplateDoesntFitBody, _) => "Le modèle de rognage « {0} » ne peut pas être appliqué à « {1} », car le morceau est trop court.",
+        (Key::TemplateMissingTitle, _) => "Modèle de rognage introuvable",
+        (Key::TemplateMissingBody, _) => "Le modèle de rognage « {0} » n'existe plus.",
+        (Key::ToastInlineEditConflict, _) => "« {0} » a été modifié en dehors de CrossPlay, votre modification n'a donc pas été enregistrée",
+        (Key::SelectionCount, Plural::One) => "{0} morceau sélectionné",
+        (Key::SelectionCount, Plural::Other) => "{0} morceaux sélectionnés",
+        (Key::EditSelected, _) => "Modifier",
+        (Key::Hide, _) => "Masquer",
+        (Key::ClearSelection, _) => "Désélectionner",
+        (Key::HideSelectedTitle, _) => "Masquer les morceaux ?",
+        (Key::HideSelectedBody, Plural::One) => "{0} morceau restera téléchargé et visible dans CrossPlay, mais n'apparaîtra plus dans les lecteurs multimédias.",
+        (Key::HideSelectedBody, Plural::Other) => "{0} morceaux resteront téléchargés et visibles dans CrossPlay, mais n'apparaîtront plus dans les lecteurs multimédias.",
+        (Key::ToastHidSelected, Plural::One) => "{0} morceau masqué",
+        (Key::ToastHidSelected, Plural::Other) => "{0} morceaux masqués",
+        (Key::DeleteSelectedTitle, _) => "Supprimer les morceaux ?",
+        (Key::DeleteSelectedBody, Plural::One) => "Cela supprimera définitivement {0} morceau et toutes ses modifications. Continuer ?",
+        (Key::DeleteSelectedBody, Plural::Other) => "Cela supprimera définitivement {0} morceaux et toutes leurs modifications. Continuer ?",
+        (Key::ToastDeletedSelected, Plural::One) => "{0} morceau supprimé",
+        (Key::ToastDeletedSelected, Plural::Other) => "{0} morceaux supprimés",
+        (Key::ToastDeleteSelectedFailed, Plural::One) => "{0} morceau n'a pas pu être supprimé (les {1} autres l'ont été)",
+        (Key::ToastDeleteSelectedFailed, Plural::Other) => "{0} morceaux n'ont pas pu être supprimés (les {1} autres l'ont été)",
+        (Key::ShownCount, Plural::One) => "{0} morceau affiché",
+        (Key::ShownCount, Plural::Other) => "{0} morceaux affichés",
+        (Key::HideAllShown, _) => "Masquer tous les morceaux affichés",
+        (Key::UnhideAllShown, _) => "Ne plus masquer les morceaux affichés",
+        (Key::HidingShownProgress, _) => "Masquage des morceaux... ({0} sur {1})",
+        (Key::UnhidingShownProgress, _) => "Démasquage des morceaux... ({0} sur {1})",
+        (Key::UnhideShownTitle, _) => "Ne plus masquer les morceaux ?",
+        (Key::UnhideShownBody, Plural::One) => "{0} morceau apparaîtra de nouveau dans les lecteurs multimédias.",
+        (Key::UnhideShownBody, Plural::Other) => "{0} morceaux apparaîtront de nouveau dans les lecteurs multimédias.",
+        (Key::ToastUnhidShown, Plural::One) => "{0} morceau n'est plus masqué",
+        (Key::ToastUnhidShown, Plural::Other) => "{0} morceaux ne sont plus masqués",
+        (Key::ToastVisibilityChangeFailed, Plural::One) => "{0} morceau n'a pas pu être modifié (les {1} autres l'ont été)",
+        (Key::ToastVisibilityChangeFailed, Plural::Other) => "{0} morceaux n'ont pas pu être modifiés (les {1} autres l'ont été)",
+
+        (Key::CropTitle, _) => "Rogner : {0}",
+        (Key::MediaPlayer, _) => "Lecteur",
+        (Key::StartPoint, _) => "Point de début",
+        (Key::EndPoint, _) => "Point de fin",
+        (Key::FadeInSeconds, _) => "Fondu en entrée (secondes) :",
+        (Key::FadeOutSeconds, _) => "Fondu en sortie (secondes) :",
+        (Key::KeepSegment, _) => "Garder ce segment et en ajouter un autre",
+        (Key::KeptSegmentsHeading, _) => "Segments à garder, joints dans l'ordre :",
+        (Key::KeptSegment, _) => "{0} à {1}",
+        (Key::RemoveSegment, _) => "Retirer",
+        (Key::CropShortcutsHint, _) => "Espace lance ou met en pause, les flèches déplacent la tête de lecture (maintenez Maj pour aller plus loin), et [ et ] placent le début et la fin.",
+        (Key::NotSet, _) => "Non défini",
+        (Key::Set, _) => "Définir",
+        (Key::Jump, _) => "Aller",
+        (Key::TemplateNamePlaceholder, _) => "Nom du modèle...",
+        (Key::SaveAsTemplate, _) => "Enregistrer comme modèle",
+        (Key::ReplaceTemplateTitle, _) => "Remplacer le modèle de rognage ?",
+        (Key::ReplaceTemplateBody, _) => "Un modèle de rognage nommé « {0} » existe déjà. Voulez-vous le remplacer ?",
+        (Key::ToastSavedTemplate, _) => "Modèle de rognage « {0} » enregistré",
+        (Key::ToastCropped, _) => "« {0} » rogné",
+
+        (Key::EditMetadataTitle, _) => "Modifier les métadonnées",
+        (Key::ToastEdited, _) => "« {0} » modifié",
+        (Key::BatchEditTitle, Plural::One) => "Modifier {0} morceau",
+        (Key::BatchEditTitle, Plural::Other) => "Modifier {0} morceaux",
+        (Key::BatchEditMoreSongs, Plural::One) => "...et {0} autre",
+        (Key::BatchEditMoreSongs, Plural::Other) => "...et {0} autres",
+        (Key::BatchEditHint, _) => "Les champs laissés vides ne seront pas modifiés.",
+        (Key::ToastBatchEdited, Plural::One) => "{0} morceau modifié",
+        (Key::ToastBatchEdited, Plural::Other) => "{0} morceaux modifiés",
+        (Key::ToastBatchEditFailed, Plural::One) => "{0} morceau n'a pas pu être modifié (les {1} autres l'ont été)",
+        (Key::ToastBatchEditFailed, Plural::Other) => "{0} morceaux n'ont pas pu être modifiés (les {1} autres l'ont été)",
+        (Key::SquareAlbumArt, _) => "Rogner en carré",
+        (Key::BrokenAlbumArt, _) => "La pochette de ce morceau est endommagée et ne peut pas être affichée.",
+        (Key::AlbumArtUnreadable, _) => "La pochette de ce morceau n'a pas pu être lue : {0}",
+        (Key::RemoveBrokenAlbumArt, _) => "Supprimer la pochette endommagée",
+        (Key::ChangeCover, _) => "Changer la pochette...",
+        (Key::LyricsHeading, _) => "Paroles",
+        (Key::LyricsHint, _) => "Appuyez sur Entrée pour commencer une nouvelle ligne.",
+        (Key::ClearLyrics, _) => "Effacer les paroles",
+        (Key::ImageFilter, _) => "Image",
+        (Key::ToastCoverUnreadable, _) => "Impossible de lire '{0}' comme une image",
+        (Key::ToastRemovedBrokenAlbumArt, _) => "Pochette endommagée supprimée de « {0} »",
+        (Key::MusicBrainzLookUp, _) => "Rechercher sur MusicBrainz",
+        (Key::MusicBrainzSearching, _) => "Recherche sur MusicBrainz...",
+        (Key::MusicBrainzNoMatches, _) => "MusicBrainz ne contient rien qui corresponde à ce titre et à cet artiste.",
+        (Key::MusicBrainzMatch, _) => "« {0} » par {1}",
+        (Key::MusicBrainzUse, _) => "Utiliser",
+        (Key::MusicBrainzErrorNetwork, _) => "MusicBrainz n'a pas pu être contacté - vérifiez votre connexion internet, puis réessayez",
+        (Key::MusicBrainzErrorRateLimited, _) => "MusicBrainz est occupé - attendez quelques secondes, puis réessayez",
+        (Key::MusicBrainzErrorBadResponse, _) => "MusicBrainz a envoyé une réponse que CrossPlay n'a pas pu comprendre",
+        (Key::ActivityHeading, _) => "Historique",
+        (Key::ActivityNone, _) => "Rien n'a encore été enregistré pour ce morceau.",
+        (Key::ActivityDownloaded, _) => "Téléchargé",
+        (Key::ActivityCropped, _) => "Rogné",
+        (Key::ActivityMetadataEdited, _) => "Métadonnées modifiées",
+        (Key::ActivityPostProcessed, _) => "Post-traité",
+        (Key::ActivityRestored, _) => "Original restauré",
+        (Key::ActivityHidden, _) => "Masqué",
+        (Key::ActivityUnhidden, _) => "Affiché à nouveau",
+        (Key::ActivityImported, _) => "Importé",
+        (Key::ActivityRedownloaded, _) => "Téléchargé à nouveau",
+        (Key::ActivityApproved, _) => "Approuvé depuis l'attente",
+        (Key::ActivityPostDownloadCommandRan, _) => "Commande après téléchargement exécutée",
+        (Key::ToastPostDownloadCommandFailed, _) => "« {0} » a été téléchargé, mais la commande après téléchargement a échoué",
+        (Key::LoadingLibrary, _) => "Chargement de la bibliothèque…",
+
+        (Key::LanguageLabel, _) => "Langue :",
+        (Key::CropTemplateNameTakenTitle, _) => "Nom déjà utilisé",
+        (Key::CropTemplateNameTakenBody, _) => "Il existe déjà un modèle de rognage nommé « {0} ».",
+        (Key::DeleteCropTemplateTitle, _) => "Supprimer le modèle de rognage ?",
+        (Key::DeleteCropTemplateBody, _) => "Voulez-vous vraiment supprimer le modèle de rognage « {0} » ? Les morceaux déjà rognés avec lui ne seront pas modifiés.",
+        (Key::CropTemplatesHeading, _) => "Modèles de rognage",
+        (Key::CropTemplatesEmpty, _) => "Enregistrez un rognage comme modèle depuis la vue de rognage pour le réutiliser sur d'autres morceaux.",
+        (Key::Rename, _) => "Renommer",
+        (Key::CropTemplateRange, _) => "De {0} à {1}",
+        (Key::CropTemplateRangeEnd, _) => "la fin",
+        (Key::CropNudgeLabel, _) => "Les flèches déplacent la tête de lecture de (ms) :",
+        (Key::SongListHeading, _) => "Liste des morceaux",
+        (Key::DoubleClickLabel, _) => "Double-clic sur un morceau :",
+        (Key::MiddleClickLabel, _) => "Clic du milieu sur un morceau :",
+        (Key::SplitLayoutLabel, _) => "Afficher les détails du morceau à côté de la liste :",
+        (Key::ReviewRulesLabel, _) => "Le filtre « À vérifier » liste les morceaux ayant l'un de ces problèmes :",
+        (Key::DownloadsHeading, _) => "Téléchargements",
+        (Key::AudioFormatLabel, _) => "Format audio :",
+        (Key::OpusNoAlbumArtHint, _) => "Les pochettes ne peuvent pas être enregistrées dans les fichiers Opus, donc les morceaux dans ce format n'en auront pas.",
+        (Key::AudioQualityLabel, _) => "Qualité audio :",
+        (Key::LosslessQualityHint, _) => "Le FLAC est sans perte, donc le réglage de qualité audio ne s'y applique pas.",
+        (Key::AlbumArtSizeLabel, _) => "Taille des pochettes :",
+        (Key::SplitChaptersSetting, _) => "Découper les vidéos avec des chapitres, comme les albums complets, en un morceau par chapitre",
+        (Key::EmbedLyricsSetting, _) => "Intégrer les sous-titres anglais des vidéos comme paroles, s'il y en a",
+        (Key::MissingFfmpegLabel, _) => "Si ffmpeg n'est pas installé :",
+        (Key::DownloadUnconvertedHint, _) => "Sans ffmpeg, les morceaux sont enregistrés en MP3 ou en M4A, selon ce que propose YouTube, et les étapes de post-traitement sont ignorées. Seuls les morceaux MP3 peuvent être tagués sans ffmpeg, donc les morceaux M4A n'apparaîtront pas dans la bibliothèque tant qu'il n'est pas installé.",
+        (Key::MaxConcurrentDownloadsLabel, _) => "Téléchargements simultanés :",
+        (Key::MetadataFailureLabel, _) => "Si les infos d'une vidéo sont introuvables :",
+        (Key::ParseArtistSetting, _) => "Prendre l'artiste dans les titres de vidéos comme « Artiste - Morceau », et retirer « (Official Video) » et les mentions semblables",
+        (Key::DownloaderProgramLabel, _) => "Outil de téléchargement :",
+        (Key::Apply, _) => "Appliquer",
+        (Key::DownloaderBinaryHint, _) => "Un nom présent dans votre PATH, ou un chemin complet. S'il ne peut pas être lancé, yt-dlp puis youtube-dl sont utilisés à la place.",
+        (Key::ChooseDownloaderButton, _) => "Parcourir...",
+        (Key::RateLimitLabel, _) => "Limiter chaque téléchargement à :",
+        (Key::RateLimitPlaceholder, _) => "Aucune limite",
+        (Key::PerSecond, _) => "par seconde",
+        (Key::RateLimitHint, _) => "Octets par seconde, par exemple 500K ou 2M. Laissez vide pour ne pas limiter. S'applique aux téléchargements lancés ensuite.",
+        (Key::RateLimitInvalid, _) => "Limite invalide - utilisez un nombre, éventuellement suivi de K, M ou G, par exemple 500K ou 2M.",
+        (Key::ExtraArgsHint, _) => "Transmis à youtube-dl pour chaque téléchargement, par exemple --force-ipv4. Mettez entre guillemets les arguments qui contiennent des espaces.",
+        (Key::PipelineLabel, _) => "Après le téléchargement d'un morceau, effectuer ces étapes, dans cet ordre :",
+        (Key::PipelineHint, _) => "Chaque étape peut aussi être lancée sur un morceau depuis son menu « Plus ». Restaurer l'original les annule.",
+        (Key::LoudnessTargetLabel, _) => "Normaliser le volume à (LUFS) :",
+        (Key::Earlier, _) => "Plus tôt",
+        (Key::Later, _) => "Plus tard",
+        (Key::StorageHeading, _) => "Stockage",
+        (Key::DeduplicateOriginalsSetting, _) => "Stocker les originaux par somme de contrôle, pour que les originaux identiques ne soient gardés qu'une fois",
+        (Key::DeduplicateOriginalsHint, _) => "Les originaux existants sont déplacés la prochaine fois que chaque morceau est modifié.",
+        (Key::ScanRecursivelySetting, _) => "Chercher des morceaux dans les sous-dossiers de la bibliothèque",
+        (Key::LibraryCheckLabel, _) => "Vérifier les modifications faites en dehors de CrossPlay :",
+        (Key::RowActionPlayInCrossPlay, _) => "Lire dans CrossPlay",
+        (Key::RowActionEditMetadata, _) => "Modifier les métadonnées",
+        (Key::RowActionOpenOnYouTube, _) => "Ouvrir la page de la vidéo",
+        (Key::RowActionNothing, _) => "Ne rien faire",
+        (Key::SplitLayoutNever, _) => "Jamais",
+        (Key::SplitLayoutWideWindows, _) => "Quand la fenêtre est large",
+        (Key::SplitLayoutAlways, _) => "Toujours",
+        (Key::MetadataFailureKeepFallback, _) => "Garder l'identifiant de la vidéo comme titre",
+        (Key::MetadataFailureOpenEditor, _) => "Ouvrir l'éditeur de métadonnées",
+        (Key::MetadataFailureFlag, _) => "Signaler comme ayant besoin de métadonnées",
+        (Key::MissingFfmpegRefuse, _) => "Ne pas télécharger",
+        (Key::MissingFfmpegDownloadUnconverted, _) => "Télécharger sans convertir",
+        (Key::LibraryCheckOnFocus, _) => "Seulement quand la fenêtre est active",
+        (Key::LibraryCheckEveryMinutes, Plural::One) => "Chaque minute",
+        (Key::LibraryCheckEveryMinutes, Plural::Other) => "Toutes les {0} minutes",
+        (Key::AudioQualityBest, _) => "La meilleure disponible",
+        (Key::AudioQualityKbps, _) => "{0} kbit/s",
+        (Key::AlbumArtSizeOriginal, _) => "La plus grande disponible",
+        (Key::AlbumArtSizeMax, _) => "{0} px au maximum",
+        (Key::ReviewRuleTooShortSetting, _) => "Moins de 30 secondes",
+        (Key::ReviewRuleTooLongSetting, _) => "Plus de 20 minutes",
+        (Key::PostProcessTrimSilence, _) => "Couper les silences",
+        (Key::PostProcessSquareAlbumArt, _) => "Rogner la pochette en carré",
+        (Key::PostProcessShrinkAlbumArt, _) => "Réduire les grandes pochettes",
+        (Key::PostProcessNormalizeLoudness, _) => "Normaliser le volume",
+        (Key::ResetBandwidthTitle, _) => "Réinitialiser la consommation de données ?",
+        (Key::ResetBandwidthBody, _) => "Le total des données téléchargées ce mois-ci sera remis à zéro.",
+        (Key::ResetBandwidthFailedTitle, _) => "Impossible de réinitialiser la consommation de données",
+        (Key::ResetBandwidthFailedBody, _) => "La consommation enregistrée n'a pas pu être effacée : {0}",
+        (Key::StatisticsHeading, _) => "Statistiques",
+        (Key::BandwidthThisMonth, _) => "Téléchargé ce mois-ci ({0}) : {1}",
+        (Key::Reset, _) => "Réinitialiser",
+        (Key::DownloadStatsAllTime, _) => "Depuis le début : {0} téléchargés, {1} échoués, {2} d'audio ({3}), {4} passées à télécharger",
+        (Key::ApproveStagedTitle, _) => "Approuver les morceaux en attente ?",
+        (Key::ApproveStagedBody, Plural::One) => "{0} morceau est en attente. Voulez-vous l'approuver maintenant, pour le déplacer dans la bibliothèque ?",
+        (Key::ApproveStagedBody, Plural::Other) => "{0} morceaux sont en attente. Voulez-vous tous les approuver maintenant, pour les déplacer dans la bibliothèque ?",
+        (Key::ApproveStagedFailedTitle, _) => "Certains morceaux n'ont pas pu être approuvés",
+        (Key::ApproveStagedFailedBody, _) => "Ces morceaux sont toujours en attente :\n\n{0}",
+        (Key::ApproveStagedFailure, _) => "« {0} » : {1}",
+        (Key::StagingSetting, _) => "Garder les nouveaux téléchargements en attente jusqu'à leur approbation",
+        (Key::StagingHint, _) => "Les morceaux en attente sont listés à part, et exclus des playlists exportées, jusqu'à ce qu'ils soient déplacés dans la bibliothèque.",
+        (Key::TestCommandTitle, _) => "Tester la commande ?",
+        (Key::TestCommandBody, _) => "La commande sera lancée sur « {0} », le dernier morceau téléchargé, comme s'il venait d'être téléchargé à nouveau.",
+        (Key::CommandSucceededTitle, _) => "Commande réussie",
+        (Key::CommandSucceededSilent, _) => "La commande s'est terminée sans rien afficher.",
+        (Key::CommandSucceededOutput, _) => "La commande a affiché :\n\n{0}",
+        (Key::CommandFailedTitle, _) => "Échec de la commande",
+        (Key::CommandFailedBody, _) => "Le test n'a pas réussi - {0}",
+        (Key::PostDownloadCommandSetting, _) => "Après chaque téléchargement réussi, lancer cette commande :",
+        (Key::TestRun, _) => "Tester",
+        (Key::TestRunning, _) => "Exécution...",
+        (Key::PostDownloadCommandHint, Plural::One) => "{path} est remplacé par le fichier du morceau téléchargé, et {id} par l'identifiant de sa vidéo. La commande est lancée après les étapes ci-dessus, seulement pour les téléchargements réussis, et est arrêtée si elle dure plus de {0} minute. Ce qu'elle affiche est gardé dans l'historique du morceau.",
+        (Key::PostDownloadCommandHint, Plural::Other) => "{path} est remplacé par le fichier du morceau téléchargé, et {id} par l'identifiant de sa vidéo. La commande est lancée après les étapes ci-dessus, seulement pour les téléchargements réussis, et est arrêtée si elle dure plus de {0} minutes. Ce qu'elle affiche est gardé dans l'historique du morceau.",
+        (Key::PostDownloadCommandUnclosedQuote, _) => "Elle ne peut pas être lancée - un guillemet n'y est jamais fermé.",
+        (Key::HookErrorUnclosedQuote, _) => "un guillemet de la commande n'est jamais fermé",
+        (Key::HookErrorEmpty, _) => "la commande est vide",
+        (Key::HookErrorSpawnFailed, _) => "la commande n'a pas pu être lancée : {0}",
+        (Key::HookErrorTimedOut, Plural::One) => "la commande a duré plus de {0} minute, elle a donc été arrêtée",
+        (Key::HookErrorTimedOut, Plural::Other) => "la commande a duré plus de {0} minutes, elle a donc été arrêtée",
+        (Key::HookErrorExitCode, _) => "la commande a échoué avec le code de sortie {0}",
+        (Key::HookErrorTerminated, _) => "la commande a été interrompue",
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_fill_replaces_placeholders() {
+        assert_eq!(fill("{0} by {1}", &[&"Song", &"Artist"]), "Song by Artist");
+        assert_eq!(fill("{1}, then {0}, then {1}", &[&1, &2]), "2, then 1, then 2");
+    }
+
+    #[test]
+    fn test_fill_doesnt_replace_inside_arguments() {
+        assert_eq!(fill("'{0}' by {1}", &[&"{1}", &"Artist"]), "'{1}' by Artist");
+        assert_eq!(fill("{0}", &[&"{0}"]), "{0}");
+    }
+
+    #[test]
+    fn test_fill_leaves_other_braces() {
+        assert_eq!(fill("{path} and {2} and {", &[&"unused"]), "{path} and {2} and {");
+        assert_eq!(fill("{{0}}", &[&"x"]), "{x}");
+    }
+}
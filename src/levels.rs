@@ -0,0 +1,72 @@
+use std::{path::Path, process::Command, time::Duration};
+
+use crate::library::LibraryError;
+
+/// The sample rate songs are decoded at to measure their levels. This is far too low to listen to,
+/// but plenty to tell loud from quiet.
+const SAMPLE_RATE: usize = 8000;
+
+/// The length of audio each level is measured over.
+const LEVEL_WINDOW: Duration = Duration::from_millis(20);
+
+/// The quietest level shown, in decibels relative to full scale. Anything quieter counts as silent.
+const FLOOR_DB: f32 = -60.0;
+
+/// How loud a song is over time, measured ahead of time so that looking up the level at any
+/// position is just an index into a list.
+#[derive(Debug, Clone)]
+pub struct LevelEnvelope {
+    /// The RMS level of each [`LEVEL_WINDOW`] of the song, from 0.0 to 1.0.
+    levels: Vec<f32>,
+}
+
+impl LevelEnvelope {
+    /// Measures the levels of the song at the given path, by decoding it with ffmpeg. This reads
+    /// the whole song, so it's slow.
+    pub fn measure(path: &Path) -> Result<Self, LibraryError> {
+        let output = Command::new("ffmpeg")
+            .arg("-v")
+            .arg("error")
+            .arg("-i")
+            .arg(path)
+            .arg("-ac")
+            .arg("1")
+            .arg("-ar")
+            .arg(SAMPLE_RATE.to_string())
+            .arg("-f")
+            .arg("s16le")
+            .arg("-")
+            .output()
+            .map_err(|e| LibraryError::FfmpegFailed { stderr: format!("could not run ffmpeg: {}", e) })?;
+        if !output.status.success() {
+            return Err(LibraryError::FfmpegFailed { stderr: String::from_utf8_lossy(&output.stderr).into_owned() });
+        }
+
+        let samples = output.stdout
+            .chunks_exact(2)
+            .map(|b| i16::from_le_bytes([b[0], b[1]]) as f32 / i16::MAX as f32)
+            .collect::<Vec<_>>();
+
+        let window_samples = SAMPLE_RATE * LEVEL_WINDOW.as_millis() as usize / 1000;
+        let levels = samples
+            .chunks(window_samples)
+            .map(|window| (window.iter().map(|s| s * s).sum::<f32>() / window.len() as f32).sqrt())
+            .collect();
+
+        Ok(Self { levels })
+    }
+
+    /// The level at a position in the song, in decibels relative to full scale, or `None` if the
+    /// position is past the end.
+    pub fn level_db_at(&self, position: Duration) -> Option<f32> {
+        let index = (position.as_millis() / LEVEL_WINDOW.as_millis()) as usize;
+        let rms = *self.levels.get(index)?;
+        Some(if rms > 0.0 { (20.0 * rms.log10()).max(FLOOR_DB) } else { FLOOR_DB })
+    }
+
+    /// The level at a position in the song, scaled from 0.0 for silence to 1.0 for full scale,
+    /// for drawing a meter.
+    pub fn meter_at(&self, position: Duration) -> Option<f32> {
+        self.level_db_at(position).map(|db| 1.0 - db / FLOOR_DB)
+    }
+}
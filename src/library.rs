@@ -1,9 +1,446 @@
-use std::{path::{PathBuf, Path}, fs::read_dir, time::Duration, process::Command};
+use std::{path::{PathBuf, Path}, fs::{read_dir, File}, time::{Duration, SystemTime}, process::Command, fmt::Display, io, sync::Arc, collections::{HashMap, HashSet}};
 
-use anyhow::Result;
-use id3::{Tag, TagLike, frame::{Picture, PictureType}};
+use id3::{Tag, TagLike, frame::{Picture, PictureType, Lyrics}};
+use serde::{Serialize, Deserialize, Serializer, Deserializer};
+use sha2::{Sha256, Digest};
 
-use crate::tag_interface::{YouTubeIdTag, DownloadTimeTag, CroppedTag, MetadataEditedTag, CustomTagExtensions};
+use crate::{settings::{AudioFormat, ReviewRule, PostProcessStepKind}, ffmpeg_tags, activity_log::{self, Activity}, ui_util::format_duration, youtube::unix_time_now, library_index::{LibraryIndex, IndexEntry}};
+use crate::tag_interface::{YouTubeIdTag, SourceUrlTag, SourceIdTag, DownloadTimeTag, CroppedTag, MetadataEditedTag, PinnedTag, ReviewedTag, PostProcessedTag, NeedsMetadataTag, OriginalHashTag, UploaderTag, VideoTitleTag, ChapterTag, CustomTagExtensions};
+
+/// Where the original copies of modified songs are kept.
+#[derive(PartialEq, Eq, Debug, Clone, Copy)]
+pub enum OriginalStorage {
+    /// Alongside each song, with an extra `.original` extension.
+    Sidecar,
+
+    /// In the library's `.originals` folder, named by the SHA-256 checksum of their contents, so
+    /// that identical originals are only stored once.
+    Deduplicated,
+}
+
+/// Placeholders used when a song's tags don't say what it is. These end up written into tags, so
+/// they are never translated, keeping files the same whichever language CrossPlay is used in.
+pub const UNKNOWN_TITLE: &str = "Unknown Title";
+pub const UNKNOWN_ARTIST: &str = "Unknown Artist";
+pub const UNKNOWN_ALBUM: &str = "Unknown Album";
+
+/// The name of the folder inside a library where deduplicated originals are kept.
+const DEDUPLICATED_ORIGINALS_DIR: &str = ".originals";
+
+/// The name of the folder inside a library where deleted songs, and working copies replaced by
+/// their originals, are kept so that they can be put back. This is emptied whenever CrossPlay
+/// starts, since there's nothing left to undo by then - see [`Library::empty_trash`].
+const TRASH_DIR: &str = ".trash";
+
+/// The name of the folder inside a library where songs being downloaded again are kept until
+/// they replace the old copy - see [`Song::replace_with_download`].
+pub const REDOWNLOAD_DIR: &str = ".redownloads";
+
+/// The name of the folder inside a library where new downloads wait to be approved, while
+/// [`crate::settings::Settings::staging_enabled`] is on. Songs in here are loaded separately from
+/// the rest of the library - see [`Library::staged_songs`].
+pub const STAGING_DIR: &str = ".staging";
+
+/// Something which went wrong while reading or modifying a library.
+#[derive(Debug, Clone)]
+pub enum LibraryError {
+    /// A file which should exist doesn't - usually because it was moved or deleted outside of
+    /// CrossPlay.
+    NotFound(PathBuf),
+
+    PermissionDenied(PathBuf),
+
+    /// A song's ID3 tags are corrupt, or are missing something CrossPlay requires.
+    TagParse { path: PathBuf, message: String },
+
+    /// ffmpeg or ffprobe couldn't be run, or didn't succeed.
+    FfmpegFailed { stderr: String },
+
+    AlreadyExists(PathBuf),
+
+    /// The file is in use by another program. This is common on Windows, where media players lock
+    /// the songs they're playing, and usually goes away if the operation is retried later.
+    Busy(PathBuf),
+
+    /// Any other I/O error.
+    Io { path: PathBuf, error: Arc<io::Error> },
+}
+
+impl LibraryError {
+    /// Classifies an I/O error which occurred while working with the given path.
+    pub fn from_io(path: &Path, error: io::Error) -> Self {
+        // Windows: ERROR_SHARING_VIOLATION, ERROR_LOCK_VIOLATION
+        #[cfg(windows)]
+        const BUSY_CODES: [i32; 2] = [32, 33];
+        // Unix: EBUSY, ETXTBSY
+        #[cfg(not(windows))]
+        const BUSY_CODES: [i32; 2] = [16, 26];
+
+        let path = path.to_path_buf();
+        match error.kind() {
+            io::ErrorKind::NotFound => LibraryError::NotFound(path),
+            io::ErrorKind::PermissionDenied => LibraryError::PermissionDenied(path),
+            io::ErrorKind::AlreadyExists => LibraryError::AlreadyExists(path),
+            _ if matches!(error.raw_os_error(), Some(code) if BUSY_CODES.contains(&code)) => LibraryError::Busy(path),
+            _ => LibraryError::Io { path, error: Arc::new(error) },
+        }
+    }
+
+    /// Whether trying the same operation again later has a reasonable chance of succeeding.
+    pub fn is_retryable(&self) -> bool {
+        matches!(self, LibraryError::Busy(_))
+    }
+}
+
+impl Display for LibraryError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            LibraryError::NotFound(path) => write!(f, "{} does not exist", path.to_string_lossy()),
+            LibraryError::PermissionDenied(path) => write!(f, "permission denied for {}", path.to_string_lossy()),
+            LibraryError::TagParse { path, message } => write!(f, "could not read tags of {}: {}", path.to_string_lossy(), message),
+            LibraryError::FfmpegFailed { stderr } => write!(f, "ffmpeg failed: {}", stderr),
+            LibraryError::AlreadyExists(path) => write!(f, "{} already exists", path.to_string_lossy()),
+            LibraryError::Busy(path) => write!(f, "{} is in use by another program", path.to_string_lossy()),
+            LibraryError::Io { path, error } => write!(f, "{}: {}", path.to_string_lossy(), error),
+        }
+    }
+}
+
+impl std::error::Error for LibraryError {}
+
+type Result<T> = std::result::Result<T, LibraryError>;
+
+/// Converts errors from the filesystem and tag library into [`LibraryError`]s, recording the path
+/// which was being worked on.
+trait ErrorPathExtensions<T> {
+    fn at(self, path: &Path) -> Result<T>;
+}
+
+impl<T> ErrorPathExtensions<T> for io::Result<T> {
+    fn at(self, path: &Path) -> Result<T> {
+        self.map_err(|e| LibraryError::from_io(path, e))
+    }
+}
+
+impl<T> ErrorPathExtensions<T> for id3::Result<T> {
+    fn at(self, path: &Path) -> Result<T> {
+        self.map_err(|e| match e.kind {
+            id3::ErrorKind::Io(io_error) => LibraryError::from_io(path, io_error),
+            _ => LibraryError::TagParse { path: path.to_path_buf(), message: e.description },
+        })
+    }
+}
+
+/// Replaces the file at `path` by having `write` create the new version at a temporary path
+/// alongside it, then renaming that over the original. If CrossPlay is killed or the disk fills up
+/// part-way through, the original is left intact rather than half-written.
+/// 
+/// The temporary file is deleted if anything fails.
+fn replace_atomically(path: &Path, write: impl FnOnce(&Path) -> Result<()>) -> Result<()> {
+    // Songs are only loaded from files with particular extensions, so a leftover temporary file
+    // won't show up in the library
+    let mut temp_name = std::ffi::OsString::from(".");
+    temp_name.push(path.file_name().expect("path has no file name"));
+    temp_name.push(".crossplay-tmp");
+    let temp_path = path.with_file_name(temp_name);
+
+    let result = write(&temp_path)
+        .and_then(|()| File::open(&temp_path).and_then(|f| f.sync_all()).at(&temp_path))
+        .and_then(|()| std::fs::rename(&temp_path, path).at(path));
+
+    if result.is_err() {
+        let _ = std::fs::remove_file(&temp_path);
+    }
+    result
+}
+
+/// Runs the ffmpeg and ffprobe processes which cut songs. Tests stand in for ffmpeg with their own,
+/// to check how CrossPlay copes with it misbehaving in ways a real ffmpeg can't easily be made to.
+pub(crate) trait ProcessRunner {
+    fn output(&self, command: &mut Command) -> io::Result<ProcessOutput>;
+}
+
+/// What a process run by a [`ProcessRunner`] printed, and whether it succeeded.
+pub(crate) struct ProcessOutput {
+    pub success: bool,
+    pub stdout: Vec<u8>,
+    pub stderr: Vec<u8>,
+}
+
+/// Runs processes for real.
+pub(crate) struct SystemProcesses;
+
+impl ProcessRunner for SystemProcesses {
+    fn output(&self, command: &mut Command) -> io::Result<ProcessOutput> {
+        let output = command.output()?;
+        Ok(ProcessOutput { success: output.status.success(), stdout: output.stdout, stderr: output.stderr })
+    }
+}
+
+/// Cuts the given segments out of a song file with ffmpeg, joining them together and applying
+/// `fades` to the result, and writes them to `output`. A single segment with no fades is copied
+/// as-is, since anything else means re-encoding.
+///
+/// The output has no tag worth keeping, so the caller should write one.
+fn cut_segments(runner: &dyn ProcessRunner, input: &Path, segments: &[(Duration, Duration)], fades: &[String], format: AudioFormat, output: &Path) -> Result<()> {
+    // TODO: There are probably pure-Rust libraries for this, look into using those
+    println!("Starting FFMPEG...");
+
+    let mut command = Command::new("ffmpeg");
+    if let [(start, end)] = segments {
+        command
+            .arg("-ss")
+            .arg((start.as_secs_f64()).to_string())
+            .arg("-to")
+            .arg((end.as_secs_f64()).to_string())
+            .arg("-i")
+            .arg(input)
+            .arg("-y");
+
+        if fades.is_empty() {
+            command.arg("-acodec").arg("copy");
+        } else {
+            // Album art is written back with the rest of the metadata afterwards, so only
+            // the audio needs encoding
+            command.arg("-map").arg("0:a").arg("-af").arg(fades.join(","));
+        }
+    } else {
+        command
+            .arg("-i")
+            .arg(input)
+            .arg("-y")
+            .arg("-filter_complex")
+            .arg(crop_segments_filter(segments, fades))
+            .arg("-map")
+            .arg("[out]");
+    }
+
+    // Temporary files don't have an extension ffmpeg recognises
+    command.arg("-f").arg(format.ffmpeg_muxer()).arg(output);
+    let process_output = runner.output(&mut command)
+        .map_err(|e| LibraryError::FfmpegFailed { stderr: format!("could not run ffmpeg: {}", e) })?;
+
+    println!("FFMPEG is done!");
+
+    // Check success
+    let stderr = String::from_utf8_lossy(&process_output.stderr);
+    if !process_output.success {
+        return Err(LibraryError::FfmpegFailed { stderr: stderr.into_owned() });
+    }
+
+    check_cut_output(runner, output, &stderr)
+}
+
+/// Checks that ffmpeg has really written some audio to `output`, since it's been known to exit
+/// successfully having written nothing, or nothing playable. `stderr` is what ffmpeg printed, to
+/// include in the error.
+fn check_cut_output(runner: &dyn ProcessRunner, output: &Path, stderr: &str) -> Result<()> {
+    let size = std::fs::metadata(output).map(|m| m.len()).unwrap_or(0);
+    if size < MIN_CUT_OUTPUT_BYTES {
+        return Err(LibraryError::FfmpegFailed { stderr: format!("ffmpeg wrote {} bytes of output\n{}", size, stderr) });
+    }
+    match probe_duration_with(runner, output) {
+        Ok(duration) if !duration.is_zero() => Ok(()),
+        Ok(_) => Err(LibraryError::FfmpegFailed { stderr: format!("ffmpeg's output has no audio\n{}", stderr) }),
+        Err(e) => Err(LibraryError::FfmpegFailed { stderr: format!("ffmpeg's output could not be read back: {}\n{}", e, stderr) }),
+    }
+}
+
+/// Anything ffmpeg writes which is smaller than this can't be a real cut of a song, even a very
+/// short one, so it's treated as a failure.
+const MIN_CUT_OUTPUT_BYTES: u64 = 1024;
+
+/// Probes the duration of an audio file by shelling out to ffprobe, which is distributed alongside
+/// ffmpeg.
+pub(crate) fn probe_duration(path: &Path) -> Result<Duration> {
+    probe_duration_with(&SystemProcesses, path)
+}
+
+/// Like [`probe_duration`], running ffprobe with the given [`ProcessRunner`].
+fn probe_duration_with(runner: &dyn ProcessRunner, path: &Path) -> Result<Duration> {
+    let output = runner.output(Command::new("ffprobe")
+        .arg("-v")
+        .arg("error")
+        .arg("-show_entries")
+        .arg("format=duration")
+        .arg("-of")
+        .arg("default=noprint_wrappers=1:nokey=1")
+        .arg(path))
+        .map_err(|e| LibraryError::FfmpegFailed { stderr: format!("could not run ffprobe: {}", e) })?;
+    if !output.success {
+        return Err(LibraryError::FfmpegFailed { stderr: String::from_utf8_lossy(&output.stderr).into_owned() });
+    }
+
+    let stdout = String::from_utf8_lossy(&output.stdout);
+    let seconds: f64 = stdout.trim().parse()
+        .map_err(|_| LibraryError::FfmpegFailed { stderr: format!("unexpected ffprobe output: {}", stdout) })?;
+    Ok(Duration::from_secs_f64(seconds))
+}
+
+/// The ffmpeg `afade` filters for a crop of the given length. Each fade is cut short if needed so
+/// that it fits within the crop. With no fades, there are no filters.
+fn crop_fade_filters(length: Duration, fade_in: Duration, fade_out: Duration) -> Vec<String> {
+    let mut filters = vec![];
+    if !fade_in.is_zero() {
+        filters.push(format!("afade=t=in:st=0:d={}", fade_in.min(length).as_secs_f64()));
+    }
+    if !fade_out.is_zero() {
+        let fade_out = fade_out.min(length);
+        filters.push(format!("afade=t=out:st={}:d={}", (length - fade_out).as_secs_f64(), fade_out.as_secs_f64()));
+    }
+    filters
+}
+
+/// An ffmpeg filter graph which cuts each segment out of the first input and joins them together,
+/// followed by the given filters, into an output named `out`.
+fn crop_segments_filter(segments: &[(Duration, Duration)], then: &[String]) -> String {
+    let mut graph = segments.iter()
+        .enumerate()
+        .map(|(i, (start, end))| format!(
+            "[0:a]atrim=start={}:end={},asetpts=PTS-STARTPTS[s{}]",
+            start.as_secs_f64(), end.as_secs_f64(), i,
+        ))
+        .collect::<Vec<_>>();
+
+    let inputs = (0..segments.len()).map(|i| format!("[s{}]", i)).collect::<String>();
+    let mut joined = format!("{}concat=n={}:v=0:a=1", inputs, segments.len());
+    for filter in then {
+        joined.push(',');
+        joined.push_str(filter);
+    }
+    graph.push(format!("{}[out]", joined));
+
+    graph.join(";")
+}
+
+/// The format of the song file at `path`, or an error if it isn't a format CrossPlay supports.
+fn format_of(path: &Path) -> Result<AudioFormat> {
+    AudioFormat::from_path(path)
+        .ok_or_else(|| LibraryError::TagParse { path: path.to_path_buf(), message: "unsupported audio format".to_string() })
+}
+
+/// Reads the tag of a song file. Only MP3s can hold ID3 tags, so other formats have their tags
+/// converted - see [`ffmpeg_tags`].
+/// 
+/// The format is passed separately because temporary files don't have the song's extension.
+fn read_tag(path: &Path, format: AudioFormat) -> Result<Tag> {
+    match format {
+        AudioFormat::Mp3 => Tag::read_from_path(path).at(path),
+        _ => ffmpeg_tags::read_tag(path),
+    }
+}
+
+/// Reads the tag of a song file to load its metadata, along with whether it has album art.
+///
+/// Album art is only read when it's needed - see [`Library::album_art_for`]. An MP3's comes with
+/// the rest of its tag anyway, but other formats' takes another run of ffmpeg, so it's skipped.
+fn read_metadata_tag(path: &Path, format: AudioFormat) -> Result<(Tag, bool)> {
+    match format {
+        AudioFormat::Mp3 => {
+            let tag = Tag::read_from_path(path).at(path)?;
+            let has_album_art = SongMetadata::get_album_art(&tag).is_some();
+            Ok((tag, has_album_art))
+        }
+        _ => ffmpeg_tags::read_tag_without_cover(path),
+    }
+}
+
+/// Writes a tag into a song file, replacing its existing tag. See [`read_tag`].
+fn write_tag(tag: &Tag, path: &Path, format: AudioFormat) -> Result<()> {
+    match format {
+        AudioFormat::Mp3 => tag.write_to_path(path, id3::Version::Id3v23).at(path),
+        _ => ffmpeg_tags::write_tag(tag, path, format),
+    }
+}
+
+/// Writes a tag into the file at `path`, without risking corrupting it - see
+/// [`replace_atomically`].
+fn write_tag_atomically(tag: &Tag, path: &Path) -> Result<()> {
+    let format = format_of(path)?;
+    replace_atomically(path, |temp_path| {
+        std::fs::copy(path, temp_path).at(path)?;
+        write_tag(tag, temp_path, format)
+    })
+}
+
+// Only returned by our own custom tag parsing
+impl<T> ErrorPathExtensions<T> for anyhow::Result<T> {
+    fn at(self, path: &Path) -> Result<T> {
+        self.map_err(|e| LibraryError::TagParse { path: path.to_path_buf(), message: e.to_string() })
+    }
+}
+
+/// Whether a path has the given extension, ignoring case. Files copied from other machines often
+/// have uppercase extensions, like `song.MP3`, so extensions should always be checked with this.
+pub fn has_extension(path: &Path, extension: &str) -> bool {
+    path.extension().map(|e| e.eq_ignore_ascii_case(extension)) == Some(true)
+}
+
+/// How a song is named in an exported playlist. Both M3U and PLS are line-based, so any line
+/// breaks in the title are flattened.
+fn playlist_entry_title(song: &Song) -> String {
+    format!("{} - {}", song.metadata.artist, song.metadata.title).replace(['\r', '\n'], " ")
+}
+
+/// Whether a song file has been hidden, by adding a `.hidden` extension - see [`Song::hide`].
+fn is_hidden_path(path: &Path) -> bool {
+    has_extension(path, "hidden")
+}
+
+/// Errors if something already exists at `path`, so that moving a file there doesn't clobber it.
+/// On case-insensitive filesystems, this also catches a file whose name only differs by case.
+fn ensure_vacant(path: &Path) -> Result<()> {
+    if path.exists() {
+        Err(LibraryError::AlreadyExists(path.to_path_buf()))
+    } else {
+        Ok(())
+    }
+}
+
+/// The files in a folder of a library, along with their metadata, including those in subfolders if
+/// `recursive` is set. Folders whose names start with a dot are skipped, since they're either
+/// CrossPlay's own, like [`TRASH_DIR`], or temporary. Symbolic links to folders aren't followed,
+/// so that a link back up the tree can't loop forever.
+fn library_files(folder: &Path, recursive: bool) -> Result<Vec<(PathBuf, std::fs::Metadata)>> {
+    let mut files = vec![];
+    let mut folders = vec![folder.to_path_buf()];
+    while let Some(folder) = folders.pop() {
+        for entry in read_dir(&folder).at(&folder)? {
+            let entry = entry.at(&folder)?;
+            let path = entry.path();
+            let metadata = entry.metadata().at(&path)?;
+
+            if metadata.is_file() {
+                files.push((path, metadata));
+            } else if recursive && metadata.is_dir() && !entry.file_name().to_string_lossy().starts_with('.') {
+                folders.push(path);
+            }
+        }
+    }
+    Ok(files)
+}
+
+/// The root of the library which a folder CrossPlay downloads into belongs to. This is either the
+/// library itself, or one of the folders CrossPlay keeps directly inside it, like [`STAGING_DIR`].
+pub fn library_root_of(folder: &Path) -> PathBuf {
+    match folder.file_name().and_then(|n| n.to_str()) {
+        Some(STAGING_DIR | REDOWNLOAD_DIR) => folder.parent().unwrap_or(folder).to_path_buf(),
+        _ => folder.to_path_buf(),
+    }
+}
+
+/// Songs shorter than this break [`ReviewRule::TooShort`].
+const REVIEW_MIN_DURATION: Duration = Duration::from_secs(30);
+
+/// Songs longer than this break [`ReviewRule::TooLong`].
+const REVIEW_MAX_DURATION: Duration = Duration::from_secs(20 * 60);
+
+/// A song which probably needs the user's attention, and the review rules it breaks.
+#[derive(Debug, Clone)]
+pub struct ReviewItem {
+    pub song: Song,
+    pub problems: Vec<ReviewRule>,
+}
 
 /// A collection of songs, managed by CrossPlay, saved to a particular location.
 /// 
@@ -13,292 +450,2193 @@ use crate::tag_interface::{YouTubeIdTag, DownloadTimeTag, CroppedTag, MetadataEd
 #[derive(Debug)]
 pub struct Library {
     pub path: PathBuf,
+    pub original_storage: OriginalStorage,
+
+    /// Whether songs are looked for in subfolders of the library, like `Artist/Album/`, rather
+    /// than just at its root.
+    pub scan_recursively: bool,
+
+    loaded_songs: Vec<Song>,
+
+    /// Songs in the library's staging folder, which aren't part of the library until they're
+    /// approved - see [`Song::approve`].
+    staged_songs: Vec<Song>,
+
+    /// Files in the library which look like they were created by a cloud sync service, after two
+    /// different versions of the same file conflicted.
+    conflicted_copies: Vec<PathBuf>,
+
+    /// The total size of the files in the library, including original copies, as of the last
+    /// [`apply_scan`].
+    total_size: u64,
+
+    /// The state of the library folder as of the last [`apply_scan`], to tell whether anything else
+    /// has changed it since.
+    loaded_folder_state: Option<FolderState>,
+
+    /// The size and modified time of each loaded song's file, as of the last [`apply_scan`], so
+    /// that renamed files can be recognised by the next one.
+    loaded_file_stamps: HashMap<PathBuf, FileStamp>,
+}
+
+/// Everything needed to scan a library's folder, copied out of the [`Library`] so that the scan can
+/// run in the background without keeping it locked - see [`Library::scanner`].
+#[derive(Debug, Clone)]
+pub struct LibraryScanner {
+    path: PathBuf,
+    original_storage: OriginalStorage,
+    scan_recursively: bool,
+
+    /// The songs loaded before, including staged ones, which are reused for files which haven't
+    /// changed since.
     loaded_songs: Vec<Song>,
+    loaded_file_stamps: HashMap<PathBuf, FileStamp>,
+}
+
+/// What was found by [`LibraryScanner::scan`], ready to be loaded with [`Library::apply_scan`].
+#[derive(Debug, Clone)]
+pub struct LibraryScan {
+    songs: Vec<Song>,
+    staged_songs: Vec<Song>,
+    conflicted_copies: Vec<PathBuf>,
+    total_size: u64,
+    folder_state: FolderState,
+    file_stamps: HashMap<PathBuf, FileStamp>,
+}
+
+/// The most threads tags are read on at once while scanning. Beyond a handful, they only compete
+/// for the disk.
+const MAX_SCAN_THREADS: usize = 8;
+
+/// The size and modified time of a file, neither of which change when it's renamed.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+struct FileStamp {
+    size: u64,
+    modified: Option<SystemTime>,
+}
+
+/// A cheap summary of the contents of the folders songs are looked for in, which changes when files
+/// are added to, removed from or renamed in any of them.
+#[derive(Debug, Clone, PartialEq, Eq)]
+struct FolderState {
+    /// The modified time and number of entries of each folder. As well as the library folder, this
+    /// has its staging folder, if it has one, since downloads arrive there without touching the
+    /// library folder itself, and each subfolder which is scanned.
+    folders: HashMap<PathBuf, (Option<SystemTime>, usize)>,
+}
+
+impl FolderState {
+    /// Reads the state of a library's folders. Subfolders are included if `recursive` is set,
+    /// following the same rules as [`library_files`], since a folder's modified time only changes
+    /// when its own entries do.
+    fn read(path: &Path, recursive: bool) -> Result<Self> {
+        let mut unread = vec![(path.to_path_buf(), recursive)];
+        let staging_path = path.join(STAGING_DIR);
+        if staging_path.is_dir() {
+            unread.push((staging_path, false));
+        }
+
+        let mut folders = HashMap::new();
+        while let Some((folder, recursive)) = unread.pop() {
+            let mut entry_count = 0;
+            for entry in read_dir(&folder).at(&folder)? {
+                entry_count += 1;
+
+                if recursive {
+                    let entry = entry.at(&folder)?;
+                    if entry.file_type().at(&folder)?.is_dir() && !entry.file_name().to_string_lossy().starts_with('.') {
+                        unread.push((entry.path(), true));
+                    }
+                }
+            }
+
+            let modified_time = std::fs::metadata(&folder).and_then(|m| m.modified()).ok();
+            folders.insert(folder, (modified_time, entry_count));
+        }
+
+        Ok(Self { folders })
+    }
+}
+
+impl LibraryScanner {
+    /// Reads the songs in the library folder, ready to be loaded with [`Library::apply_scan`]. This
+    /// is slow for large libraries, but doesn't need the library itself, so can be run in the
+    /// background.
+    ///
+    /// For a song to be loaded, it must:
+    ///   - Be in the root of the library folder, or in a subfolder if
+    ///     [`Library::scan_recursively`] is set, or in its staging folder, in which case it's one of
+    ///     the [`Library::staged_songs`]. Folders whose names start with a dot are never looked in -
+    ///     see [`library_files`]
+    ///   - Have the extension of one of the [`AudioFormat`]s, whichever is currently used for
+    ///     downloads, so that changing the format doesn't lose older songs
+    ///   - Have a CrossPlay video ID tag
+    ///
+    /// Songs whose files have the same size and modified time as when they were last loaded are
+    /// kept as they were, rather than having their tags read again. When nothing has been loaded
+    /// yet, the same goes for songs in the library's [`LibraryIndex`], which is updated afterwards.
+    pub fn scan(&self) -> Result<LibraryScan> {
+        let mut scan = LibraryScan {
+            songs: vec![],
+            staged_songs: vec![],
+            conflicted_copies: vec![],
+            total_size: 0,
+            folder_state: FolderState::read(&self.path, self.scan_recursively)?,
+            file_stamps: HashMap::new(),
+        };
+
+        let loaded_songs = self.loaded_songs.iter()
+            .map(|song| (&song.path, song))
+            .collect::<HashMap<_, _>>();
+
+        // Songs which are already loaded are the most up-to-date, so the index is only needed when
+        // there aren't any, like on startup
+        let index = if self.loaded_songs.is_empty() {
+            LibraryIndex::load(&self.path)
+        } else {
+            LibraryIndex::default()
+        };
+        let previously_indexed = if self.loaded_songs.is_empty() { index.entry_count() } else { self.loaded_file_stamps.len() };
+
+        // Look for songs in the library folder, and then in the staging folder
+        let mut folders = vec![(self.path.clone(), false)];
+        let staging_path = self.path.join(STAGING_DIR);
+        if staging_path.is_dir() {
+            folders.push((staging_path, true));
+        }
+
+        // Files whose tags need reading, with their stamps and whether they're staged
+        let mut unread = vec![];
+        for (folder, staged) in folders {
+            // Staged songs are only ever put at the top of the staging folder
+            for (path, entry_metadata) in library_files(&folder, self.scan_recursively && !staged)? {
+                // While we're here, tot up sizes - this is much cheaper than a separate pass
+                scan.total_size += entry_metadata.len();
+
+                // Conflicted copies might have an MP3 extension, but they're not really part of the
+                // library, so keep them out of the song list
+                if is_conflicted_copy(&path) {
+                    scan.conflicted_copies.push(path);
+                    continue;
+                }
+
+                if AudioFormat::from_path(&path).is_some() {
+                    let stamp = FileStamp { size: entry_metadata.len(), modified: entry_metadata.modified().ok() };
+
+                    // Reading tags is the slow part of loading, so skip it for files which haven't
+                    // changed. Without a modified time there's no telling, so those are always read
+                    let unchanged = stamp.modified.is_some() && self.loaded_file_stamps.get(&path) == Some(&stamp);
+                    if let Some(song) = loaded_songs.get(&path).filter(|_| unchanged) {
+                        let mut song = (*song).clone();
+                        song.original_storage = self.original_storage;
+                        song.library_path = self.path.clone();
+                        scan.file_stamps.insert(path, stamp);
+                        if staged { scan.staged_songs.push(song) } else { scan.songs.push(song) }
+                        continue;
+                    }
+
+                    let relative_path = path.strip_prefix(&self.path).unwrap_or(&path);
+                    let indexed = stamp.modified.and_then(|modified| index.get(relative_path, stamp.size, modified));
+                    if let Some(metadata) = indexed {
+                        let song = Song::new(path.clone(), metadata.clone(), is_hidden_path(&path), self.original_storage, self.path.clone());
+                        scan.file_stamps.insert(path, stamp);
+                        if staged { scan.staged_songs.push(song) } else { scan.songs.push(song) }
+                        continue;
+                    }
+
+                    unread.push((path, stamp, staged));
+                }
+            }
+        }
+
+        let paths = unread.iter().map(|(path, _, _)| path.clone()).collect::<Vec<_>>();
+        let mut any_read = false;
+        for ((path, stamp, staged), metadata) in unread.into_iter().zip(read_metadata_in_parallel(&paths)) {
+            // If there's no video ID, then this didn't come from CrossPlay, so ignore it
+            if let Some(metadata) = metadata {
+                any_read = true;
+                let hidden = is_hidden_path(&path);
+                scan.file_stamps.insert(path.clone(), stamp);
+                let song = Song::new(path, metadata, hidden, self.original_storage, self.path.clone());
+                if staged { scan.staged_songs.push(song) } else { scan.songs.push(song) }
+            }
+        }
+
+        // Deduplicated originals aren't songs, but they're still part of the library's size
+        let originals_path = self.path.join(DEDUPLICATED_ORIGINALS_DIR);
+        if originals_path.is_dir() {
+            for original in read_dir(&originals_path).at(&originals_path)? {
+                scan.total_size += original.and_then(|o| o.metadata()).at(&originals_path)?.len();
+            }
+        }
+
+        // Only rewrite the index if a song was read, or one has gone
+        if any_read || scan.file_stamps.len() != previously_indexed {
+            self.save_index(&scan);
+        }
+
+        Ok(scan)
+    }
+
+    /// Replaces the library's index with the songs found by a scan. Failing to save it only means
+    /// the next startup is slower, so isn't an error.
+    fn save_index(&self, scan: &LibraryScan) {
+        let entries = scan.songs.iter().chain(scan.staged_songs.iter())
+            .filter_map(|song| {
+                let stamp = scan.file_stamps.get(&song.path)?;
+                let relative_path = song.path.strip_prefix(&self.path).ok()?;
+                Some((relative_path.to_owned(), IndexEntry {
+                    size: stamp.size,
+                    modified: stamp.modified?,
+                    metadata: song.metadata.clone(),
+                }))
+            })
+            .collect();
+
+        if let Err(e) = LibraryIndex::save(&self.path, entries) {
+            println!("[Library] Couldn't save library index: {}", e);
+        }
+    }
+}
+
+/// Reads the metadata of each song file, giving `None` for those which can't be read or weren't
+/// downloaded by CrossPlay. The files are shared out between threads, since each read mostly waits
+/// on the disk.
+fn read_metadata_in_parallel(paths: &[PathBuf]) -> Vec<Option<SongMetadata>> {
+    let threads = std::thread::available_parallelism().map_or(1, |n| n.get()).min(MAX_SCAN_THREADS);
+    let chunk_size = ((paths.len() + threads - 1) / threads).max(1);
+
+    std::thread::scope(|scope| {
+        let handles = paths.chunks(chunk_size)
+            .map(|chunk| scope.spawn(move ||
+                chunk.iter()
+                    .map(|path| {
+                        let (tag, has_album_art) = read_metadata_tag(path, AudioFormat::from_path(path)?).ok()?;
+                        Library::load_one_song_metadata(tag, has_album_art, path).ok()
+                    })
+                    .collect::<Vec<_>>()
+            ))
+            .collect::<Vec<_>>();
+
+        handles.into_iter()
+            .flat_map(|handle| handle.join().expect("tag reading thread panicked"))
+            .collect()
+    })
 }
 
-impl Library {
-    /// Creates a new reference to a library on-disk.
-    pub fn new(path: PathBuf) -> Self {
-        Self { path, loaded_songs: vec![] }
+impl Library {
+    /// Creates a new reference to a library on-disk.
+    pub fn new(path: PathBuf) -> Self {
+        Self { path, original_storage: OriginalStorage::Sidecar, scan_recursively: false, loaded_songs: vec![], staged_songs: vec![], conflicted_copies: vec![], total_size: 0, loaded_folder_state: None, loaded_file_stamps: HashMap::new() }
+    }
+    
+    /// Iterates over all loaded songs.
+    /// 
+    /// You must call [`apply_scan`] before this.
+    pub fn songs(&self) -> impl Iterator<Item = &Song> {
+        self.loaded_songs.iter()
+    }
+
+    /// Iterates over the loaded songs which are waiting in the staging folder. These aren't
+    /// included in [`songs`], so they're left out of exported playlists until they're approved.
+    ///
+    /// You must call [`apply_scan`] before this.
+    pub fn staged_songs(&self) -> impl Iterator<Item = &Song> {
+        self.staged_songs.iter()
+    }
+
+    /// The folder where new downloads go while staging is enabled. This might not exist yet.
+    pub fn staging_path(&self) -> PathBuf {
+        self.path.join(STAGING_DIR)
+    }
+
+    /// All songs from the same album as the given song, including the song itself. Album names are
+    /// compared case-insensitively.
+    /// 
+    /// Songs without an album all share [`UNKNOWN_ALBUM`], but don't really belong together, so
+    /// nothing is returned for those.
+    pub fn songs_in_album_of<'a>(&'a self, song: &'a Song) -> impl Iterator<Item = &'a Song> {
+        let album = song.metadata.album.to_lowercase();
+        self.songs()
+            .filter(move |s| song.has_known_album() && s.metadata.album.to_lowercase() == album)
+    }
+
+    /// The total size of the files in the library, in bytes, including original copies.
+    pub fn total_size(&self) -> u64 {
+        self.total_size
+    }
+
+    /// Writes an extended M3U playlist of the songs in this library, so that it can be opened in
+    /// other players. Hidden songs are only included if `include_hidden` is set. Returns how many
+    /// songs were written.
+    ///
+    /// Songs are referred to by their absolute paths, so the playlist keeps working wherever it's
+    /// saved, as long as the library doesn't move.
+    pub fn export_m3u(&self, path: &Path, include_hidden: bool) -> Result<usize> {
+        let songs = self.playlist_songs(include_hidden);
+
+        let mut contents = "#EXTM3U\n".to_string();
+        for song in &songs {
+            // Durations aren't known without probing every song, which M3U allows by giving -1
+            contents.push_str(&format!("#EXTINF:-1,{}\n", playlist_entry_title(song)));
+            contents.push_str(&format!("{}\n", song.path.to_string_lossy()));
+        }
+
+        std::fs::write(path, contents).at(path)?;
+        Ok(songs.len())
+    }
+
+    /// Like [`export_m3u`], but writes a PLS playlist instead, for players which prefer those.
+    pub fn export_pls(&self, path: &Path, include_hidden: bool) -> Result<usize> {
+        let songs = self.playlist_songs(include_hidden);
+
+        let mut contents = "[playlist]\n".to_string();
+        for (i, song) in songs.iter().enumerate() {
+            let number = i + 1;
+            contents.push_str(&format!("File{}={}\n", number, song.path.to_string_lossy()));
+            contents.push_str(&format!("Title{}={}\n", number, playlist_entry_title(song)));
+            contents.push_str(&format!("Length{}=-1\n", number));
+        }
+        contents.push_str(&format!("NumberOfEntries={}\nVersion=2\n", songs.len()));
+
+        std::fs::write(path, contents).at(path)?;
+        Ok(songs.len())
+    }
+
+    /// The songs to put in an exported playlist, sorted by artist and then title, so that the
+    /// playlist doesn't depend on the order files happened to be loaded in.
+    fn playlist_songs(&self, include_hidden: bool) -> Vec<&Song> {
+        let mut songs = self.songs()
+            .filter(|s| include_hidden || !s.is_hidden())
+            .collect::<Vec<_>>();
+        songs.sort_by_cached_key(|s| (s.metadata.artist.to_lowercase(), s.metadata.title.to_lowercase()));
+        songs
+    }
+
+    /// Permanently deletes everything in the library's trash folder.
+    pub fn empty_trash(&self) -> Result<()> {
+        let trash_path = self.path.join(TRASH_DIR);
+        if trash_path.exists() {
+            std::fs::remove_dir_all(&trash_path).at(&trash_path)?;
+        }
+        Ok(())
+    }
+
+    /// Iterates over conflicted copies of files found in the library by the last [`apply_scan`].
+    pub fn conflicted_copies(&self) -> impl Iterator<Item = &PathBuf> {
+        self.conflicted_copies.iter()
+    }
+
+    /// Copies what's needed to reload the list of songs in this library, so that the folder can be
+    /// scanned without keeping the library locked - see [`LibraryScanner::scan`]. What it finds is
+    /// loaded with [`apply_scan`].
+    pub fn scanner(&self) -> LibraryScanner {
+        LibraryScanner {
+            path: self.path.clone(),
+            original_storage: self.original_storage,
+            scan_recursively: self.scan_recursively,
+            loaded_songs: self.loaded_songs.iter().chain(&self.staged_songs).cloned().collect(),
+            loaded_file_stamps: self.loaded_file_stamps.clone(),
+        }
+    }
+
+    /// Replaces the loaded songs with those found by a scan, all at once, so that a half-loaded
+    /// library is never seen.
+    pub fn apply_scan(&mut self, scan: LibraryScan) {
+        self.loaded_songs = scan.songs;
+        self.staged_songs = scan.staged_songs;
+        self.conflicted_copies = scan.conflicted_copies;
+        self.total_size = scan.total_size;
+        self.loaded_folder_state = Some(scan.folder_state);
+        self.loaded_file_stamps = scan.file_stamps;
+    }
+
+    /// Like [`apply_scan`], but also works out how the songs changed compared to the ones which
+    /// were loaded before.
+    pub fn apply_scan_and_diff(&mut self, scan: LibraryScan) -> LibraryDiff {
+        let previous = std::mem::take(&mut self.loaded_songs);
+        let previous_stamps = std::mem::take(&mut self.loaded_file_stamps);
+        self.apply_scan(scan);
+
+        let mut diff = LibraryDiff::between(&previous, &self.loaded_songs);
+        diff.find_moves(&previous_stamps, &self.loaded_file_stamps);
+        diff
+    }
+
+    /// Whether the songs have been loaded at least once.
+    pub fn is_loaded(&self) -> bool {
+        self.loaded_folder_state.is_some()
+    }
+
+    /// Whether files have been added to, removed from or renamed in the library folder since the
+    /// last [`apply_scan`], so that the loaded songs no longer match what's on disk.
+    ///
+    /// This only looks at the folders songs are looked for in, including subfolders if
+    /// [`Library::scan_recursively`] is set, not the songs inside them, so it is cheap enough to
+    /// call often. It won't notice songs being changed in place.
+    pub fn is_stale(&self) -> bool {
+        match (&self.loaded_folder_state, FolderState::read(&self.path, self.scan_recursively)) {
+            (Some(loaded), Ok(current)) => *loaded != current,
+
+            // If the folder can't be read any more, a reload will surface the error
+            (Some(_), Err(_)) => true,
+
+            // Never loaded, so there's nothing to be stale
+            (None, _) => false,
+        }
+    }
+
+    /// Checks the given songs against the enabled review rules, returning those which break any of
+    /// them - see [`Song::review`].
+    /// 
+    /// This takes songs rather than using the loaded ones, so that it can run on a copy of them
+    /// without keeping the library locked, since probing durations is slow.
+    pub fn review_items<'a>(songs: impl IntoIterator<Item = &'a Song>, rules: &[ReviewRule]) -> Vec<ReviewItem> {
+        songs.into_iter()
+            .filter_map(|song| {
+                let problems = song.review(rules);
+                if problems.is_empty() {
+                    None
+                } else {
+                    Some(ReviewItem { song: song.clone(), problems })
+                }
+            })
+            .collect()
+    }
+
+    /// Reads the album art of the song at the given path.
+    /// 
+    /// Songs are loaded without their album art, since it's large and usually not needed, so this
+    /// can be used to fetch it on demand.
+    pub fn album_art_for(path: &Path) -> Result<Option<Picture>> {
+        let tag = read_tag(path, format_of(path)?)?;
+        Ok(SongMetadata::get_album_art(&tag).cloned())
+    }
+
+    /// Copies an MP3 from elsewhere into the library, tagging it so that it's loaded like a song
+    /// CrossPlay downloaded. Any title, artist and album it already has are kept, and if it has no
+    /// title, its file name is used instead.
+    ///
+    /// Imported songs have an empty video ID, like other songs which didn't come from YouTube. If
+    /// the file is already tagged as a CrossPlay song, from another library, its tags are kept
+    /// as they are.
+    ///
+    /// If a file with the same name is already in the library, a number is added to the new one's
+    /// name rather than replacing it.
+    pub fn import_file(&self, source: &Path) -> Result<Song> {
+        // Only ID3 can hold an empty video ID - other formats' tags lose empty values
+        if !has_extension(source, AudioFormat::Mp3.extension()) {
+            return Err(LibraryError::TagParse { path: source.to_path_buf(), message: "only MP3 files can be imported".to_string() });
+        }
+
+        let mut tag = match Tag::read_from_path(source) {
+            Ok(tag) => tag,
+            Err(e) if matches!(e.kind, id3::ErrorKind::NoTag) => Tag::new(),
+            Err(e) => return Err(e).at(source),
+        };
+        if tag.read_custom::<YouTubeIdTag>().is_err() {
+            tag.write_custom::<YouTubeIdTag>(String::new());
+            tag.write_custom::<DownloadTimeTag>(unix_time_now());
+            if tag.title().map_or(true, |t| t.trim().is_empty()) {
+                tag.set_title(source.file_stem().unwrap_or_default().to_string_lossy());
+            }
+        }
+
+        let destination = self.vacant_path_for(source);
+        replace_atomically(&destination, |temp_path| {
+            std::fs::copy(source, temp_path).at(source)?;
+            write_tag(&tag, temp_path, AudioFormat::Mp3)
+        })?;
+
+        let song = self.load_song(&destination)?;
+        activity_log::record(&song, Activity::Imported, source.to_string_lossy());
+        Ok(song)
+    }
+
+    /// A path in the library for a copy of the given file, with the same name unless that's
+    /// already taken, in which case it's numbered like `song (2).mp3`.
+    fn vacant_path_for(&self, source: &Path) -> PathBuf {
+        let stem = source.file_stem().unwrap_or_default().to_string_lossy();
+        let extension = source.extension().unwrap_or_default().to_string_lossy();
+
+        let mut path = self.path.join(format!("{}.{}", stem, extension));
+        let mut number = 2;
+        while path.exists() {
+            path = self.path.join(format!("{} ({}).{}", stem, number, extension));
+            number += 1;
+        }
+        path
+    }
+
+    /// Loads a single song from the library, without reloading the rest.
+    pub fn load_song(&self, path: &Path) -> Result<Song> {
+        let (tag, has_album_art) = read_metadata_tag(path, format_of(path)?)?;
+        let metadata = Self::load_one_song_metadata(tag, has_album_art, path)?;
+        let hidden = is_hidden_path(path);
+        Ok(Song::new(path.to_path_buf(), metadata, hidden, self.original_storage, self.path.clone()))
+    }
+
+    fn load_one_song_metadata(tag: Tag, has_album_art: bool, path: &Path) -> Result<SongMetadata> {
+        // Songs from YouTube are identified by their video ID, and songs from other sites by the
+        // URL they came from, so a song without either wasn't downloaded by CrossPlay
+        let source_url = tag.read_custom::<SourceUrlTag>().at(path)?;
+        let youtube_id = match &source_url {
+            Some(url) => tag.read_custom::<SourceIdTag>().at(path)?.unwrap_or_else(|| url.clone()),
+            None => tag.read_custom::<YouTubeIdTag>().at(path)?,
+        };
+
+        Ok(SongMetadata {
+            title: tag.title().unwrap_or(UNKNOWN_TITLE).into(),
+            artist: tag.artist().unwrap_or(UNKNOWN_ARTIST).into(),
+            album: tag.album().unwrap_or(UNKNOWN_ALBUM).into(),
+            genre: tag.genre().map(Into::into),
+            // ID3v2.4 tags keep the year in the recording date instead
+            year: tag.year().or_else(|| tag.date_recorded().map(|d| d.year)),
+            track: tag.track(),
+            youtube_id,
+            source_url,
+            album_art: has_album_art.then_some(AlbumArt::NotLoaded),
+            is_cropped: tag.read_custom::<CroppedTag>().at(path)?,
+            is_metadata_edited: tag.read_custom::<MetadataEditedTag>().at(path)?,
+            is_post_processed: tag.read_custom::<PostProcessedTag>().at(path)?,
+            is_pinned: tag.read_custom::<PinnedTag>().at(path)?,
+            needs_metadata: tag.read_custom::<NeedsMetadataTag>().at(path)?,
+            is_reviewed: tag.read_custom::<ReviewedTag>().at(path)?,
+            original_hash: tag.read_custom::<OriginalHashTag>().at(path)?,
+            download_unix_time: tag.read_custom::<DownloadTimeTag>().at(path)?,
+            uploader: tag.read_custom::<UploaderTag>().at(path)?,
+            video_title: tag.read_custom::<VideoTitleTag>().at(path)?,
+            chapter: tag.read_custom::<ChapterTag>().at(path)?,
+            duration: tag.duration().map(|millis| Duration::from_millis(millis as u64)),
+            lyrics: tag.frames().find_map(|f| f.content().lyrics()).map(|l| l.text.clone()),
+        })
+    }
+}
+
+/// If the given path is inside a folder synced by a known cloud storage service, returns the name
+/// of that service.
+/// 
+/// This is a best-effort check, based on the well-known names of these services' folders.
+pub fn cloud_sync_provider(path: &Path) -> Option<&'static str> {
+    for ancestor in path.ancestors() {
+        let name = match ancestor.file_name() {
+            Some(name) => name.to_string_lossy().to_lowercase(),
+            None => continue,
+        };
+
+        // Dropbox also leaves marker files in the root of its folder
+        if name.starts_with("dropbox") || ancestor.join(".dropbox").exists() {
+            return Some("Dropbox");
+        }
+        if name.starts_with("onedrive") {
+            return Some("OneDrive");
+        }
+        if name.starts_with("google drive") || name.starts_with("googledrive") || name == "my drive" {
+            return Some("Google Drive");
+        }
+        if name == "icloud drive" || name == "mobile documents" || name == "com~apple~clouddocs" {
+            return Some("iCloud Drive");
+        }
+        if name == "cloudstorage" {
+            return Some("a cloud storage service");
+        }
+    }
+
+    None
+}
+
+/// Returns true if the given file looks like a copy created by a cloud sync service, when two
+/// versions of a file conflicted - for example, `song (Aaron's conflicted copy 2022-06-01).mp3`.
+fn is_conflicted_copy(path: &Path) -> bool {
+    let name = match path.file_name() {
+        Some(name) => name.to_string_lossy().to_lowercase(),
+        None => return false,
+    };
+
+    name.contains("conflicted copy") || name.contains("(conflict")
+}
+
+/// How the songs in a library changed between two loads, as reported by
+/// [`Library::apply_scan_and_diff`].
+#[derive(Debug, Clone, Default)]
+pub struct LibraryDiff {
+    pub added: Vec<Song>,
+    pub removed: Vec<Song>,
+
+    /// Songs which are in both loads, but whose tags differ. These are the newer versions.
+    pub changed: Vec<Song>,
+
+    /// Songs whose files were renamed, as the old song and the new one. These aren't counted as
+    /// removed and added, so anything which refers to a song by its path can be carried over.
+    pub moved: Vec<(Song, Song)>,
+}
+
+impl LibraryDiff {
+    /// Compares two sets of loaded songs, matching them up by path.
+    ///
+    /// Songs are matched by their [`Song::root_path`], so hiding or unhiding a song doesn't count as
+    /// removing and adding it. Only the tags which a user would edit are compared - CrossPlay's own
+    /// tags, like whether a song is cropped or pinned, change whenever CrossPlay works on a song,
+    /// so comparing them would flag every song CrossPlay has just touched.
+    pub fn between(old: &[Song], new: &[Song]) -> Self {
+        let find_in = |songs: &[Song], song: &Song| -> Option<Song> {
+            let path = song.root_path();
+            songs.iter().find(|s| s.root_path() == path).cloned()
+        };
+
+        let mut diff = Self::default();
+        for song in new {
+            match find_in(old, song) {
+                None => diff.added.push(song.clone()),
+                Some(old_song) if !old_song.metadata.same_user_tags(&song.metadata) => diff.changed.push(song.clone()),
+                Some(_) => (),
+            }
+        }
+        diff.removed = old.iter().filter(|song| find_in(new, song).is_none()).cloned().collect();
+
+        diff
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.added.is_empty() && self.removed.is_empty() && self.changed.is_empty() && self.moved.is_empty()
+    }
+
+    /// Picks out songs which were removed and then added again under another file name, which is
+    /// how a renamed file looks, and moves them into [`LibraryDiff::moved`] - see [`match_moves`].
+    fn find_moves(&mut self, old_stamps: &HashMap<PathBuf, FileStamp>, new_stamps: &HashMap<PathBuf, FileStamp>) {
+        let removed = self.removed.iter().map(|s| SongIdentity::of(s, old_stamps.get(&s.path))).collect::<Vec<_>>();
+        let added = self.added.iter().map(|s| SongIdentity::of(s, new_stamps.get(&s.path))).collect::<Vec<_>>();
+        let pairs = match_moves(&removed, &added);
+        if pairs.is_empty() {
+            return;
+        }
+
+        self.moved = pairs.iter().map(|(r, a)| (self.removed[*r].clone(), self.added[*a].clone())).collect();
+
+        let (moved_removed, moved_added): (HashSet<_>, HashSet<_>) = pairs.into_iter().unzip();
+        self.removed = std::mem::take(&mut self.removed).into_iter().enumerate()
+            .filter(|(i, _)| !moved_removed.contains(i))
+            .map(|(_, song)| song)
+            .collect();
+        self.added = std::mem::take(&mut self.added).into_iter().enumerate()
+            .filter(|(i, _)| !moved_added.contains(i))
+            .map(|(_, song)| song)
+            .collect();
+    }
+}
+
+/// What a song can be recognised by after its file is renamed.
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+enum SongIdentity {
+    /// Songs from YouTube and other sites are recognised by where they came from. Chapters split
+    /// from the same video share that, so they're told apart by their number.
+    Downloaded { id: String, chapter: Option<u32> },
+
+    /// Imported songs have no ID, so are recognised by their file's size and modified time
+    /// instead, which renaming doesn't change.
+    Imported(FileStamp),
+}
+
+impl SongIdentity {
+    /// The identity of a song, given its file's stamp from when it was loaded. Imported songs whose
+    /// file couldn't be looked at have none.
+    fn of(song: &Song, stamp: Option<&FileStamp>) -> Option<Self> {
+        if song.metadata.youtube_id.is_empty() {
+            stamp.map(|stamp| Self::Imported(*stamp))
+        } else {
+            Some(Self::Downloaded { id: song.metadata.youtube_id.clone(), chapter: song.metadata.chapter })
+        }
+    }
+}
+
+/// Pairs up songs which disappeared with songs which appeared in their place, as indices into
+/// `removed` and `added`.
+///
+/// Only identities which appear exactly once on both sides are paired. If there are two files with
+/// the same identity on either side, there's no telling which became which, so none of them are.
+fn match_moves(removed: &[Option<SongIdentity>], added: &[Option<SongIdentity>]) -> Vec<(usize, usize)> {
+    let positions = |identities: &[Option<SongIdentity>], identity: &SongIdentity| -> Vec<usize> {
+        identities.iter().enumerate()
+            .filter(|(_, i)| i.as_ref() == Some(identity))
+            .map(|(index, _)| index)
+            .collect()
+    };
+
+    removed.iter().enumerate()
+        .filter_map(|(r, identity)| {
+            let identity = identity.as_ref()?;
+            match (positions(removed, identity).as_slice(), positions(added, identity).as_slice()) {
+                ([_], [a]) => Some((r, *a)),
+                _ => None,
+            }
+        })
+        .collect()
+}
+
+/// How a song's file has changed since a particular point, as reported by [`Song::change_since`].
+#[derive(PartialEq, Eq, Debug, Clone, Copy)]
+pub enum SongFileChange {
+    Unchanged,
+    Modified,
+    Deleted,
+}
+
+/// A part of a video, as marked by its uploader, which can be split out into a song of its own -
+/// see [`Song::split_into_chapters`].
+#[derive(PartialEq, Eq, Debug, Clone)]
+pub struct Chapter {
+    pub title: String,
+    pub start: Duration,
+    pub end: Duration,
+}
+
+/// A song loaded from a library.
+#[derive(PartialEq, Eq, Debug, Clone)]
+pub struct Song {
+    /// The path to the working copy of this song, possibly modified.
+    pub path: PathBuf,
+
+    /// This song's metadata, loaded from ID3 tags.
+    pub metadata: SongMetadata,
+
+    /// Whether this song is hidden. This is separate from the metadata since it is encoded by the
+    /// file extension, not ID3 tags.
+    hidden: bool,
+
+    /// Where this song's original copy is kept, if it is modified. Originals which were created in
+    /// a different way are still found, and are migrated when the song is next modified.
+    original_storage: OriginalStorage,
+
+    /// The root of the library this song belongs to, which is where its trash and deduplicated
+    /// originals are kept. The song itself might be in a subfolder, or the staging folder.
+    library_path: PathBuf,
+}
+
+impl Song {
+    /// Creates a new reference to a song on-disk.
+    pub(crate) fn new(path: PathBuf, metadata: SongMetadata, hidden: bool, original_storage: OriginalStorage, library_path: PathBuf) -> Self {
+        Self { path, metadata, hidden, original_storage, library_path }
+    }
+
+    /// Reads this song's metadata from disk again, returning an up-to-date copy of this song.
+    /// 
+    /// This can be compared with the existing song to check whether it was changed outside of
+    /// CrossPlay, and errors if the song no longer exists.
+    pub fn reload(&self) -> Result<Song> {
+        let (tag, has_album_art) = read_metadata_tag(&self.path, self.format())?;
+        let metadata = Library::load_one_song_metadata(tag, has_album_art, &self.path)?;
+        Ok(Song::new(self.path.clone(), metadata, self.hidden, self.original_storage, self.library_path.clone()))
+    }
+
+    /// If this song's file was renamed, as found by a library reload, points this at the new file.
+    /// Any changes to its metadata which haven't been saved yet are kept.
+    pub fn follow_move(&mut self, moved: &[(Song, Song)]) {
+        if let Some((_, new)) = moved.iter().find(|(old, _)| old.path == self.path) {
+            self.path = new.path.clone();
+            self.hidden = new.hidden;
+        }
+    }
+
+    /// The last time the working copy of this song was modified.
+    pub fn modified_time(&self) -> Result<SystemTime> {
+        std::fs::metadata(&self.path).and_then(|m| m.modified()).at(&self.path)
+    }
+
+    /// Checks whether the working copy of this song has changed since it had the given
+    /// modification time. If either time couldn't be read, there's no telling, so the song is
+    /// assumed to have changed.
+    pub fn change_since(&self, modified_time: Option<SystemTime>) -> SongFileChange {
+        if !self.path.exists() {
+            return SongFileChange::Deleted;
+        }
+
+        match (modified_time, self.modified_time().ok()) {
+            (Some(then), Some(now)) if then == now => SongFileChange::Unchanged,
+            _ => SongFileChange::Modified,
+        }
+    }
+
+    /// The path to this song assuming it is not hidden.
+    /// 
+    /// If the song is already not hidden, then this will be the same as the current path.
+    pub fn root_path(&self) -> PathBuf {
+        if self.hidden {
+            // Strip the ".hidden" off the end first
+            self.path.with_extension("")
+        } else {
+            self.path.clone()
+        }
+    }
+
+    /// The format of this song's file.
+    pub fn format(&self) -> AudioFormat {
+        AudioFormat::from_path(&self.path).expect("song has an unsupported extension")
+    }
+
+    /// Whether the current song is hidden.
+    pub fn is_hidden(&self) -> bool {
+        self.hidden
+    }
+
+    /// Whether this song is waiting in its library's staging folder to be approved.
+    pub fn is_staged(&self) -> bool {
+        self.path.parent() == Some(self.library_path.join(STAGING_DIR).as_path())
+    }
+
+    /// The root of the library this song belongs to.
+    fn library_path(&self) -> &Path {
+        &self.library_path
+    }
+
+    /// The path to this song if/when it is hidden.
+    /// 
+    /// If the song is already hidden, then this will be the same as the current path.
+    pub fn hidden_path(&self) -> PathBuf {
+        if self.hidden {
+            self.path.clone()
+        } else {
+            format!("{}.hidden", self.path.to_string_lossy()).into()
+        }
+    }
+
+    /// The path where the original of this song will be copied to, before any modifications take
+    /// place.
+    /// 
+    /// If the song's original has been stored by checksum, this is the path within the library's
+    /// `.originals` folder. Otherwise, it is a sidecar file next to the song.
+    /// 
+    /// This will not exist if the song has not been modified (and thus [`create_original_copy`] has
+    /// not been called).
+    fn original_copy_path(&self) -> PathBuf {
+        match &self.metadata.original_hash {
+            Some(hash) => self.deduplicated_original_path(hash),
+            None => self.sidecar_original_path(),
+        }
+    }
+
+    /// The path to this song's original if it is stored as a sidecar file.
+    fn sidecar_original_path(&self) -> PathBuf {
+        format!("{}.original", self.root_path().to_string_lossy()).into()
+    }
+
+    /// The path to an original with the given checksum, in the library's `.originals` folder.
+    fn deduplicated_original_path(&self, hash: &str) -> PathBuf {
+        self.library_path()
+            .join(DEDUPLICATED_ORIGINALS_DIR)
+            .join(format!("{}.{}", hash, self.format().extension()))
+    }
+
+    /// Creates an original copy of this song, if one does not already exist. It is the caller's
+    /// responsibility to ensure this is called before modifying the file at the song's [`path`].
+    /// 
+    /// If originals are being deduplicated, this also records the original's checksum in this
+    /// song's metadata, and migrates any existing sidecar original into the `.originals` folder.
+    fn create_original_copy(&mut self) -> Result<()> {
+        match self.original_storage {
+            OriginalStorage::Sidecar => {
+                if self.original_copy_path().exists() { return Ok(()) }
+                std::fs::copy(&self.path, self.original_copy_path()).at(&self.path)?;
+            }
+
+            OriginalStorage::Deduplicated => {
+                if self.metadata.original_hash.is_some() && self.original_copy_path().exists() { return Ok(()) }
+
+                // If there's already a sidecar original, that's the one to keep - otherwise the
+                // working copy is still unmodified
+                let sidecar_path = self.sidecar_original_path();
+                let source_path = if sidecar_path.exists() { sidecar_path.clone() } else { self.path.clone() };
+
+                let hash = Self::file_hash(&source_path)?;
+                let original_path = self.deduplicated_original_path(&hash);
+                if !original_path.exists() {
+                    std::fs::create_dir_all(original_path.parent().unwrap()).at(&original_path)?;
+                    std::fs::copy(&source_path, &original_path).at(&source_path)?;
+                }
+
+                // Record the hash on the working copy straight away, so the original is never left
+                // unreferenced
+                let mut tag = read_tag(&self.path, self.format())?;
+                tag.write_custom::<OriginalHashTag>(Some(hash.clone()));
+                write_tag_atomically(&tag, &self.path)?;
+                self.metadata.original_hash = Some(hash);
+
+                if sidecar_path.exists() {
+                    std::fs::remove_file(&sidecar_path).at(&sidecar_path)?;
+                }
+            }
+        }
+
+        Ok(())
+    }
+
+    /// Computes the hex SHA-256 checksum of a file's contents.
+    fn file_hash(path: &Path) -> Result<String> {
+        let mut hasher = Sha256::new();
+        std::io::copy(&mut File::open(path).at(path)?, &mut hasher).at(path)?;
+        Ok(format!("{:x}", hasher.finalize()))
+    }
+
+    /// Returns true if any other song in this song's library refers to the deduplicated original
+    /// with the given checksum.
+    ///
+    /// Subfolders are always looked in, even if the library isn't scanned recursively, since
+    /// wrongly keeping an original is much better than wrongly throwing it away.
+    fn is_original_shared(&self, hash: &str) -> Result<bool> {
+        // Staged songs keep their originals in the library's `.originals` folder too
+        let library_path = self.library_path();
+        for folder in [library_path.to_path_buf(), library_path.join(STAGING_DIR)] {
+            if !folder.is_dir() { continue }
+
+            for (path, _) in library_files(&folder, true)? {
+                if path == self.path { continue }
+
+                if let Some(format) = AudioFormat::from_path(&path) {
+                    if let Ok(tag) = read_tag(&path, format) {
+                        if tag.read_custom::<OriginalHashTag>().at(&path)?.as_deref() == Some(hash) {
+                            return Ok(true)
+                        }
+                    }
+                }
+            }
+        }
+
+        Ok(false)
+    }
+
+    /// Restores the original copy of this song, replacing the working copy. The original copy is
+    /// left intact.
+    /// 
+    /// Errors if an original does not exist.
+    pub fn restore_original_copy(&self) -> Result<()> {
+        let result = replace_atomically(&self.path, |temp_path| {
+            std::fs::copy(self.original_copy_path(), temp_path).at(&self.original_copy_path())?;
+
+            // Pinning isn't a modification, so carry it over to the restored copy, and keep
+            // referring to the original so that it can be cleaned up later
+            let mut tag = read_tag(temp_path, self.format())?;
+            tag.write_custom::<PinnedTag>(self.metadata.is_pinned);
+            tag.write_custom::<OriginalHashTag>(self.metadata.original_hash.clone());
+            write_tag(&tag, temp_path, self.format())
+        });
+
+        if result.is_ok() {
+            activity_log::record(self, Activity::Restored, "");
+        }
+        result
+    }
+
+    /// Whether this song's original copy is where it should be, so that it can be restored.
+    pub fn has_original_copy(&self) -> bool {
+        self.original_copy_path().exists()
+    }
+
+    /// Probes how long this song's original copy is, to compare against the working copy before
+    /// restoring it.
+    pub fn probe_original_duration(&self) -> Result<Duration> {
+        probe_duration(&self.original_copy_path())
+    }
+
+    /// Returns true if this song's metadata indicates that it has been modified from the original.
+    pub fn is_modified(&self) -> bool {
+        self.metadata.is_cropped || self.metadata.is_metadata_edited || self.metadata.is_post_processed
+    }
+
+    /// Returns true if this song has an album, rather than being filed under [`UNKNOWN_ALBUM`].
+    pub fn has_known_album(&self) -> bool {
+        self.metadata.album != UNKNOWN_ALBUM
+    }
+
+    /// Hides this song. If the song is already hidden, has no effect.
+    /// 
+    /// The song list MUST be updated after this operation, or paths will break.
+    pub fn hide(mut self) -> Result<()> {
+        if self.hidden { return Ok(()) }
+
+        // Move to hidden path
+        let hidden_path = self.hidden_path();
+        ensure_vacant(&hidden_path)?;
+        std::fs::rename(&self.path, &hidden_path).at(&self.path)?;
+
+        // Update path on self
+        self.path = hidden_path;
+        activity_log::record(&self, Activity::Hidden, self.path.file_name().unwrap_or_default().to_string_lossy());
+
+        Ok(())
+    }
+
+    /// Unhides this song. If the song is already not hidden, has no effect.
+    /// 
+    /// The song list MUST be updated after this operation, or paths will break.
+    pub fn unhide(mut self) -> Result<()> {
+        if !self.hidden { return Ok(()) }
+
+        // Move away from hidden path
+        let new_path = self.root_path();
+        ensure_vacant(&new_path)?;
+        std::fs::rename(&self.path, &new_path).at(&self.path)?;
+
+        // Update path on self
+        self.path = new_path;
+        activity_log::record(&self, Activity::Unhidden, self.path.file_name().unwrap_or_default().to_string_lossy());
+
+        Ok(())
+    }
+
+    /// Modifies the working copy of this song to keep only the given segments, each a start and end
+    /// point, joined together in the order given. This is accomplished by shelling out to ffmpeg.
+    /// There must be at least one segment.
+    /// 
+    /// Also sets the [`SongMetadata.is_cropped`] flag to true, and re-writes metadata to the
+    /// working copy.
+    /// 
+    /// The audio can fade in from the new start and out to the new end, which avoids a click where
+    /// it's cut. Fading or joining several segments means the song has to be re-encoded, so a
+    /// single segment with no fades is copied as-is instead.
+    ///
+    /// This will create an original copy first, if one does not already exist. The working copy is
+    /// only replaced once the cropped version is complete.
+    pub fn crop(&mut self, segments: &[(Duration, Duration)], fade_in: Duration, fade_out: Duration) -> Result<()> {
+        self.crop_with(&SystemProcesses, segments, fade_in, fade_out)
+    }
+
+    /// Like [`crop`], running ffmpeg with the given [`ProcessRunner`].
+    pub(crate) fn crop_with(&mut self, runner: &dyn ProcessRunner, segments: &[(Duration, Duration)], fade_in: Duration, fade_out: Duration) -> Result<()> {
+        self.metadata.load_album_art(&self.path)?;
+        self.create_original_copy()?;
+
+        let mut metadata = self.metadata.clone();
+        metadata.is_cropped = true;
+        let format = self.format();
+
+        let result = replace_atomically(&self.path, |temp_path| {
+            // TODO: should this be async like downloads are?
+            let length = segments.iter().map(|(start, end)| end.saturating_sub(*start)).sum();
+            let fades = crop_fade_filters(length, fade_in, fade_out);
+            cut_segments(runner, &self.original_copy_path(), segments, &fades, format, temp_path)?;
+
+            metadata.duration = probe_duration_with(runner, temp_path).ok();
+            metadata.write_tag_into(temp_path, format)
+        });
+        if let Err(e) = result {
+            // The crop is written alongside the working copy, so that should be untouched - but if
+            // it's been left empty anyway, put back the original which was just made from it
+            if std::fs::metadata(&self.path).map_or(true, |m| m.len() == 0) {
+                println!("Crop left {} empty, restoring original", self.path.to_string_lossy());
+                if let Err(restore_error) = self.restore_original_copy() {
+                    println!("Could not restore original: {}", restore_error);
+                }
+            }
+            return Err(e);
+        }
+
+        self.metadata = metadata;
+
+        let mut summary = segments.iter()
+            .map(|(start, end)| format!("{} - {}", format_duration(*start), format_duration(*end)))
+            .collect::<Vec<_>>()
+            .join(", ");
+        if !fade_in.is_zero() || !fade_out.is_zero() {
+            summary.push_str(&format!(" (fades {:.1}s / {:.1}s)", fade_in.as_secs_f64(), fade_out.as_secs_f64()));
+        }
+        activity_log::record(self, Activity::Cropped, summary);
+
+        Ok(())
+    }
+
+    /// Splits a freshly-downloaded song into one song per chapter, cutting each out as [`crop`]
+    /// does. Each chapter's song is titled after the chapter and numbered by it, with the video's
+    /// title as its album, unless this song already has an album. This song's file is deleted once
+    /// they're all done, and the new songs are returned in order.
+    ///
+    /// The chapters are cut into a folder of their own, and only moved into the library once every
+    /// one has been cut and tagged. If anything fails, that folder is deleted and this song is left
+    /// as it was, so the library never ends up with some of the chapters.
+    ///
+    /// `on_chapter` is called with each chapter's index as it's started, to report progress.
+    pub fn split_into_chapters(self, chapters: &[Chapter], mut on_chapter: impl FnMut(usize)) -> Result<Vec<Song>> {
+        let mut metadata = self.metadata.clone();
+        metadata.load_album_art(&self.path)?;
+        let format = self.format();
+
+        let library_path = self.path.parent().expect("song is not in a folder");
+        let stem = self.path.file_stem().expect("song has no file name").to_string_lossy().into_owned();
+
+        // Like temporary files, the leading dot keeps this out of the library
+        let staging_path = library_path.join(format!(".{}.chapters", stem));
+        if staging_path.exists() {
+            std::fs::remove_dir_all(&staging_path).at(&staging_path)?;
+        }
+        std::fs::create_dir(&staging_path).at(&staging_path)?;
+
+        let mut cut = || -> Result<Vec<(PathBuf, SongMetadata)>> {
+            let album = if metadata.album != UNKNOWN_ALBUM {
+                metadata.album.clone()
+            } else {
+                metadata.video_title.clone().unwrap_or_else(|| metadata.title.clone())
+            };
+            let mut staged = vec![];
+            for (i, chapter) in chapters.iter().enumerate() {
+                on_chapter(i);
+
+                let number = i as u32 + 1;
+                let file_name = format!("{}.chapter-{:02}.{}", stem, number, format.extension());
+                let staged_path = staging_path.join(&file_name);
+                cut_segments(&SystemProcesses, &self.path, &[(chapter.start, chapter.end)], &[], format, &staged_path)?;
+
+                let mut chapter_metadata = metadata.clone();
+                chapter_metadata.title = chapter.title.clone();
+                chapter_metadata.album = album.clone();
+                chapter_metadata.track = Some(number);
+                chapter_metadata.chapter = Some(number);
+                // The video's lyrics won't line up with any one chapter
+                chapter_metadata.lyrics = None;
+                chapter_metadata.duration = probe_duration(&staged_path).ok();
+                chapter_metadata.write_tag_into(&staged_path, format)?;
+
+                staged.push((staged_path, chapter_metadata));
+            }
+            Ok(staged)
+        };
+        let staged = match cut() {
+            Ok(staged) => staged,
+            Err(e) => {
+                let _ = std::fs::remove_dir_all(&staging_path);
+                return Err(e);
+            }
+        };
+
+        // Moving within the same folder can't run out of space, so this is very unlikely to fail -
+        // but if it does, take back whichever chapters already made it into the library
+        let mut songs = vec![];
+        for (staged_path, chapter_metadata) in staged {
+            let path = library_path.join(staged_path.file_name().unwrap());
+            if let Err(e) = std::fs::rename(&staged_path, &path).at(&path) {
+                for song in songs {
+                    let _ = std::fs::remove_file(song.path);
+                }
+                let _ = std::fs::remove_dir_all(&staging_path);
+                return Err(e);
+            }
+            songs.push(Song::new(path, chapter_metadata, false, self.original_storage, self.library_path.clone()));
+        }
+
+        std::fs::remove_dir(&staging_path).at(&staging_path)?;
+        std::fs::remove_file(&self.path).at(&self.path)?;
+
+        Ok(songs)
+    }
+
+    /// Runs a post-processing step on the working copy of this song, and sets the
+    /// [`SongMetadata.is_post_processed`] flag.
+    ///
+    /// This will create an original copy first, if one does not already exist. The working copy is
+    /// only replaced once the step has finished successfully. `loudness_target` is only used by
+    /// [`PostProcessStepKind::NormalizeLoudness`].
+    pub fn post_process(&mut self, step: PostProcessStepKind, loudness_target: i32) -> Result<()> {
+        self.metadata.load_album_art(&self.path)?;
+        self.create_original_copy()?;
+
+        let mut metadata = self.metadata.clone();
+        metadata.is_post_processed = true;
+        let format = self.format();
+
+        replace_atomically(&self.path, |temp_path| {
+            std::fs::copy(&self.path, temp_path).at(&self.path)?;
+            step.step(loudness_target).process(temp_path, format, &mut metadata)?;
+            metadata.duration = probe_duration(temp_path).ok();
+            metadata.write_tag_into(temp_path, format)
+        })?;
+
+        self.metadata = metadata;
+        activity_log::record(self, Activity::PostProcessed, step.to_string());
+
+        Ok(())
+    }
+
+    /// Probes the duration of the working copy of this song. This is accomplished by shelling out
+    /// to ffprobe, which is distributed alongside ffmpeg.
+    pub fn duration(&self) -> Result<Duration> {
+        probe_duration(&self.path)
+    }
+
+    /// Modifies the working copy of this song to update its metadata to the current value of
+    /// [`self.metadata`], as well as setting the [`SongMetadata.is_metadata_edited`] flag to true.
+    /// 
+    /// This will create an original copy first, if one does not already exist.
+    pub fn user_edit_metadata(&mut self) -> Result<()> {
+        self.metadata.load_album_art(&self.path)?;
+        self.create_original_copy()?;
+
+        self.metadata.is_metadata_edited = true;
+        self.metadata.needs_metadata = false;
+        self.metadata.write_into_file(&self.path)?;
+
+        activity_log::record(self, Activity::MetadataEdited, format!("{} / {} / {}", self.metadata.title, self.metadata.artist, self.metadata.album));
+
+        Ok(())
+    }
+
+    /// Removes this song's album art, for when it's damaged and can't be decoded. Nothing else in
+    /// the tag is touched, but like any other edit, an original copy is kept first.
+    pub fn remove_album_art(&mut self) -> Result<()> {
+        self.create_original_copy()?;
+
+        let mut tag = read_tag(&self.path, self.format())?;
+        tag.remove_picture_by_type(PictureType::CoverFront);
+        tag.write_custom::<MetadataEditedTag>(true);
+        write_tag_atomically(&tag, &self.path)?;
+
+        self.metadata.album_art = None;
+        self.metadata.is_metadata_edited = true;
+
+        activity_log::record(self, Activity::MetadataEdited, "removed broken album art");
+
+        Ok(())
+    }
+
+    /// Pins or unpins this song, so that it is shown at the top of the song list.
+    /// 
+    /// Pinning is a user preference rather than a modification, so this writes the flag directly
+    /// into the working copy without creating an original copy or affecting [`is_modified`].
+    pub fn set_pinned(&mut self, pinned: bool) -> Result<()> {
+        let mut tag = read_tag(&self.path, self.format())?;
+        tag.write_custom::<PinnedTag>(pinned);
+        write_tag_atomically(&tag, &self.path)?;
+
+        self.metadata.is_pinned = pinned;
+
+        Ok(())
+    }
+
+    /// Flags this song as needing its metadata to be filled in, because it couldn't be looked up
+    /// when the song was downloaded. Like pinning, this writes directly into the working copy.
+    pub fn flag_needs_metadata(&mut self) -> Result<()> {
+        let mut tag = read_tag(&self.path, self.format())?;
+        tag.write_custom::<NeedsMetadataTag>(true);
+        write_tag_atomically(&tag, &self.path)?;
+
+        self.metadata.needs_metadata = true;
+
+        Ok(())
+    }
+
+    /// Marks this song as fine, so that it isn't listed as needing attention any more. Like pinning,
+    /// this writes directly into the working copy.
+    pub fn mark_reviewed(&mut self) -> Result<()> {
+        let mut tag = read_tag(&self.path, self.format())?;
+        tag.write_custom::<ReviewedTag>(true);
+        write_tag_atomically(&tag, &self.path)?;
+
+        self.metadata.is_reviewed = true;
+
+        Ok(())
+    }
+
+    /// The rules, out of those given, which this song breaks. Songs which have been marked as fine
+    /// never break any.
+    /// 
+    /// The duration rules have to probe the song with ffprobe, so this can be slow with them
+    /// enabled. If the duration can't be probed, those rules are skipped.
+    pub fn review(&self, rules: &[ReviewRule]) -> Vec<ReviewRule> {
+        if self.metadata.is_reviewed {
+            return vec![];
+        }
+
+        let duration = if rules.contains(&ReviewRule::TooShort) || rules.contains(&ReviewRule::TooLong) {
+            self.duration().ok()
+        } else {
+            None
+        };
+
+        rules.iter()
+            .copied()
+            .filter(|rule| match rule {
+                ReviewRule::TitleIsVideoId => self.metadata.title == self.metadata.youtube_id,
+                ReviewRule::MissingAlbumArt => self.metadata.album_art.is_none(),
+                ReviewRule::UnknownArtist => self.metadata.artist == UNKNOWN_ARTIST,
+                ReviewRule::TooShort => duration.map(|d| d < REVIEW_MIN_DURATION).unwrap_or(false),
+                ReviewRule::TooLong => duration.map(|d| d > REVIEW_MAX_DURATION).unwrap_or(false),
+            })
+            .collect()
+    }
+
+    /// Moves all copies of this song (working and original) out of the library, into its trash
+    /// folder. Returns the files which were moved, so that the deletion can be undone.
+    /// 
+    /// A deduplicated original is only moved if no other song refers to it.
+    pub fn delete(&mut self) -> Result<Vec<TrashedFile>> {
+        let library_path = self.library_path().to_owned();
+        let mut trashed = vec![];
+        trashed.extend(self.trash_original_copy()?);
+
+        // Don't leave the song without its original if the working copy can't be moved
+        match TrashedFile::trash(&self.path, &library_path, false) {
+            Ok(file) => trashed.push(file),
+            Err(e) => {
+                for file in &trashed {
+                    if let Err(restore_error) = file.restore() {
+                        println!("Could not put back {}: {}", file.path.to_string_lossy(), restore_error);
+                    }
+                }
+                return Err(e);
+            }
+        }
+
+        Ok(trashed)
+    }
+
+    /// Moves this song's original copy into the library's trash folder, if it has one and no other
+    /// song refers to it.
+    fn trash_original_copy(&self) -> Result<Option<TrashedFile>> {
+        if !self.original_copy_path().exists() {
+            return Ok(None);
+        }
+
+        let shared = match &self.metadata.original_hash {
+            Some(hash) => self.is_original_shared(hash)?,
+            None => false,
+        };
+        if shared {
+            return Ok(None);
+        }
+
+        TrashedFile::trash(&self.original_copy_path(), self.library_path(), false).map(Some)
+    }
+
+    /// Replaces the working copy of this song with a new download of it, like one in a better
+    /// format. The song's tags are carried over, but crops and post-processing can't be, and the
+    /// original copy is of the old download, so that's thrown away too.
+    ///
+    /// If the new download is in a different format, the song's file extension changes to match.
+    /// If anything goes wrong before the working copy is replaced, it's left as it was.
+    pub fn replace_with_download(&mut self, downloaded: &Path) -> Result<()> {
+        let format = format_of(downloaded)?;
+        self.metadata.load_album_art(&self.path)?;
+
+        let mut metadata = self.metadata.clone();
+        metadata.is_cropped = false;
+        metadata.is_post_processed = false;
+        metadata.original_hash = None;
+        metadata.duration = probe_duration(downloaded).ok();
+
+        let root_path = self.root_path().with_extension(format.extension());
+        let new_path: PathBuf = if self.hidden {
+            format!("{}.hidden", root_path.to_string_lossy()).into()
+        } else {
+            root_path
+        };
+        if new_path != self.path {
+            ensure_vacant(&new_path)?;
+        }
+
+        replace_atomically(&new_path, |temp_path| {
+            std::fs::rename(downloaded, temp_path).at(downloaded)?;
+            metadata.write_tag_into(temp_path, format)
+        })?;
+
+        // The song has already been replaced by now, so leftovers are reported rather than failing
+        match self.trash_original_copy() {
+            Ok(_) => (),
+            Err(e) => println!("[Library] Could not remove the old original of {}: {}", self.path.to_string_lossy(), e),
+        }
+        if new_path != self.path {
+            if let Err(e) = TrashedFile::trash(&self.path, self.library_path(), false) {
+                println!("[Library] Could not remove the old copy of {}: {}", self.path.to_string_lossy(), e);
+            }
+        }
+
+        self.path = new_path;
+        self.metadata = metadata;
+        activity_log::record(self, Activity::Redownloaded, format.extension());
+
+        Ok(())
+    }
+
+    /// Copies the working copy of this song into the library's trash folder, so that it can be put
+    /// back after being replaced, like by [`restore_original_copy`].
+    pub fn stash_working_copy(&self) -> Result<TrashedFile> {
+        TrashedFile::trash(&self.path, self.library_path(), true)
+    }
+
+    /// Moves this song out of the staging folder into its library, along with its sidecar
+    /// original if it has one. A deduplicated original is already kept with the library's, so it
+    /// stays where it is. If the song isn't staged, has no effect.
+    ///
+    /// The song list MUST be updated after this operation, or paths will break.
+    pub fn approve(mut self) -> Result<()> {
+        if !self.is_staged() { return Ok(()) }
+
+        let library_path = self.library_path().to_owned();
+        let new_path = library_path.join(self.path.file_name().expect("song has no file name"));
+        ensure_vacant(&new_path)?;
+
+        // Move the original first, so that the song is never in the library without it
+        let sidecar_path = self.sidecar_original_path();
+        let new_sidecar_path = library_path.join(sidecar_path.file_name().expect("original has no file name"));
+        let has_sidecar = sidecar_path.exists();
+        if has_sidecar {
+            ensure_vacant(&new_sidecar_path)?;
+            std::fs::rename(&sidecar_path, &new_sidecar_path).at(&sidecar_path)?;
+        }
+
+        if let Err(e) = std::fs::rename(&self.path, &new_path).at(&self.path) {
+            if has_sidecar {
+                if let Err(restore_error) = std::fs::rename(&new_sidecar_path, &sidecar_path) {
+                    println!("[Library] Could not put back {}: {}", sidecar_path.to_string_lossy(), restore_error);
+                }
+            }
+            return Err(e);
+        }
+
+        self.path = new_path;
+        activity_log::record(&self, Activity::Approved, "");
+
+        Ok(())
+    }
+}
+
+/// A file which was moved into a library's trash folder, and can be put back.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct TrashedFile {
+    /// Where the file was, and where it's put back to.
+    pub path: PathBuf,
+    trash_path: PathBuf,
+}
+
+impl TrashedFile {
+    /// Moves the file at `path` into the trash folder of the library at `library_path`, or copies
+    /// it there if `keep` is true.
+    fn trash(path: &Path, library_path: &Path, keep: bool) -> Result<Self> {
+        let trash_dir = library_path.join(TRASH_DIR);
+        std::fs::create_dir_all(&trash_dir).at(&trash_dir)?;
+
+        // The same song could be trashed more than once, so each file is named by when it was
+        // trashed too
+        let file_name = path.file_name().expect("trashed path has no file name").to_string_lossy();
+        let mut stamp = SystemTime::now().duration_since(SystemTime::UNIX_EPOCH).unwrap_or_default().as_nanos();
+        let mut trash_path = trash_dir.join(format!("{}-{}", stamp, file_name));
+        while trash_path.exists() {
+            stamp += 1;
+            trash_path = trash_dir.join(format!("{}-{}", stamp, file_name));
+        }
+
+        if keep {
+            std::fs::copy(path, &trash_path).at(path)?;
+        } else {
+            std::fs::rename(path, &trash_path).at(path)?;
+        }
+
+        Ok(Self { path: path.to_owned(), trash_path })
+    }
+
+    /// Moves the file back to where it was trashed from, replacing anything which is there now.
+    pub fn restore(&self) -> Result<()> {
+        if let Some(parent) = self.path.parent() {
+            std::fs::create_dir_all(parent).at(parent)?;
+        }
+        std::fs::rename(&self.trash_path, &self.path).at(&self.trash_path)
+    }
+
+    /// Deletes the file from the trash for good, once it can no longer be put back.
+    pub fn discard(&self) -> Result<()> {
+        std::fs::remove_file(&self.trash_path).at(&self.trash_path)
+    }
+}
+
+/// The album art of a song, which might not have been read into memory yet.
+#[derive(PartialEq, Eq, Debug, Clone)]
+pub enum AlbumArt {
+    /// The song has album art, but it is only read when needed, with [`Library::album_art_for`].
+    NotLoaded,
+    Loaded(Picture),
+}
+
+// The picture itself is far too big for the library index, so only that there is one is kept, and
+// it's read again when needed
+impl Serialize for AlbumArt {
+    fn serialize<S: Serializer>(&self, serializer: S) -> std::result::Result<S::Ok, S::Error> {
+        serializer.serialize_bool(true)
+    }
+}
+
+impl<'de> Deserialize<'de> for AlbumArt {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> std::result::Result<Self, D::Error> {
+        bool::deserialize(deserializer)?;
+        Ok(AlbumArt::NotLoaded)
+    }
+}
+
+#[derive(PartialEq, Eq, Debug, Clone, Serialize, Deserialize)]
+pub struct SongMetadata {
+    pub title: String,
+    pub artist: String,
+    pub album: String,
+
+    /// These are optional because, unlike the title, artist and album, CrossPlay has never written
+    /// them - so most songs won't have them.
+    pub genre: Option<String>,
+    pub year: Option<i32>,
+    pub track: Option<u32>,
+
+    /// The ID of the video this song was downloaded from. Songs which didn't come from YouTube have
+    /// the site's name in front, like "soundcloud:123456", so that they can't clash.
+    pub youtube_id: String,
+
+    /// The URL of the video this song was downloaded from, if it wasn't on YouTube. YouTube videos
+    /// are found by their ID instead.
+    pub source_url: Option<String>,
+
+    pub album_art: Option<AlbumArt>,
+
+    pub is_cropped: bool,
+    pub is_metadata_edited: bool,
+
+    /// Set once any post-processing step has been run on the song - see [`Song::post_process`].
+    pub is_post_processed: bool,
+
+    pub is_pinned: bool,
+
+    /// Set on songs whose metadata couldn't be looked up when they were downloaded, until their
+    /// metadata is edited.
+    pub needs_metadata: bool,
+
+    /// Set when the user has marked a song as fine, so that it isn't listed as needing attention
+    /// - see [`Song::review`].
+    pub is_reviewed: bool,
+
+    pub original_hash: Option<String>,
+    pub download_unix_time: u64,
+
+    /// The channel the song was downloaded from. Songs downloaded before this was recorded don't
+    /// have one.
+    pub uploader: Option<String>,
+
+    /// The title of the video the song was downloaded from, exactly as it was on YouTube. Songs
+    /// downloaded before this was recorded don't have one.
+    pub video_title: Option<String>,
+
+    /// If this song was split out of a video by its chapters, which chapter it is, counting from 1
+    /// - see [`Song::split_into_chapters`].
+    pub chapter: Option<u32>,
+
+    /// How long the song is, kept in the tag so that it doesn't need to be probed every time the
+    /// library loads. Songs downloaded before this was recorded don't have one.
+    pub duration: Option<Duration>,
+
+    /// Unsynchronised lyrics, kept in the tag's USLT frame so that players can show them.
+    pub lyrics: Option<String>,
+}
+
+impl SongMetadata {
+    fn get_album_art(tag: &Tag) -> Option<&Picture> {
+        tag.frames().find_map(|f|
+            f.content().picture().filter(|picture| picture.picture_type == PictureType::CoverFront)
+        )
+    }
+
+    /// Reads the album art from the given file into memory, if it hasn't been already.
+    pub fn load_album_art(&mut self, path: &Path) -> Result<()> {
+        if let Some(AlbumArt::NotLoaded) = self.album_art {
+            self.album_art = Library::album_art_for(path)?.map(AlbumArt::Loaded);
+        }
+
+        Ok(())
     }
-    
-    /// Iterates over all loaded songs.
-    /// 
-    /// You must call [`load_songs`] before this.
-    pub fn songs(&self) -> impl Iterator<Item = &Song> {
-        self.loaded_songs.iter()
+
+    /// Returns the album art, if it has been loaded.
+    pub fn loaded_album_art(&self) -> Option<&Picture> {
+        match &self.album_art {
+            Some(AlbumArt::Loaded(picture)) => Some(picture),
+            _ => None,
+        }
     }
 
-    /// Reloads the list of songs in this library.
-    /// 
-    /// For a song to be loaded, it must:
-    ///   - Be in the root of the library folder
-    ///   - Be an MP3 file with a .mp3 extension
-    ///   - Have a CrossPlay video ID comment in its ID3 tags
-    pub fn load_songs(&mut self) -> Result<()> {
-        // Look for MP3 files at the root of the directory
-        self.loaded_songs.clear();
-        let entries = read_dir(&self.path)?;
-
-        for entry in entries {
-            let entry = entry?;
-            let path = entry.path();
+    /// Whether the standard tags, which a user could edit in CrossPlay or another program, are the
+    /// same as another song's.
+    pub fn same_user_tags(&self, other: &SongMetadata) -> bool {
+        self.title == other.title
+            && self.artist == other.artist
+            && self.album == other.album
+            && self.genre == other.genre
+            && self.year == other.year
+            && self.track == other.track
+    }
 
-            let extension = path.extension().map(|s| s.to_ascii_lowercase());
-            if extension == Some("mp3".into()) || extension == Some("hidden".into()) {
-                let tag = Tag::read_from_path(&path);
-                let hidden = extension == Some("hidden".into());
-        
-                // If there's no video ID, then this didn't come from CrossPlay, so ignore it
-                if let Ok(tag) = tag {
-                    if let Ok(metadata) = Self::load_one_song_metadata(tag) {
-                        self.loaded_songs.push(Song::new(path, metadata, hidden));
-                    }
-                }
-            }
+    fn write_into_tag(&self, tag: &mut Tag) {
+        // Unpacking here looks a bit weird, but it ensures that new fields will cause an error if
+        // we forget to consider saving them
+        let Self { title, artist, album, genre, year, track, youtube_id, source_url, album_art, is_cropped, is_metadata_edited, is_post_processed, is_pinned, needs_metadata, is_reviewed, original_hash, download_unix_time, uploader, video_title, chapter, duration, lyrics } = self;
+
+        tag.set_title(title.clone());
+        tag.set_artist(artist.clone());
+        tag.set_album(album.clone());
+        match genre {
+            Some(genre) => tag.set_genre(genre.clone()),
+            None => tag.remove_genre(),
+        }
+        // A recording date would take the place of a removed year when the tag is next read
+        tag.remove("TDRC");
+        match year {
+            Some(year) => tag.set_year(*year),
+            None => tag.remove_year(),
+        }
+        match track {
+            Some(track) => tag.set_track(*track),
+            None => tag.remove_track(),
+        }
+        match duration {
+            Some(duration) => tag.set_duration(duration.as_millis() as u32),
+            None => tag.remove_duration(),
+        }
+        tag.remove("USLT");
+        if let Some(lyrics) = lyrics {
+            tag.add_frame(Lyrics { lang: "eng".into(), description: String::new(), text: lyrics.clone() });
+        }
+        if let Some(AlbumArt::Loaded(album_art)) = album_art.clone() {
+            // Replaced art might have a different description, so it wouldn't replace the old
+            // frame by itself
+            tag.remove_picture_by_type(PictureType::CoverFront);
+            tag.add_frame(album_art);
         }
 
-        Ok(())
+        match source_url {
+            Some(url) => {
+                tag.write_custom::<SourceUrlTag>(Some(url.clone()));
+                tag.write_custom::<SourceIdTag>(Some(youtube_id.clone()));
+            }
+            None => tag.write_custom::<YouTubeIdTag>(youtube_id.to_string()),
+        }
+        tag.write_custom::<DownloadTimeTag>(*download_unix_time);
+        tag.write_custom::<UploaderTag>(uploader.clone());
+        tag.write_custom::<VideoTitleTag>(video_title.clone());
+        tag.write_custom::<ChapterTag>(*chapter);
+        tag.write_custom::<CroppedTag>(*is_cropped);
+        tag.write_custom::<MetadataEditedTag>(*is_metadata_edited);
+        tag.write_custom::<PostProcessedTag>(*is_post_processed);
+        tag.write_custom::<PinnedTag>(*is_pinned);
+        tag.write_custom::<NeedsMetadataTag>(*needs_metadata);
+        tag.write_custom::<ReviewedTag>(*is_reviewed);
+        tag.write_custom::<OriginalHashTag>(original_hash.clone());
     }
 
-    fn load_one_song_metadata(tag: Tag) -> Result<SongMetadata> {            
-        Ok(SongMetadata {
-            title: tag.title().unwrap_or("Unknown Title").into(),
-            artist: tag.artist().unwrap_or("Unknown Artist").into(),
-            album: tag.album().unwrap_or("Unknown Album").into(),
-            youtube_id: tag.read_custom::<YouTubeIdTag>()?,
-            album_art: SongMetadata::get_album_art(&tag),
-            is_cropped: tag.read_custom::<CroppedTag>()?,
-            is_metadata_edited: tag.read_custom::<MetadataEditedTag>()?,
-            download_unix_time: tag.read_custom::<DownloadTimeTag>()?,
+    /// Replaces the tag of the given file with this metadata. The file is never left half-written,
+    /// even if CrossPlay is killed part-way through.
+    pub(crate) fn write_into_file(&self, file: &Path) -> Result<()> {
+        let format = format_of(file)?;
+        replace_atomically(file, |temp_path| {
+            std::fs::copy(file, temp_path).at(file)?;
+            self.write_tag_into(temp_path, format)
         })
     }
+
+    /// Replaces the tag of the given file with this metadata, writing directly into the file.
+    fn write_tag_into(&self, file: &Path, format: AudioFormat) -> Result<()> {
+        // The new tag replaces the old one entirely, so writing now would lose the album art
+        assert!(
+            !matches!(self.album_art, Some(AlbumArt::NotLoaded)),
+            "album art must be loaded before writing metadata",
+        );
+
+        let mut tag = Tag::new();
+        self.write_into_tag(&mut tag);
+        write_tag(&tag, file, format)
+    }
 }
 
-/// A song loaded from a library.
-#[derive(PartialEq, Eq, Debug, Clone)]
-pub struct Song {
-    /// The path to the working copy of this song, possibly modified.
-    pub path: PathBuf,
+#[cfg(test)]
+pub(crate) mod tests {
+    use std::io::Write;
 
-    /// This song's metadata, loaded from ID3 tags.
-    pub metadata: SongMetadata,
+    use super::*;
 
-    /// Whether this song is hidden. This is separate from the metadata since it is encoded by the
-    /// file extension, not ID3 tags.
-    hidden: bool,
-}
+    /// Metadata for a song downloaded from the given video, with only the required tags set.
+    pub(crate) fn metadata(youtube_id: &str) -> SongMetadata {
+        SongMetadata {
+            title: format!("Title of {}", youtube_id),
+            artist: "Artist".into(),
+            album: "Album".into(),
+            genre: None,
+            year: None,
+            track: None,
+            youtube_id: youtube_id.into(),
+            source_url: None,
+            album_art: None,
+            is_cropped: false,
+            is_metadata_edited: false,
+            is_post_processed: false,
+            is_pinned: false,
+            needs_metadata: false,
+            is_reviewed: false,
+            original_hash: None,
+            download_unix_time: 0,
+            uploader: None,
+            video_title: None,
+            chapter: None,
+            duration: None,
+            lyrics: None,
+        }
+    }
 
-impl Song {
-    /// Creates a new reference to a song on-disk.
-    fn new(path: PathBuf, metadata: SongMetadata, hidden: bool) -> Self {
-        Self { path, metadata, hidden }
+    /// A song at the given path, in a library at `/library`. Nothing is read from disk, so the file
+    /// doesn't need to exist.
+    pub(crate) fn song(path: &str, youtube_id: &str) -> Song {
+        Song::new(PathBuf::from(path), metadata(youtube_id), false, OriginalStorage::Sidecar, PathBuf::from("/library"))
     }
 
-    /// The path to this song assuming it is not hidden.
-    /// 
-    /// If the song is already not hidden, then this will be the same as the current path.
-    pub fn root_path(&self) -> PathBuf {
-        if self.hidden {
-            // Strip the ".hidden" off the end first
-            self.path.with_extension("")
-        } else {
-            self.path.clone()
+    /// An empty folder for a test's files, which is deleted when dropped. Each test should use a
+    /// different name, since tests run at the same time.
+    pub(crate) struct TestDir(pub PathBuf);
+
+    impl TestDir {
+        pub(crate) fn new(name: &str) -> Self {
+            let path = std::env::temp_dir().join(format!("crossplay-test-{}-{}", std::process::id(), name));
+            let _ = std::fs::remove_dir_all(&path);
+            std::fs::create_dir_all(&path).unwrap();
+            Self(path)
         }
     }
 
-    /// Whether the current song is hidden.
-    pub fn is_hidden(&self) -> bool {
-        self.hidden
+    impl Drop for TestDir {
+        fn drop(&mut self) {
+            let _ = std::fs::remove_dir_all(&self.0);
+        }
     }
 
-    /// The path to this song if/when it is hidden.
-    /// 
-    /// If the song is already hidden, then this will be the same as the current path.
-    pub fn hidden_path(&self) -> PathBuf {
-        if self.hidden {
-            self.path.clone()
-        } else {
-            format!("{}.hidden", self.path.to_string_lossy()).into()
-        }
+    #[test]
+    fn test_replace_atomically_replaces_target() {
+        let dir = TestDir::new("replace-succeeds");
+        let path = dir.0.join("song.mp3");
+        std::fs::write(&path, b"old").unwrap();
+
+        replace_atomically(&path, |temp_path| std::fs::write(temp_path, b"new").at(temp_path)).unwrap();
+
+        assert_eq!(std::fs::read(&path).unwrap(), b"new");
+        assert_eq!(read_dir(&dir.0).unwrap().count(), 1);
     }
 
-    /// The path where the original of this song will be copied to, before any modifications take
-    /// place.
-    /// 
-    /// This will not exist if the song has not been modified (and thus [`create_original_copy`] has
-    /// not been called).
-    fn original_copy_path(&self) -> PathBuf {
-        format!("{}.original", self.root_path().to_string_lossy()).into()
+    #[test]
+    fn test_replace_atomically_failure_leaves_target_intact() {
+        let dir = TestDir::new("replace-fails");
+        let path = dir.0.join("song.mp3");
+        std::fs::write(&path, b"old").unwrap();
+
+        // Fail part-way through, once something has been written to the temporary file
+        let result = replace_atomically(&path, |temp_path| {
+            std::fs::write(temp_path, b"half").at(temp_path)?;
+            Err(LibraryError::FfmpegFailed { stderr: "failed".into() })
+        });
+
+        assert!(matches!(result, Err(LibraryError::FfmpegFailed { .. })));
+        assert_eq!(std::fs::read(&path).unwrap(), b"old");
+        assert_eq!(read_dir(&dir.0).unwrap().count(), 1, "temporary file was left behind");
     }
 
-    /// Creates an original copy of this song, if one does not already exist. It is the caller's
-    /// responsibility to ensure this is called before modifying the file at the song's [`path`].
-    fn create_original_copy(&self) -> Result<()> {
-        if self.original_copy_path().exists() { return Ok(()) }
-        std::fs::copy(&self.path, self.original_copy_path())?;
+    #[test]
+    fn test_review_flags_placeholder_metadata() {
+        let mut song = song("/library/abc.mp3", "abc");
+        song.metadata.title = "abc".into();
+        song.metadata.artist = UNKNOWN_ARTIST.into();
 
-        Ok(())
+        assert_eq!(
+            song.review(&[ReviewRule::TitleIsVideoId, ReviewRule::MissingAlbumArt, ReviewRule::UnknownArtist]),
+            vec![ReviewRule::TitleIsVideoId, ReviewRule::MissingAlbumArt, ReviewRule::UnknownArtist],
+        );
+
+        // Only the rules asked about are checked
+        assert_eq!(song.review(&[ReviewRule::UnknownArtist]), vec![ReviewRule::UnknownArtist]);
+        assert_eq!(song.review(&[]), vec![]);
     }
 
-    /// Restores the original copy of this song, replacing the working copy. The original copy is
-    /// left intact.
-    /// 
-    /// Errors if an original does not exist.
-    pub fn restore_original_copy(&self) -> Result<()> {
-        std::fs::copy(self.original_copy_path(), &self.path)?;
-        Ok(())
+    #[test]
+    fn test_review_passes_complete_song() {
+        let mut song = song("/library/abc.mp3", "abc");
+        song.metadata.album_art = Some(AlbumArt::NotLoaded);
+
+        assert_eq!(song.review(&[ReviewRule::TitleIsVideoId, ReviewRule::MissingAlbumArt, ReviewRule::UnknownArtist]), vec![]);
     }
 
-    /// Returns true if this song's metadata indicates that it has been modified from the original.
-    pub fn is_modified(&self) -> bool {
-        self.metadata.is_cropped || self.metadata.is_metadata_edited
+    #[test]
+    fn test_review_skips_songs_marked_as_fine() {
+        let mut song = song("/library/abc.mp3", "abc");
+        song.metadata.title = "abc".into();
+        song.metadata.is_reviewed = true;
+
+        assert_eq!(song.review(&ReviewRule::ALL), vec![]);
     }
 
-    /// Hides this song. If the song is already hidden, has no effect.
-    /// 
-    /// The song list MUST be updated after this operation, or paths will break.
-    pub fn hide(mut self) -> Result<()> {
-        if self.hidden { return Ok(()) }
+    #[test]
+    fn test_review_skips_duration_rules_when_unprobeable() {
+        // The file doesn't exist, so its duration can't be probed
+        let song = song("/library/missing.mp3", "abc");
 
-        // Move to hidden path
-        let hidden_path = self.hidden_path();
-        std::fs::rename(&self.path, &hidden_path)?;
+        assert_eq!(song.review(&[ReviewRule::TooShort, ReviewRule::TooLong]), vec![]);
+    }
 
-        // Update path on self
-        self.path = hidden_path;
+    #[test]
+    fn test_has_extension_ignores_case() {
+        assert!(has_extension(Path::new("song.mp3"), "mp3"));
+        assert!(has_extension(Path::new("Song.MP3"), "mp3"));
+        assert!(has_extension(Path::new("song.Mp3.HIDDEN"), "hidden"));
 
-        Ok(())
+        assert!(!has_extension(Path::new("song.mp3.hidden"), "mp3"));
+        assert!(!has_extension(Path::new("mp3"), "mp3"));
     }
 
-    /// Unhides this song. If the song is already not hidden, has no effect.
-    /// 
-    /// The song list MUST be updated after this operation, or paths will break.
-    pub fn unhide(mut self) -> Result<()> {
-        if !self.hidden { return Ok(()) }
+    #[test]
+    fn test_hide_refuses_to_clobber_existing_file() {
+        let dir = TestDir::new("hide-clobber");
+        let path = dir.0.join("Song.MP3");
+        let hidden_path = dir.0.join("Song.MP3.hidden");
+        std::fs::write(&path, b"song").unwrap();
+        std::fs::write(&hidden_path, b"other").unwrap();
 
-        // Move away from hidden path
-        let new_path = self.root_path();
-        std::fs::rename(&self.path, &new_path)?;
+        let song = Song::new(path.clone(), metadata("abc"), false, OriginalStorage::Sidecar, dir.0.clone());
+        assert!(matches!(song.hide(), Err(LibraryError::AlreadyExists(p)) if p == hidden_path));
 
-        // Update path on self
-        self.path = new_path;
+        assert_eq!(std::fs::read(&path).unwrap(), b"song");
+        assert_eq!(std::fs::read(&hidden_path).unwrap(), b"other");
+    }
 
-        Ok(())
+    #[test]
+    fn test_diff_finds_added_and_removed_songs() {
+        let kept = song("/library/kept.mp3", "kept");
+        let removed = song("/library/removed.mp3", "removed");
+        let added = song("/library/added.mp3", "added");
+
+        let diff = LibraryDiff::between(&[kept.clone(), removed.clone()], &[kept, added.clone()]);
+        assert_eq!(diff.added, vec![added]);
+        assert_eq!(diff.removed, vec![removed]);
+        assert!(diff.changed.is_empty());
     }
 
-    /// Modifies the working copy of this song to start and end at the selected points. This is
-    /// accomplished by shelling out to ffmpeg.
-    /// 
-    /// Also sets the [`SongMetadata.is_cropped`] flag to true, and re-writes metadata to the
-    /// working copy.
-    /// 
-    /// This will create an original copy first, if one does not already exist.
-    pub fn crop(&mut self, start: Duration, end: Duration) -> Result<()> {
-        self.create_original_copy()?;
+    #[test]
+    fn test_diff_finds_edited_tags() {
+        let old = song("/library/abc.mp3", "abc");
+        let mut new = old.clone();
+        new.metadata.artist = "Another Artist".into();
 
-        // TODO: There are probably pure-Rust libraries for this, look into using those
-        // TODO: should this be async like downloads are?
-        println!("Starting FFMPEG...");
+        let diff = LibraryDiff::between(&[old], &[new.clone()]);
+        assert_eq!(diff.changed, vec![new]);
+        assert!(diff.added.is_empty() && diff.removed.is_empty());
+    }
 
-        let output = Command::new("ffmpeg")
-            .arg("-ss")
-            .arg((start.as_secs_f64()).to_string())
-            .arg("-to")
-            .arg((end.as_secs_f64()).to_string())
-            .arg("-i")
-            .arg(self.original_copy_path())
-            .arg("-y")
-            .arg("-acodec")
-            .arg("copy")
-            .arg(&self.path)
-            .output()?;
+    #[test]
+    fn test_diff_ignores_crossplay_tags() {
+        let old = song("/library/abc.mp3", "abc");
+        let mut new = old.clone();
+        new.metadata.is_cropped = true;
+        new.metadata.is_pinned = true;
+        new.metadata.original_hash = Some("hash".into());
+        new.metadata.duration = Some(Duration::from_secs(60));
 
-        println!("FFMPEG is done!");
+        assert!(LibraryDiff::between(&[old], &[new]).is_empty());
+    }
 
-        // Check success
-        output.status.exit_ok()?;
+    #[test]
+    fn test_diff_ignores_hiding() {
+        let old = song("/library/abc.mp3", "abc");
+        let new = Song::new(PathBuf::from("/library/abc.mp3.hidden"), metadata("abc"), true, OriginalStorage::Sidecar, PathBuf::from("/library"));
 
-        self.metadata.is_cropped = true;
-        self.metadata.write_into_file(&self.path)?;
+        assert!(LibraryDiff::between(&[old], &[new]).is_empty());
+    }
 
-        Ok(())
+    #[test]
+    fn test_empty_cut_output_is_rejected() {
+        let dir = TestDir::new("empty-cut");
+        let path = dir.0.join("cut.mp3");
+        std::fs::write(&path, b"").unwrap();
+
+        let result = check_cut_output(&SystemProcesses, &path, "ffmpeg's output");
+        assert!(matches!(result, Err(LibraryError::FfmpegFailed { stderr }) if stderr.contains("0 bytes") && stderr.contains("ffmpeg's output")));
     }
 
-    /// Modifies the working copy of this song to update its metadata to the current value of
-    /// [`self.metadata`], as well as setting the [`SongMetadata.is_metadata_edited`] flag to true.
-    /// 
-    /// This will create an original copy first, if one does not already exist.
-    pub fn user_edit_metadata(&mut self) -> Result<()> {
-        self.create_original_copy()?;
+    #[test]
+    fn test_unplayable_cut_output_is_rejected() {
+        let dir = TestDir::new("unplayable-cut");
+        let path = dir.0.join("cut.mp3");
+        std::fs::write(&path, vec![0; MIN_CUT_OUTPUT_BYTES as usize * 2]).unwrap();
 
-        self.metadata.is_metadata_edited = true;
-        self.metadata.write_into_file(&self.path)?;
+        assert!(matches!(check_cut_output(&SystemProcesses, &path, ""), Err(LibraryError::FfmpegFailed { .. })));
+    }
 
-        Ok(())
+    #[test]
+    fn test_failed_crop_leaves_song_intact() {
+        let dir = TestDir::new("failed-crop");
+        let path = dir.0.join("song.mp3");
+        let contents = vec![0; MIN_CUT_OUTPUT_BYTES as usize * 2];
+        std::fs::write(&path, &contents).unwrap();
+
+        // There's no audio to cut, so ffmpeg either fails or writes nothing playable
+        let mut song = Song::new(path.clone(), metadata("abc"), false, OriginalStorage::Sidecar, dir.0.clone());
+        let result = song.crop(&[(Duration::from_secs(1), Duration::from_secs(2))], Duration::ZERO, Duration::ZERO);
+
+        assert!(matches!(result, Err(LibraryError::FfmpegFailed { .. })));
+        assert!(!song.metadata.is_cropped);
+        assert_eq!(std::fs::read(&path).unwrap(), contents);
+        assert_eq!(std::fs::read(song.original_copy_path()).unwrap(), contents);
+    }
+
+    #[test]
+    fn test_unknown_modified_time_counts_as_changed() {
+        let dir = TestDir::new("unknown-mtime");
+        let path = dir.0.join("song.mp3");
+        std::fs::write(&path, b"song").unwrap();
+        let song = Song::new(path.clone(), metadata("abc"), false, OriginalStorage::Sidecar, dir.0.clone());
+
+        assert_eq!(song.change_since(song.modified_time().ok()), SongFileChange::Unchanged);
+        assert_eq!(song.change_since(None), SongFileChange::Modified);
+
+        std::fs::remove_file(&path).unwrap();
+        assert_eq!(song.change_since(None), SongFileChange::Deleted);
+    }
+
+    /// Stands in for an ffmpeg which exits successfully having written nothing. If `truncate` is
+    /// set, that file is emptied too, like an ffmpeg which wrote over the wrong file.
+    struct EmptyOutputFfmpeg {
+        truncate: Option<PathBuf>,
     }
 
-    /// Deletes all copies of this song (working and original) from the library folder on disk.
-    pub fn delete(&mut self) -> Result<()> {
-        if self.original_copy_path().exists() {
-            std::fs::remove_file(self.original_copy_path())?;
+    impl ProcessRunner for EmptyOutputFfmpeg {
+        fn output(&self, command: &mut Command) -> io::Result<ProcessOutput> {
+            // ffmpeg's output is always its last argument
+            let output = command.get_args().last().map(PathBuf::from).unwrap();
+            std::fs::write(output, b"")?;
+            if let Some(path) = &self.truncate {
+                std::fs::write(path, b"")?;
+            }
+            Ok(ProcessOutput { success: true, stdout: vec![], stderr: b"fake ffmpeg".to_vec() })
         }
-        std::fs::remove_file(&self.path)?;
+    }
 
-        Ok(())
+    /// A tagged song file at `song.mp3` in the given folder, with nothing playable in it.
+    fn tagged_song(dir: &TestDir) -> Song {
+        let path = dir.0.join("song.mp3");
+        std::fs::write(&path, vec![0; MIN_CUT_OUTPUT_BYTES as usize * 2]).unwrap();
+        metadata("abc").write_into_file(&path).unwrap();
+        Song::new(path, metadata("abc"), false, OriginalStorage::Sidecar, dir.0.clone())
     }
-}
 
-#[derive(PartialEq, Eq, Debug, Clone)]
-pub struct SongMetadata {
-    pub title: String,
-    pub artist: String,
-    pub album: String,
-    pub youtube_id: String,
-    pub album_art: Option<Picture>,
+    #[test]
+    fn test_crop_with_empty_output_leaves_song_intact() {
+        let dir = TestDir::new("crop-empty-output");
+        let mut song = tagged_song(&dir);
+        let contents = std::fs::read(&song.path).unwrap();
 
-    pub is_cropped: bool,
-    pub is_metadata_edited: bool,
-    pub download_unix_time: u64,
-}
+        let ffmpeg = EmptyOutputFfmpeg { truncate: None };
+        let result = song.crop_with(&ffmpeg, &[(Duration::from_secs(1), Duration::from_secs(2))], Duration::ZERO, Duration::ZERO);
 
-impl SongMetadata {
-    fn get_album_art(tag: &Tag) -> Option<Picture> {
-        tag.frames().find_map(|f|
-            if let Some(picture) = f.content().picture() {
-                if picture.picture_type == PictureType::CoverFront {
-                    Some(picture.clone())
-                } else {
-                    None
-                }
-            } else {
-                None
-            }
-        )
+        assert!(matches!(result, Err(LibraryError::FfmpegFailed { stderr }) if stderr.contains("fake ffmpeg")));
+        assert_eq!(song.metadata, metadata("abc"));
+        assert_eq!(std::fs::read(&song.path).unwrap(), contents);
+        assert_eq!(std::fs::read(song.original_copy_path()).unwrap(), contents);
     }
 
-    fn write_into_tag(&self, tag: &mut Tag) {
-        // Unpacking here looks a bit weird, but it ensures that new fields will cause an error if
-        // we forget to consider saving them
-        let Self { title, artist, album, youtube_id, album_art, is_cropped, is_metadata_edited, download_unix_time } = self;
+    #[test]
+    fn test_crop_which_empties_song_restores_original() {
+        let dir = TestDir::new("crop-emptied-song");
+        let mut song = tagged_song(&dir);
 
-        tag.set_title(title.clone());
-        tag.set_artist(artist.clone());
-        tag.set_album(album.clone());
-        if let Some(album_art) = album_art.clone() {
-            tag.add_frame(album_art);
+        let ffmpeg = EmptyOutputFfmpeg { truncate: Some(song.path.clone()) };
+        let result = song.crop_with(&ffmpeg, &[(Duration::from_secs(1), Duration::from_secs(2))], Duration::ZERO, Duration::ZERO);
+
+        assert!(matches!(result, Err(LibraryError::FfmpegFailed { .. })));
+        assert_eq!(song.metadata, metadata("abc"));
+        assert!(std::fs::metadata(&song.path).unwrap().len() > 0);
+        let tag = Tag::read_from_path(&song.path).unwrap();
+        assert_eq!(tag.title(), Some("Title of abc"));
+        assert!(!tag.read_custom::<CroppedTag>().unwrap());
+    }
+
+    #[test]
+    fn test_failed_pin_leaves_song_unpinned() {
+        let dir = TestDir::new("failed-pin");
+        let mut song = tagged_song(&dir);
+        std::fs::remove_file(&song.path).unwrap();
+
+        assert!(song.set_pinned(true).is_err());
+        assert!(!song.metadata.is_pinned);
+    }
+
+    /// The stamp of an imported song's file, with the given size.
+    fn stamp(size: u64) -> FileStamp {
+        FileStamp { size, modified: Some(SystemTime::UNIX_EPOCH + Duration::from_secs(1_000_000)) }
+    }
+
+    #[test]
+    fn test_diff_finds_move_by_video_id() {
+        let old = song("/library/old name.mp3", "abc");
+        let new = song("/library/new name.mp3", "abc");
+        let other = song("/library/other.mp3", "def");
+
+        let mut diff = LibraryDiff::between(&[old.clone()], &[new.clone(), other.clone()]);
+        diff.find_moves(&HashMap::new(), &HashMap::new());
+
+        assert_eq!(diff.moved, vec![(old, new)]);
+        assert!(diff.removed.is_empty());
+        assert_eq!(diff.added, vec![other]);
+    }
+
+    #[test]
+    fn test_diff_finds_move_of_imported_song_by_stamp() {
+        // Imported songs have no video ID
+        let old = song("/library/old name.mp3", "");
+        let new = song("/library/new name.mp3", "");
+        let replaced = song("/library/replaced.mp3", "");
+        let replacement = song("/library/replacement.mp3", "");
+
+        let old_stamps = HashMap::from([(old.path.clone(), stamp(100)), (replaced.path.clone(), stamp(200))]);
+        let new_stamps = HashMap::from([(new.path.clone(), stamp(100)), (replacement.path.clone(), stamp(300))]);
+
+        let mut diff = LibraryDiff::between(&[old.clone(), replaced.clone()], &[new.clone(), replacement.clone()]);
+        diff.find_moves(&old_stamps, &new_stamps);
+
+        // Only the files with the same size and modified time are the same song
+        assert_eq!(diff.moved, vec![(old, new)]);
+        assert_eq!(diff.removed, vec![replaced]);
+        assert_eq!(diff.added, vec![replacement]);
+    }
+
+    #[test]
+    fn test_diff_does_not_guess_between_identical_songs() {
+        let old = song("/library/old.mp3", "abc");
+        let first = song("/library/first copy.mp3", "abc");
+        let second = song("/library/second copy.mp3", "abc");
+
+        let mut diff = LibraryDiff::between(&[old.clone()], &[first.clone(), second.clone()]);
+        diff.find_moves(&HashMap::new(), &HashMap::new());
+
+        assert!(diff.moved.is_empty());
+        assert_eq!(diff.removed, vec![old]);
+        assert_eq!(diff.added, vec![first, second]);
+    }
+
+    #[test]
+    fn test_match_moves_skips_ambiguous_and_unknown_identities() {
+        let id = |id: &str| Some(SongIdentity::Downloaded { id: id.into(), chapter: None });
+
+        assert_eq!(match_moves(&[id("a"), id("b")], &[id("b"), id("a")]), vec![(0, 1), (1, 0)]);
+        assert_eq!(match_moves(&[id("a"), id("a")], &[id("a")]), vec![]);
+        assert_eq!(match_moves(&[None], &[None]), vec![]);
+
+        // Chapters of the same video are different songs
+        let chapter = |n| Some(SongIdentity::Downloaded { id: "a".into(), chapter: Some(n) });
+        assert_eq!(match_moves(&[chapter(1), chapter(2)], &[chapter(2)]), vec![(1, 0)]);
+    }
+
+    #[test]
+    fn test_rescan_only_reads_changed_files() {
+        let dir = TestDir::new("rescan");
+        let unchanged_path = dir.0.join("unchanged.mp3");
+        let changed_path = dir.0.join("changed.mp3");
+        for (path, id) in [(&unchanged_path, "abc"), (&changed_path, "def")] {
+            std::fs::write(path, vec![0; 1024]).unwrap();
+            metadata(id).write_tag_into(path, AudioFormat::Mp3).unwrap();
         }
 
-        tag.write_custom::<YouTubeIdTag>(youtube_id.to_string());
-        tag.write_custom::<DownloadTimeTag>(*download_unix_time);
-        tag.write_custom::<CroppedTag>(*is_cropped);
-        tag.write_custom::<MetadataEditedTag>(*is_metadata_edited);
+        let mut library = Library::new(dir.0.clone());
+        library.apply_scan(library.scanner().scan().unwrap());
+        assert_eq!(library.songs().count(), 2);
+        let stamps_before = library.loaded_file_stamps.clone();
+
+        // Change the loaded songs without touching their files, so it's clear which are read again
+        for song in &mut library.loaded_songs {
+            song.metadata.title = "Not read again".into();
+        }
+        std::fs::OpenOptions::new().append(true).open(&changed_path).unwrap().write_all(&[0; 1024]).unwrap();
+
+        library.apply_scan(library.scanner().scan().unwrap());
+        let title_of = |path: &Path| library.songs().find(|s| s.path == path).unwrap().metadata.title.clone();
+
+        assert_eq!(title_of(&unchanged_path), "Not read again");
+        assert_eq!(library.loaded_file_stamps[&unchanged_path], stamps_before[&unchanged_path]);
+
+        assert_eq!(title_of(&changed_path), "Title of def");
+        assert_ne!(library.loaded_file_stamps[&changed_path], stamps_before[&changed_path]);
     }
 
-    pub(crate) fn write_into_file(&self, file: &Path) -> Result<()> {
+    #[test]
+    fn test_error_for_missing_song() {
+        let dir = TestDir::new("missing-song");
+        let path = dir.0.join("missing.mp3");
+
+        let result = Library::new(dir.0.clone()).load_song(&path);
+        assert!(matches!(result, Err(LibraryError::NotFound(p)) if p == path));
+    }
+
+    #[test]
+    fn test_error_for_unreadable_tag() {
+        let dir = TestDir::new("unreadable-tag");
+        let path = dir.0.join("garbage.mp3");
+        std::fs::write(&path, b"this is not an MP3").unwrap();
+
+        let result = Library::new(dir.0.clone()).load_song(&path);
+        assert!(matches!(result, Err(LibraryError::TagParse { path: p, .. }) if p == path));
+    }
+
+    #[test]
+    fn test_error_for_song_not_from_crossplay() {
+        let dir = TestDir::new("not-from-crossplay");
+        let path = dir.0.join("other.mp3");
+        std::fs::write(&path, b"").unwrap();
         let mut tag = Tag::new();
-        self.write_into_tag(&mut tag);
-        Tag::write_to_path(&tag, file, id3::Version::Id3v23)?;
-        Ok(())
+        tag.set_title("Downloaded elsewhere");
+        tag.write_to_path(&path, id3::Version::Id3v23).unwrap();
+
+        let result = Library::new(dir.0.clone()).load_song(&path);
+        assert!(matches!(result, Err(LibraryError::TagParse { .. })));
+    }
+
+    #[test]
+    fn test_error_from_io() {
+        let path = Path::new("/library/song.mp3");
+        let error = |kind: io::ErrorKind| LibraryError::from_io(path, io::Error::from(kind));
+
+        assert!(matches!(error(io::ErrorKind::NotFound), LibraryError::NotFound(_)));
+        assert!(matches!(error(io::ErrorKind::PermissionDenied), LibraryError::PermissionDenied(_)));
+        assert!(matches!(error(io::ErrorKind::AlreadyExists), LibraryError::AlreadyExists(_)));
+        assert!(matches!(error(io::ErrorKind::Other), LibraryError::Io { .. }));
+    }
+
+    #[test]
+    fn test_only_busy_errors_are_retryable() {
+        #[cfg(windows)]
+        const SHARING_VIOLATION: i32 = 32;
+        #[cfg(not(windows))]
+        const SHARING_VIOLATION: i32 = 16;
+
+        let busy = LibraryError::from_io(Path::new("/library/song.mp3"), io::Error::from_raw_os_error(SHARING_VIOLATION));
+        assert!(matches!(busy, LibraryError::Busy(_)));
+        assert!(busy.is_retryable());
+
+        assert!(!LibraryError::NotFound(PathBuf::from("/library/song.mp3")).is_retryable());
+        assert!(!LibraryError::FfmpegFailed { stderr: String::new() }.is_retryable());
+    }
+
+    #[test]
+    fn test_change_in_nested_folder_makes_library_stale() {
+        let dir = TestDir::new("nested-stale");
+        let album_path = dir.0.join("Artist").join("Album");
+        std::fs::create_dir_all(&album_path).unwrap();
+
+        let mut library = Library::new(dir.0.clone());
+        library.scan_recursively = true;
+        library.apply_scan(library.scanner().scan().unwrap());
+        assert!(!library.is_stale());
+
+        std::fs::write(album_path.join("song.mp3"), b"").unwrap();
+        assert!(library.is_stale());
+    }
+
+    #[test]
+    fn test_nested_folders_ignored_without_recursive_scan() {
+        let dir = TestDir::new("nested-not-stale");
+        let album_path = dir.0.join("Artist").join("Album");
+        std::fs::create_dir_all(&album_path).unwrap();
+
+        let mut library = Library::new(dir.0.clone());
+        library.apply_scan(library.scanner().scan().unwrap());
+
+        std::fs::write(album_path.join("song.mp3"), b"").unwrap();
+        assert!(!library.is_stale());
     }
 }
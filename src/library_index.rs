@@ -0,0 +1,100 @@
+use std::{path::{PathBuf, Path}, collections::HashMap, time::SystemTime};
+
+use anyhow::Result;
+use serde::{Serialize, Deserialize};
+
+use crate::library::SongMetadata;
+
+/// The name of the file inside a library where its songs' metadata is cached. Like the activity
+/// log, the leading dot keeps it out of the way.
+const INDEX_FILE: &str = ".crossplay-index.json";
+
+/// Changed whenever what's kept in the index changes, so that an index written by another version
+/// of CrossPlay is rebuilt rather than misread.
+const INDEX_VERSION: u32 = 1;
+
+/// A song file's metadata, as of when it had this size and modified time.
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct IndexEntry {
+    pub size: u64,
+    pub modified: SystemTime,
+    pub metadata: SongMetadata,
+}
+
+/// The metadata of every song in a library, kept so that their tags don't all need reading again
+/// each time CrossPlay starts. Album art isn't kept, only whether there is any, since it's read when
+/// it's needed anyway.
+#[derive(Debug, Serialize, Deserialize, Default)]
+pub struct LibraryIndex {
+    version: u32,
+
+    /// Keyed by path relative to the library folder, so that the index still works if the library
+    /// is moved.
+    entries: HashMap<PathBuf, IndexEntry>,
+}
+
+/// Just enough of an index to tell which version wrote it, so that one with a different layout
+/// can be recognised rather than failing to parse.
+#[derive(Deserialize)]
+struct IndexVersion {
+    version: u32,
+}
+
+impl LibraryIndex {
+    fn path(library_path: &Path) -> PathBuf {
+        library_path.join(INDEX_FILE)
+    }
+
+    /// Loads a library's index. If there isn't one, or it can't be understood, or it was written by
+    /// a different version, it's empty, so that every song is read from its file and the index is
+    /// rebuilt.
+    pub fn load(library_path: &Path) -> Self {
+        let contents = match std::fs::read_to_string(Self::path(library_path)) {
+            Ok(contents) => contents,
+            Err(_) => return Self::default(),
+        };
+
+        match serde_json::from_str::<IndexVersion>(&contents) {
+            Ok(IndexVersion { version: INDEX_VERSION }) => (),
+            Ok(_) => {
+                println!("[Library] Index is from another version of CrossPlay, rebuilding it");
+                return Self::default();
+            }
+            Err(e) => {
+                println!("[Library] Index is corrupt, rebuilding it: {}", e);
+                return Self::default();
+            }
+        }
+
+        match serde_json::from_str(&contents) {
+            Ok(index) => index,
+            Err(e) => {
+                println!("[Library] Index is corrupt, rebuilding it: {}", e);
+                Self::default()
+            }
+        }
+    }
+
+    /// Replaces a library's index with the given entries, keyed by path relative to the library.
+    ///
+    /// The file is overwritten in place, rather than replaced, so that the library folder itself
+    /// doesn't look like it's changed. If CrossPlay is closed halfway through, the index is
+    /// corrupt, and is rebuilt next time.
+    pub fn save(library_path: &Path, entries: HashMap<PathBuf, IndexEntry>) -> Result<()> {
+        let index = Self { version: INDEX_VERSION, entries };
+        std::fs::write(Self::path(library_path), serde_json::to_string(&index)?)?;
+        Ok(())
+    }
+
+    /// The metadata kept for a file, if it has the same size and modified time as when it was
+    /// indexed.
+    pub fn get(&self, relative_path: &Path, size: u64, modified: SystemTime) -> Option<&SongMetadata> {
+        self.entries.get(relative_path)
+            .filter(|entry| entry.size == size && entry.modified == modified)
+            .map(|entry| &entry.metadata)
+    }
+
+    pub fn entry_count(&self) -> usize {
+        self.entries.len()
+    }
+}
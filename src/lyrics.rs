@@ -0,0 +1,63 @@
+use regex::Regex;
+
+/// Turns a WebVTT or SRT subtitles file into plain lyrics, one line of text per line, with the
+/// timings and formatting stripped out.
+///
+/// Automatically-generated subtitles show each line several times as the words roll in, so a line
+/// which repeats the one before it is dropped. Returns `None` if there's no text left.
+pub fn subtitles_to_lyrics(subtitles: &str) -> Option<String> {
+    // Inline tags, like the word timings in auto-generated subtitles: <00:00:01.500><c>word</c>
+    let tag_regex = Regex::new("<[^>]*>").unwrap();
+
+    let mut lines: Vec<String> = vec![];
+    let mut in_header = false;
+    for line in subtitles.lines() {
+        let line = line.trim().trim_start_matches('\u{feff}');
+
+        // The header and any styling blocks run until the next blank line
+        if line.is_empty() {
+            in_header = false;
+            continue;
+        }
+        if line.starts_with("WEBVTT") || line.starts_with("NOTE") || line.starts_with("STYLE") || line.starts_with("REGION") {
+            in_header = true;
+        }
+        if in_header {
+            continue;
+        }
+
+        // Cue numbers and timings
+        if line.contains("-->") || line.chars().all(|c| c.is_ascii_digit()) {
+            continue;
+        }
+
+        let text = decode_entities(&tag_regex.replace_all(line, ""));
+        let text = text.trim();
+
+        // Cues which only say that music is playing aren't lyrics
+        if text.is_empty() || text.chars().all(|c| c == '♪' || c.is_whitespace()) || text == "[Music]" {
+            continue;
+        }
+
+        if lines.last().map_or(true, |last| last != text) {
+            lines.push(text.to_string());
+        }
+    }
+
+    if lines.is_empty() {
+        None
+    } else {
+        Some(lines.join("\n"))
+    }
+}
+
+/// Replaces the HTML entities which subtitles use for characters that would otherwise look like
+/// formatting.
+fn decode_entities(text: &str) -> String {
+    text.replace("&nbsp;", " ")
+        .replace("&lt;", "<")
+        .replace("&gt;", ">")
+        .replace("&quot;", "\"")
+        .replace("&#39;", "'")
+        .replace("&amp;", "&")
+}
@@ -1,27 +1,59 @@
 #![feature(async_closure)]
 #![feature(iter_intersperse)]
-#![feature(exit_status_error)]
 
-use std::{sync::{Arc, RwLock}, future::ready};
+use std::{sync::{Arc, RwLock}, future::ready, time::{Duration, Instant}, path::Path};
 
-use iced::{pure::{Element, widget::Column, Application}, executor, Command, Subscription};
+use iced::{pure::{Element, widget::{Column, Container, Text, Row, Button}, Application}, executor, Command, Subscription, time, container, button, Background, Length, Alignment, Space};
 use iced_native::{subscription, window, Event};
-use library::Library;
+use library::{Library, OriginalStorage, cloud_sync_provider};
 use native_dialog::{MessageDialog, MessageType, FileDialog};
 use settings::Settings;
+use i18n::{Key, tr, tr_args, tr_count};
+use ui_util::{ElementContainerExtensions, ButtonExtensions, ContainerStyleSheet, ButtonStyleSheet, format_size};
 use views::{download::{DownloadMessage, DownloadView}, content::{ContentMessage, ContentView}};
 
 mod youtube;
 mod library;
+mod library_index;
 mod views;
 mod ui_util;
 mod settings;
 mod assets;
 mod tag_interface;
+mod platform;
+mod snapshot;
+mod i18n;
+mod bandwidth;
+mod ffmpeg_tags;
+mod levels;
+mod post_process;
+mod musicbrainz;
+mod download_queue;
+mod preflight;
+mod hooks;
+mod activity_log;
+mod download_stats;
+mod download_history;
+mod lyrics;
 
 fn main() {
     let mut settings = iced::Settings::with_flags(());
     settings.exit_on_close_request = false;
+    settings.default_font = platform::unicode_font();
+
+    // Lets the settings folder be moved without setting the environment variable, which is
+    // awkward in a desktop shortcut
+    let mut args = std::env::args().skip(1);
+    while let Some(arg) = args.next() {
+        let dir = match arg.strip_prefix("--settings-dir=") {
+            Some(dir) => Some(dir.to_string()),
+            None if arg == "--settings-dir" => args.next(),
+            None => None,
+        };
+        if let Some(dir) = dir {
+            std::env::set_var(settings::SETTINGS_DIR_VAR, dir);
+        }
+    }
 
     MainView::run(settings).unwrap();
 }
@@ -33,6 +65,23 @@ pub enum Message {
 
     UpdateLibraryPath,
 
+    /// Checks whether something other than CrossPlay has changed the library folder, and refreshes
+    /// it if so. This is skipped while downloads are writing to the folder.
+    CheckLibraryStale,
+
+    /// Briefly shows a non-blocking notification at the top of the window. This is also kept as
+    /// the last action in the status bar.
+    ShowToast(String),
+    /// Like [`ShowToast`], but for a failure, with more detail which can be viewed by clicking on
+    /// the last action in the status bar.
+    ShowErrorToast(String, String),
+    /// Like [`ShowToast`], with more detail which can be viewed in the same way as
+    /// [`ShowErrorToast`], but for something which isn't a failure.
+    ShowDetailedToast(String, String),
+    ExpireToast,
+    ShowLastActionDetail,
+    DismissSettingsSaveWarning,
+
     DownloadMessage(DownloadMessage),
     ContentMessage(ContentMessage),
 }
@@ -43,6 +92,139 @@ struct MainView {
     
     download_view: DownloadView,
     content_view: ContentView,
+
+    toast: Option<(LastAction, Instant)>,
+    last_action: Option<LastAction>,
+
+    /// Whether the warning that settings can't be saved has been dismissed. It isn't shown again
+    /// for the rest of the session, even though later changes can't be saved either.
+    settings_save_warning_dismissed: bool,
+}
+
+/// The outcome of the most recent operation, shown in the status bar so that it can still be seen
+/// after its toast disappears.
+#[derive(Debug, Clone)]
+struct LastAction {
+    summary: String,
+    detail: Option<String>,
+    is_error: bool,
+}
+
+/// How long a toast is shown for before it disappears.
+const TOAST_DURATION: Duration = Duration::from_secs(4);
+
+/// If the given library path is inside a cloud-synced folder, warns the user that syncing can
+/// corrupt files which CrossPlay is writing to.
+///
+/// If `ask` is true, the user can choose not to use this path, and the return value is whether
+/// they chose to use it anyway. Otherwise, the warning is purely informational and this always
+/// returns true.
+fn confirm_cloud_synced_library(path: &Path, ask: bool) -> bool {
+    let provider = match cloud_sync_provider(path) {
+        Some(provider) => provider,
+        None => return true,
+    };
+
+    let text = tr_args(Key::CloudSyncBody, &[&provider, &path.to_string_lossy()]);
+    let dialog = MessageDialog::new()
+        .set_title(tr(Key::CloudSyncTitle))
+        .set_type(MessageType::Warning);
+
+    if ask {
+        dialog
+            .set_text(&format!("{}\n\n{}", text, tr(Key::CloudSyncAsk)))
+            .show_confirm()
+            .unwrap()
+    } else {
+        dialog
+            .set_text(&format!("{}\n\n{}", text, tr(Key::CloudSyncInform)))
+            .show_alert()
+            .unwrap();
+        true
+    }
+}
+
+/// Explains which of the programs CrossPlay needs couldn't be found, and what won't work until
+/// they're installed.
+fn warn_missing_tools(missing: &[preflight::Tool], settings: &Settings) {
+    let mut text = vec![tr(Key::MissingToolsBody).to_string()];
+    for tool in missing {
+        text.push(match tool {
+            preflight::Tool::Downloader => tr_args(Key::MissingToolDownloader, &[&settings.downloader_binary]),
+            preflight::Tool::Ffmpeg => tr(Key::MissingToolFfmpeg).to_string(),
+        });
+    }
+    text.push(tr(Key::MissingToolsAfter).to_string());
+
+    MessageDialog::new()
+        .set_title(tr(Key::MissingToolsTitle))
+        .set_text(&text.join("\n\n"))
+        .set_type(MessageType::Error)
+        .show_alert()
+        .unwrap();
+}
+
+impl MainView {
+    /// Warns that changes to the settings will be lost when CrossPlay closes, because they can't
+    /// be saved.
+    fn settings_save_warning(&self, error: &str) -> Element<'_, Message> {
+        Container::new(
+            Row::new()
+                .spacing(10)
+                .align_items(Alignment::Center)
+                .push(Text::new(tr_args(Key::SettingsSaveFailed, &[&error])).width(Length::Fill))
+                .push(Button::new(Text::new(tr(Key::Ok)))
+                    .on_press(Message::DismissSettingsSaveWarning))
+        )
+            .padding(10)
+            .width(Length::Fill)
+            .style(ContainerStyleSheet(container::Style {
+                background: Some(Background::Color([1.0, 0.9, 0.7].into())),
+                ..Default::default()
+            }))
+            .into()
+    }
+
+    fn status_bar(&self) -> Element<'_, Message> {
+        let library = self.library.read().unwrap();
+        let song_count = library.songs().count();
+        let hidden_count = library.songs().filter(|s| s.is_hidden()).count();
+
+        let mut summary = vec![
+            tr_count(Key::StatusSongCount, song_count, &[]),
+            format_size(library.total_size()),
+        ];
+        if hidden_count > 0 {
+            summary.push(tr_args(Key::StatusHiddenCount, &[&hidden_count]));
+        }
+
+        Container::new(
+            Row::new()
+                .spacing(10)
+                .align_items(Alignment::Center)
+                .push(Text::new(summary.join(" · ")).size(16))
+                .push(Space::with_width(Length::Fill))
+                .push_if_let(&self.last_action, |action| {
+                    let text = Text::new(action.summary.clone())
+                        .size(16)
+                        .color(if action.is_error { [0.8, 0.0, 0.0] } else { [0.3, 0.3, 0.3] });
+
+                    // Errors, and anything else with more to say, can be clicked to see the full
+                    // message
+                    Button::new(text)
+                        .padding(0)
+                        .style(ButtonStyleSheet(button::Style::default()))
+                        .on_press_if(action.detail.is_some(), Message::ShowLastActionDetail)
+                })
+        )
+            .padding([3, 10])
+            .width(Length::Fill)
+            .style(ContainerStyleSheet(container::Style {
+                background: Some(Background::Color([0.85, 0.85, 0.85].into())),
+                ..Default::default()
+            }))
+            .into()
+    }
 }
 
 impl Application for MainView {
@@ -51,10 +233,32 @@ impl Application for MainView {
     type Flags = ();
 
     fn new(_flags: Self::Flags) -> (Self, Command<Self::Message>) {
+        let first_run = !Settings::settings_path().exists();
         let settings = Settings::load().unwrap();
+        i18n::set_language(settings.language);
+
+        // There's no choice to make on the first run, since the library is already being created
+        // in the default location - but it's still worth letting the user know
+        if first_run {
+            confirm_cloud_synced_library(&settings.library_path, false);
+        }
 
         let mut library = Library::new(settings.library_path.clone());
-        library.load_songs().unwrap();
+        if settings.deduplicate_originals {
+            library.original_storage = OriginalStorage::Deduplicated;
+        }
+        library.scan_recursively = settings.scan_recursively;
+
+        // Catch a missing downloader or ffmpeg now, rather than when the first download fails
+        let missing_tools = preflight::check(&settings);
+        if !missing_tools.is_empty() {
+            warn_missing_tools(&missing_tools, &settings);
+        }
+
+        // Anything trashed last time can't be undone any more
+        if let Err(e) = library.empty_trash() {
+            println!("[Library] Couldn't empty trash: {}", e);
+        }
 
         let library = Arc::new(RwLock::new(library));
         let settings = Arc::new(RwLock::new(settings));
@@ -66,8 +270,22 @@ impl Application for MainView {
 
                 download_view: DownloadView::new(library.clone(), settings.clone()),
                 content_view: ContentView::new(library, settings),
+
+                toast: None,
+                last_action: None,
+                settings_save_warning_dismissed: false,
             },
-            Command::none()
+            Command::batch([
+                // Reading every song's tags takes a while for large libraries, so the window is
+                // shown first and the songs are loaded in the background
+                Command::perform(ready(()), |_| ContentMessage::RefreshLibrary { report_changes: false }.into()),
+                // Carry on with any downloads which were still queued when CrossPlay was closed,
+                // and offer to resume any which were running
+                Command::perform(ready(()), |_| DownloadMessage::StartQueuedDownloads.into()),
+                Command::perform(ready(()), |_| DownloadMessage::ResumeInterruptedDownloads.into()),
+                // Old downloaders are the most common reason for downloads failing
+                Command::perform(ready(()), |_| DownloadMessage::CheckDownloaderVersion.into()),
+            ])
         )
     }
 
@@ -80,12 +298,22 @@ impl Application for MainView {
             self.content_view.subscription(),
             self.download_view.subscription(),
             subscription::events().map(|e| {
-                if let Event::Window(window::Event::CloseRequested) = e {
-                    Message::Close
-                } else {
-                    Message::None
+                match e {
+                    Event::Window(window::Event::CloseRequested) => Message::Close,
+                    Event::Window(window::Event::Focused) => Message::CheckLibraryStale,
+                    Event::Window(window::Event::Resized { width, .. }) => ContentMessage::WindowResized(width).into(),
+                    _ => Message::None,
                 }
             }),
+            match self.settings.read().unwrap().library_check_interval.duration() {
+                Some(interval) => time::every(interval).map(|_| Message::CheckLibraryStale),
+                None => Subscription::none(),
+            },
+            if self.toast.is_some() {
+                time::every(Duration::from_millis(500)).map(|_| Message::ExpireToast)
+            } else {
+                Subscription::none()
+            },
         ])
     }
 
@@ -93,14 +321,15 @@ impl Application for MainView {
         match message {
             Message::None => (),
             Message::Close => {
+                // Running downloads are saved along with the queue, so that they can be resumed
+                // when CrossPlay is next opened
+                self.download_view.flush_queue();
                 if self.download_view.downloads_in_progress.is_empty() {
                     std::process::exit(0);
                 } else {
                     let confirmation = MessageDialog::new()
-                        .set_title("Cancel downloads?")
-                        .set_text(
-                            "There are currently downloads in progress. Exiting now will cancel them. Are you sure you would like to exit?",
-                        )
+                        .set_title(tr(Key::CancelDownloadsTitle))
+                        .set_text(tr(Key::CancelDownloadsBody))
                         .set_type(MessageType::Warning)
                         .show_confirm()
                         .unwrap();
@@ -110,13 +339,50 @@ impl Application for MainView {
                     }
                 }
             },
+            Message::ShowToast(summary) => {
+                let action = LastAction { summary, detail: None, is_error: false };
+                self.toast = Some((action.clone(), Instant::now()));
+                self.last_action = Some(action);
+            }
+            Message::ShowErrorToast(summary, detail) => {
+                let action = LastAction { summary, detail: Some(detail), is_error: true };
+                self.toast = Some((action.clone(), Instant::now()));
+                self.last_action = Some(action);
+            }
+            Message::ShowDetailedToast(summary, detail) => {
+                let action = LastAction { summary, detail: Some(detail), is_error: false };
+                self.toast = Some((action.clone(), Instant::now()));
+                self.last_action = Some(action);
+            }
+            Message::ShowLastActionDetail =>
+                if let Some(LastAction { summary, detail: Some(detail), is_error }) = &self.last_action {
+                    MessageDialog::new()
+                        .set_title(summary)
+                        .set_text(detail)
+                        .set_type(if *is_error { MessageType::Error } else { MessageType::Info })
+                        .show_alert()
+                        .unwrap();
+                },
+            Message::DismissSettingsSaveWarning => self.settings_save_warning_dismissed = true,
+            Message::ExpireToast =>
+                if let Some((_, shown_at)) = self.toast {
+                    if shown_at.elapsed() >= TOAST_DURATION {
+                        self.toast = None;
+                    }
+                },
+
+            Message::CheckLibraryStale =>
+                if self.download_view.downloads_in_progress.is_empty() {
+                    return Command::perform(ready(()), |_| ContentMessage::RefreshLibraryIfStale.into());
+                },
+
             Message::ContentMessage(cm) => return self.content_view.update(cm),
             Message::DownloadMessage(dm) => return self.download_view.update(dm),
 
             Message::UpdateLibraryPath => {
                 let confirmation = MessageDialog::new()
-                    .set_title("Pick new library?")
-                    .set_text(&format!("Would you like to pick a new library folder? Your songs will not be copied to the new location, but will be preserved in the old location so you can switch back to it later.\n\nThe current library path is: {}", self.library.read().unwrap().path.to_string_lossy()))
+                    .set_title(tr(Key::PickLibraryTitle))
+                    .set_text(&tr_args(Key::PickLibraryBody, &[&self.library.read().unwrap().path.to_string_lossy()]))
                     .show_confirm();
 
                 if !confirmation.unwrap() {
@@ -124,9 +390,13 @@ impl Application for MainView {
                 }
 
                 if let Some(new_path) = FileDialog::new().show_open_single_dir().unwrap() {
+                    if !confirm_cloud_synced_library(&new_path, true) {
+                        return Command::none();
+                    }
+
                     let mut settings = self.settings.write().unwrap();
                     settings.library_path = new_path;
-                    settings.save().unwrap();
+                    settings.save();
 
                     self.library.write().unwrap().path = settings.library_path.clone();
                 }
@@ -139,9 +409,25 @@ impl Application for MainView {
     }
 
     fn view(&self) -> Element<'_, Self::Message> {
+        let settings_save_error = Settings::save_error().filter(|_| !self.settings_save_warning_dismissed);
+
         Column::new()
             .push(self.download_view.view())
-            .push(self.content_view.view())
+            .push_if_let(&settings_save_error, |error| self.settings_save_warning(error))
+            .push_if_let(&self.toast, |(action, _)|
+                Container::new(Text::new(action.summary.clone()))
+                    .padding(10)
+                    .width(Length::Fill)
+                    .style(ContainerStyleSheet(container::Style {
+                        background: Some(Background::Color(
+                            if action.is_error { [0.8, 0.2, 0.2] } else { [0.2, 0.4, 0.8] }.into()
+                        )),
+                        text_color: Some([1.0, 1.0, 1.0].into()),
+                        ..Default::default()
+                    }))
+            )
+            .push(Container::new(self.content_view.view(&self.download_view.downloads_in_progress)).height(Length::Fill))
+            .push(self.status_bar())
             .into()
     }
 }
@@ -0,0 +1,156 @@
+//! Looks up corrected song metadata from [MusicBrainz](https://musicbrainz.org), since the channel
+//! and video title which songs are downloaded with rarely make good tags.
+
+use std::{fmt::Display, sync::Mutex, time::{Duration, Instant}};
+
+use serde_json::Value;
+
+use crate::library::UNKNOWN_ARTIST;
+
+const SEARCH_URL: &str = "https://musicbrainz.org/ws/2/recording";
+
+/// MusicBrainz rejects requests without a descriptive user agent.
+const USER_AGENT: &str = concat!("CrossPlay/", env!("CARGO_PKG_VERSION"), " ( https://github.com/AaronC81/crossplay )");
+
+/// MusicBrainz allows one request per second from each client.
+const MIN_REQUEST_INTERVAL: Duration = Duration::from_secs(1);
+
+/// How many matches to ask for.
+const MAX_MATCHES: usize = 5;
+
+/// How long to wait for MusicBrainz to respond before giving up.
+const TIMEOUT: Duration = Duration::from_secs(15);
+
+/// When the last request was sent, so that the next one can wait long enough to respect the rate
+/// limit.
+static LAST_REQUEST: Mutex<Option<Instant>> = Mutex::new(None);
+
+/// A recording on MusicBrainz which might be the song being looked up.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct RecordingMatch {
+    pub title: String,
+    pub artist: String,
+
+    /// The first release the recording appeared on, if it's been released on any.
+    pub album: Option<String>,
+    pub year: Option<i32>,
+
+    /// How closely this matches the search, out of 100.
+    pub score: u8,
+}
+
+/// Something which went wrong while looking up metadata.
+#[derive(Debug, Clone)]
+pub enum LookupError {
+    /// MusicBrainz couldn't be reached - usually because there's no internet connection.
+    Network(String),
+
+    /// MusicBrainz is refusing requests because too many were sent.
+    RateLimited,
+
+    /// MusicBrainz responded, but not with anything we could understand.
+    BadResponse(String),
+}
+
+impl Display for LookupError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            LookupError::Network(e) => write!(f, "could not reach MusicBrainz: {}", e),
+            LookupError::RateLimited => write!(f, "MusicBrainz is rate-limiting requests"),
+            LookupError::BadResponse(e) => write!(f, "unexpected response from MusicBrainz: {}", e),
+        }
+    }
+}
+
+impl std::error::Error for LookupError {}
+
+/// Searches MusicBrainz for recordings with the given title and artist, best matches first.
+///
+/// This blocks until MusicBrainz responds, and also waits beforehand if another request was sent
+/// too recently, so it should be run in the background.
+pub fn search_recordings(title: &str, artist: &str) -> Result<Vec<RecordingMatch>, LookupError> {
+    wait_for_rate_limit();
+
+    let response = reqwest::blocking::Client::builder()
+        .user_agent(USER_AGENT)
+        .timeout(TIMEOUT)
+        .build()
+        .map_err(|e| LookupError::Network(e.to_string()))?
+        .get(SEARCH_URL)
+        .query(&[
+            ("query", search_query(title, artist)),
+            ("limit", MAX_MATCHES.to_string()),
+            ("fmt", "json".to_string()),
+        ])
+        .send()
+        .map_err(|e| LookupError::Network(e.to_string()))?;
+
+    // MusicBrainz uses 503 rather than 429 when it's rate-limiting
+    let status = response.status();
+    if status == reqwest::StatusCode::SERVICE_UNAVAILABLE || status == reqwest::StatusCode::TOO_MANY_REQUESTS {
+        return Err(LookupError::RateLimited);
+    }
+    if !status.is_success() {
+        return Err(LookupError::BadResponse(status.to_string()));
+    }
+
+    let json: Value = response.json().map_err(|e| LookupError::BadResponse(e.to_string()))?;
+    let recordings = json["recordings"].as_array()
+        .ok_or_else(|| LookupError::BadResponse("no recordings listed".to_string()))?;
+
+    Ok(recordings.iter().filter_map(parse_recording).collect())
+}
+
+/// Sleeps until enough time has passed since the last request, then records that a request is
+/// being made now.
+fn wait_for_rate_limit() {
+    let mut last_request = LAST_REQUEST.lock().unwrap();
+    if let Some(last) = *last_request {
+        let elapsed = last.elapsed();
+        if elapsed < MIN_REQUEST_INTERVAL {
+            std::thread::sleep(MIN_REQUEST_INTERVAL - elapsed);
+        }
+    }
+    *last_request = Some(Instant::now());
+}
+
+/// Builds a Lucene search query for a title and artist. Artists which don't say anything about who
+/// performed the song are left out, so that they don't rule out good matches.
+fn search_query(title: &str, artist: &str) -> String {
+    let quote = |s: &str| format!("\"{}\"", s.replace('\\', "\\\\").replace('"', "\\\""));
+
+    // YouTube's auto-generated channels are named like "Artist - Topic"
+    let artist = artist.trim().trim_end_matches(" - Topic").trim();
+
+    let mut query = format!("recording:{}", quote(title.trim()));
+    if !artist.is_empty() && artist != UNKNOWN_ARTIST {
+        query.push_str(&format!(" AND artist:{}", quote(artist)));
+    }
+    query
+}
+
+fn parse_recording(json: &Value) -> Option<RecordingMatch> {
+    // Artists are credited in parts, like "Artist" + " feat. " + "Other Artist"
+    let artist = json["artist-credit"].as_array()?
+        .iter()
+        .map(|credit| format!(
+            "{}{}",
+            credit["name"].as_str().unwrap_or(""),
+            credit["joinphrase"].as_str().unwrap_or(""),
+        ))
+        .collect::<String>();
+
+    let release = json["releases"].as_array().and_then(|r| r.first());
+
+    // Dates are given like 2022-01-31, or just 2022
+    let date = json["first-release-date"].as_str()
+        .or_else(|| release.and_then(|r| r["date"].as_str()));
+
+    Some(RecordingMatch {
+        title: json["title"].as_str()?.to_string(),
+        artist,
+        album: release.and_then(|r| r["title"].as_str()).map(Into::into),
+        year: date.and_then(|d| d.get(..4)).and_then(|y| y.parse().ok()),
+        score: json["score"].as_u64().unwrap_or(0).min(100) as u8,
+    })
+}
@@ -0,0 +1,59 @@
+use std::{ffi::OsStr, process::Command, path::Path};
+
+use anyhow::Result;
+
+/// Opens a file, folder, or URL with the operating system's default application for it.
+pub fn open(target: impl AsRef<OsStr>) -> Result<()> {
+    #[cfg(target_os = "macos")]
+    let mut command = Command::new("open");
+
+    #[cfg(target_os = "windows")]
+    let mut command = {
+        // The empty argument is the title of the window which `start` creates
+        let mut command = Command::new("cmd");
+        command.arg("/C").arg("start").arg("");
+        command
+    };
+
+    #[cfg(not(any(target_os = "macos", target_os = "windows")))]
+    let mut command = Command::new("xdg-open");
+
+    command.arg(target).spawn()?;
+    Ok(())
+}
+
+/// Fonts which cover far more of Unicode than iced's built-in font, such as CJK, Arabic and
+/// Cyrillic, in the places each OS usually installs them, most preferred first.
+#[cfg(target_os = "windows")]
+const UNICODE_FONTS: &[&str] = &[
+    r"C:\Windows\Fonts\ArialUni.ttf",
+    r"C:\Windows\Fonts\YuGothM.ttc",
+    r"C:\Windows\Fonts\msgothic.ttc",
+];
+
+#[cfg(target_os = "macos")]
+const UNICODE_FONTS: &[&str] = &[
+    "/System/Library/Fonts/Supplemental/Arial Unicode.ttf",
+    "/Library/Fonts/Arial Unicode.ttf",
+    "/System/Library/Fonts/Hiragino Sans GB.ttc",
+];
+
+#[cfg(not(any(target_os = "macos", target_os = "windows")))]
+const UNICODE_FONTS: &[&str] = &[
+    "/usr/share/fonts/opentype/noto/NotoSansCJK-Regular.ttc",
+    "/usr/share/fonts/noto-cjk/NotoSansCJK-Regular.ttc",
+    "/usr/share/fonts/google-noto-cjk/NotoSansCJK-Regular.ttc",
+    "/usr/share/fonts/truetype/droid/DroidSansFallbackFull.ttf",
+];
+
+/// Loads an installed font with broad Unicode coverage, to use instead of iced's built-in font,
+/// which only covers Latin scripts. iced can't fall back between fonts for missing characters, so
+/// this has to be a single font which covers as much as possible.
+///
+/// iced needs the font for as long as the app runs, so it's leaked.
+pub fn unicode_font() -> Option<&'static [u8]> {
+    UNICODE_FONTS.iter()
+        .map(Path::new)
+        .find_map(|path| std::fs::read(path).ok())
+        .map(|data| &*Box::leak(data.into_boxed_slice()))
+}
@@ -0,0 +1,253 @@
+use std::{path::Path, process::Command, io::Cursor};
+
+use image::{DynamicImage, ImageOutputFormat, GenericImageView};
+use serde::Deserialize;
+
+use crate::{library::{LibraryError, SongMetadata, AlbumArt}, settings::{AudioFormat, PostProcessStepKind}, ffmpeg_tags::{run, with_suffix}};
+
+type Result<T> = std::result::Result<T, LibraryError>;
+
+/// Something done to a song once it's downloaded, if the user has added it to their
+/// post-processing pipeline. Steps can also be run on songs in the library on demand - see
+/// [`crate::library::Song::post_process`].
+pub trait PostProcessStep {
+    /// Processes a copy of a song's file at `path`. This copy won't have an extension, so the
+    /// format is given separately.
+    ///
+    /// Changes to the song's tags, including its album art, should be made to `metadata` rather
+    /// than the file, since `metadata` is written over the file's tag afterwards.
+    fn process(&self, path: &Path, format: AudioFormat, metadata: &mut SongMetadata) -> Result<()>;
+}
+
+impl PostProcessStepKind {
+    /// The implementation of this step. `loudness_target` is the user's
+    /// [`crate::settings::Settings::loudness_target`], which only [`NormalizeLoudness`] uses.
+    pub fn step(&self, loudness_target: i32) -> Box<dyn PostProcessStep> {
+        match self {
+            PostProcessStepKind::TrimSilence => Box::new(TrimSilence),
+            PostProcessStepKind::SquareAlbumArt => Box::new(SquareAlbumArt),
+            PostProcessStepKind::ShrinkAlbumArt => Box::new(ShrinkAlbumArt),
+            PostProcessStepKind::NormalizeLoudness => Box::new(NormalizeLoudness { target: loudness_target }),
+        }
+    }
+}
+
+/// Anything quieter than this counts as silence when trimming.
+const SILENCE_THRESHOLD: &str = "-50dB";
+
+/// Album art larger than this on either side is scaled down by [`ShrinkAlbumArt`].
+const MAX_ALBUM_ART_SIZE: u32 = 500;
+
+/// The JPEG quality which album art is re-encoded at after being edited.
+const ALBUM_ART_QUALITY: u8 = 85;
+
+/// The highest peak allowed by [`NormalizeLoudness`], in dBTP. This leaves room for lossy encoding
+/// to overshoot without clipping.
+const LOUDNESS_TRUE_PEAK: f32 = -1.5;
+
+/// The loudness range aimed for by [`NormalizeLoudness`], in LU. This is loudnorm's default.
+const LOUDNESS_RANGE: u32 = 11;
+
+/// Cuts silence from the start and end of a song. Unlike a crop, the cut points don't line up with
+/// the audio's frames, so the song is re-encoded.
+pub struct TrimSilence;
+
+impl PostProcessStep for TrimSilence {
+    fn process(&self, path: &Path, format: AudioFormat, _: &mut SongMetadata) -> Result<()> {
+        // silenceremove only trims the start, so reverse the song to trim its end too
+        let filter = format!(
+            "silenceremove=start_periods=1:start_threshold={0},areverse,silenceremove=start_periods=1:start_threshold={0},areverse",
+            SILENCE_THRESHOLD,
+        );
+
+        // ffmpeg can't write into the file it's reading from
+        let trimmed_path = with_suffix(path, "trimmed");
+        let result = run(Command::new("ffmpeg")
+            .arg("-v")
+            .arg("error")
+            .arg("-y")
+            .arg("-i")
+            .arg(path)
+            .arg("-map")
+            .arg("0:a")
+            .arg("-af")
+            .arg(filter)
+            .arg("-f")
+            .arg(format.ffmpeg_muxer())
+            .arg(&trimmed_path));
+        if let Err(e) = result {
+            let _ = std::fs::remove_file(&trimmed_path);
+            return Err(e);
+        }
+
+        std::fs::rename(&trimmed_path, path).map_err(|e| LibraryError::from_io(path, e))
+    }
+}
+
+/// Brings a song to the user's loudness target with ffmpeg's EBU R128 `loudnorm` filter, so that songs
+/// from different uploads play at a similar volume. Like [`TrimSilence`], this re-encodes the song.
+///
+/// This takes two passes: the first measures the song, so that the second can apply one gain to
+/// the whole of it. A single pass would have to adjust the gain as it went, pumping the volume of
+/// songs with quiet and loud parts.
+pub struct NormalizeLoudness {
+    /// The loudness to bring the song to, in LUFS.
+    pub target: i32,
+}
+
+impl PostProcessStep for NormalizeLoudness {
+    fn process(&self, path: &Path, format: AudioFormat, _: &mut SongMetadata) -> Result<()> {
+        let target = format!("I={}:TP={}:LRA={}", self.target, LOUDNESS_TRUE_PEAK, LOUDNESS_RANGE);
+        let measured = measure_loudness(path, &target)?;
+
+        // loudnorm works at a much higher sample rate than it was given, so keep the song's own
+        // rate
+        let sample_rate = probe_sample_rate(path)?;
+
+        let normalized_path = with_suffix(path, "normalized");
+        let result = run(Command::new("ffmpeg")
+            .arg("-v")
+            .arg("error")
+            .arg("-y")
+            .arg("-i")
+            .arg(path)
+            .arg("-map")
+            .arg("0:a")
+            .arg("-af")
+            .arg(format!(
+                "loudnorm={}:measured_I={}:measured_TP={}:measured_LRA={}:measured_thresh={}:offset={}:linear=true",
+                target, measured.input_i, measured.input_tp, measured.input_lra, measured.input_thresh, measured.target_offset,
+            ))
+            .arg("-ar")
+            .arg(sample_rate.to_string())
+            .arg("-f")
+            .arg(format.ffmpeg_muxer())
+            .arg(&normalized_path));
+        if let Err(e) = result {
+            let _ = std::fs::remove_file(&normalized_path);
+            return Err(e);
+        }
+
+        std::fs::rename(&normalized_path, path).map_err(|e| LibraryError::from_io(path, e))
+    }
+}
+
+/// What the first pass of `loudnorm` measured about a song, to be passed into the second pass.
+/// loudnorm prints these as strings, and they're passed back the same way.
+#[derive(Debug, Deserialize)]
+struct LoudnessMeasurement {
+    input_i: String,
+    input_tp: String,
+    input_lra: String,
+    input_thresh: String,
+    target_offset: String,
+}
+
+/// Runs the first, measuring pass of `loudnorm` over a song, towards the given target parameters.
+fn measure_loudness(path: &Path, target: &str) -> Result<LoudnessMeasurement> {
+    let output = Command::new("ffmpeg")
+        .arg("-hide_banner")
+        .arg("-nostats")
+        .arg("-i")
+        .arg(path)
+        .arg("-map")
+        .arg("0:a")
+        .arg("-af")
+        .arg(format!("loudnorm={}:print_format=json", target))
+        .arg("-f")
+        .arg("null")
+        .arg("-")
+        .output()
+        .map_err(|e| LibraryError::FfmpegFailed { stderr: format!("could not run ffmpeg: {}", e) })?;
+
+    // The measurements are printed to stderr, as the last thing in it
+    let stderr = String::from_utf8_lossy(&output.stderr);
+    if !output.status.success() {
+        return Err(LibraryError::FfmpegFailed { stderr: stderr.into_owned() });
+    }
+
+    stderr.rfind('{')
+        .and_then(|start| serde_json::from_str(stderr[start..].trim()).ok())
+        .ok_or_else(|| LibraryError::FfmpegFailed { stderr: format!("unexpected loudnorm output: {}", stderr) })
+}
+
+/// The sample rate of a song's audio, from ffprobe.
+fn probe_sample_rate(path: &Path) -> Result<u32> {
+    let stdout = run(Command::new("ffprobe")
+        .arg("-v")
+        .arg("error")
+        .arg("-select_streams")
+        .arg("a:0")
+        .arg("-show_entries")
+        .arg("stream=sample_rate")
+        .arg("-of")
+        .arg("default=noprint_wrappers=1:nokey=1")
+        .arg(path))?;
+
+    let stdout = String::from_utf8_lossy(&stdout);
+    stdout.trim().parse()
+        .map_err(|_| LibraryError::FfmpegFailed { stderr: format!("unexpected ffprobe output: {}", stdout) })
+}
+
+/// Crops album art to a square, keeping its centre. YouTube thumbnails are widescreen, which most
+/// players show with bars above and below.
+pub struct SquareAlbumArt;
+
+impl PostProcessStep for SquareAlbumArt {
+    fn process(&self, path: &Path, _: AudioFormat, metadata: &mut SongMetadata) -> Result<()> {
+        square_album_art(path, metadata)
+    }
+}
+
+/// Crops a song's loaded album art to a square, as [`SquareAlbumArt`] does. `path` is only used to
+/// describe errors.
+pub fn square_album_art(path: &Path, metadata: &mut SongMetadata) -> Result<()> {
+    edit_album_art(path, metadata, square_image)
+}
+
+/// Crops an image to a square, keeping its centre. Wide images lose their sides and tall ones lose
+/// their top and bottom. Square images are left alone.
+fn square_image(image: DynamicImage) -> DynamicImage {
+    let (width, height) = image.dimensions();
+    if width == height {
+        return image;
+    }
+
+    let size = width.min(height);
+    image.crop_imm((width - size) / 2, (height - size) / 2, size, size)
+}
+
+/// Scales down large album art, to save space in songs which are copied to devices with little
+/// storage.
+pub struct ShrinkAlbumArt;
+
+impl PostProcessStep for ShrinkAlbumArt {
+    fn process(&self, path: &Path, _: AudioFormat, metadata: &mut SongMetadata) -> Result<()> {
+        edit_album_art(path, metadata, |image| {
+            if image.width() > MAX_ALBUM_ART_SIZE || image.height() > MAX_ALBUM_ART_SIZE {
+                image.thumbnail(MAX_ALBUM_ART_SIZE, MAX_ALBUM_ART_SIZE)
+            } else {
+                image
+            }
+        })
+    }
+}
+
+/// Replaces a song's album art with an edited version, re-encoded as a JPEG. Songs without album
+/// art are left alone.
+fn edit_album_art(path: &Path, metadata: &mut SongMetadata, edit: impl FnOnce(DynamicImage) -> DynamicImage) -> Result<()> {
+    let picture = match &mut metadata.album_art {
+        Some(AlbumArt::Loaded(picture)) => picture,
+        _ => return Ok(()),
+    };
+
+    let image_error = |e: image::ImageError| LibraryError::TagParse { path: path.to_path_buf(), message: e.to_string() };
+    let image = image::load_from_memory(&picture.data).map_err(image_error)?;
+
+    let mut jpeg_bytes = Cursor::new(vec![]);
+    edit(image).write_to(&mut jpeg_bytes, ImageOutputFormat::Jpeg(ALBUM_ART_QUALITY)).map_err(image_error)?;
+    picture.mime_type = "image/jpeg".to_string();
+    picture.data = jpeg_bytes.into_inner();
+
+    Ok(())
+}
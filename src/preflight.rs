@@ -0,0 +1,53 @@
+use std::sync::atomic::{AtomicBool, Ordering};
+
+use crate::{settings::{Settings, MissingFfmpegAction}, youtube::Downloader, ffmpeg_tags::ffmpeg_available};
+
+/// A program which CrossPlay runs, but doesn't come with.
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub enum Tool {
+    Downloader,
+    Ffmpeg,
+}
+
+// Both are assumed to be installed until checked, so that nothing is disabled while CrossPlay is
+// starting up
+static DOWNLOADER_FOUND: AtomicBool = AtomicBool::new(true);
+static FFMPEG_FOUND: AtomicBool = AtomicBool::new(true);
+
+/// Checks whether the configured downloader and ffmpeg can be run, remembering the results for
+/// [`downloader_found`] and [`ffmpeg_found`]. Returns the tools which are missing.
+///
+/// This runs each tool, so blocks for a moment.
+pub fn check(settings: &Settings) -> Vec<Tool> {
+    let downloader_found = Downloader::detect(&settings.downloader_binary).is_some();
+    let ffmpeg_found = ffmpeg_available();
+
+    DOWNLOADER_FOUND.store(downloader_found, Ordering::Relaxed);
+    FFMPEG_FOUND.store(ffmpeg_found, Ordering::Relaxed);
+
+    let mut missing = vec![];
+    if !downloader_found {
+        missing.push(Tool::Downloader);
+    }
+    if !ffmpeg_found {
+        missing.push(Tool::Ffmpeg);
+    }
+    missing
+}
+
+/// Whether a downloader could be run at the last [`check`].
+pub fn downloader_found() -> bool {
+    DOWNLOADER_FOUND.load(Ordering::Relaxed)
+}
+
+/// Whether ffmpeg could be run at the last [`check`]. Cropping needs it.
+pub fn ffmpeg_found() -> bool {
+    FFMPEG_FOUND.load(Ordering::Relaxed)
+}
+
+/// Whether the last [`check`] found everything needed to download. Without ffmpeg, downloads can
+/// only go ahead if the user has chosen to keep them unconverted.
+pub fn can_download(settings: &Settings) -> bool {
+    downloader_found()
+        && (ffmpeg_found() || settings.missing_ffmpeg_action == MissingFfmpegAction::DownloadUnconverted)
+}
@@ -1,9 +1,11 @@
-use std::path::PathBuf;
+use std::{path::{PathBuf, Path}, time::Duration, fmt::Display, collections::HashMap, sync::Mutex};
 
 use serde::{Serialize, Deserialize};
 use anyhow::Result;
 
-#[derive(Debug, Serialize, Deserialize, Copy, Clone, PartialEq, Eq)]
+use crate::{i18n::{Language, Key, tr, tr_args, tr_count}, library::has_extension};
+
+#[derive(Debug, Serialize, Deserialize, Copy, Clone, PartialEq, Eq, Hash)]
 pub enum SortBy {
     Title,
     Artist,
@@ -26,6 +28,408 @@ impl SortDirection {
     }
 }
 
+/// Something which can be done to a song by clicking on its row in the song list.
+#[derive(Debug, Serialize, Deserialize, Copy, Clone, PartialEq, Eq)]
+pub enum RowAction {
+    /// Opens the song in the system's default player.
+    Play,
+    /// Plays the song in the now-playing bar, without leaving CrossPlay.
+    PlayInCrossPlay,
+    EditMetadata,
+    Crop,
+    OpenOnYouTube,
+    Nothing,
+}
+
+impl RowAction {
+    pub const ALL: [RowAction; 6] = [
+        RowAction::Play,
+        RowAction::PlayInCrossPlay,
+        RowAction::EditMetadata,
+        RowAction::Crop,
+        RowAction::OpenOnYouTube,
+        RowAction::Nothing,
+    ];
+}
+
+impl Display for RowAction {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.write_str(tr(match self {
+            RowAction::Play => Key::Play,
+            RowAction::PlayInCrossPlay => Key::RowActionPlayInCrossPlay,
+            RowAction::EditMetadata => Key::RowActionEditMetadata,
+            RowAction::Crop => Key::Crop,
+            RowAction::OpenOnYouTube => Key::RowActionOpenOnYouTube,
+            RowAction::Nothing => Key::RowActionNothing,
+        }))
+    }
+}
+
+/// What to do with a downloaded song when its metadata couldn't be looked up, and so its title is
+/// just its video ID.
+#[derive(Debug, Serialize, Deserialize, Copy, Clone, PartialEq, Eq)]
+pub enum MetadataLookupFailureAction {
+    /// Keep the placeholder metadata without doing anything else.
+    KeepFallback,
+    /// Open the edit metadata view for the song straight away.
+    OpenEditor,
+    /// Flag the song as needing metadata, which is shown in the song list until it's edited.
+    FlagNeedsMetadata,
+}
+
+impl MetadataLookupFailureAction {
+    pub const ALL: [MetadataLookupFailureAction; 3] = [
+        MetadataLookupFailureAction::KeepFallback,
+        MetadataLookupFailureAction::OpenEditor,
+        MetadataLookupFailureAction::FlagNeedsMetadata,
+    ];
+}
+
+impl Display for MetadataLookupFailureAction {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.write_str(tr(match self {
+            MetadataLookupFailureAction::KeepFallback => Key::MetadataFailureKeepFallback,
+            MetadataLookupFailureAction::OpenEditor => Key::MetadataFailureOpenEditor,
+            MetadataLookupFailureAction::FlagNeedsMetadata => Key::MetadataFailureFlag,
+        }))
+    }
+}
+
+/// What to do when a download is started but ffmpeg, which youtube-dl needs to convert downloads,
+/// isn't installed.
+#[derive(Debug, Serialize, Deserialize, Copy, Clone, PartialEq, Eq)]
+pub enum MissingFfmpegAction {
+    /// Refuse to start the download, explaining why.
+    Refuse,
+    /// Download the audio in whichever format YouTube has it, without converting it. Post-processing
+    /// steps are skipped.
+    DownloadUnconverted,
+}
+
+impl MissingFfmpegAction {
+    pub const ALL: [MissingFfmpegAction; 2] = [
+        MissingFfmpegAction::Refuse,
+        MissingFfmpegAction::DownloadUnconverted,
+    ];
+}
+
+impl Display for MissingFfmpegAction {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.write_str(tr(match self {
+            MissingFfmpegAction::Refuse => Key::MissingFfmpegRefuse,
+            MissingFfmpegAction::DownloadUnconverted => Key::MissingFfmpegDownloadUnconverted,
+        }))
+    }
+}
+
+/// Whether the song list shares the window with a panel showing the selected song's details, which
+/// can be edited there without leaving the list.
+#[derive(Debug, Serialize, Deserialize, Copy, Clone, PartialEq, Eq)]
+pub enum SplitLayout {
+    Never,
+    /// Only once the window is at least [`SplitLayout::MIN_WIDTH`] wide, since the list is cramped
+    /// otherwise.
+    WideWindows,
+    Always,
+}
+
+impl SplitLayout {
+    pub const ALL: [SplitLayout; 3] = [
+        SplitLayout::Never,
+        SplitLayout::WideWindows,
+        SplitLayout::Always,
+    ];
+
+    /// How wide the window must be for [`SplitLayout::WideWindows`] to use the split layout.
+    pub const MIN_WIDTH: u32 = 1400;
+
+    /// Whether to use the split layout in a window of the given width.
+    pub fn is_split(&self, window_width: u32) -> bool {
+        match self {
+            SplitLayout::Never => false,
+            SplitLayout::WideWindows => window_width >= Self::MIN_WIDTH,
+            SplitLayout::Always => true,
+        }
+    }
+}
+
+impl Display for SplitLayout {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.write_str(tr(match self {
+            SplitLayout::Never => Key::SplitLayoutNever,
+            SplitLayout::WideWindows => Key::SplitLayoutWideWindows,
+            SplitLayout::Always => Key::SplitLayoutAlways,
+        }))
+    }
+}
+
+/// How often to check whether the library folder has been changed by something other than CrossPlay.
+/// It's always checked when the window is focused, too.
+#[derive(Debug, Serialize, Deserialize, Copy, Clone, PartialEq, Eq)]
+pub enum LibraryCheckInterval {
+    Never,
+    Minutes(u64),
+}
+
+impl LibraryCheckInterval {
+    pub const ALL: [LibraryCheckInterval; 4] = [
+        LibraryCheckInterval::Never,
+        LibraryCheckInterval::Minutes(5),
+        LibraryCheckInterval::Minutes(15),
+        LibraryCheckInterval::Minutes(60),
+    ];
+
+    pub fn duration(&self) -> Option<Duration> {
+        match self {
+            LibraryCheckInterval::Never => None,
+            LibraryCheckInterval::Minutes(m) => Some(Duration::from_secs(m * 60)),
+        }
+    }
+}
+
+impl Display for LibraryCheckInterval {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            LibraryCheckInterval::Never => f.write_str(tr(Key::LibraryCheckOnFocus)),
+            LibraryCheckInterval::Minutes(m) => f.write_str(&tr_count(Key::LibraryCheckEveryMinutes, *m as usize, &[])),
+        }
+    }
+}
+
+/// The audio format which videos are converted to when they're downloaded.
+#[derive(Debug, Serialize, Deserialize, Copy, Clone, PartialEq, Eq)]
+pub enum AudioFormat {
+    Mp3,
+    M4a,
+    Opus,
+    Flac,
+}
+
+impl AudioFormat {
+    pub const ALL: [AudioFormat; 4] = [
+        AudioFormat::Mp3,
+        AudioFormat::M4a,
+        AudioFormat::Opus,
+        AudioFormat::Flac,
+    ];
+
+    /// The file extension for this format, which is also the name youtube-dl uses for it.
+    pub fn extension(&self) -> &'static str {
+        match self {
+            AudioFormat::Mp3 => "mp3",
+            AudioFormat::M4a => "m4a",
+            AudioFormat::Opus => "opus",
+            AudioFormat::Flac => "flac",
+        }
+    }
+
+    /// The name of the ffmpeg muxer which writes files in this format.
+    pub fn ffmpeg_muxer(&self) -> &'static str {
+        match self {
+            AudioFormat::Mp3 => "mp3",
+            AudioFormat::M4a => "ipod",
+            AudioFormat::Opus => "opus",
+            AudioFormat::Flac => "flac",
+        }
+    }
+
+    /// Whether this format stores audio without losing any quality, so that there's no bitrate to
+    /// choose.
+    pub fn is_lossless(&self) -> bool {
+        *self == AudioFormat::Flac
+    }
+
+    /// Whether album art can be stored inside files of this format. ffmpeg can't attach pictures
+    /// to Ogg files, which Opus uses.
+    pub fn supports_embedded_art(&self) -> bool {
+        *self != AudioFormat::Opus
+    }
+
+    /// The format of a song file, judging by its extension. Hidden songs have an extra `.hidden`
+    /// extension, which is looked past.
+    pub fn from_path(path: &Path) -> Option<AudioFormat> {
+        if has_extension(path, "hidden") {
+            return Self::from_path(&path.with_extension(""));
+        }
+
+        Self::ALL.into_iter().find(|f| has_extension(path, f.extension()))
+    }
+}
+
+impl Display for AudioFormat {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.write_str(match self {
+            AudioFormat::Mp3 => "MP3",
+            AudioFormat::M4a => "M4A (AAC)",
+            AudioFormat::Opus => "Opus",
+            AudioFormat::Flac => "FLAC",
+        })
+    }
+}
+
+/// The quality which youtube-dl converts downloads to. This has no effect on lossless formats.
+#[derive(Debug, Serialize, Deserialize, Copy, Clone, PartialEq, Eq)]
+pub enum AudioQuality {
+    /// The best variable bitrate the encoder offers.
+    Best,
+    Kbps(u32),
+}
+
+impl AudioQuality {
+    pub const ALL: [AudioQuality; 6] = [
+        AudioQuality::Best,
+        AudioQuality::Kbps(320),
+        AudioQuality::Kbps(256),
+        AudioQuality::Kbps(192),
+        AudioQuality::Kbps(128),
+        AudioQuality::Kbps(96),
+    ];
+
+    /// The value passed to youtube-dl's `--audio-quality` option, which takes either a VBR quality
+    /// from 0 (best) to 9, or a bitrate.
+    pub fn youtube_dl_arg(&self) -> String {
+        match self {
+            AudioQuality::Best => "0".to_string(),
+            AudioQuality::Kbps(k) => format!("{}K", k),
+        }
+    }
+}
+
+impl Display for AudioQuality {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            AudioQuality::Best => f.write_str(tr(Key::AudioQualityBest)),
+            AudioQuality::Kbps(k) => f.write_str(&tr_args(Key::AudioQualityKbps, &[k])),
+        }
+    }
+}
+
+/// The largest album art which downloads are given. Art which is bigger in either dimension is
+/// scaled down to fit, which saves space in large libraries.
+#[derive(Debug, Serialize, Deserialize, Copy, Clone, PartialEq, Eq)]
+pub enum AlbumArtSize {
+    /// Keep the thumbnail at whatever size YouTube has it.
+    Original,
+    MaxPixels(u32),
+}
+
+impl AlbumArtSize {
+    pub const ALL: [AlbumArtSize; 4] = [
+        AlbumArtSize::Original,
+        AlbumArtSize::MaxPixels(1280),
+        AlbumArtSize::MaxPixels(800),
+        AlbumArtSize::MaxPixels(500),
+    ];
+
+    /// The largest width or height allowed, if there's a limit.
+    pub fn max_dimension(&self) -> Option<u32> {
+        match self {
+            AlbumArtSize::Original => None,
+            AlbumArtSize::MaxPixels(p) => Some(*p),
+        }
+    }
+}
+
+impl Display for AlbumArtSize {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            AlbumArtSize::Original => f.write_str(tr(Key::AlbumArtSizeOriginal)),
+            AlbumArtSize::MaxPixels(p) => f.write_str(&tr_args(Key::AlbumArtSizeMax, &[p])),
+        }
+    }
+}
+
+/// A check for songs which probably need the user's attention, such as songs whose metadata couldn't
+/// be looked up. Songs which break any enabled rule are listed by the "Needs attention" filter.
+#[derive(Debug, Serialize, Deserialize, Copy, Clone, PartialEq, Eq, Hash)]
+pub enum ReviewRule {
+    TitleIsVideoId,
+    MissingAlbumArt,
+    UnknownArtist,
+    TooShort,
+    TooLong,
+}
+
+impl ReviewRule {
+    pub const ALL: [ReviewRule; 5] = [
+        ReviewRule::TitleIsVideoId,
+        ReviewRule::MissingAlbumArt,
+        ReviewRule::UnknownArtist,
+        ReviewRule::TooShort,
+        ReviewRule::TooLong,
+    ];
+}
+
+impl Display for ReviewRule {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.write_str(tr(match self {
+            ReviewRule::TitleIsVideoId => Key::ReviewRuleTitleIsVideoId,
+            ReviewRule::MissingAlbumArt => Key::ReviewRuleMissingAlbumArt,
+            ReviewRule::UnknownArtist => Key::ReviewRuleUnknownArtist,
+            ReviewRule::TooShort => Key::ReviewRuleTooShortSetting,
+            ReviewRule::TooLong => Key::ReviewRuleTooLongSetting,
+        }))
+    }
+}
+
+/// Something which can be done to songs automatically once they're downloaded, as part of the
+/// post-processing pipeline. See [`crate::post_process`].
+#[derive(Debug, Serialize, Deserialize, Copy, Clone, PartialEq, Eq, Hash)]
+pub enum PostProcessStepKind {
+    TrimSilence,
+    SquareAlbumArt,
+    ShrinkAlbumArt,
+    NormalizeLoudness,
+}
+
+impl PostProcessStepKind {
+    pub const ALL: [PostProcessStepKind; 4] = [
+        PostProcessStepKind::TrimSilence,
+        PostProcessStepKind::SquareAlbumArt,
+        PostProcessStepKind::ShrinkAlbumArt,
+        PostProcessStepKind::NormalizeLoudness,
+    ];
+}
+
+impl Display for PostProcessStepKind {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.write_str(tr(match self {
+            PostProcessStepKind::TrimSilence => Key::PostProcessTrimSilence,
+            PostProcessStepKind::SquareAlbumArt => Key::PostProcessSquareAlbumArt,
+            PostProcessStepKind::ShrinkAlbumArt => Key::PostProcessShrinkAlbumArt,
+            PostProcessStepKind::NormalizeLoudness => Key::PostProcessNormalizeLoudness,
+        }))
+    }
+}
+
+/// A reusable crop, which can be applied to any song without opening the crop view.
+#[derive(Debug, Serialize, Deserialize, Clone, PartialEq, Eq)]
+pub struct CropTemplate {
+    pub name: String,
+    pub start_millis: u64,
+
+    /// Where the crop ends, or `None` to keep everything up to the end of the song.
+    pub end_millis: Option<u64>,
+}
+
+impl CropTemplate {
+    /// Resolves this template into concrete start and end points for a song of the given duration,
+    /// or `None` if the template's offsets do not fit within the song.
+    pub fn range_for(&self, duration: Duration) -> Option<(Duration, Duration)> {
+        let start = Duration::from_millis(self.start_millis);
+        let end = match self.end_millis {
+            Some(end) => Duration::from_millis(end),
+            None => duration,
+        };
+
+        if start < end && end <= duration {
+            Some((start, end))
+        } else {
+            None
+        }
+    }
+}
+
 #[derive(Debug, Serialize, Deserialize)]
 pub struct Settings {
     #[serde(default = "Settings::default_library_path")]
@@ -34,41 +438,286 @@ pub struct Settings {
     #[serde(default = "Settings::default_sort_by")]
     pub sort_by: SortBy,
 
-    #[serde(default = "Settings::default_sort_direction")]
-    pub sort_direction: SortDirection,
+    /// The direction of each sort, so that switching between sorts keeps the direction each one was
+    /// last used with. Sorts which aren't in here go in their normal direction.
+    #[serde(default)]
+    pub sort_directions: HashMap<SortBy, SortDirection>,
+
+    /// Before directions were remembered for each sort, there was just one direction, which is
+    /// migrated into `sort_directions` when loaded.
+    #[serde(default, rename = "sort_direction", skip_serializing)]
+    legacy_sort_direction: Option<SortDirection>,
+
+    /// Album art used to be squared by its own setting, rather than by a step in
+    /// `post_process_pipeline`, which it's migrated into when loaded.
+    #[serde(default, rename = "square_album_art", skip_serializing)]
+    legacy_square_album_art: Option<bool>,
+
+    /// Like squaring album art, normalizing loudness used to have its own setting.
+    #[serde(default, rename = "normalize_loudness", skip_serializing)]
+    legacy_normalize_loudness: Option<bool>,
+
+    /// The loudness which the [`PostProcessStepKind::NormalizeLoudness`] step brings songs to, in
+    /// LUFS. This was kept when normalizing moved into the pipeline, so older settings carry it over.
+    #[serde(default = "Settings::default_loudness_target")]
+    pub loudness_target: i32,
+
+    #[serde(default)]
+    pub crop_templates: Vec<CropTemplate>,
+
+    /// If true, originals are stored in a shared folder named by checksum, rather than alongside
+    /// each song, so that identical originals are only stored once.
+    #[serde(default)]
+    pub deduplicate_originals: bool,
+
+    /// How far the arrow keys move the playhead in the crop view, in milliseconds.
+    #[serde(default = "Settings::default_crop_nudge_millis")]
+    pub crop_nudge_millis: u64,
+
+    #[serde(default = "Settings::default_row_double_click_action")]
+    pub row_double_click_action: RowAction,
+
+    #[serde(default = "Settings::default_row_middle_click_action")]
+    pub row_middle_click_action: RowAction,
+
+    #[serde(default = "Settings::default_split_layout")]
+    pub split_layout: SplitLayout,
+
+    #[serde(default)]
+    pub language: Language,
+
+    #[serde(default = "Settings::default_metadata_lookup_failure_action")]
+    pub metadata_lookup_failure_action: MetadataLookupFailureAction,
+
+    /// The name of the program used to download videos on the PATH, or a full path to it. If it
+    /// can't be run, yt-dlp and then youtube-dl are tried instead.
+    #[serde(default = "Settings::default_downloader_binary")]
+    pub downloader_binary: String,
+
+    /// A downloader executable chosen from the settings menu used to be kept apart from
+    /// `downloader_binary`, and took its place. It's migrated into `downloader_binary` when loaded.
+    #[serde(default, rename = "downloader_path", skip_serializing)]
+    legacy_downloader_path: Option<PathBuf>,
+
+    #[serde(default = "Settings::default_library_check_interval")]
+    pub library_check_interval: LibraryCheckInterval,
+
+    /// The format which new downloads are saved in. Songs which have already been downloaded keep
+    /// their format.
+    #[serde(default = "Settings::default_audio_format")]
+    pub audio_format: AudioFormat,
+
+    #[serde(default = "Settings::default_audio_quality")]
+    pub audio_quality: AudioQuality,
+
+    #[serde(default = "Settings::default_album_art_size")]
+    pub album_art_size: AlbumArtSize,
+
+    /// Whether to split videos which have chapters, like full albums, into one song per chapter.
+    /// This can be changed for each download from its preview.
+    #[serde(default)]
+    pub split_chapters: bool,
+
+    #[serde(default = "Settings::default_missing_ffmpeg_action")]
+    pub missing_ffmpeg_action: MissingFfmpegAction,
+
+    /// Whether to work out the artist from video titles like "Artist - Song", rather than using the
+    /// channel's name, and trim noise like "(Official Video)" off titles.
+    #[serde(default = "Settings::default_parse_artist_from_title")]
+    pub parse_artist_from_title: bool,
+
+    /// The rules which songs are checked against for the "Needs attention" filter.
+    #[serde(default = "Settings::default_review_rules")]
+    pub review_rules: Vec<ReviewRule>,
+
+    /// How many downloads can run at once. Any more are queued until one finishes.
+    #[serde(default = "Settings::default_max_concurrent_downloads")]
+    pub max_concurrent_downloads: usize,
+
+    /// The steps run on each song after it's downloaded, in order.
+    #[serde(default)]
+    pub post_process_pipeline: Vec<PostProcessStepKind>,
+
+    /// The fastest downloads may go, in youtube-dl's format like "500K" or "2M", so that they
+    /// don't use all of the connection. Applies to each download separately.
+    #[serde(default)]
+    pub download_rate_limit: Option<String>,
+
+    /// Whether the song list is split into collapsible groups by the active sort, when that sort
+    /// is by artist or album.
+    #[serde(default)]
+    pub group_songs: bool,
+
+    /// A cookies file passed to youtube-dl, so that it can download videos which need the user to
+    /// sign in, like age-restricted ones. This holds credentials, so shouldn't be shown in errors.
+    #[serde(default)]
+    pub cookies_file: Option<PathBuf>,
+
+    /// Whether to fetch videos' English subtitles when downloading, and embed them as the song's
+    /// lyrics.
+    #[serde(default)]
+    pub embed_lyrics: bool,
+
+    /// Arguments passed to youtube-dl for every download, for options CrossPlay doesn't have a
+    /// setting for.
+    #[serde(default)]
+    pub extra_downloader_args: Vec<String>,
+
+    /// Whether new downloads are kept in the library's staging folder until they're approved, so
+    /// that they aren't part of the library while their metadata is still being fixed up.
+    #[serde(default)]
+    pub staging_enabled: bool,
+
+    /// Whether songs are looked for in subfolders of the library too, like `Artist/Album/`. This
+    /// can be turned off for libraries inside a large folder of unrelated files.
+    #[serde(default = "Settings::default_scan_recursively")]
+    pub scan_recursively: bool,
+
+    /// A command run after each successful download, like importing the song into another music
+    /// library. `{path}` in its arguments is replaced with the downloaded song's path, and `{id}`
+    /// with its video ID. See [`crate::hooks::run_post_download_command`].
+    #[serde(default)]
+    pub post_download_command: Option<String>,
+
+    /// Whether [`Settings::post_download_command`] is run, so that it can be turned off without
+    /// losing it.
+    #[serde(default)]
+    pub post_download_command_enabled: bool,
 }
 
+/// The environment variable which, if set, moves the settings folder somewhere else - for example,
+/// when the usual one can't be written to. The `--settings-dir` flag sets this too.
+pub const SETTINGS_DIR_VAR: &str = "CROSSPLAY_SETTINGS_DIR";
+
+/// Why the settings couldn't be saved the last time they were, if they couldn't.
+static SAVE_ERROR: Mutex<Option<String>> = Mutex::new(None);
+
 impl Settings {
     pub fn settings_dir() -> PathBuf {
-        dirs::config_dir().expect("unknown OS").join("CrossPlay")
+        match std::env::var_os(SETTINGS_DIR_VAR) {
+            Some(dir) if !dir.is_empty() => dir.into(),
+            _ => dirs::config_dir().expect("unknown OS").join("CrossPlay"),
+        }
     }
 
     pub fn settings_path() -> PathBuf {
         Self::settings_dir().join("settings.json")
     }
 
+    /// A "CrossPlay" folder in the user's music folder. Some systems, like Linux desktops without
+    /// XDG user folders set up, don't have one, so the home folder is used instead - and failing
+    /// that, a folder relative to wherever CrossPlay was started.
     pub fn default_library_path() -> PathBuf {
-        dirs::audio_dir().expect("unknown OS").join("CrossPlay")
+        dirs::audio_dir()
+            .or_else(dirs::home_dir)
+            .unwrap_or_default()
+            .join("CrossPlay")
     }
     pub fn default_sort_by() -> SortBy { SortBy::Downloaded }
-    pub fn default_sort_direction() -> SortDirection { SortDirection::Normal }
+    pub fn default_crop_nudge_millis() -> u64 { 100 }
+    pub fn default_row_double_click_action() -> RowAction { RowAction::Play }
+    pub fn default_row_middle_click_action() -> RowAction { RowAction::Nothing }
+    pub fn default_split_layout() -> SplitLayout { SplitLayout::WideWindows }
+    pub fn default_metadata_lookup_failure_action() -> MetadataLookupFailureAction { MetadataLookupFailureAction::FlagNeedsMetadata }
+    pub fn default_downloader_binary() -> String { "yt-dlp".to_string() }
+    pub fn default_library_check_interval() -> LibraryCheckInterval { LibraryCheckInterval::Never }
+    pub fn default_audio_format() -> AudioFormat { AudioFormat::Mp3 }
+    pub fn default_audio_quality() -> AudioQuality { AudioQuality::Best }
+    pub fn default_album_art_size() -> AlbumArtSize { AlbumArtSize::Original }
+    pub fn default_missing_ffmpeg_action() -> MissingFfmpegAction { MissingFfmpegAction::Refuse }
+    pub fn default_parse_artist_from_title() -> bool { true }
+    pub fn default_review_rules() -> Vec<ReviewRule> { ReviewRule::ALL.to_vec() }
+    pub fn default_max_concurrent_downloads() -> usize { 3 }
+    pub fn default_scan_recursively() -> bool { true }
+    pub fn default_loudness_target() -> i32 { -14 }
 
     /// Loads the application settings, or creates them from defaults if they do not exist.
     pub fn load() -> Result<Self> {
         let path = Self::settings_path();
         if !path.exists() {
-            Settings::default().save()?;
+            // If the defaults can't be saved, they're still fine to use for this session
+            let settings = Settings::default();
+            settings.save();
+            return Ok(settings);
         }
 
-        let settings_contents = std::fs::read_to_string(path)?;
-        Ok(serde_json::from_str(&settings_contents)?)
+        Self::from_json(&std::fs::read_to_string(path)?)
+    }
+
+    /// Parses saved settings, migrating them if they're from an older version of CrossPlay.
+    fn from_json(json: &str) -> Result<Self> {
+        let mut settings: Self = serde_json::from_str(json)?;
+        settings.migrate();
+        Ok(settings)
+    }
+
+    /// Updates settings loaded from an older version of CrossPlay to use newer fields.
+    fn migrate(&mut self) {
+        // The single sort direction applied to whichever sort was active at the time
+        if let Some(direction) = self.legacy_sort_direction.take() {
+            self.sort_directions.entry(self.sort_by).or_insert(direction);
+        }
+
+        // Squaring album art happened before any other post-processing
+        if self.legacy_square_album_art.take() == Some(true) && !self.post_process_pipeline.contains(&PostProcessStepKind::SquareAlbumArt) {
+            self.post_process_pipeline.insert(0, PostProcessStepKind::SquareAlbumArt);
+        }
+
+        // ...and normalizing loudness after everything else
+        if self.legacy_normalize_loudness.take() == Some(true) && !self.post_process_pipeline.contains(&PostProcessStepKind::NormalizeLoudness) {
+            self.post_process_pipeline.push(PostProcessStepKind::NormalizeLoudness);
+        }
+
+        // A chosen downloader was used instead of the binary whenever there was one
+        if let Some(path) = self.legacy_downloader_path.take() {
+            self.downloader_binary = path.to_string_lossy().into_owned();
+        }
+    }
+
+    /// The direction of the given sort.
+    pub fn sort_direction_for(&self, sort: SortBy) -> SortDirection {
+        self.sort_directions.get(&sort).copied().unwrap_or(SortDirection::Normal)
+    }
+
+    /// The direction of the active sort.
+    pub fn sort_direction(&self) -> SortDirection {
+        self.sort_direction_for(self.sort_by)
+    }
+
+    /// Changes to the given sort, in the given direction.
+    pub fn set_sort(&mut self, sort: SortBy, direction: SortDirection) {
+        self.sort_by = sort;
+        self.sort_directions.insert(sort, direction);
+    }
+
+    /// Reverses the direction of the active sort only.
+    pub fn reverse_sort_direction(&mut self) {
+        let direction = self.sort_direction().reverse();
+        self.sort_directions.insert(self.sort_by, direction);
     }
 
     /// Saves the application settings.
-    pub fn save(&self) -> Result<()> {
+    ///
+    /// If they can't be saved, like if the settings folder is read-only, the changes still apply
+    /// for the rest of the session. Why is printed, and kept for [`Settings::save_error`] so that
+    /// it can be shown once rather than on every change.
+    pub fn save(&self) {
+        let result = self.try_save();
+        if let Err(e) = &result {
+            println!("[Settings] Couldn't save settings: {}", e);
+        }
+        *SAVE_ERROR.lock().unwrap() = result.err().map(|e| e.to_string());
+    }
+
+    /// Why the settings couldn't be saved the last time they were, or `None` if they were.
+    pub fn save_error() -> Option<String> {
+        SAVE_ERROR.lock().unwrap().clone()
+    }
+
+    fn try_save(&self) -> Result<()> {
         // Ensure settings dir exists
         if !Self::settings_dir().exists() {
-            std::fs::create_dir(Self::settings_dir())?;
+            std::fs::create_dir_all(Self::settings_dir())?;
         }
 
         // Ensure library dir exists
@@ -88,7 +737,139 @@ impl Default for Settings {
         Self {
             library_path: Self::default_library_path(),
             sort_by: Self::default_sort_by(),
-            sort_direction: Self::default_sort_direction(),
+            sort_directions: HashMap::new(),
+            legacy_sort_direction: None,
+            legacy_square_album_art: None,
+            legacy_normalize_loudness: None,
+            loudness_target: Self::default_loudness_target(),
+            crop_templates: vec![],
+            deduplicate_originals: false,
+            crop_nudge_millis: Self::default_crop_nudge_millis(),
+            row_double_click_action: Self::default_row_double_click_action(),
+            row_middle_click_action: Self::default_row_middle_click_action(),
+            split_layout: Self::default_split_layout(),
+            language: Language::default(),
+            metadata_lookup_failure_action: Self::default_metadata_lookup_failure_action(),
+            downloader_binary: Self::default_downloader_binary(),
+            legacy_downloader_path: None,
+            library_check_interval: Self::default_library_check_interval(),
+            audio_format: Self::default_audio_format(),
+            audio_quality: Self::default_audio_quality(),
+            album_art_size: Self::default_album_art_size(),
+            split_chapters: false,
+            missing_ffmpeg_action: Self::default_missing_ffmpeg_action(),
+            parse_artist_from_title: Self::default_parse_artist_from_title(),
+            review_rules: Self::default_review_rules(),
+            max_concurrent_downloads: Self::default_max_concurrent_downloads(),
+            // YouTube's thumbnails are widescreen, but most players expect square covers
+            post_process_pipeline: vec![PostProcessStepKind::SquareAlbumArt],
+            download_rate_limit: None,
+            group_songs: false,
+            cookies_file: None,
+            embed_lyrics: false,
+            extra_downloader_args: vec![],
+            staging_enabled: false,
+            scan_recursively: Self::default_scan_recursively(),
+            post_download_command: None,
+            post_download_command_enabled: false,
         }
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_format_from_mixed_case_path() {
+        assert_eq!(AudioFormat::from_path(Path::new("song.mp3")), Some(AudioFormat::Mp3));
+        assert_eq!(AudioFormat::from_path(Path::new("Song.MP3")), Some(AudioFormat::Mp3));
+        assert_eq!(AudioFormat::from_path(Path::new("song.Opus.HIDDEN")), Some(AudioFormat::Opus));
+        assert_eq!(AudioFormat::from_path(Path::new("song.txt")), None);
+        assert_eq!(AudioFormat::from_path(Path::new("song.hidden")), None);
+    }
+
+    /// Settings as saved by a version of CrossPlay which had one direction for every sort.
+    fn legacy_settings_json(sort_by: SortBy, direction: SortDirection) -> serde_json::Value {
+        let mut json = serde_json::to_value(Settings::default()).unwrap();
+        let object = json.as_object_mut().unwrap();
+        object.remove("sort_directions");
+        object.insert("sort_by".into(), serde_json::to_value(sort_by).unwrap());
+        object.insert("sort_direction".into(), serde_json::to_value(direction).unwrap());
+        json
+    }
+
+    #[test]
+    fn test_migrate_legacy_sort_direction() {
+        let json = legacy_settings_json(SortBy::Artist, SortDirection::Reverse);
+        let settings = Settings::from_json(&json.to_string()).unwrap();
+
+        assert_eq!(settings.sort_directions.get(&SortBy::Artist), Some(&SortDirection::Reverse));
+        assert_eq!(settings.sort_direction(), SortDirection::Reverse);
+        assert_eq!(settings.sort_direction_for(SortBy::Title), SortDirection::Normal);
+    }
+
+    #[test]
+    fn test_migrate_keeps_newer_sort_directions() {
+        let mut json = legacy_settings_json(SortBy::Artist, SortDirection::Reverse);
+        json["sort_directions"] = serde_json::json!({ "Artist": "Normal" });
+        let settings = Settings::from_json(&json.to_string()).unwrap();
+
+        assert_eq!(settings.sort_direction(), SortDirection::Normal);
+    }
+
+    #[test]
+    fn test_migrated_settings_save_without_legacy_sort_direction() {
+        let json = legacy_settings_json(SortBy::Album, SortDirection::Reverse);
+        let settings = Settings::from_json(&json.to_string()).unwrap();
+
+        let saved = serde_json::to_value(&settings).unwrap();
+        assert!(saved.get("sort_direction").is_none());
+        assert_eq!(saved["sort_directions"]["Album"], "Reverse");
+    }
+
+    #[test]
+    fn test_migrate_square_album_art_into_pipeline() {
+        let mut json = serde_json::to_value(Settings::default()).unwrap();
+        json["square_album_art"] = true.into();
+        json["post_process_pipeline"] = serde_json::json!(["TrimSilence"]);
+        let settings = Settings::from_json(&json.to_string()).unwrap();
+        assert_eq!(settings.post_process_pipeline, vec![PostProcessStepKind::SquareAlbumArt, PostProcessStepKind::TrimSilence]);
+
+        json["square_album_art"] = false.into();
+        let settings = Settings::from_json(&json.to_string()).unwrap();
+        assert_eq!(settings.post_process_pipeline, vec![PostProcessStepKind::TrimSilence]);
+    }
+
+    #[test]
+    fn test_migrate_normalize_loudness_into_pipeline() {
+        let mut json = serde_json::to_value(Settings::default()).unwrap();
+        json["normalize_loudness"] = true.into();
+        json["loudness_target"] = (-18).into();
+        let settings = Settings::from_json(&json.to_string()).unwrap();
+        assert_eq!(settings.post_process_pipeline, vec![PostProcessStepKind::SquareAlbumArt, PostProcessStepKind::NormalizeLoudness]);
+        assert_eq!(settings.loudness_target, -18);
+
+        // Settings which haven't been saved since they were migrated still have the old setting,
+        // which mustn't add the step again
+        json["post_process_pipeline"] = serde_json::json!(["NormalizeLoudness", "TrimSilence"]);
+        let settings = Settings::from_json(&json.to_string()).unwrap();
+        assert_eq!(settings.post_process_pipeline, vec![PostProcessStepKind::NormalizeLoudness, PostProcessStepKind::TrimSilence]);
+    }
+
+    #[test]
+    fn test_migrate_downloader_path_into_binary() {
+        let mut json = serde_json::to_value(Settings::default()).unwrap();
+        json["downloader_binary"] = "youtube-dl".into();
+        json["downloader_path"] = "/opt/venv/bin/yt-dlp".into();
+        let settings = Settings::from_json(&json.to_string()).unwrap();
+        assert_eq!(settings.downloader_binary, "/opt/venv/bin/yt-dlp");
+
+        // Without a chosen path, the binary is left alone
+        json["downloader_path"] = serde_json::Value::Null;
+        let settings = Settings::from_json(&json.to_string()).unwrap();
+        assert_eq!(settings.downloader_binary, "youtube-dl");
+
+        assert!(serde_json::to_value(&settings).unwrap().get("downloader_path").is_none());
+    }
+}
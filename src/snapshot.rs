@@ -0,0 +1,267 @@
+use std::{path::{PathBuf, Path}, fs::OpenOptions, io::{self, Write}};
+
+use anyhow::Result;
+use serde::{Serialize, Deserialize};
+
+use crate::{library::Song, settings::Settings, youtube::unix_time_now};
+
+/// Operations touching more than this many songs take a snapshot of their tags first.
+const BULK_OPERATION_THRESHOLD: usize = 5;
+
+/// The number of snapshots to keep before the oldest ones are deleted.
+const MAX_SNAPSHOTS: usize = 20;
+
+/// A backup of the user-editable tags of some songs, so that a bulk operation which went wrong can
+/// be undone.
+#[derive(Debug, Serialize, Deserialize)]
+pub struct TagSnapshot {
+    pub created_unix_time: u64,
+    pub songs: Vec<SongTagSnapshot>,
+}
+
+/// The tags of one song within a [`TagSnapshot`].
+///
+/// Snapshots taken before the genre, year, track and flags were kept don't have them, so restoring
+/// one of those leaves them empty and unset.
+#[derive(Debug, Serialize, Deserialize, Clone, PartialEq, Eq)]
+pub struct SongTagSnapshot {
+    pub path: PathBuf,
+    pub youtube_id: String,
+    pub title: String,
+    pub artist: String,
+    pub album: String,
+    #[serde(default)]
+    pub genre: Option<String>,
+    #[serde(default)]
+    pub year: Option<i32>,
+    #[serde(default)]
+    pub track: Option<u32>,
+    #[serde(default)]
+    pub is_pinned: bool,
+    #[serde(default)]
+    pub is_reviewed: bool,
+    #[serde(default)]
+    pub needs_metadata: bool,
+}
+
+impl SongTagSnapshot {
+    fn new(song: &Song) -> Self {
+        Self {
+            path: song.path.clone(),
+            youtube_id: song.metadata.youtube_id.clone(),
+            title: song.metadata.title.clone(),
+            artist: song.metadata.artist.clone(),
+            album: song.metadata.album.clone(),
+            genre: song.metadata.genre.clone(),
+            year: song.metadata.year,
+            track: song.metadata.track,
+            is_pinned: song.metadata.is_pinned,
+            is_reviewed: song.metadata.is_reviewed,
+            needs_metadata: song.metadata.needs_metadata,
+        }
+    }
+
+    /// Returns true if the song's tags already match this snapshot.
+    fn matches(&self, song: &Song) -> bool {
+        self.title == song.metadata.title
+            && self.artist == song.metadata.artist
+            && self.album == song.metadata.album
+            && self.genre == song.metadata.genre
+            && self.year == song.metadata.year
+            && self.track == song.metadata.track
+            && self.is_pinned == song.metadata.is_pinned
+            && self.is_reviewed == song.metadata.is_reviewed
+            && self.needs_metadata == song.metadata.needs_metadata
+    }
+
+    /// Overwrites the song's tags in memory with the values from this snapshot.
+    pub fn apply_to(&self, song: &mut Song) {
+        song.metadata.title = self.title.clone();
+        song.metadata.artist = self.artist.clone();
+        song.metadata.album = self.album.clone();
+        song.metadata.genre = self.genre.clone();
+        song.metadata.year = self.year;
+        song.metadata.track = self.track;
+        song.metadata.is_pinned = self.is_pinned;
+        song.metadata.is_reviewed = self.is_reviewed;
+        song.metadata.needs_metadata = self.needs_metadata;
+    }
+
+    /// Rewrites the song's tags with the values from this snapshot.
+    pub fn restore(&self, song: &mut Song) -> Result<()> {
+        self.apply_to(song);
+        song.user_edit_metadata()?;
+
+        // Editing metadata counts as filling it in, so the flag has to be put back afterwards
+        if self.needs_metadata {
+            song.flag_needs_metadata()?;
+        }
+
+        Ok(())
+    }
+}
+
+impl TagSnapshot {
+    pub fn new(songs: &[Song]) -> Self {
+        Self {
+            created_unix_time: unix_time_now(),
+            songs: songs.iter().map(SongTagSnapshot::new).collect(),
+        }
+    }
+
+    /// The folder where snapshots are saved.
+    pub fn snapshots_dir() -> PathBuf {
+        Settings::settings_dir().join("snapshots")
+    }
+
+    pub fn load(path: &Path) -> Result<Self> {
+        Ok(serde_json::from_str(&std::fs::read_to_string(path)?)?)
+    }
+
+    /// Saves this snapshot into the snapshots folder, deleting the oldest snapshots if there are now
+    /// too many.
+    pub fn save(&self) -> Result<PathBuf> {
+        std::fs::create_dir_all(Self::snapshots_dir())?;
+
+        // Several snapshots can be taken within a second, so they're numbered too, rather than
+        // overwriting each other
+        let mut number = 0;
+        let (path, mut file) = loop {
+            let path = Self::snapshots_dir().join(format!("tags-{}-{:04}.json", self.created_unix_time, number));
+            match OpenOptions::new().write(true).create_new(true).open(&path) {
+                Ok(file) => break (path, file),
+                Err(e) if e.kind() == io::ErrorKind::AlreadyExists => number += 1,
+                Err(e) => return Err(e.into()),
+            }
+        };
+        file.write_all(serde_json::to_string(self)?.as_bytes())?;
+
+        // The names start with the timestamp, so sorting them sorts by age
+        let mut existing = std::fs::read_dir(Self::snapshots_dir())?
+            .map(|e| e.map(|e| e.path()))
+            .collect::<Result<Vec<_>, _>>()?;
+        existing.retain(|p| p.extension() == Some("json".as_ref()));
+        existing.sort();
+        for old in existing.iter().take(existing.len().saturating_sub(MAX_SNAPSHOTS)) {
+            std::fs::remove_file(old)?;
+        }
+
+        Ok(path)
+    }
+
+    /// Takes and saves a snapshot of the given songs, if there are enough of them that the
+    /// operation about to be performed on them counts as a bulk operation.
+    pub fn take_before_bulk_operation(songs: &[Song]) -> Result<()> {
+        if songs.len() > BULK_OPERATION_THRESHOLD {
+            Self::new(songs).save()?;
+        }
+
+        Ok(())
+    }
+
+    /// Works out which of the given songs would be changed by restoring this snapshot, pairing each
+    /// with the snapshot entry to restore.
+    ///
+    /// Songs are matched by path first, or otherwise by YouTube ID if exactly one song has that ID.
+    /// Songs which already match the snapshot, and snapshot entries with no matching song, are
+    /// skipped.
+    pub fn plan_restore<'a>(&'a self, songs: &'a [Song]) -> Vec<(&'a Song, &'a SongTagSnapshot)> {
+        self.songs
+            .iter()
+            .filter_map(|entry| {
+                let song = songs.iter().find(|s| s.path == entry.path).or_else(|| {
+                    let mut by_id = songs.iter().filter(|s| s.metadata.youtube_id == entry.youtube_id);
+                    match (by_id.next(), by_id.next()) {
+                        (Some(song), None) => Some(song),
+                        _ => None,
+                    }
+                })?;
+
+                if entry.matches(song) {
+                    None
+                } else {
+                    Some((song, entry))
+                }
+            })
+            .collect()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::library::tests::song;
+
+    #[test]
+    fn test_plan_restore_matches_by_path() {
+        let before = song("/library/a.mp3", "a");
+        let snapshot = TagSnapshot::new(&[before.clone()]);
+
+        let mut after = before;
+        after.metadata.title = "Renamed".into();
+        after.metadata.year = Some(2001);
+        let songs = [after];
+
+        let plan = snapshot.plan_restore(&songs);
+        assert_eq!(plan.len(), 1);
+        assert_eq!(plan[0].0.path, PathBuf::from("/library/a.mp3"));
+        assert_eq!(plan[0].1.title, "Title of a");
+        assert_eq!(plan[0].1.year, None);
+    }
+
+    #[test]
+    fn test_plan_restore_skips_unchanged_songs() {
+        let songs = [song("/library/a.mp3", "a")];
+        let snapshot = TagSnapshot::new(&songs);
+
+        assert!(snapshot.plan_restore(&songs).is_empty());
+    }
+
+    #[test]
+    fn test_plan_restore_falls_back_to_unique_id() {
+        let snapshot = TagSnapshot::new(&[song("/library/old name.mp3", "a")]);
+
+        let mut moved = song("/library/new name.mp3", "a");
+        moved.metadata.is_pinned = true;
+        let songs = [moved, song("/library/b.mp3", "b")];
+
+        let plan = snapshot.plan_restore(&songs);
+        assert_eq!(plan.len(), 1);
+        assert_eq!(plan[0].0.path, PathBuf::from("/library/new name.mp3"));
+        assert!(!plan[0].1.is_pinned);
+    }
+
+    #[test]
+    fn test_plan_restore_skips_ambiguous_id() {
+        let snapshot = TagSnapshot::new(&[song("/library/old name.mp3", "a")]);
+
+        // Two songs from the same video, like chapters, can't be told apart without the path
+        let mut first = song("/library/part 1.mp3", "a");
+        first.metadata.title = "Part 1".into();
+        let mut second = song("/library/part 2.mp3", "a");
+        second.metadata.title = "Part 2".into();
+        let songs = [first, second];
+
+        assert!(snapshot.plan_restore(&songs).is_empty());
+    }
+
+    #[test]
+    fn test_plan_restore_skips_missing_songs() {
+        let mut changed = song("/library/a.mp3", "a");
+        let snapshot = TagSnapshot::new(&[changed.clone(), song("/library/deleted.mp3", "deleted")]);
+        changed.metadata.artist = "Someone else".into();
+        let songs = [changed];
+
+        let plan = snapshot.plan_restore(&songs);
+        assert_eq!(plan.len(), 1);
+        assert_eq!(plan[0].1.youtube_id, "a");
+    }
+
+    #[test]
+    fn test_old_snapshots_without_extra_tags_load() {
+        let json = r#"{"created_unix_time": 1, "songs": [{"path": "/library/a.mp3", "youtube_id": "a", "title": "T", "artist": "A", "album": "B"}]}"#;
+        let snapshot: TagSnapshot = serde_json::from_str(json).unwrap();
+        assert_eq!(snapshot.songs[0].genre, None);
+        assert!(!snapshot.songs[0].is_pinned);
+    }
+}
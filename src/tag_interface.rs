@@ -112,6 +112,40 @@ impl CustomTag for YouTubeIdTag {
     fn value_if_comment_missing() -> Option<Self::T> { None }
 }
 
+/// The URL a song was downloaded from, for songs which didn't come from YouTube. These don't have
+/// a [`YouTubeIdTag`], so this is what marks them as CrossPlay's.
+pub struct SourceUrlTag;
+impl CustomTag for SourceUrlTag {
+    type T = Option<String>;
+    const NAME: &'static str = "[CrossPlay] Source URL";
+
+    fn from_comment_text(str: &str) -> Self::T { Some(str.to_string()) }
+    fn to_comment_text(value: Self::T) -> Option<String> { value }
+    fn value_if_comment_missing() -> Option<Self::T> { Some(None) }
+}
+
+/// The ID of a song which didn't come from YouTube, made up of the site's name and the video's ID
+/// on it, like "soundcloud:123456". Songs from YouTube use their [`YouTubeIdTag`] instead.
+pub struct SourceIdTag;
+impl CustomTag for SourceIdTag {
+    type T = Option<String>;
+    const NAME: &'static str = "[CrossPlay] Source ID";
+
+    fn from_comment_text(str: &str) -> Self::T { Some(str.to_string()) }
+    fn to_comment_text(value: Self::T) -> Option<String> { value }
+    fn value_if_comment_missing() -> Option<Self::T> { Some(None) }
+}
+
+pub struct OriginalHashTag;
+impl CustomTag for OriginalHashTag {
+    type T = Option<String>;
+    const NAME: &'static str = "[CrossPlay] Original hash";
+
+    fn from_comment_text(str: &str) -> Self::T { Some(str.to_string()) }
+    fn to_comment_text(value: Self::T) -> Option<String> { value }
+    fn value_if_comment_missing() -> Option<Self::T> { Some(None) }
+}
+
 pub struct CroppedTag;
 impl FlagTag for CroppedTag {
     const NAME: &'static str = "[CrossPlay] Cropped";
@@ -122,6 +156,44 @@ impl FlagTag for MetadataEditedTag {
     const NAME: &'static str = "[CrossPlay] Metadata edited";
 }
 
+pub struct NeedsMetadataTag;
+impl FlagTag for NeedsMetadataTag {
+    const NAME: &'static str = "[CrossPlay] Needs metadata";
+}
+
+pub struct PinnedTag;
+impl FlagTag for PinnedTag {
+    const NAME: &'static str = "[CrossPlay] Pinned";
+}
+
+/// The names of all of CrossPlay's custom tags.
+pub const CUSTOM_TAG_NAMES: [&str; 14] = [
+    <YouTubeIdTag as CustomTag>::NAME,
+    <SourceUrlTag as CustomTag>::NAME,
+    <SourceIdTag as CustomTag>::NAME,
+    <OriginalHashTag as CustomTag>::NAME,
+    <CroppedTag as FlagTag>::NAME,
+    <MetadataEditedTag as FlagTag>::NAME,
+    <NeedsMetadataTag as FlagTag>::NAME,
+    <PinnedTag as FlagTag>::NAME,
+    <ReviewedTag as FlagTag>::NAME,
+    <DownloadTimeTag as CustomTag>::NAME,
+    <UploaderTag as CustomTag>::NAME,
+    <PostProcessedTag as FlagTag>::NAME,
+    <VideoTitleTag as CustomTag>::NAME,
+    <ChapterTag as CustomTag>::NAME,
+];
+
+pub struct ReviewedTag;
+impl FlagTag for ReviewedTag {
+    const NAME: &'static str = "[CrossPlay] Reviewed";
+}
+
+pub struct PostProcessedTag;
+impl FlagTag for PostProcessedTag {
+    const NAME: &'static str = "[CrossPlay] Post-processed";
+}
+
 pub struct DownloadTimeTag;
 impl CustomTag for DownloadTimeTag {
     type T = u64;
@@ -131,3 +203,39 @@ impl CustomTag for DownloadTimeTag {
     fn to_comment_text(value: Self::T) -> Option<String> { Some(value.to_string()) }
     fn value_if_comment_missing() -> Option<Self::T> { Some(0) }
 }
+
+/// The name of the channel a song was downloaded from. This is kept separately from the artist,
+/// since the artist is often edited, but the channel is useful for finding related music.
+pub struct UploaderTag;
+impl CustomTag for UploaderTag {
+    type T = Option<String>;
+    const NAME: &'static str = "[CrossPlay] Uploader";
+
+    fn from_comment_text(str: &str) -> Self::T { Some(str.to_string()) }
+    fn to_comment_text(value: Self::T) -> Option<String> { value }
+    fn value_if_comment_missing() -> Option<Self::T> { Some(None) }
+}
+
+/// The title of the video a song was downloaded from, before the artist was split out of it and
+/// noise was trimmed off. This is kept in case the song's title was worked out wrongly.
+pub struct VideoTitleTag;
+impl CustomTag for VideoTitleTag {
+    type T = Option<String>;
+    const NAME: &'static str = "[CrossPlay] Video title";
+
+    fn from_comment_text(str: &str) -> Self::T { Some(str.to_string()) }
+    fn to_comment_text(value: Self::T) -> Option<String> { value }
+    fn value_if_comment_missing() -> Option<Self::T> { Some(None) }
+}
+
+/// For songs which were split out of a video by its chapters, which chapter this is, counting from
+/// 1. These share their video's ID, so this is what tells them apart.
+pub struct ChapterTag;
+impl CustomTag for ChapterTag {
+    type T = Option<u32>;
+    const NAME: &'static str = "[CrossPlay] Chapter";
+
+    fn from_comment_text(str: &str) -> Self::T { str.parse().ok() }
+    fn to_comment_text(value: Self::T) -> Option<String> { value.map(|c| c.to_string()) }
+    fn value_if_comment_missing() -> Option<Self::T> { Some(None) }
+}
@@ -1,4 +1,12 @@
-use iced::{pure::{Element, widget::{Row, Column, Button}}, container};
+use std::{future::ready, path::Path, time::Duration, ffi::OsStr};
+
+use iced::{pure::{Element, widget::{Row, Column, Button}}, container, button, Command};
+use native_dialog::{MessageDialog, MessageType};
+use iced_native::{event::{self, Event}, layout::{self, Layout}, mouse, keyboard, overlay, renderer, widget::text_input, Clipboard, Length, Point, Rectangle, Shell};
+use iced_pure::{Widget, widget::{Tree, tree}};
+use unicode_bidi::BidiInfo;
+
+use crate::{Message, library::{LibraryError, Song}, snapshot::TagSnapshot, platform, youtube::{DownloadError, ExitReason, ExtraArgsError}, i18n::{Key, tr, tr_args, tr_count}};
 
 pub(crate) trait ElementContainerExtensions<'a, Message> where Self: Sized {
     fn push(self, child: impl Into<Element<'a, Message>>) -> Self;
@@ -46,5 +54,448 @@ impl<'a, Message> ButtonExtensions<'a, Message> for Button<'a, Message> {
     fn on_press(self, msg: Message) -> Self { self.on_press(msg) }
 }
 
+/// Formats a number of bytes for display, e.g. "2.1 GB".
+pub fn format_size(bytes: u64) -> String {
+    const UNITS: [&str; 4] = ["KB", "MB", "GB", "TB"];
+
+    if bytes < 1000 {
+        return format!("{} bytes", bytes);
+    }
+
+    let mut size = bytes as f64;
+    let mut unit = "";
+    for next_unit in UNITS {
+        if size < 1000.0 {
+            break;
+        }
+        size /= 1000.0;
+        unit = next_unit;
+    }
+
+    format!("{:.1} {}", size, unit)
+}
+
+/// Formats a length of time for display, e.g. "3:07" or "1:02:45".
+pub fn format_duration(duration: Duration) -> String {
+    let total_seconds = duration.as_secs();
+    let (hours, minutes, seconds) = (total_seconds / 3600, total_seconds / 60 % 60, total_seconds % 60);
+
+    if hours > 0 {
+        format!("{}:{:02}:{:02}", hours, minutes, seconds)
+    } else {
+        format!("{}:{:02}", minutes, seconds)
+    }
+}
+
+/// Formats a position within a song precisely, e.g. "03:07:250" for 3 minutes, 7.25 seconds.
+pub fn format_millis(millis: f64) -> String {
+    let total_seconds = (millis / 1000.0).floor() as i32;
+
+    let partitioned_minutes = total_seconds / 60;
+    let partitioned_seconds = total_seconds % 60;
+    let partitioned_millis = (millis % 1000.0).floor() as i32;
+
+    format!("{:0>2}:{:0>2}:{:0>3}", partitioned_minutes, partitioned_seconds, partitioned_millis)
+}
+
+/// Formats a Unix timestamp as a date and time in UTC, e.g. "2022-07-14 18:03".
+pub fn format_unix_time(unix_time: u64) -> String {
+    let (year, month, day) = date_from_unix_days((unix_time / 86400) as i64);
+    let seconds_of_day = unix_time % 86400;
+    format!("{:04}-{:02}-{:02} {:02}:{:02}", year, month, day, seconds_of_day / 3600, seconds_of_day / 60 % 60)
+}
+
+/// Converts a number of days since the Unix epoch into a year, month and day of the Gregorian
+/// calendar.
+///
+/// This is Howard Hinnant's `civil_from_days` algorithm, which treats years as starting in March
+/// so that leap days fall at the end.
+pub fn date_from_unix_days(days: i64) -> (i64, u32, u32) {
+    let z = days + 719468;
+    let era = z.div_euclid(146097);
+    let day_of_era = z - era * 146097;
+    let year_of_era = (day_of_era - day_of_era / 1460 + day_of_era / 36524 - day_of_era / 146096) / 365;
+    let day_of_year = day_of_era - (365 * year_of_era + year_of_era / 4 - year_of_era / 100);
+    let shifted_month = (5 * day_of_year + 2) / 153;
+    let day = day_of_year - (153 * shifted_month + 2) / 5 + 1;
+    let month = if shifted_month < 10 { shifted_month + 3 } else { shifted_month - 9 };
+
+    let year = year_of_era + era * 400 + if month <= 2 { 1 } else { 0 };
+    (year, month as u32, day as u32)
+}
+
+/// Reorders text containing right-to-left scripts, like Arabic or Hebrew, so that it reads in the
+/// right direction. iced lays characters out left-to-right in the order they're stored, so RTL
+/// titles would otherwise come out backwards.
+pub fn display_order(text: &str) -> String {
+    let bidi = BidiInfo::new(text, None);
+    if !bidi.has_rtl() {
+        return text.to_string();
+    }
+
+    bidi.paragraphs.iter()
+        .map(|paragraph| bidi.reorder_line(paragraph, paragraph.range.clone()))
+        .collect()
+}
+
+/// Describes why a library operation failed, in terms of what the user can do about it.
+pub fn describe_library_error(error: &LibraryError) -> String {
+    let file_name = |path: &Path| path.file_name().unwrap_or(path.as_os_str()).to_string_lossy().into_owned();
+
+    match error {
+        LibraryError::NotFound(path) => tr_args(Key::ErrorNotFound, &[&file_name(path)]),
+        LibraryError::PermissionDenied(path) => tr_args(Key::ErrorPermissionDenied, &[&file_name(path)]),
+        LibraryError::TagParse { path, .. } => tr_args(Key::ErrorTagParse, &[&file_name(path)]),
+        LibraryError::FfmpegFailed { .. } => tr(Key::ErrorFfmpegFailed).to_string(),
+        LibraryError::AlreadyExists(path) => tr_args(Key::ErrorAlreadyExists, &[&file_name(path)]),
+        LibraryError::Busy(path) => tr_args(Key::ErrorBusy, &[&file_name(path)]),
+        LibraryError::Io { path, .. } => tr_args(Key::ErrorIo, &[&file_name(path)]),
+    }
+}
+
+/// Describes why a download failed, in terms of what the user can do about it.
+pub fn describe_download_error(error: &DownloadError) -> String {
+    match error {
+        DownloadError::DownloaderNotFound => tr(Key::DownloadErrorDownloaderNotFound).to_string(),
+        DownloadError::SpawnFailed(_) => tr(Key::DownloadErrorSpawnFailed).to_string(),
+        DownloadError::NonZeroExit { stderr_tail, .. } => match error.exit_reason() {
+            Some(ExitReason::Unavailable) => tr(Key::DownloadErrorVideoUnavailable).to_string(),
+            Some(ExitReason::Private) => tr(Key::DownloadErrorVideoPrivate).to_string(),
+            Some(ExitReason::AgeRestricted) => tr(Key::DownloadErrorAgeRestricted).to_string(),
+            Some(ExitReason::RegionBlocked) => tr(Key::DownloadErrorRegionBlocked).to_string(),
+            Some(ExitReason::RateLimited) => tr(Key::DownloadErrorRateLimited).to_string(),
+            Some(ExitReason::DiskFull) => tr(Key::DownloadErrorDiskFull).to_string(),
+            Some(ExitReason::ConversionFailed) => tr(Key::DownloadErrorConversionFailed).to_string(),
+            Some(ExitReason::Network) => tr(Key::DownloadErrorNetwork).to_string(),
+
+            // Otherwise, youtube-dl's last line is usually the most useful thing to show
+            None => match stderr_tail.lines().last() {
+                Some(line) => tr_args(Key::DownloadErrorNonZeroExitReason, &[&line.trim_start_matches("ERROR: ")]),
+                None => tr(Key::DownloadErrorNonZeroExit).to_string(),
+            },
+        },
+        DownloadError::AudioNotFound => tr(Key::DownloadErrorAudioNotFound).to_string(),
+        DownloadError::ThumbnailNotFound | DownloadError::ThumbnailConversionFailed(_) =>
+            tr(Key::DownloadErrorThumbnail).to_string(),
+        DownloadError::MetadataWriteFailed(e) => describe_library_error(e),
+        DownloadError::PostProcessFailed { step, error } =>
+            tr_args(Key::DownloadErrorPostProcess, &[step, &describe_library_error(error)]),
+        DownloadError::ChapterSplitFailed(e) =>
+            tr_args(Key::DownloadErrorChapterSplit, &[&describe_library_error(e)]),
+        DownloadError::ReplaceFailed(e) =>
+            tr_args(Key::DownloadErrorReplace, &[&describe_library_error(e)]),
+        DownloadError::PlaylistEntryUnavailable => tr(Key::DownloadErrorPlaylistEntryUnavailable).to_string(),
+        DownloadError::InfoUnreadable => tr(Key::DownloadErrorInfoUnreadable).to_string(),
+        DownloadError::FfmpegNotFound => tr(Key::DownloadErrorFfmpegNotFound).to_string(),
+        DownloadError::CookiesFileMissing => tr(Key::DownloadErrorCookiesFileMissing).to_string(),
+        DownloadError::Io(_) if error.is_disk_full() => tr(Key::DownloadErrorDiskFull).to_string(),
+        DownloadError::Io(_) => tr(Key::DownloadErrorIo).to_string(),
+    }
+}
+
+/// Describes why extra youtube-dl arguments typed by the user can't be used.
+pub fn describe_extra_args_error(error: &ExtraArgsError) -> String {
+    match error {
+        ExtraArgsError::UnclosedQuote => tr(Key::ExtraArgsUnclosedQuote).to_string(),
+        ExtraArgsError::Forbidden(arg) => tr_args(Key::ExtraArgsForbidden, &[arg]),
+    }
+}
+
+/// Reports a failed library operation with an error toast, whose details hold the full error.
+pub fn library_error_toast(error: &LibraryError) -> Command<Message> {
+    let summary = describe_library_error(error);
+    let detail = error.to_string();
+    Command::perform(ready(()), move |_| Message::ShowErrorToast(summary.clone(), detail.clone()))
+}
+
+/// Reports how a bulk operation on songs went: a toast with how many songs it succeeded on, or an
+/// error toast listing the title of each song it failed on, and why.
+pub fn bulk_result_toast(succeeded_key: Key, failed_key: Key, succeeded: usize, failures: &[(String, LibraryError)]) -> Command<Message> {
+    if failures.is_empty() {
+        let summary = tr_count(succeeded_key, succeeded, &[]);
+        Command::perform(ready(()), move |_| Message::ShowToast(summary.clone()))
+    } else {
+        let summary = tr_count(failed_key, failures.len(), &[&succeeded]);
+        let detail = failures.iter()
+            .map(|(title, e)| format!("'{}': {}", title, describe_library_error(e)))
+            .collect::<Vec<_>>()
+            .join("\n");
+        Command::perform(ready(()), move |_| Message::ShowErrorToast(summary.clone(), detail.clone()))
+    }
+}
+
+/// Opens a file, folder or URL with the system's default application. If that fails, the user is
+/// told why, and what couldn't be opened is copied to the clipboard so that they can open it
+/// themselves.
+pub fn open_or_report(target: impl AsRef<OsStr>) -> Command<Message> {
+    let target = target.as_ref();
+    match platform::open(target) {
+        Ok(()) => Command::none(),
+        Err(e) => {
+            let target = target.to_string_lossy().into_owned();
+            println!("[Platform] Couldn't open {}: {}", target, e);
+            MessageDialog::new()
+                .set_title(tr(Key::OpenFailedTitle))
+                .set_text(&tr_args(Key::OpenFailedBody, &[&target, &e]))
+                .set_type(MessageType::Error)
+                .show_alert()
+                .unwrap();
+            iced::clipboard::write(target)
+        }
+    }
+}
+
+/// Asks the user whether to go ahead with changing songs, after a snapshot of their tags couldn't
+/// be saved. Without one, the change can't be undone by restoring a snapshot.
+pub fn confirm_without_snapshot(error: &anyhow::Error) -> bool {
+    println!("[Snapshot] Couldn't take snapshot: {}", error);
+    MessageDialog::new()
+        .set_title(tr(Key::SnapshotFailedTitle))
+        .set_text(&tr_args(Key::SnapshotFailedBody, &[error]))
+        .set_type(MessageType::Warning)
+        .show_confirm()
+        .unwrap()
+}
+
+/// Takes a snapshot of the tags of songs which a bulk operation is about to change - see
+/// [`TagSnapshot::take_before_bulk_operation`].
+///
+/// If the snapshot can't be saved, the user is asked whether to go ahead anyway. Returns `None` if
+/// they'd rather not, and otherwise a command which reports the failure, if there was one.
+pub fn snapshot_before_bulk_operation(songs: &[Song]) -> Option<Command<Message>> {
+    match TagSnapshot::take_before_bulk_operation(songs) {
+        Ok(()) => Some(Command::none()),
+        Err(e) if confirm_without_snapshot(&e) => Some(snapshot_error_toast(&e)),
+        Err(_) => None,
+    }
+}
+
+/// Reports that a snapshot of songs' tags couldn't be saved with an error toast, whose details
+/// hold the full error.
+pub fn snapshot_error_toast(error: &anyhow::Error) -> Command<Message> {
+    let summary = tr(Key::ToastSnapshotFailed).to_string();
+    let detail = error.to_string();
+    Command::perform(ready(()), move |_| Message::ShowErrorToast(summary.clone(), detail.clone()))
+}
+
 pub struct ContainerStyleSheet(pub container::Style);
 impl container::StyleSheet for ContainerStyleSheet { fn style(&self) -> container::Style { self.0 } }
+
+pub struct ButtonStyleSheet(pub button::Style);
+impl button::StyleSheet for ButtonStyleSheet { fn active(&self) -> button::Style { self.0 } }
+
+/// A transparent wrapper around some content, which produces messages when the content is clicked
+/// with the left or middle mouse buttons.
+/// 
+/// Clicks are only handled if the content ignores them, so any buttons inside the area still work
+/// as usual.
+pub struct MouseArea<'a, Message, Renderer> {
+    content: iced_pure::Element<'a, Message, Renderer>,
+    on_left_press: Option<Message>,
+    on_middle_press: Option<Message>,
+}
+
+impl<'a, Message, Renderer> MouseArea<'a, Message, Renderer> {
+    pub fn new(content: impl Into<iced_pure::Element<'a, Message, Renderer>>) -> Self {
+        Self { content: content.into(), on_left_press: None, on_middle_press: None }
+    }
+
+    pub fn on_left_press(mut self, msg: Message) -> Self {
+        self.on_left_press = Some(msg);
+        self
+    }
+
+    pub fn on_middle_press(mut self, msg: Message) -> Self {
+        self.on_middle_press = Some(msg);
+        self
+    }
+}
+
+impl<'a, Message: Clone, Renderer: iced_native::Renderer> Widget<Message, Renderer> for MouseArea<'a, Message, Renderer> {
+    fn children(&self) -> Vec<Tree> {
+        vec![Tree::new(&self.content)]
+    }
+
+    fn diff(&self, tree: &mut Tree) {
+        tree.diff_children(std::slice::from_ref(&self.content))
+    }
+
+    fn width(&self) -> Length {
+        self.content.as_widget().width()
+    }
+
+    fn height(&self) -> Length {
+        self.content.as_widget().height()
+    }
+
+    fn layout(&self, renderer: &Renderer, limits: &layout::Limits) -> layout::Node {
+        self.content.as_widget().layout(renderer, limits)
+    }
+
+    fn on_event(
+        &mut self,
+        tree: &mut Tree,
+        event: Event,
+        layout: Layout<'_>,
+        cursor_position: Point,
+        renderer: &Renderer,
+        clipboard: &mut dyn Clipboard,
+        shell: &mut Shell<'_, Message>,
+    ) -> event::Status {
+        let status = self.content.as_widget_mut().on_event(
+            &mut tree.children[0], event.clone(), layout, cursor_position, renderer, clipboard, shell,
+        );
+        if status == event::Status::Captured || !layout.bounds().contains(cursor_position) {
+            return status;
+        }
+
+        let message = match event {
+            Event::Mouse(mouse::Event::ButtonPressed(mouse::Button::Left)) => self.on_left_press.clone(),
+            Event::Mouse(mouse::Event::ButtonPressed(mouse::Button::Middle)) => self.on_middle_press.clone(),
+            _ => None,
+        };
+
+        if let Some(message) = message {
+            shell.publish(message);
+            event::Status::Captured
+        } else {
+            status
+        }
+    }
+
+    fn mouse_interaction(&self, tree: &Tree, layout: Layout<'_>, cursor_position: Point, viewport: &Rectangle, renderer: &Renderer) -> mouse::Interaction {
+        self.content.as_widget().mouse_interaction(&tree.children[0], layout, cursor_position, viewport, renderer)
+    }
+
+    fn draw(&self, tree: &Tree, renderer: &mut Renderer, style: &renderer::Style, layout: Layout<'_>, cursor_position: Point, viewport: &Rectangle) {
+        self.content.as_widget().draw(&tree.children[0], renderer, style, layout, cursor_position, viewport)
+    }
+
+    fn overlay<'b>(&'b self, tree: &'b mut Tree, layout: Layout<'_>, renderer: &Renderer) -> Option<overlay::Element<'b, Message, Renderer>> {
+        self.content.as_widget().overlay(&mut tree.children[0], layout, renderer)
+    }
+}
+
+impl<'a, Message: Clone + 'a, Renderer: iced_native::Renderer + 'a> From<MouseArea<'a, Message, Renderer>> for iced_pure::Element<'a, Message, Renderer> {
+    fn from(area: MouseArea<'a, Message, Renderer>) -> Self { iced_pure::Element::new(area) }
+}
+
+/// Wraps a text input to give it the keyboard conventions used by CrossPlay's text fields: Enter
+/// submits (using the text input's own `on_submit`), Escape produces a message - usually to clear
+/// the field - and the field can be focused from code, for keyboard shortcuts.
+///
+/// To focus the field, increment the number passed to [`FocusableField::focus_request`]. The
+/// field remembers the last request it acted on, so each increment focuses it once.
+///
+/// iced only has keyboard focus for text inputs, so buttons and pick lists can't be part of this.
+pub struct FocusableField<'a, Message, Renderer> {
+    content: iced_pure::Element<'a, Message, Renderer>,
+    on_escape: Option<Message>,
+    focus_request: u64,
+}
+
+/// The last focus request which a [`FocusableField`] acted on.
+struct AppliedFocusRequest(u64);
+
+impl<'a, Message, Renderer> FocusableField<'a, Message, Renderer> {
+    /// Wraps the given text input. Wrapping anything else will panic.
+    pub fn new(text_input: impl Into<iced_pure::Element<'a, Message, Renderer>>) -> Self {
+        Self { content: text_input.into(), on_escape: None, focus_request: 0 }
+    }
+
+    pub fn on_escape(mut self, msg: Message) -> Self {
+        self.on_escape = Some(msg);
+        self
+    }
+
+    pub fn focus_request(mut self, request: u64) -> Self {
+        self.focus_request = request;
+        self
+    }
+
+    fn text_input_state(tree: &mut Tree) -> &mut text_input::State {
+        tree.children[0].state.downcast_mut::<text_input::State>()
+    }
+}
+
+impl<'a, Message: Clone, Renderer: iced_native::text::Renderer> Widget<Message, Renderer> for FocusableField<'a, Message, Renderer> {
+    fn tag(&self) -> tree::Tag {
+        tree::Tag::of::<AppliedFocusRequest>()
+    }
+
+    fn state(&self) -> tree::State {
+        // A newly-created field shouldn't steal focus because of an old request
+        tree::State::new(AppliedFocusRequest(self.focus_request))
+    }
+
+    fn children(&self) -> Vec<Tree> {
+        vec![Tree::new(&self.content)]
+    }
+
+    fn diff(&self, tree: &mut Tree) {
+        tree.diff_children(std::slice::from_ref(&self.content));
+
+        let applied = tree.state.downcast_mut::<AppliedFocusRequest>();
+        if self.focus_request > applied.0 {
+            applied.0 = self.focus_request;
+
+            let state = Self::text_input_state(tree);
+            state.focus();
+            state.move_cursor_to_end();
+        }
+    }
+
+    fn width(&self) -> Length {
+        self.content.as_widget().width()
+    }
+
+    fn height(&self) -> Length {
+        self.content.as_widget().height()
+    }
+
+    fn layout(&self, renderer: &Renderer, limits: &layout::Limits) -> layout::Node {
+        self.content.as_widget().layout(renderer, limits)
+    }
+
+    fn on_event(
+        &mut self,
+        tree: &mut Tree,
+        event: Event,
+        layout: Layout<'_>,
+        cursor_position: Point,
+        renderer: &Renderer,
+        clipboard: &mut dyn Clipboard,
+        shell: &mut Shell<'_, Message>,
+    ) -> event::Status {
+        // The text input unfocuses itself on Escape, so check before passing the event on
+        let escape_pressed = matches!(
+            event,
+            Event::Keyboard(keyboard::Event::KeyPressed { key_code: keyboard::KeyCode::Escape, .. })
+        );
+        if escape_pressed && Self::text_input_state(tree).is_focused() {
+            if let Some(message) = self.on_escape.clone() {
+                shell.publish(message);
+            }
+        }
+
+        self.content.as_widget_mut().on_event(
+            &mut tree.children[0], event, layout, cursor_position, renderer, clipboard, shell,
+        )
+    }
+
+    fn mouse_interaction(&self, tree: &Tree, layout: Layout<'_>, cursor_position: Point, viewport: &Rectangle, renderer: &Renderer) -> mouse::Interaction {
+        self.content.as_widget().mouse_interaction(&tree.children[0], layout, cursor_position, viewport, renderer)
+    }
+
+    fn draw(&self, tree: &Tree, renderer: &mut Renderer, style: &renderer::Style, layout: Layout<'_>, cursor_position: Point, viewport: &Rectangle) {
+        self.content.as_widget().draw(&tree.children[0], renderer, style, layout, cursor_position, viewport)
+    }
+
+    fn overlay<'b>(&'b self, tree: &'b mut Tree, layout: Layout<'_>, renderer: &Renderer) -> Option<overlay::Element<'b, Message, Renderer>> {
+        self.content.as_widget().overlay(&mut tree.children[0], layout, renderer)
+    }
+}
+
+impl<'a, Message: Clone + 'a, Renderer: iced_native::text::Renderer + 'a> From<FocusableField<'a, Message, Renderer>> for iced_pure::Element<'a, Message, Renderer> {
+    fn from(field: FocusableField<'a, Message, Renderer>) -> Self { iced_pure::Element::new(field) }
+}
@@ -0,0 +1,138 @@
+use std::future::ready;
+
+use iced::{Command, pure::{widget::{Button, Column, Text, Row}, Element}, Length};
+
+use crate::{library::Song, Message, ui_util::{ElementContainerExtensions, ButtonExtensions, bulk_result_toast, snapshot_before_bulk_operation}, i18n::{Key, tr, tr_count}};
+
+use super::{content::ContentMessage, edit_metadata::EditMetadataView};
+
+/// How many of the songs being edited are listed by title, so that a huge selection doesn't push
+/// the fields off the screen.
+const LISTED_SONGS: usize = 10;
+
+#[derive(Debug, Clone)]
+pub enum BatchEditMetadataMessage {
+    ArtistChange(String),
+    AlbumChange(String),
+    YearChange(String),
+    ApplyBatchEdit,
+}
+
+impl From<BatchEditMetadataMessage> for Message {
+    fn from(bemm: BatchEditMetadataMessage) -> Self { Message::ContentMessage(ContentMessage::BatchEditMetadataMessage(bemm)) }
+}
+
+/// Edits the fields which several songs usually have in common, like when an album has been
+/// downloaded as separate videos. Fields left blank are left alone on every song.
+pub struct BatchEditMetadataView {
+    songs: Vec<Song>,
+
+    artist_input: String,
+    album_input: String,
+    year_input: String,
+}
+
+impl BatchEditMetadataView {
+    pub fn new(songs: Vec<Song>) -> Self {
+        Self { songs, artist_input: "".to_string(), album_input: "".to_string(), year_input: "".to_string() }
+    }
+
+    pub fn songs(&self) -> &[Song] {
+        &self.songs
+    }
+
+    pub fn update(&mut self, message: BatchEditMetadataMessage) -> Command<Message> {
+        match message {
+            BatchEditMetadataMessage::ArtistChange(v) => self.artist_input = v,
+            BatchEditMetadataMessage::AlbumChange(v) => self.album_input = v,
+            // Like the single-song editor, anything other than digits is ignored
+            BatchEditMetadataMessage::YearChange(v) =>
+                self.year_input = v.chars().filter(char::is_ascii_digit).take(4).collect(),
+
+            BatchEditMetadataMessage::ApplyBatchEdit => {
+                let artist = Some(self.artist_input.trim()).filter(|s| !s.is_empty());
+                let album = Some(self.album_input.trim()).filter(|s| !s.is_empty());
+                let year = self.year_input.parse::<i32>().ok();
+
+                let snapshot_command = match snapshot_before_bulk_operation(&self.songs) {
+                    Some(command) => command,
+                    None => return Command::none(),
+                };
+
+                // Keep going past failures, so that one bad song doesn't stop the rest
+                let mut edited = 0;
+                let mut failures = vec![];
+                for song in &self.songs {
+                    let title = song.metadata.title.clone();
+
+                    // Start from what's on disk, in case the song changed since it was selected
+                    let result = song.reload().and_then(|mut song| {
+                        if let Some(artist) = artist {
+                            song.metadata.artist = artist.to_string();
+                        }
+                        if let Some(album) = album {
+                            song.metadata.album = album.to_string();
+                        }
+                        if let Some(year) = year {
+                            song.metadata.year = Some(year);
+                        }
+                        song.user_edit_metadata()
+                    });
+
+                    match result {
+                        Ok(()) => edited += 1,
+                        Err(e) => failures.push((title, e)),
+                    }
+                }
+
+                return Command::batch([
+                    snapshot_command,
+                    Command::perform(ready(()), |_| ContentMessage::OpenSongList.into()),
+                    bulk_result_toast(Key::ToastBatchEdited, Key::ToastBatchEditFailed, edited, &failures),
+                ])
+            }
+        }
+
+        Command::none()
+    }
+
+    pub fn view(&self) -> Element<Message> {
+        let nothing_to_apply = self.artist_input.trim().is_empty()
+            && self.album_input.trim().is_empty()
+            && self.year_input.is_empty();
+
+        Column::new()
+            .padding(10)
+            .spacing(10)
+            .push(Text::new(tr_count(Key::BatchEditTitle, self.songs.len(), &[])).size(28))
+            .push(
+                Column::with_children(
+                    self.songs.iter()
+                        .take(LISTED_SONGS)
+                        .map(|song| Text::new(song.metadata.title.clone()).color([0.3, 0.3, 0.3]).into())
+                        .collect()
+                )
+            )
+            .push_if(self.songs.len() > LISTED_SONGS, ||
+                Text::new(tr_count(Key::BatchEditMoreSongs, self.songs.len() - LISTED_SONGS, &[])).color([0.3, 0.3, 0.3])
+            )
+            .push(Text::new(tr(Key::BatchEditHint)))
+            .push(
+                Column::new()
+                    .spacing(10)
+                    .width(Length::Units(500))
+                    .push(EditMetadataView::field(tr(Key::Artist), &self.artist_input, |v| BatchEditMetadataMessage::ArtistChange(v).into()))
+                    .push(EditMetadataView::field(tr(Key::Album), &self.album_input, |v| BatchEditMetadataMessage::AlbumChange(v).into()))
+                    .push(EditMetadataView::field(tr(Key::Year), &self.year_input, |v| BatchEditMetadataMessage::YearChange(v).into()))
+            )
+            .push(
+                Row::new()
+                    .spacing(10)
+                    .push(Button::new(Text::new(tr(Key::Cancel)))
+                        .on_press(ContentMessage::OpenSongList.into()))
+                    .push(Button::new(Text::new(tr(Key::ApplyAndSave)))
+                        .on_press_if(!nothing_to_apply, BatchEditMetadataMessage::ApplyBatchEdit.into()))
+            )
+            .into()
+    }
+}
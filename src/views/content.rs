@@ -1,37 +1,158 @@
-use std::sync::{RwLock, Arc};
+use std::{sync::{RwLock, Arc}, path::PathBuf, time::{Duration, SystemTime}, future::ready};
 
-use iced::{pure::Element, Subscription, Command};
+use iced::{pure::{Element, widget::{Button, Column, Container, Row, Rule, Scrollable, Text}}, Subscription, Command, Length, Alignment};
+use native_dialog::{MessageDialog, MessageType};
 
-use crate::{library::{Song, Library}, Message, settings::Settings};
+use crate::{library::{Song, Library, SongFileChange, LibraryDiff, LibraryError, LibraryScan}, youtube::{MediaDownload, YouTubeDownloadProgress}, Message, settings::Settings, ui_util::{ElementContainerExtensions, describe_library_error, format_millis}, i18n::{Key, tr, tr_args, tr_count}};
 
-use super::{song_list::{SongListMessage, SongListView}, crop::{CropView, CropMessage}, edit_metadata::{EditMetadataView, EditMetadataMessage}};
+use super::{song_list::{SongListMessage, SongListView}, crop::{CropView, CropMessage}, edit_metadata::{EditMetadataView, EditMetadataMessage}, batch_edit_metadata::{BatchEditMetadataView, BatchEditMetadataMessage}, settings::{SettingsView, SettingsMessage}, now_playing::{NowPlayingView, NowPlayingMessage}, history::HistoryView};
 
 #[derive(Debug, Clone)]
 pub enum ContentMessage {
     OpenSongList,
+    /// Reloads the library without changing the open view. If `report_changes` is true, songs
+    /// which were added, removed or changed since the last load are listed in a toast - this is
+    /// only useful when something other than CrossPlay might have changed them.
+    RefreshLibrary { report_changes: bool },
+    /// A background reload of the library has finished. Only the latest reload's result is used,
+    /// identified by its `generation`.
+    LibraryScanned { generation: u64, report_changes: bool, result: Result<LibraryScan, LibraryError> },
+    /// Refreshes the library if something else has changed its folder, unless a view is open which
+    /// is working on a song.
+    RefreshLibraryIfStale,
     OpenCrop(Song),
+    /// Opens the song's metadata for editing - in the side panel if the split layout is in use,
+    /// otherwise taking over the window.
     OpenEditMetadata(Song),
+    /// Opens several songs for editing the fields they have in common, like their album.
+    OpenBatchEditMetadata(Vec<Song>),
+    /// Shows the song's details in the side panel if the split layout is in use, otherwise does
+    /// nothing.
+    SelectSong(Song),
+    OpenSettings,
+    OpenHistory,
+    Play(Song),
+    WindowResized(u32),
 
     SongListMessage(SongListMessage),
     CropMessage(CropMessage),
     EditMetadataMessage(EditMetadataMessage),
+    BatchEditMetadataMessage(BatchEditMetadataMessage),
+    SettingsMessage(SettingsMessage),
+    NowPlayingMessage(NowPlayingMessage),
 }
 
 impl From<ContentMessage> for Message {
     fn from(cm: ContentMessage) -> Self { Message::ContentMessage(cm) }
 }
 
+/// Summarises what a library refresh changed in a toast, which can be clicked to list the songs.
+fn library_diff_toast(diff: &LibraryDiff) -> Command<Message> {
+    let moved = diff.moved.iter().map(|(_, new)| new.clone()).collect::<Vec<_>>();
+    let groups = [
+        (Key::LibraryDiffAdded, Key::LibraryDiffAddedHeading, &diff.added),
+        (Key::LibraryDiffRemoved, Key::LibraryDiffRemovedHeading, &diff.removed),
+        (Key::LibraryDiffChanged, Key::LibraryDiffChangedHeading, &diff.changed),
+        (Key::LibraryDiffMoved, Key::LibraryDiffMovedHeading, &moved),
+    ];
+
+    let mut summary = vec![];
+    let mut detail = vec![];
+    for (count_key, heading_key, songs) in groups {
+        if songs.is_empty() {
+            continue;
+        }
+
+        summary.push(tr_count(count_key, songs.len(), &[]));
+        detail.push(tr(heading_key).to_string());
+        for song in songs {
+            let file_name = song.path.file_name().unwrap_or(song.path.as_os_str()).to_string_lossy();
+            detail.push(tr_args(Key::LibraryDiffSong, &[&song.metadata.title, &file_name]));
+        }
+        detail.push("".to_string());
+    }
+
+    let summary = tr_args(Key::ToastLibraryChanged, &[&summary.join(", ")]);
+    let detail = detail.join("\n").trim_end().to_string();
+    Command::perform(ready(()), move |_| Message::ShowDetailedToast(summary.clone(), detail.clone()))
+}
+
+/// Checks that a song hasn't been changed on disk since a view opened it, which is needed before
+/// applying any changes made in that view.
+///
+/// If it has changed, asks the user whether to reload the song or abandon their changes, and
+/// returns a command to do that. `reopen` builds the message to open the view again with the
+/// reloaded song.
+pub fn check_song_unchanged(song: &Song, opened_modified_time: Option<SystemTime>, reopen: fn(Song) -> ContentMessage) -> Option<Command<Message>> {
+    match song.change_since(opened_modified_time) {
+        SongFileChange::Unchanged => None,
+
+        SongFileChange::Deleted => {
+            MessageDialog::new()
+                .set_title(tr(Key::SongDeletedTitle))
+                .set_text(&tr_args(Key::SongDeletedBody, &[&song.metadata.title]))
+                .set_type(MessageType::Error)
+                .show_alert()
+                .unwrap();
+
+            Some(Command::perform(ready(()), |_| ContentMessage::OpenSongList.into()))
+        }
+
+        SongFileChange::Modified => {
+            let reload = MessageDialog::new()
+                .set_title(tr(Key::SongChangedTitle))
+                .set_text(&tr_args(Key::SongChangedBody, &[&song.metadata.title]))
+                .set_type(MessageType::Warning)
+                .show_confirm()
+                .unwrap();
+
+            match song.reload() {
+                Ok(song) if reload => Some(Command::perform(ready(()), move |_| reopen(song.clone()).into())),
+                _ => Some(Command::perform(ready(()), |_| ContentMessage::OpenSongList.into())),
+            }
+        }
+    }
+}
+
 enum ContentViewState {
-    SongList(SongListView),
+    SongList,
     Crop(CropView),
     EditMetadata(EditMetadataView),
+    BatchEditMetadata(BatchEditMetadataView),
+    Settings(SettingsView),
+    History(HistoryView),
 }
 
 pub struct ContentView {
     library: Arc<RwLock<Library>>,
     settings: Arc<RwLock<Settings>>,
 
+    /// The song list is kept around while other views are open, so that UI state like filters is
+    /// preserved when returning to it.
+    song_list: SongListView,
     state: ContentViewState,
+
+    /// The selected song's details, shown beside the song list when the split layout is in use.
+    /// Cropping still takes over the whole window, since the player needs the room.
+    side_panel: Option<EditMetadataView>,
+    window_width: u32,
+
+    now_playing: Option<NowPlayingView>,
+
+    /// If playback was stopped so that the playing song could be edited or cropped, the path of
+    /// that song and the position it was at, so that playback can be resumed afterwards.
+    suspended_playback: Option<(PathBuf, Duration)>,
+
+    /// Why the library couldn't be loaded last time it was tried, if it couldn't. The songs from
+    /// the last successful load are still shown, if there was one.
+    load_error: Option<String>,
+
+    /// Counts the library reloads which have been started, so that one which was overtaken by a
+    /// newer one can be ignored when it finishes.
+    reload_generation: u64,
+
+    /// Whether the latest reload is still running.
+    reloading: bool,
 }
 
 impl ContentView {
@@ -39,45 +160,332 @@ impl ContentView {
         Self {
             library: library.clone(),
             settings: settings.clone(),
-            state: ContentViewState::SongList(SongListView::new(library, settings)),
+            song_list: SongListView::new(library, settings),
+            state: ContentViewState::SongList,
+            side_panel: None,
+            // iced's default window size, until we're told otherwise
+            window_width: 1024,
+            now_playing: None,
+            suspended_playback: None,
+            load_error: None,
+            reload_generation: 0,
+            reloading: false,
+        }
+    }
+
+    /// Offers to try loading the library again, or to pick another one, after it failed to load.
+    fn load_error_banner<'a>(error: &str) -> Element<'a, Message> {
+        Container::new(
+            Row::new()
+                .spacing(10)
+                .align_items(Alignment::Center)
+                .push(Text::new(error.to_string()).color([0.8, 0.0, 0.0]).width(Length::Fill))
+                .push(Button::new(Text::new(tr(Key::Retry)))
+                    .on_press(ContentMessage::RefreshLibrary { report_changes: false }.into()))
+                .push(Button::new(Text::new(tr(Key::ChangeLibrary)))
+                    .on_press(Message::UpdateLibraryPath))
+        )
+            .padding(10)
+            .width(Length::Fill)
+            .into()
+    }
+
+    /// Starts reloading the library in the background. Reading every song's tags is slow for large
+    /// libraries, so the songs already loaded are kept until it's done, and then all replaced at
+    /// once - see [`ContentMessage::LibraryScanned`].
+    fn reload_library(&mut self, report_changes: bool) -> Command<Message> {
+        self.reload_generation += 1;
+        self.reloading = true;
+        let generation = self.reload_generation;
+
+        let library = self.library.read().unwrap();
+        if !library.is_loaded() {
+            self.song_list.set_loading(true);
         }
+        let scanner = library.scanner();
+        drop(library);
+
+        Command::perform(
+            tokio::task::spawn_blocking(move || scanner.scan()),
+            move |r| ContentMessage::LibraryScanned {
+                generation,
+                report_changes,
+                result: r.expect("library scan task failed"),
+            }.into(),
+        )
     }
 
-    pub fn view(&self) -> Element<Message> {
-        match self.state {
-            ContentViewState::SongList(ref v) => v.view(),
+    /// Loads the songs found by a background reload, keeping the songs already loaded if it
+    /// failed. Returns how the songs changed, or a toast explaining the failure.
+    fn library_scanned(&mut self, result: Result<LibraryScan, LibraryError>) -> Result<LibraryDiff, Command<Message>> {
+        self.reloading = false;
+        self.song_list.set_loading(false);
+
+        let mut library = self.library.write().unwrap();
+        match result {
+            Ok(scan) => {
+                let diff = library.apply_scan_and_diff(scan);
+                drop(library);
+                self.load_error = None;
+                self.song_list.follow_moves(&diff.moved);
+                Ok(diff)
+            }
+
+            // If the library has never loaded, like if it's on a drive which isn't plugged in,
+            // there are no songs to show until it can be, so offer to try again or pick another
+            Err(e) if !library.is_loaded() => {
+                println!("[Library] Couldn't load library: {}", e);
+                self.load_error = Some(tr_args(Key::LibraryLoadFailed, &[&library.path.to_string_lossy(), &describe_library_error(&e)]));
+                Err(Command::none())
+            }
+
+            Err(e) => {
+                println!("[Library] Couldn't reload library: {}", e);
+                let summary = tr_args(Key::LibraryRefreshFailed, &[&describe_library_error(&e)]);
+                let detail = e.to_string();
+                self.load_error = Some(summary.clone());
+                Err(Command::perform(ready(()), move |_| Message::ShowErrorToast(summary.clone(), detail.clone())))
+            }
+        }
+    }
+
+    /// `downloads` are the downloads currently running, which the song list shows a summary of.
+    pub fn view<'a>(&'a self, downloads: &'a [(MediaDownload, Arc<RwLock<YouTubeDownloadProgress>>)]) -> Element<'a, Message> {
+        let state_view = match self.state {
+            ContentViewState::SongList => match self.side_panel {
+                Some(ref panel) =>
+                    Row::new()
+                        .push(Container::new(self.song_list.view(downloads)).width(Length::FillPortion(3)))
+                        .push(Rule::vertical(1))
+                        .push(Scrollable::new(panel.view()).width(Length::FillPortion(2)))
+                        .into(),
+                None => self.song_list.view(downloads),
+            },
             ContentViewState::Crop(ref v) => v.view(),
             ContentViewState::EditMetadata(ref v) => v.view(),
+            ContentViewState::BatchEditMetadata(ref v) => v.view(),
+            ContentViewState::Settings(ref v) => v.view(),
+            ContentViewState::History(ref v) => v.view(),
+        };
+
+        let column = Column::new()
+            .push_if_let(&self.load_error, |error| Self::load_error_banner(error))
+            .push(Container::new(state_view).height(Length::Fill));
+        match self.now_playing {
+            Some(ref np) => column.push(np.view()).into(),
+            None => column.into(),
         }
     }
 
     pub fn subscription(&self) -> Subscription<Message> {
-        match self.state {
-            ContentViewState::Crop(ref v) => v.subscription(),
-            _ => Subscription::none(),
+        Subscription::batch([
+            match self.state {
+                ContentViewState::SongList => self.song_list.subscription(),
+                ContentViewState::Crop(ref v) => v.subscription(),
+                _ => Subscription::none(),
+            },
+            match self.now_playing {
+                Some(ref np) => np.subscription(),
+                None => Subscription::none(),
+            },
+        ])
+    }
+
+    fn is_split(&self) -> bool {
+        self.settings.read().unwrap().split_layout.is_split(self.window_width)
+    }
+
+    /// Moves an open metadata editor between the side panel and the whole window, if the layout
+    /// has changed since it was opened.
+    fn apply_layout(&mut self) {
+        if self.is_split() {
+            if matches!(self.state, ContentViewState::EditMetadata(_)) {
+                if let ContentViewState::EditMetadata(v) = std::mem::replace(&mut self.state, ContentViewState::SongList) {
+                    self.side_panel = Some(v);
+                }
+            }
+        } else if matches!(self.state, ContentViewState::SongList) {
+            if let Some(v) = self.side_panel.take() {
+                // Editing in the whole window stops the song playing, as if it had been opened there
+                let song = v.song().clone();
+                self.suspend_playback_of(&song);
+                self.state = ContentViewState::EditMetadata(v);
+            }
+        }
+    }
+
+    /// If the given song is playing, stops it so that its file can be modified, remembering where
+    /// it was so that it can be resumed later.
+    fn suspend_playback_of(&mut self, song: &Song) {
+        if self.now_playing.as_ref().map(|np| &np.song().path) == Some(&song.path) {
+            // Dropping the player releases its handle on the file
+            let np = self.now_playing.take().unwrap();
+            self.suspended_playback = Some((song.path.clone(), np.position()));
+        }
+    }
+
+    /// Offers to resume playback which was stopped by [`suspend_playback_of`].
+    fn offer_resume_playback(&mut self) {
+        let (path, position) = match self.suspended_playback.take() {
+            Some(suspended) => suspended,
+            None => return,
+        };
+
+        // The song might not exist any more, or its file could've changed underneath us
+        let song = match self.library.read().unwrap().songs().find(|s| s.path == path) {
+            Some(song) => song.clone(),
+            None => return,
+        };
+
+        let mut np = NowPlayingView::new(song);
+        np.set_paused(true);
+
+        // A crop may have made the song shorter than where we were
+        let position = position.min(np.duration());
+
+        let confirmation = MessageDialog::new()
+            .set_title(tr(Key::ResumePlaybackTitle))
+            .set_text(&tr_args(
+                Key::ResumePlaybackBody,
+                &[&np.song().metadata.title, &format_millis(position.as_millis() as f64)],
+            ))
+            .show_confirm()
+            .unwrap();
+
+        if confirmation {
+            np.seek(position);
+            np.set_paused(false);
+            self.now_playing = Some(np);
         }
     }
 
     pub fn update(&mut self, message: ContentMessage) -> Command<Message> {
         match message {
             ContentMessage::OpenSongList => {
-                self.library.write().unwrap().load_songs().unwrap();
-                self.state = ContentViewState::SongList(
-                    SongListView::new(self.library.clone(), self.settings.clone())
-                );
+                // Go straight back to the song list, showing what was there before, and update it
+                // once the reload is done
+                self.song_list.rebuild_song_views();
+                self.state = ContentViewState::SongList;
+                self.side_panel = None;
+                self.offer_resume_playback();
+
+                return self.reload_library(false);
+            },
+
+            ContentMessage::RefreshLibraryIfStale => {
+                // Starting another reload while one is running would only make it throw its results
+                // away - anything it misses is caught by the next check
+                let busy = self.reloading
+                    || matches!(self.state, ContentViewState::Crop(_) | ContentViewState::EditMetadata(_) | ContentViewState::BatchEditMetadata(_));
+                if !busy && self.library.read().unwrap().is_stale() {
+                    return Command::perform(ready(()), |_| ContentMessage::RefreshLibrary { report_changes: true }.into());
+                }
+            },
+
+            ContentMessage::RefreshLibrary { report_changes } => return self.reload_library(report_changes),
+
+            ContentMessage::LibraryScanned { generation, report_changes, result } => {
+                // A newer reload is still running, which will have fresher results than this
+                if generation != self.reload_generation {
+                    return Command::none();
+                }
+
+                let diff = match self.library_scanned(result) {
+                    Ok(diff) => diff,
+                    Err(toast) => return toast,
+                };
+                self.song_list.rebuild_song_views();
+
+                // Any open view might be working on a song which just changed
+                match self.state {
+                    ContentViewState::Crop(ref mut v) => v.library_reloaded(&diff.moved),
+                    ContentViewState::EditMetadata(ref mut v) => v.library_reloaded(&diff.moved),
+                    ContentViewState::SongList | ContentViewState::BatchEditMetadata(_) | ContentViewState::Settings(_) | ContentViewState::History(_) => (),
+                }
+                if let Some(ref mut panel) = self.side_panel {
+                    panel.library_reloaded(&diff.moved);
+                }
+
+                let mut commands = vec![
+                    Command::perform(ready(()), |_| SongListMessage::LoadThumbnails.into()),
+                    Command::perform(ready(()), |_| SongListMessage::ReviewSongs.into()),
+                ];
+                if report_changes && !diff.is_empty() {
+                    commands.push(library_diff_toast(&diff));
+                }
+                return Command::batch(commands);
             },
 
-            ContentMessage::OpenCrop(song) =>
-                self.state = ContentViewState::Crop(CropView::new(song)),
+            ContentMessage::OpenCrop(song) => {
+                self.suspend_playback_of(&song);
+                let view = CropView::new(song, self.settings.clone());
+                let measure_levels = view.measure_levels();
+                self.side_panel = None;
+                self.state = ContentViewState::Crop(view);
+                return measure_levels;
+            }
             ContentMessage::OpenEditMetadata(song) =>
-                self.state = ContentViewState::EditMetadata(EditMetadataView::new(song)),
+                if self.is_split() {
+                    // Playback is only suspended when the edit is applied, so that picking a song
+                    // to look at doesn't stop it playing
+                    self.state = ContentViewState::SongList;
+                    self.side_panel = Some(EditMetadataView::new(song));
+                } else {
+                    self.suspend_playback_of(&song);
+                    self.state = ContentViewState::EditMetadata(EditMetadataView::new(song));
+                },
+            ContentMessage::OpenBatchEditMetadata(songs) => {
+                self.side_panel = None;
+                self.state = ContentViewState::BatchEditMetadata(BatchEditMetadataView::new(songs));
+            }
+            ContentMessage::SelectSong(song) =>
+                if self.is_split() && matches!(self.state, ContentViewState::SongList) {
+                    self.side_panel = Some(EditMetadataView::new(song));
+                },
+            ContentMessage::OpenSettings => {
+                self.side_panel = None;
+                self.state = ContentViewState::Settings(SettingsView::new(self.library.clone(), self.settings.clone()));
+            }
+            ContentMessage::OpenHistory => {
+                self.side_panel = None;
+                self.state = ContentViewState::History(HistoryView::new());
+            }
+
+            ContentMessage::Play(song) => self.now_playing = Some(NowPlayingView::new(song)),
+            ContentMessage::WindowResized(width) => {
+                self.window_width = width;
+                self.apply_layout();
+            }
 
-            ContentMessage::SongListMessage(m) =>
-                if let ContentViewState::SongList(ref mut v) = self.state { return v.update(m); }
+            ContentMessage::SongListMessage(m) => return self.song_list.update(m),
             ContentMessage::CropMessage(m) =>
                 if let ContentViewState::Crop(ref mut v) = self.state { return v.update(m); }
             ContentMessage::EditMetadataMessage(m) =>
-                if let ContentViewState::EditMetadata(ref mut v) = self.state { return v.update(m); }
+                if let ContentViewState::EditMetadata(ref mut v) = self.state {
+                    return v.update(m);
+                } else if let Some(ref panel) = self.side_panel {
+                    if let EditMetadataMessage::ApplyMetadataEdit = m {
+                        let song = panel.song().clone();
+                        self.suspend_playback_of(&song);
+                    }
+                    return self.side_panel.as_mut().unwrap().update(m);
+                },
+            ContentMessage::BatchEditMetadataMessage(m) => {
+                // Like the side panel, playback is only stopped once there's something to write
+                let songs_to_write = match (&self.state, &m) {
+                    (ContentViewState::BatchEditMetadata(v), BatchEditMetadataMessage::ApplyBatchEdit) => v.songs().to_vec(),
+                    _ => vec![],
+                };
+                for song in songs_to_write {
+                    self.suspend_playback_of(&song);
+                }
+                if let ContentViewState::BatchEditMetadata(ref mut v) = self.state { return v.update(m); }
+            }
+            ContentMessage::SettingsMessage(m) =>
+                if let ContentViewState::Settings(ref mut v) = self.state { return v.update(m); }
+            ContentMessage::NowPlayingMessage(NowPlayingMessage::Stop) => self.now_playing = None,
+            ContentMessage::NowPlayingMessage(m) =>
+                if let Some(ref mut np) = self.now_playing { return np.update(m); }
         }
 
         Command::none()
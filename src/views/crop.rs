@@ -1,12 +1,14 @@
-use std::{time::Duration, future::ready, cell::RefCell, cmp::max};
+use std::{time::{Duration, SystemTime}, future::ready, cell::RefCell, cmp::max, sync::{Arc, RwLock}};
 
-use iced::{Command, Subscription, time, pure::{Element, widget::{Column, Slider, Button, Text, Row, Container}}, Alignment, Length, Rule, Space, container::Style, Background};
+use iced::{Command, Subscription, time, pure::{Element, widget::{Column, Slider, Button, Text, Row, Container, TextInput, ProgressBar}}, Alignment, Length, Rule, Space, container::Style, Background};
+use iced_native::{subscription, event, keyboard::{self, KeyCode}, Event};
 use iced_video_player::{VideoPlayer, VideoPlayerMessage};
+use native_dialog::{MessageDialog, MessageType};
 use url::Url;
 
-use crate::{library::Song, Message, ui_util::{ButtonExtensions, ContainerStyleSheet}};
+use crate::{library::{Song, SongFileChange}, levels::LevelEnvelope, Message, ui_util::{ButtonExtensions, ContainerStyleSheet, ElementContainerExtensions, library_error_toast, format_millis}, settings::{Settings, CropTemplate}, i18n::{Key, tr, tr_args}};
 
-use super::content::ContentMessage;
+use super::content::{ContentMessage, check_song_unchanged};
 
 #[derive(Debug, Clone)]
 pub enum CropMessage {
@@ -14,14 +16,25 @@ pub enum CropMessage {
     SetSeekSongTarget(f64),
     SeekSong,
     TickPlayer,
+    /// Moves the playhead by a number of steps, each as long as the nudge step in the settings.
+    /// Negative numbers move it backwards.
+    NudgeBy(i64),
 
     SetStart,
     JumpStart,
     SetEnd,
     JumpEnd,
+    KeepSegment,
+    RemoveSegment(usize),
+    FadeInChange(String),
+    FadeOutChange(String),
     ApplyCrop,
 
+    TemplateNameChange(String),
+    SaveTemplate,
+
     VideoPlayerMessage(VideoPlayerMessage),
+    LevelsMeasured(Option<Arc<LevelEnvelope>>),
 }
 
 impl From<CropMessage> for Message {
@@ -30,6 +43,14 @@ impl From<CropMessage> for Message {
 
 pub struct CropView {
     song: Song,
+
+    /// When the song's file was last modified as of opening this view, to detect whether something
+    /// else changes it while we're cropping. If it couldn't be read, the song is treated as
+    /// changed when the crop is applied.
+    opened_modified_time: Option<SystemTime>,
+    changed_on_disk: bool,
+
+    settings: Arc<RwLock<Settings>>,
     player: VideoPlayer,
 
     seek_song_target: Option<(f64, bool)>,
@@ -37,10 +58,25 @@ pub struct CropView {
 
     crop_start_point: Option<f64>,
     crop_end_point: Option<f64>,
+
+    /// Segments which have already been marked with the start and end points, in milliseconds.
+    /// These are kept along with the segment currently being marked, and everything else is cut.
+    kept_segments: Vec<(f64, f64)>,
+
+    /// The lengths of the fades at the start and end of the crop, in seconds, as they've been
+    /// typed. Anything which doesn't parse is treated as no fade.
+    fade_in_input: String,
+    fade_out_input: String,
+
+    template_name: String,
+
+    /// How loud the song is over time, for the level meter. This is measured in the background
+    /// when the view opens, and the meter is hidden until then, or if measuring fails.
+    levels: Option<Arc<LevelEnvelope>>,
 }
 
 impl CropView {
-    pub fn new(song: Song) -> Self {
+    pub fn new(song: Song, settings: Arc<RwLock<Settings>>) -> Self {
         let mut player = VideoPlayer::new(
             &Url::from_file_path(song.path.clone()).unwrap(),
             false,
@@ -49,7 +85,10 @@ impl CropView {
         player.set_paused(true);
 
         Self {
+            opened_modified_time: song.modified_time().ok(),
+            changed_on_disk: false,
             song,
+            settings,
             player,
 
             last_drawn_slider_position: RefCell::new(0.0),
@@ -57,9 +96,33 @@ impl CropView {
 
             crop_start_point: None,
             crop_end_point: None,
+            kept_segments: vec![],
+
+            fade_in_input: "".to_string(),
+            fade_out_input: "".to_string(),
+
+            template_name: "".to_string(),
+
+            levels: None,
         }
     }
 
+    /// Measures the song's levels in the background, for the level meter.
+    pub fn measure_levels(&self) -> Command<Message> {
+        let path = self.song.path.clone();
+        Command::perform(
+            tokio::task::spawn_blocking(move || LevelEnvelope::measure(&path).ok().map(Arc::new)),
+            |result| CropMessage::LevelsMeasured(result.ok().flatten()).into(),
+        )
+    }
+
+    /// Called when the library is reloaded while this view is open, to check whether the song
+    /// changed underneath us. If its file was only renamed, the crop carries on with the new one.
+    pub fn library_reloaded(&mut self, moved: &[(Song, Song)]) {
+        self.song.follow_move(moved);
+        self.changed_on_disk = self.song.change_since(self.opened_modified_time) != SongFileChange::Unchanged;
+    }
+
     pub fn update(&mut self, message: CropMessage) -> Command<Message> {
         match message {
             CropMessage::PlayPauseSong => self.player.set_paused(!self.player.paused()),
@@ -89,6 +152,18 @@ impl CropView {
                 // update the UI
             }
 
+            CropMessage::NudgeBy(steps) => {
+                // Don't fight with the slider if it's being dragged
+                if self.seek_song_target.is_none() {
+                    let step = self.settings.read().unwrap().crop_nudge_millis as f64;
+                    let duration = self.player.duration().as_millis() as f64;
+                    let millis = (self.slider_millis() + steps as f64 * step).clamp(0.0, duration);
+
+                    self.player.seek(Duration::from_secs_f64(millis / 1000.0)).unwrap();
+                    *self.last_drawn_slider_position.borrow_mut() = millis;
+                }
+            }
+
             CropMessage::SetStart => 
                 self.crop_start_point = Some(self.player.position().as_millis() as f64),
             CropMessage::JumpStart =>
@@ -103,14 +178,75 @@ impl CropView {
                     self.player.seek(Duration::from_secs_f64(millis / 1000.0)).unwrap();
                 },
 
+            CropMessage::KeepSegment =>
+                if let (Some(start), Some(end)) = (self.crop_start_point.take(), self.crop_end_point.take()) {
+                    self.kept_segments.push((start, end));
+                },
+            CropMessage::RemoveSegment(i) => { self.kept_segments.remove(i); }
+
+            CropMessage::FadeInChange(v) => self.fade_in_input = Self::filter_seconds_input(&v),
+            CropMessage::FadeOutChange(v) => self.fade_out_input = Self::filter_seconds_input(&v),
+
             CropMessage::ApplyCrop => {
-                self.song.crop(
-                    Duration::from_secs_f64(self.crop_start_point.unwrap() / 1000.0),
-                    Duration::from_secs_f64(self.crop_end_point.unwrap() / 1000.0)
-                ).unwrap();
-                return Command::perform(ready(()), |_| ContentMessage::OpenSongList.into())
+                if let Some(command) = check_song_unchanged(&self.song, self.opened_modified_time, ContentMessage::OpenCrop) {
+                    return command;
+                }
+
+                let segments = self.segments_to_keep()
+                    .into_iter()
+                    .map(|(start, end)| (Duration::from_secs_f64(start / 1000.0), Duration::from_secs_f64(end / 1000.0)))
+                    .collect::<Vec<_>>();
+
+                // Stay here on failure, so the crop points aren't lost and it can be retried
+                if let Err(e) = self.song.crop(
+                    &segments,
+                    Self::parse_seconds_input(&self.fade_in_input),
+                    Self::parse_seconds_input(&self.fade_out_input),
+                ) {
+                    return library_error_toast(&e);
+                }
+
+                let toast = tr_args(Key::ToastCropped, &[&self.song.metadata.title]);
+                return Command::batch([
+                    Command::perform(ready(()), |_| ContentMessage::OpenSongList.into()),
+                    Command::perform(ready(()), move |_| Message::ShowToast(toast.clone())),
+                ])
             }
 
+            CropMessage::TemplateNameChange(name) => self.template_name = name,
+
+            CropMessage::SaveTemplate => {
+                let template = CropTemplate {
+                    name: self.template_name.trim().to_string(),
+                    start_millis: self.crop_start_point.unwrap() as u64,
+                    end_millis: self.crop_end_point.map(|end| end as u64),
+                };
+
+                let mut settings = self.settings.write().unwrap();
+                if let Some(existing) = settings.crop_templates.iter_mut().find(|t| t.name == template.name) {
+                    let confirmation = MessageDialog::new()
+                        .set_title(tr(Key::ReplaceTemplateTitle))
+                        .set_text(&tr_args(Key::ReplaceTemplateBody, &[&template.name]))
+                        .set_type(MessageType::Warning)
+                        .show_confirm()
+                        .unwrap();
+
+                    if !confirmation {
+                        return Command::none();
+                    }
+                    *existing = template.clone();
+                } else {
+                    settings.crop_templates.push(template.clone());
+                }
+                settings.save();
+                drop(settings);
+
+                self.template_name = "".to_string();
+                return Command::perform(ready(()), move |_| Message::ShowToast(tr_args(Key::ToastSavedTemplate, &[&template.name])))
+            }
+
+            CropMessage::LevelsMeasured(levels) => self.levels = levels,
+
             CropMessage::VideoPlayerMessage(msg) => {
                 return self.player.update(msg).map(|m| CropMessage::VideoPlayerMessage(m).into());
             }
@@ -123,16 +259,23 @@ impl CropView {
         Column::new()
             .padding(10)
             .spacing(10)
-            .push(Text::new(format!("Crop: {}", self.song.metadata.title)).size(28))
+            .push(Text::new(tr_args(Key::CropTitle, &[&self.song.metadata.title])).size(28))
+            .push_if(self.changed_on_disk, ||
+                Text::new(tr(Key::ChangedOnDisk)).color([0.8, 0.4, 0.0])
+            )
             .push(self.player.frame_view()) // Actually invisible
             .push(self.player_controls())
+            .push(Text::new(tr(Key::CropShortcutsHint)).color([0.3, 0.3, 0.3]))
             .push(Rule::horizontal(1))
             .push(
                 Row::new()
-                    .push(self.marker_display("Start", &self.crop_start_point, CropMessage::SetStart, CropMessage::JumpStart))
-                    .push(self.marker_display("End", &self.crop_end_point, CropMessage::SetEnd, CropMessage::JumpEnd))
+                    .push(self.marker_display(tr(Key::StartPoint), &self.crop_start_point, CropMessage::SetStart, CropMessage::JumpStart))
+                    .push(self.marker_display(tr(Key::EndPoint), &self.crop_end_point, CropMessage::SetEnd, CropMessage::JumpEnd))
                     .height(Length::Shrink)
             )
+            .push(self.segment_controls())
+            .push(self.fade_controls())
+            .push(self.template_controls())
             .push(
                 Column::new()
                     .align_items(Alignment::Center)
@@ -141,10 +284,10 @@ impl CropView {
                         Row::new()
                             .padding(10)
                             .spacing(10)
-                            .push(Button::new(Text::new("Cancel"))
+                            .push(Button::new(Text::new(tr(Key::Cancel)))
                                 .on_press(ContentMessage::OpenSongList.into()))
-                            .push(Button::new(Text::new("Apply and save"))
-                                .on_press_if(self.crop_start_point.is_some() && self.crop_end_point.is_some(), CropMessage::ApplyCrop.into()))
+                            .push(Button::new(Text::new(tr(Key::ApplyAndSave)))
+                                .on_press_if(!self.segments_to_keep().is_empty(), CropMessage::ApplyCrop.into()))
                     )
             )
             .into()
@@ -155,7 +298,7 @@ impl CropView {
             .align_items(Alignment::Center)
             .padding(10)
             .spacing(10)
-            .push(Text::new("Media player").size(25))
+            .push(Text::new(tr(Key::MediaPlayer)).size(25))
             .push(
                 Slider::new(
                     0.0..=self.player.duration().as_millis() as f64,
@@ -165,8 +308,9 @@ impl CropView {
                     .on_release(CropMessage::SeekSong.into())
             )
             .push(self.player_controls_markers())
-            .push(Text::new(Self::render_millis(self.slider_millis())))
-            .push(Button::new(Text::new(if self.player.paused() { "Play" } else { "Pause" }))
+            .push(Text::new(format_millis(self.slider_millis())))
+            .push_if_let(&self.levels, |levels| self.level_meter(levels))
+            .push(Button::new(Text::new(tr(if self.player.paused() { Key::Play } else { Key::Pause })))
                 .on_press(CropMessage::PlayPauseSong.into()))
             .into()
     }
@@ -212,25 +356,132 @@ impl CropView {
             .into()
     }
 
-    fn marker_display(&self, name: &str, value: &Option<f64>, set: CropMessage, jump: CropMessage) -> Element<Message> {
+    /// A meter showing how loud the song is at the playhead, which makes it easy to see exactly
+    /// when a fade-out goes silent.
+    fn level_meter(&self, levels: &LevelEnvelope) -> Element<Message> {
+        let position = Duration::from_secs_f64(self.slider_millis() / 1000.0);
+        let meter = levels.meter_at(position).unwrap_or(0.0);
+        let db = levels.level_db_at(position);
+
+        Row::new()
+            .spacing(10)
+            .align_items(Alignment::Center)
+            .push(
+                ProgressBar::new(0.0..=1.0, meter)
+                    .height(Length::Units(8))
+                    .width(Length::Units(200))
+            )
+            .push(
+                Text::new(match db {
+                    Some(db) => format!("{:.0} dB", db),
+                    None => "".to_string(),
+                })
+                    .size(14)
+                    .width(Length::Units(60))
+            )
+            .into()
+    }
+
+    /// The segments which have been kept so far, and a button to keep the one currently marked.
+    fn segment_controls(&self) -> Element<Message> {
+        Column::new()
+            .spacing(5)
+            .push_if(!self.kept_segments.is_empty(), || Text::new(tr(Key::KeptSegmentsHeading)))
+            .push(Column::with_children(
+                self.kept_segments.iter().enumerate().map(|(i, (start, end))|
+                    Row::new()
+                        .spacing(10)
+                        .align_items(Alignment::Center)
+                        .push(Text::new(tr_args(Key::KeptSegment, &[&format_millis(*start), &format_millis(*end)])))
+                        .push(Button::new(Text::new(tr(Key::RemoveSegment)))
+                            .on_press(CropMessage::RemoveSegment(i).into()))
+                        .into()
+                ).collect()
+            ).spacing(5))
+            .push(Button::new(Text::new(tr(Key::KeepSegment)))
+                .on_press_if(self.crop_start_point.is_some() && self.crop_end_point.is_some(), CropMessage::KeepSegment.into()))
+            .into()
+    }
+
+    /// Every segment to keep, including the one currently marked if both its points are set, in
+    /// the order they appear in the song.
+    fn segments_to_keep(&self) -> Vec<(f64, f64)> {
+        let mut segments = self.kept_segments.clone();
+        if let (Some(start), Some(end)) = (self.crop_start_point, self.crop_end_point) {
+            segments.push((start, end));
+        }
+        segments.sort_by(|a, b| a.0.total_cmp(&b.0));
+        segments
+    }
+
+    fn fade_controls(&self) -> Element<Message> {
+        Row::new()
+            .spacing(10)
+            .align_items(Alignment::Center)
+            .push(Text::new(tr(Key::FadeInSeconds)))
+            .push(
+                TextInput::new("0", &self.fade_in_input, |v| CropMessage::FadeInChange(v).into())
+                    .padding(5)
+                    .width(Length::Units(60))
+            )
+            .push(Space::with_width(Length::Units(20)))
+            .push(Text::new(tr(Key::FadeOutSeconds)))
+            .push(
+                TextInput::new("0", &self.fade_out_input, |v| CropMessage::FadeOutChange(v).into())
+                    .padding(5)
+                    .width(Length::Units(60))
+            )
+            .into()
+    }
+
+    /// Keeps only the parts of a fade length input which could be part of a number of seconds.
+    fn filter_seconds_input(input: &str) -> String {
+        input.chars().filter(|c| c.is_ascii_digit() || *c == '.').take(6).collect()
+    }
+
+    /// The fade length typed into an input, or zero if it's empty or doesn't parse.
+    fn parse_seconds_input(input: &str) -> Duration {
+        input.parse::<f64>().ok()
+            .filter(|s| s.is_finite() && *s > 0.0)
+            .map(Duration::from_secs_f64)
+            .unwrap_or(Duration::ZERO)
+    }
+
+    fn template_controls(&self) -> Element<Message> {
+        Row::new()
+            .spacing(10)
+            .align_items(Alignment::Center)
+            .push(
+                TextInput::new(tr(Key::TemplateNamePlaceholder), &self.template_name, |v| CropMessage::TemplateNameChange(v).into())
+                    .padding(5)
+            )
+            .push(Button::new(Text::new(tr(Key::SaveAsTemplate)))
+                .on_press_if(
+                    self.crop_start_point.is_some() && !self.template_name.trim().is_empty(),
+                    CropMessage::SaveTemplate.into(),
+                ))
+            .into()
+    }
+
+    fn marker_display(&self, label: &str, value: &Option<f64>, set: CropMessage, jump: CropMessage) -> Element<Message> {
         Column::new()
             .align_items(Alignment::Center)
             .padding(10)
             .spacing(10)
             .width(Length::Fill)
-            .push(Text::new(format!("{} point", name)).size(25))
+            .push(Text::new(label).size(25))
             .push(
                 Text::new(
                     if let Some(value) = value {
-                        Self::render_millis(*value)
+                        format_millis(*value)
                     } else {
-                        "Not set".to_string()
+                        tr(Key::NotSet).to_string()
                     }
                 )
             )
-            .push(Button::new(Text::new("Set"))
+            .push(Button::new(Text::new(tr(Key::Set)))
                 .on_press(set.into()))
-            .push(Button::new(Text::new("Jump"))
+            .push(Button::new(Text::new(tr(Key::Jump)))
                 .on_press_if(value.is_some(), jump.into()))
             .into()
     }
@@ -253,17 +504,34 @@ impl CropView {
         millis / (self.player.duration().as_secs_f64() * 1000.0)
     }
 
-    pub fn render_millis(millis: f64) -> String {
-        let total_seconds = (millis / 1000.0).floor() as i32;
-
-        let partitioned_minutes = total_seconds / 60;
-        let partitioned_seconds = total_seconds % 60;
-        let partitioned_millis = (millis % 1000.0).floor() as i32;
-
-        format!("{:0>2}:{:0>2}:{:0>3}", partitioned_minutes, partitioned_seconds, partitioned_millis)
+    pub fn subscription(&self) -> Subscription<Message> {
+        Subscription::batch([
+            time::every(Duration::from_millis(20)).map(|_| CropMessage::TickPlayer.into()),
+            subscription::events_with(Self::keyboard_shortcut),
+        ])
     }
 
-    pub fn subscription(&self) -> Subscription<Message> {
-        time::every(Duration::from_millis(20)).map(|_| CropMessage::TickPlayer.into())
+    /// Maps a key press to one of the crop view's controls. Key presses which a widget has already
+    /// handled are left alone, so that typing into a text input doesn't move the playhead.
+    fn keyboard_shortcut(event: Event, status: event::Status) -> Option<Message> {
+        if status != event::Status::Ignored {
+            return None;
+        }
+
+        let (key_code, modifiers) = match event {
+            Event::Keyboard(keyboard::Event::KeyPressed { key_code, modifiers }) => (key_code, modifiers),
+            _ => return None,
+        };
+        let nudge_steps = if modifiers.shift() { 10 } else { 1 };
+
+        let message = match key_code {
+            KeyCode::Space => CropMessage::PlayPauseSong,
+            KeyCode::Left => CropMessage::NudgeBy(-nudge_steps),
+            KeyCode::Right => CropMessage::NudgeBy(nudge_steps),
+            KeyCode::LBracket => CropMessage::SetStart,
+            KeyCode::RBracket => CropMessage::SetEnd,
+            _ => return None,
+        };
+        Some(message.into())
     }
 }
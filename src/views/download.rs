@@ -1,15 +1,71 @@
-use std::{sync::{Arc, RwLock}, future::ready, time::Duration, fmt::Display};
+use std::{sync::{Arc, RwLock, atomic::{AtomicBool, Ordering}}, future::ready, time::{Duration, Instant}, fmt::Display, path::{Path, PathBuf}, collections::{HashMap, HashSet}};
 
-use iced::{pure::{Element, widget::{Column, Text, Button, TextInput, Row, Container, PickList}}, container, Background, Length, alignment::Vertical, Rule, Command, ProgressBar, Subscription, time, Space};
-use crate::{youtube::{YouTubeDownload, YouTubeDownloadProgress, extract_video_id}, Message, library::Library, ui_util::{ElementContainerExtensions, ContainerStyleSheet}, settings::{SortBy, Settings}};
-use super::song_list::SongListMessage;
+use iced::{pure::{Element, widget::{Column, Text, Button, TextInput, Row, Container, PickList, Checkbox}}, container, Background, Length, alignment::Vertical, Rule, Command, ProgressBar, Subscription, Space, Image, image::Handle};
+use native_dialog::{MessageDialog, MessageType, FileDialog};
+use iced_native::{subscription, event, Event, keyboard::{self, KeyCode}};
+use tokio::sync::watch;
+use crate::{youtube::{Downloader, DownloaderVersion, DownloadOptions, ProgressNotifier, MediaDownload, YouTubeDownloadProgress, DownloadStage, YouTubePlaylist, PlaylistEntry, DownloadOutcome, DownloadError, VideoPreview, MetadataOverride, PrefetchedInfo, extract_playlist_id, looks_like_link, parse_link_list, parse_extra_args, ExtraArgsError, partial_download_files}, Message, library::{Library, Song}, ui_util::{ElementContainerExtensions, ButtonExtensions, ContainerStyleSheet, FocusableField, describe_download_error, describe_extra_args_error, library_error_toast, open_or_report, confirm_without_snapshot, format_size, format_duration}, bandwidth::BandwidthUsage, download_stats::DownloadStats, download_history::DownloadHistory, snapshot::TagSnapshot, settings::{SortBy, SortDirection, Settings, MetadataLookupFailureAction, MissingFfmpegAction}, ffmpeg_tags::ffmpeg_available, preflight, download_queue::{DownloadQueue, QueuedDownload}, hooks::{run_post_download_command, HookError}, activity_log::{self, Activity}, i18n::{Key, tr, tr_args, tr_count}};
+use super::{song_list::SongListMessage, content::ContentMessage};
 
 #[derive(Debug, Clone)]
 pub enum DownloadMessage {
     IdInputChange(String),
+    ClearIdInput,
+    FocusIdInput,
     StartDownload,
-    DownloadComplete(YouTubeDownload, Result<(), String>),
+    /// Reads the clipboard, and downloads what's on it if it's a link.
+    PasteAndDownload,
+    ClipboardRead(Option<String>),
+    /// Picks a text file of links, and downloads all of them.
+    ImportLinkList,
+    /// Downloads something from the download history again, as if it had been entered.
+    DownloadAgain(String),
+    /// Downloads a song in the library again, replacing its file but keeping its tags.
+    Redownload(Song),
+    /// Sent a short while after the ID input changes, with the [`DownloadView::preview_generation`]
+    /// at the time. If the input hasn't changed since, the video is looked up for a preview.
+    LookUpPreview(u64),
+    PreviewLoaded(u64, Result<VideoPreview, DownloadError>),
+    /// Chooses whether the previewed video is split by its chapters, overriding the setting.
+    ToggleSplitChapters(bool),
+    /// Chooses whether the previewed video's lyrics are skipped, rather than taken from subtitles
+    /// YouTube generated.
+    ToggleSkipAutoSubtitles(bool),
+    /// Shows or hides the options for the next download.
+    ToggleDownloadOptions,
+    ArtistOverrideChange(String),
+    AlbumOverrideChange(String),
+    ExtraArgsChange(String),
+    PlaylistListed(YouTubePlaylist, Result<Vec<PlaylistEntry>, DownloadError>),
+    DownloadComplete(MediaDownload, Result<DownloadOutcome, DownloadError>),
+    PostDownloadCommandFinished(PathBuf, Result<String, HookError>),
     DismissErrors,
+    ToggleSessionStats,
+    ResetSessionStats,
+    RetryDownload(MediaDownload),
+    RetryAllDownloads,
+    CancelQueued(MediaDownload),
+    MoveQueuedUp(MediaDownload),
+    MoveQueuedDown(MediaDownload),
+    MoveQueuedToTop(MediaDownload),
+    /// Starts downloads from the queue while there are free slots. This is sent on startup, to
+    /// pick up a queue saved when CrossPlay was last closed.
+    StartQueuedDownloads,
+    /// Looks for the downloader and ffmpeg again, after they were missing on startup.
+    CheckToolsAgain,
+    /// Offers to resume downloads which were interrupted when CrossPlay was last closed, and to
+    /// clean up any partial files left behind. This is sent on startup.
+    ResumeInterruptedDownloads,
+    ChooseCookiesFile,
+    ClearCookiesFile,
+
+    /// Checks whether the downloader is too old to work with YouTube. This is sent on startup.
+    CheckDownloaderVersion,
+    DownloaderVersionChecked(Downloader, Option<DownloaderVersion>),
+    OpenDownloaderUpdateInstructions,
+    UpdateDownloader,
+    DownloaderUpdated(Result<String, DownloadError>),
+    DismissOutdatedDownloader,
 }
 
 impl From<DownloadMessage> for Message {
@@ -20,60 +76,255 @@ impl From<DownloadMessage> for Message {
 pub enum SettingsListItem {
     TopLevel,
     ChangeLibrary,
+    OpenLibraryFolder,
     RefreshLibrary,
+    RestoreTagSnapshot,
+    ExportPlaylist,
+    ImportFiles,
+    ChooseCookiesFile,
+    ClearCookiesFile,
+    OpenHistory,
+    OpenSettings,
 }
 
 impl Display for SettingsListItem {
     fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
-        f.write_str(match self {
-            SettingsListItem::TopLevel => "Settings",
-            SettingsListItem::ChangeLibrary => "Change library",
-            SettingsListItem::RefreshLibrary => "Refresh library",
-        })
+        f.write_str(tr(match self {
+            SettingsListItem::TopLevel => Key::SettingsMenu,
+            SettingsListItem::ChangeLibrary => Key::ChangeLibrary,
+            SettingsListItem::OpenLibraryFolder => Key::OpenLibraryFolder,
+            SettingsListItem::RefreshLibrary => Key::RefreshLibrary,
+            SettingsListItem::RestoreTagSnapshot => Key::RestoreTagSnapshotMenu,
+            SettingsListItem::ExportPlaylist => Key::ExportPlaylistMenu,
+            SettingsListItem::ImportFiles => Key::ImportFilesMenu,
+            SettingsListItem::ChooseCookiesFile => Key::ChooseCookiesFileMenu,
+            SettingsListItem::ClearCookiesFile => Key::ClearCookiesFileMenu,
+            SettingsListItem::OpenHistory => Key::HistoryMenu,
+            SettingsListItem::OpenSettings => Key::MoreSettings,
+        }))
     }
 }
 
 #[derive(PartialEq, Eq, Debug, Clone, Copy)]
 pub enum SortListItem {
-    ChangeSort(SortBy),
-    ToggleSortReverse,
+    /// Change to a sort, in the given direction.
+    ChangeSort(SortBy, SortDirection),
+    /// Turns grouping the song list by the active sort on, or off if false.
+    SetGrouping(bool),
 }
 
 impl Display for SortListItem {
     fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
-        f.write_str(match self {
-            SortListItem::ChangeSort(sort) => match sort {
-                SortBy::Title => "Sort by song title",
-                SortBy::Artist => "Sort by artist",
-                SortBy::Album => "Sort by album",
-                SortBy::Downloaded => "Sort by time downloaded",
-            },
-            SortListItem::ToggleSortReverse => "Reverse current order"
-        })
+        match self {
+            SortListItem::ChangeSort(sort, direction) => {
+                let reverse = *direction == SortDirection::Reverse;
+                f.write_str(tr(match sort {
+                    SortBy::Title => if reverse { Key::SortTitleDescending } else { Key::SortTitleAscending },
+                    SortBy::Artist => if reverse { Key::SortArtistDescending } else { Key::SortArtistAscending },
+                    SortBy::Album => if reverse { Key::SortAlbumDescending } else { Key::SortAlbumAscending },
+                    // The normal direction of this sort is newest first - see `sort_song_views`
+                    SortBy::Downloaded => if reverse { Key::SortOldestFirst } else { Key::SortNewestFirst },
+                }))
+            }
+            SortListItem::SetGrouping(true) => f.write_str(tr(Key::GroupSongs)),
+            SortListItem::SetGrouping(false) => f.write_str(tr(Key::UngroupSongs)),
+        }
     }
 }
 
+/// How long the ID input has to stay unchanged before the video is looked up for a preview, so
+/// that typing a link doesn't run youtube-dl for every keystroke.
+const PREVIEW_DEBOUNCE: Duration = Duration::from_millis(800);
+
+/// Videos longer than this are pointed out in their preview, since they take a while to download
+/// and use a lot of space - they're usually mixes or full albums rather than a single song.
+const LONG_VIDEO_DURATION: Duration = Duration::from_secs(60 * 60);
+
+/// How many queued downloads are listed, so that importing a long list doesn't push everything
+/// else off the screen. The rest are counted instead.
+const QUEUED_ROWS_SHOWN: usize = 5;
+
+/// A preview of the video whose link is in the ID input.
+enum PreviewState {
+    Loading,
+    Loaded { preview: VideoPreview, thumbnail: Option<Handle> },
+    Failed(DownloadError),
+}
+
+/// A downloader which is too old to be likely to work, which the user is warned about.
+struct OutdatedDownloader {
+    downloader: Downloader,
+    version: DownloaderVersion,
+
+    /// Whether yt-dlp is updating itself.
+    updating: bool,
+}
+
+/// What the user chose for a download in particular, rather than leaving to the settings.
+#[derive(Debug, Clone, Default)]
+struct DownloadChoices {
+    /// Whether to split the video by its chapters. If not chosen, the setting is used.
+    split_chapters: Option<bool>,
+
+    /// If lyrics are being fetched, whether to go without them rather than use subtitles YouTube
+    /// generated.
+    skip_auto_subtitles: bool,
+
+    metadata_override: MetadataOverride,
+
+    /// The song to download over, if it's being downloaded again.
+    replacing: Option<PathBuf>,
+
+    /// Arguments to pass to youtube-dl for this download only.
+    extra_args: Vec<String>,
+}
+
 pub struct DownloadView {
     library: Arc<RwLock<Library>>,
     settings: Arc<RwLock<Settings>>,
     id_input: String,
 
-    pub downloads_in_progress: Vec<(YouTubeDownload, Arc<RwLock<YouTubeDownloadProgress>>)>,
-    download_errors: Vec<(YouTubeDownload, String)>,
+    /// The downloader found for the configured binary, if any, along with the binary it was found
+    /// for. The downloader is looked for when it's first needed, and again whenever that setting
+    /// changes.
+    downloader: Option<Downloader>,
+    downloader_detected_for: Option<String>,
+
+    /// The downloader, if it was found to be out of date when it was checked.
+    outdated_downloader: Option<OutdatedDownloader>,
+
+    /// Incremented to focus the ID input - see [`FocusableField`].
+    id_input_focus_request: u64,
+
+    /// Set when Paste & Download found something other than a link on the clipboard, until the
+    /// ID input is next used.
+    paste_warning: bool,
+
+    /// Set when the ID input held something which isn't a video link or ID, until it's next
+    /// changed.
+    invalid_link_warning: bool,
+
+    /// A preview of the video in the ID input, if it's been looked up.
+    preview: Option<PreviewState>,
+
+    /// Incremented whenever the ID input changes, so that a preview which finishes loading after
+    /// the input has changed again can be told apart and discarded.
+    preview_generation: u64,
+
+    /// Set to cancel the preview which is loading, if any.
+    preview_cancelled: Arc<AtomicBool>,
+
+    /// Info found by previews for videos which were then downloaded, by the video's ID, until
+    /// their download starts.
+    prefetched_info: HashMap<String, PrefetchedInfo>,
+
+    /// If the user has chosen from the preview whether to split the video in the ID input by its
+    /// chapters, their choice. Otherwise, the setting is used.
+    split_chapters_override: Option<bool>,
+
+    /// Whether the user has chosen from the preview to skip the auto-generated subtitles of the
+    /// video in the ID input.
+    skip_auto_subtitles: bool,
+
+    /// Whether the options for the next download, like its artist and album, are shown. They're
+    /// cleared and hidden again once the download starts.
+    download_options_shown: bool,
+    artist_override_input: String,
+    album_override_input: String,
+    extra_args_input: String,
+
+    pub downloads_in_progress: Vec<(MediaDownload, Arc<RwLock<YouTubeDownloadProgress>>)>,
+
+    /// Given to every download, to wake [`progress_changes`] whenever its progress changes.
+    progress_notifier: ProgressNotifier,
+    progress_changes: watch::Receiver<()>,
+
+    /// Downloads waiting for one of the running downloads to finish, so that no more than the
+    /// configured number run at once. These are started in order, and saved whenever they change.
+    download_queue: DownloadQueue,
+
+    /// How many downloads have finished since there were last none running or queued, to show
+    /// how far through a big batch of downloads things are.
+    completed_in_batch: usize,
+
+    /// How downloads have gone since CrossPlay was opened, or since the user last reset this.
+    session_stats: DownloadStats,
+
+    /// Whether the details of `session_stats` are shown.
+    session_stats_expanded: bool,
+
+    /// When downloads last started running, if they're still running or queued, so that the time
+    /// spent downloading can be counted.
+    downloading_since: Option<Instant>,
+
+    /// Downloads which were still running when CrossPlay was last closed, until the user has
+    /// chosen whether to resume them.
+    interrupted_downloads: Vec<QueuedDownload>,
+
+    /// Playlists whose videos are being looked up, before they're added to
+    /// `downloads_in_progress`.
+    playlists_being_listed: Vec<YouTubePlaylist>,
+
+    /// Downloads which failed, at most one for each ID. Playlists which couldn't be listed are
+    /// included, with the playlist's ID.
+    download_errors: Vec<(MediaDownload, DownloadError)>,
+
+    /// Playlists which couldn't be listed, so that retrying them lists them again rather than
+    /// trying to download their ID as a video.
+    failed_playlists: Vec<YouTubePlaylist>,
+
+    /// Songs which couldn't be downloaded again, by their video's ID, so that retrying them
+    /// replaces the song rather than adding another copy of it.
+    failed_redownloads: HashMap<String, PathBuf>,
 }
 
 impl DownloadView {
     pub fn new(library: Arc<RwLock<Library>>, settings: Arc<RwLock<Settings>>) -> Self {
+        // A queue which can't be read isn't worth refusing to start over
+        let mut download_queue = DownloadQueue::load().unwrap_or_default();
+        let interrupted_downloads = std::mem::take(&mut download_queue.in_progress);
+        let (progress_notifier, progress_changes) = ProgressNotifier::channel();
+
         Self {
             library,
             settings,
             id_input: "".to_string(),
+            downloader: None,
+            downloader_detected_for: None,
+            outdated_downloader: None,
+            id_input_focus_request: 0,
+            paste_warning: false,
+            invalid_link_warning: false,
+            preview: None,
+            preview_generation: 0,
+            preview_cancelled: Arc::new(AtomicBool::new(false)),
+            prefetched_info: HashMap::new(),
+            split_chapters_override: None,
+            skip_auto_subtitles: false,
+            download_options_shown: false,
+            artist_override_input: "".to_string(),
+            album_override_input: "".to_string(),
+            extra_args_input: "".to_string(),
             downloads_in_progress: vec![],
+            progress_notifier,
+            progress_changes,
+            download_queue,
+            completed_in_batch: 0,
+            session_stats: DownloadStats::default(),
+            session_stats_expanded: false,
+            downloading_since: None,
+            interrupted_downloads,
+            playlists_being_listed: vec![],
             download_errors: vec![],
+            failed_playlists: vec![],
+            failed_redownloads: HashMap::new(),
         }
     }
 
     pub fn view(&self) -> Element<Message> {
+        let settings = self.settings.read().unwrap();
+        let can_download = preflight::can_download(&settings);
+
         Column::new()
             .push(
                 Container::new(
@@ -82,36 +333,73 @@ impl DownloadView {
                         .padding(10)
                         .height(Length::Units(60))
                         .push(
-                            TextInput::new(
-                                "Paste a YouTube link...", 
-                                &self.id_input, 
-                                |s| DownloadMessage::IdInputChange(s).into(),
+                            FocusableField::new(
+                                TextInput::new(
+                                    tr(Key::PasteLinkPlaceholder),
+                                    &self.id_input, 
+                                    |s| DownloadMessage::IdInputChange(s).into(),
+                                )
+                                .on_submit(DownloadMessage::StartDownload.into())
+                                .padding(5)
                             )
-                            .padding(5)
+                            .on_escape(DownloadMessage::ClearIdInput.into())
+                            .focus_request(self.id_input_focus_request)
+                        )
+                        .push(
+                            Button::new(
+                                Text::new(tr(Key::Download))
+                                    .vertical_alignment(Vertical::Center)
+                                    .height(Length::Fill)
+                            )
+                            .on_press_if(can_download && !self.id_input.trim().is_empty(), DownloadMessage::StartDownload.into())
+                            .height(Length::Fill)
+                        )
+                        .push(
+                            Button::new(
+                                Text::new(tr(Key::PasteAndDownload))
+                                    .vertical_alignment(Vertical::Center)
+                                    .height(Length::Fill)
+                            )
+                            .on_press_if(can_download, DownloadMessage::PasteAndDownload.into())
+                            .height(Length::Fill)
+                        )
+                        .push(
+                            Button::new(
+                                Text::new(tr(Key::ImportLinkList))
+                                    .vertical_alignment(Vertical::Center)
+                                    .height(Length::Fill)
+                            )
+                            .on_press(DownloadMessage::ImportLinkList.into())
+                            .height(Length::Fill)
                         )
                         .push(
                             Button::new(
-                                Text::new("Download")
+                                Text::new(tr(Key::MoreDownloadOptions))
                                     .vertical_alignment(Vertical::Center)
                                     .height(Length::Fill)
                             )
-                            .on_press(DownloadMessage::StartDownload.into())
+                            .on_press(DownloadMessage::ToggleDownloadOptions.into())
                             .height(Length::Fill)
                         )
                         .push(Space::with_width(Length::Units(80)))
                         .push(
                             PickList::new(
-                                vec![
-                                    SortListItem::ChangeSort(SortBy::Title),
-                                    SortListItem::ChangeSort(SortBy::Artist),
-                                    SortListItem::ChangeSort(SortBy::Album),
-                                    SortListItem::ChangeSort(SortBy::Downloaded),
-                                    SortListItem::ToggleSortReverse,
-                                ],
-                                Some(SortListItem::ChangeSort(self.settings.read().unwrap().sort_by)),
+                                [SortBy::Title, SortBy::Artist, SortBy::Album, SortBy::Downloaded]
+                                    .into_iter()
+                                    .flat_map(|sort| [
+                                        SortListItem::ChangeSort(sort, SortDirection::Normal),
+                                        SortListItem::ChangeSort(sort, SortDirection::Reverse),
+                                    ])
+                                    .chain(
+                                        // Only artists and albums make sensible groups
+                                        matches!(settings.sort_by, SortBy::Artist | SortBy::Album)
+                                            .then(|| SortListItem::SetGrouping(!settings.group_songs))
+                                    )
+                                    .collect::<Vec<_>>(),
+                                Some(SortListItem::ChangeSort(settings.sort_by, settings.sort_direction())),
                                 |i| match i {
-                                    SortListItem::ChangeSort(sort) => SongListMessage::ChangeSort(sort).into(),
-                                    SortListItem::ToggleSortReverse => SongListMessage::ToggleSortReverse.into(),
+                                    SortListItem::ChangeSort(sort, direction) => SongListMessage::ChangeSort(sort, direction).into(),
+                                    SortListItem::SetGrouping(grouped) => SongListMessage::SetGrouping(grouped).into(),
                                 }
                             )
                                 .padding(10)
@@ -120,15 +408,34 @@ impl DownloadView {
                         .push(
                             PickList::new(
                                 // TODO: put sorts in their own one
-                                vec![
+                                [
                                     SettingsListItem::ChangeLibrary,
+                                    SettingsListItem::OpenLibraryFolder,
                                     SettingsListItem::RefreshLibrary,
-                                ],
+                                    SettingsListItem::RestoreTagSnapshot,
+                                    SettingsListItem::ExportPlaylist,
+                                    SettingsListItem::ImportFiles,
+                                    SettingsListItem::ChooseCookiesFile,
+                                    SettingsListItem::ClearCookiesFile,
+                                    SettingsListItem::OpenHistory,
+                                    SettingsListItem::OpenSettings,
+                                ]
+                                    .into_iter()
+                                    .filter(|i| *i != SettingsListItem::ClearCookiesFile || settings.cookies_file.is_some())
+                                    .collect::<Vec<_>>(),
                                 Some(SettingsListItem::TopLevel),
                                 |i| match i {
                                     SettingsListItem::TopLevel => unreachable!(),
                                     SettingsListItem::ChangeLibrary => Message::UpdateLibraryPath,
-                                    SettingsListItem::RefreshLibrary => SongListMessage::RefreshSongList.into(),
+                                    SettingsListItem::OpenLibraryFolder => SongListMessage::OpenLibraryFolder.into(),
+                                    SettingsListItem::RefreshLibrary => ContentMessage::RefreshLibrary { report_changes: true }.into(),
+                                    SettingsListItem::RestoreTagSnapshot => SongListMessage::RestoreTagSnapshot.into(),
+                                    SettingsListItem::ExportPlaylist => SongListMessage::ExportPlaylist.into(),
+                                    SettingsListItem::ImportFiles => SongListMessage::ImportFiles.into(),
+                                    SettingsListItem::ChooseCookiesFile => DownloadMessage::ChooseCookiesFile.into(),
+                                    SettingsListItem::ClearCookiesFile => DownloadMessage::ClearCookiesFile.into(),
+                                    SettingsListItem::OpenHistory => ContentMessage::OpenHistory.into(),
+                                    SettingsListItem::OpenSettings => ContentMessage::OpenSettings.into(),
                                 },
                             )
                                 .padding(10)
@@ -140,47 +447,124 @@ impl DownloadView {
                     ..Default::default()
                 }))
             )
-            .push_if(!self.downloads_in_progress.is_empty() || !self.download_errors.is_empty(), ||
+            .push_if(!can_download, || self.missing_tools_banner(&settings))
+            .push_if(self.download_options_shown, || self.download_options_panel())
+            .push_if_let(&self.outdated_downloader, |outdated| self.outdated_downloader_banner(outdated))
+            .push_if(self.paste_warning, ||
+                Container::new(Text::new(tr(Key::ClipboardNotALink)).color([0.8, 0.4, 0.0]))
+                    .padding(10)
+                    .width(Length::Fill)
+                    .style(ContainerStyleSheet(container::Style {
+                        background: Some(Background::Color([0.9, 0.9, 0.9].into())),
+                        ..Default::default()
+                    }))
+            )
+            .push_if(self.invalid_link_warning, ||
+                Container::new(Text::new(tr(Key::NotAYouTubeLink)).color([0.8, 0.4, 0.0]))
+                    .padding(10)
+                    .width(Length::Fill)
+                    .style(ContainerStyleSheet(container::Style {
+                        background: Some(Background::Color([0.9, 0.9, 0.9].into())),
+                        ..Default::default()
+                    }))
+            )
+            .push_if_let(&self.preview, |preview| self.preview_panel(preview))
+            .push_if(!self.session_stats.is_empty(), || self.session_stats_panel())
+            .push_if(self.has_downloads() || !self.playlists_being_listed.is_empty() || !self.download_errors.is_empty(), ||
                 Container::new(
                     Column::new()
+                        .push(Column::with_children(
+                            self.playlists_being_listed.iter().map(|playlist|
+                                Text::new(tr_args(Key::PlaylistLookingUp, &[&playlist.id])).into()
+                            ).collect()
+                        ))
+                        .push_if(!self.downloads_in_progress.is_empty(), || {
+                            let mut text = tr_count(Key::DownloadsInProgress, self.downloads_in_progress.len(), &[]);
+                            let batch_size = self.completed_in_batch + self.downloads_in_progress.len() + self.download_queue.downloads.len();
+                            if batch_size > 1 {
+                                text.push_str(&tr_args(Key::DownloadBatchProgress, &[&self.completed_in_batch, &batch_size]));
+                            }
+                            Text::new(text)
+                        })
                         .push_if(!self.downloads_in_progress.is_empty(), ||
-                            Text::new(format!("{} download(s) in progress...", self.downloads_in_progress.len()))
+                            Column::with_children(self.downloads_in_progress.iter().map(|(dl, prog)|
+                                Self::progress_row(dl, prog)
+                            ).collect())
+                                .spacing(10)
                         )
-                        .push_if(!self.downloads_in_progress.is_empty(), ||
-                            Column::with_children(self.downloads_in_progress.iter().map(|(dl, prog)| {
-                                let prog = prog.read().unwrap();
-                                let text = if let Some(metadata) = &prog.metadata {
-                                    format!("{} (ID {})", metadata.title, dl.id)
-                                } else {
-                                    format!("Looking up video info... (ID {})", dl.id)
-                                };
+                        .push_if(!self.download_queue.is_empty(), ||
+                            Column::with_children(self.download_queue.downloads.iter().take(QUEUED_ROWS_SHOWN).enumerate().map(|(i, queued)| {
+                                let dl = queued.download();
+                                let is_first = i == 0;
+                                let is_last = i == self.download_queue.downloads.len() - 1;
 
                                 Row::new()
                                     .align_items(iced::Alignment::Center)
                                     .spacing(10)
                                     .width(Length::Fill)
+                                    .push(Text::new(tr(Key::DownloadQueued)).width(Length::FillPortion(2)))
+                                    .push(Text::new(tr_args(Key::DownloadQueuedDetail, &[&queued.id, &queued.format, &queued.quality])).width(Length::FillPortion(3)))
+                                    .push(
+                                        Button::new(Text::new(tr(Key::QueueMoveToTop)))
+                                            .on_press_if(!is_first, DownloadMessage::MoveQueuedToTop(dl.clone()).into())
+                                    )
+                                    .push(
+                                        Button::new(Text::new(tr(Key::QueueMoveUp)))
+                                            .on_press_if(!is_first, DownloadMessage::MoveQueuedUp(dl.clone()).into())
+                                    )
+                                    .push(
+                                        Button::new(Text::new(tr(Key::QueueMoveDown)))
+                                            .on_press_if(!is_last, DownloadMessage::MoveQueuedDown(dl.clone()).into())
+                                    )
                                     .push(
-                                        ProgressBar::new(0.0..=100.0, prog.progress)
-                                            .width(Length::FillPortion(2))
+                                        Button::new(Text::new(tr(Key::Cancel)))
+                                            .on_press(DownloadMessage::CancelQueued(dl).into())
                                     )
-                                    .push(Text::new(text).width(Length::FillPortion(3)))
                                     .into()
                             }).collect())
                                 .spacing(10)
+                                .padding([10, 0, 0, 0])
+                        )
+                        .push_if(self.download_queue.downloads.len() > QUEUED_ROWS_SHOWN, ||
+                            Text::new(tr_count(Key::QueuedNotShown, self.download_queue.downloads.len() - QUEUED_ROWS_SHOWN, &[]))
+                                .color([0.3, 0.3, 0.3])
                         )
                         .push_if(!self.download_errors.is_empty(), ||
                             Column::new()
-                                .push_if(!self.downloads_in_progress.is_empty(), || Rule::horizontal(10))
+                                .push_if(self.has_downloads(), || Rule::horizontal(10))
                                 .push(
                                     Column::with_children(
                                         self.download_errors.iter().map(|(dl, err)| {
-                                            Text::new(format!("Download {} failed: {:?}", dl.id, err)).color([1.0, 0.0, 0.0]).into()
+                                            let mut text = tr_args(Key::DownloadFailedDetail, &[&dl.id, &describe_download_error(err)]);
+                                            if err.is_retryable() {
+                                                text.push_str(tr(Key::DownloadMayWorkOnRetry));
+                                            }
+
+                                            Row::new()
+                                                .align_items(iced::Alignment::Center)
+                                                .spacing(10)
+                                                .push(Text::new(text).color([1.0, 0.0, 0.0]).width(Length::Fill))
+                                                .push_if(err.is_retryable(), ||
+                                                    Button::new(Text::new(tr(Key::Retry)))
+                                                        .on_press(DownloadMessage::RetryDownload(dl.clone()).into())
+                                                )
+                                                .into()
                                         }).collect()
                                     )
+                                        .spacing(5)
                                 )
                                 .push(
-                                    Button::new(Text::new("OK"))
-                                        .on_press(DownloadMessage::DismissErrors.into())
+                                    Row::new()
+                                        .spacing(10)
+                                        .padding([10, 0, 0, 0])
+                                        .push(
+                                            Button::new(Text::new(tr(Key::Ok)))
+                                                .on_press(DownloadMessage::DismissErrors.into())
+                                        )
+                                        .push_if(self.retryable_download_errors().count() > 1, ||
+                                            Button::new(Text::new(tr(Key::RetryAll)))
+                                                .on_press(DownloadMessage::RetryAllDownloads.into())
+                                        )
                                 )
                         )
                 )
@@ -196,54 +580,1335 @@ impl DownloadView {
 
     pub fn update(&mut self, message: DownloadMessage) -> Command<Message> { 
         match message {
-            DownloadMessage::IdInputChange(s) => self.id_input = s,
+            DownloadMessage::IdInputChange(s) => {
+                self.id_input = s;
+                self.paste_warning = false;
+                self.invalid_link_warning = false;
+                self.discard_preview();
+
+                // Playlists are previewed by listing them when they're downloaded
+                if self.id_input.trim().is_empty() || extract_playlist_id(&self.id_input).is_some() {
+                    return Command::none();
+                }
+
+                let generation = self.preview_generation;
+                return Command::perform(
+                    tokio::task::spawn_blocking(|| std::thread::sleep(PREVIEW_DEBOUNCE)),
+                    move |_| DownloadMessage::LookUpPreview(generation).into(),
+                )
+            },
+            DownloadMessage::ClearIdInput => {
+                self.id_input.clear();
+                self.paste_warning = false;
+                self.invalid_link_warning = false;
+                self.discard_preview();
+            },
+            DownloadMessage::FocusIdInput => self.id_input_focus_request += 1,
+
+            DownloadMessage::PasteAndDownload =>
+                return iced::clipboard::read(|contents| DownloadMessage::ClipboardRead(contents).into()),
+            DownloadMessage::ClipboardRead(contents) => {
+                let link = contents.map(|c| c.trim().to_string()).filter(|c| looks_like_link(c));
+                match link {
+                    // Goes through the ID input, so that a failed download puts the link back there
+                    Some(link) => {
+                        self.id_input = link;
+                        self.discard_preview();
+                        return self.update(DownloadMessage::StartDownload)
+                    }
+                    None => self.paste_warning = true,
+                }
+            },
+
+            DownloadMessage::ImportLinkList => {
+                let path = match FileDialog::new()
+                    .add_filter(tr(Key::LinkListFilter), &["txt"])
+                    .show_open_single_file()
+                    .unwrap()
+                {
+                    Some(path) => path,
+                    None => return Command::none(),
+                };
+
+                let text = match std::fs::read_to_string(&path) {
+                    Ok(text) => text,
+                    Err(e) => {
+                        let summary = tr_args(Key::ToastLinkListReadFailed, &[&path.to_string_lossy()]);
+                        let detail = e.to_string();
+                        return Command::perform(ready(()), move |_| Message::ShowErrorToast(summary.clone(), detail.clone()))
+                    }
+                };
+
+                let downloader = match self.downloader() {
+                    Some(downloader) => downloader,
+                    None => {
+                        self.show_downloader_not_found();
+                        return Command::none();
+                    }
+                };
+                if !self.check_ffmpeg() {
+                    return Command::none();
+                }
+
+                return self.download_link_list(downloader, &text, DownloadChoices::default())
+            },
 
             DownloadMessage::StartDownload => {
-                // Need two named copies for the two closures
-                let id = extract_video_id(&self.id_input);
-                let async_dl = YouTubeDownload::new(id);
-                let result_dl = async_dl.clone();
-                let progress = Arc::new(RwLock::new(YouTubeDownloadProgress::new()));
-                self.downloads_in_progress.push((result_dl.clone(), progress.clone()));
-
-                self.id_input = "".to_string();
-                
-                let library_path = self.library.read().unwrap().path.clone();
+                // Pressing Enter in an empty field shouldn't try to download nothing, and pressing it
+                // while the download button is disabled shouldn't download anything either
+                if self.id_input.trim().is_empty() || !preflight::can_download(&self.settings.read().unwrap()) {
+                    return Command::none();
+                }
+                // What's wrong with them is shown under the options
+                let extra_args = match self.extra_args() {
+                    Ok(extra_args) => extra_args,
+                    Err(_) => return Command::none(),
+                };
+                self.paste_warning = false;
+                self.invalid_link_warning = false;
+
+                let input = std::mem::take(&mut self.id_input);
+                let choices = DownloadChoices {
+                    split_chapters: self.split_chapters_override,
+                    skip_auto_subtitles: self.skip_auto_subtitles,
+                    metadata_override: self.metadata_override(),
+                    replacing: None,
+                    extra_args,
+                };
+                let prefetched_info = match self.preview.take() {
+                    Some(PreviewState::Loaded { preview, .. }) => Some(preview.info),
+                    _ => None,
+                };
+                self.discard_preview();
+
+                let downloader = match self.downloader() {
+                    Some(downloader) => downloader,
+                    None => {
+                        self.show_downloader_not_found();
+                        return match MediaDownload::from_input(&input) {
+                            Ok(dl) => self.fail_download(dl.id.clone(), dl, DownloadError::DownloaderNotFound),
+                            Err(_) => Command::none(),
+                        }
+                    }
+                };
+
+                if !self.check_ffmpeg() {
+                    self.id_input = input;
+                    return Command::none();
+                }
+
+                // Several links pasted at once are downloaded separately, rather than being
+                // mangled into one ID
+                if input.split_whitespace().nth(1).is_some() {
+                    self.hide_download_options();
+                    return self.download_link_list(downloader, &input, choices)
+                }
+
+                // Playlists need looking up first, to find which videos to download
+                if let Some(id) = extract_playlist_id(&input) {
+                    self.hide_download_options();
+                    return self.list_playlist(downloader, YouTubePlaylist::new(id))
+                }
+
+                let dl = match MediaDownload::from_input(&input) {
+                    Ok(dl) => dl,
+                    Err(_) => {
+                        // Leave it there to be corrected, rather than handing youtube-dl nonsense
+                        self.id_input = input;
+                        self.invalid_link_warning = true;
+                        return Command::none();
+                    }
+                };
+                if !self.confirm_duplicate_download(&dl) {
+                    // Put the link back, in case it was pasted by mistake and needs correcting
+                    self.id_input = input;
+                    return Command::none();
+                }
+
+                if let Some(info) = prefetched_info {
+                    self.prefetched_info.insert(dl.id.clone(), info);
+                }
+                self.hide_download_options();
+                return self.start_download(downloader, dl, choices)
+            },
+
+            DownloadMessage::DownloadAgain(id) => {
+                self.id_input = id;
+                return self.update(DownloadMessage::StartDownload)
+            },
+
+            DownloadMessage::Redownload(song) => {
+                let downloader = match self.downloader() {
+                    Some(downloader) => downloader,
+                    None => {
+                        self.show_downloader_not_found();
+                        return Command::none();
+                    }
+                };
+                if !self.check_ffmpeg() {
+                    return Command::none();
+                }
+
+                let choices = DownloadChoices { replacing: Some(song.path.clone()), ..Default::default() };
+                return self.start_download(downloader, MediaDownload::for_song(&song.metadata), choices)
+            },
+
+            DownloadMessage::LookUpPreview(generation) => {
+                if generation != self.preview_generation {
+                    return Command::none();
+                }
+
+                // Not having a downloader is reported if the user tries to download, which is
+                // enough - there's no need to show it while they're still typing
+                let downloader = match self.downloader() {
+                    Some(downloader) => downloader,
+                    None => return Command::none(),
+                };
+
+                let dl = match MediaDownload::from_input(&self.id_input) {
+                    Ok(dl) => dl,
+                    Err(_) => return Command::none(),
+                };
+                self.preview = Some(PreviewState::Loading);
+                let cancelled = self.preview_cancelled.clone();
                 return Command::perform(
-                    async move {
-                        async_dl
-                            .download(&library_path, progress)
-                            .await
-                            .map_err(|e| format!("{}", e))
-                    },
-                    move |r| DownloadMessage::DownloadComplete(result_dl.clone(), r).into()
+                    tokio::task::spawn_blocking(move || dl.preview(&downloader, &cancelled)),
+                    move |r| DownloadMessage::PreviewLoaded(generation, r.expect("preview task failed")).into(),
                 )
             },
 
+            DownloadMessage::PreviewLoaded(generation, result) =>
+                if generation == self.preview_generation {
+                    self.preview = Some(match result {
+                        Ok(preview) => {
+                            let thumbnail = preview.thumbnail.clone().map(Handle::from_memory);
+                            PreviewState::Loaded { preview, thumbnail }
+                        }
+                        Err(e) => PreviewState::Failed(e),
+                    });
+                },
+            DownloadMessage::ToggleSplitChapters(split) => self.split_chapters_override = Some(split),
+            DownloadMessage::ToggleSkipAutoSubtitles(skip) => self.skip_auto_subtitles = skip,
+            DownloadMessage::ToggleDownloadOptions => {
+                if self.download_options_shown {
+                    self.hide_download_options();
+                } else {
+                    self.download_options_shown = true;
+                }
+            },
+            DownloadMessage::ArtistOverrideChange(artist) => self.artist_override_input = artist,
+            DownloadMessage::AlbumOverrideChange(album) => self.album_override_input = album,
+            DownloadMessage::ExtraArgsChange(args) => self.extra_args_input = args,
+
+            DownloadMessage::PlaylistListed(playlist, result) => {
+                self.playlists_being_listed.retain(|p| *p != playlist);
+
+                let entries = match result {
+                    Ok(entries) => entries,
+                    Err(e) => {
+                        let detail = e.to_string();
+                        self.record_error(MediaDownload::new(playlist.id.clone()), e);
+                        if !self.failed_playlists.contains(&playlist) {
+                            self.failed_playlists.push(playlist.clone());
+                        }
+                        let toast = tr_args(Key::ToastPlaylistFailed, &[&playlist.id]);
+                        return Command::perform(ready(()), move |_| Message::ShowErrorToast(toast.clone(), detail.clone()))
+                    }
+                };
+
+                // The playlist couldn't have been listed without a downloader
+                let downloader = self.downloader.clone().expect("no downloader");
+
+                // One bad entry shouldn't stop the rest from downloading
+                let mut commands = vec![];
+                for entry in entries {
+                    match entry {
+                        PlaylistEntry::Video(dl) => commands.push(self.start_download(downloader.clone(), dl, DownloadChoices::default())),
+                        PlaylistEntry::Unavailable(dl, e) => self.record_error(dl, e),
+                    }
+                }
+                return Command::batch(commands)
+            },
+
             DownloadMessage::DownloadComplete(dl, result) => {
-                // Remove the download which just finished
+                // Remove the download which just finished, but hang onto its title for the toast
+                let title = self.downloads_in_progress
+                    .iter()
+                    .find(|(this_dl, _)| *this_dl == dl)
+                    .and_then(|(_, prog)| prog.read().unwrap().metadata.as_ref().map(|m| m.title.clone()))
+                    .unwrap_or_else(|| dl.id.clone());
                 self.downloads_in_progress.retain(|(this_dl, _)| *this_dl != dl);
+                let replacing = self.download_queue.in_progress.iter()
+                    .find(|queued| queued.id == dl.id)
+                    .and_then(|queued| queued.replacing.clone());
+                self.download_queue.finish(&dl);
+                self.save_queue();
+                if self.has_downloads() {
+                    self.completed_in_batch += 1;
+                } else {
+                    self.completed_in_batch = 0;
+
+                    // That's the end of this stretch of downloading, so count how long it took
+                    if let Some(since) = self.downloading_since.take() {
+                        let time = since.elapsed();
+                        self.session_stats.record_downloading_time(time);
+                        if let Err(e) = DownloadStats::update_saved(|s| s.record_downloading_time(time)) {
+                            println!("[Download] Couldn't record download stats: {}", e);
+                        }
+                    }
+                }
+
+                // Reload without leaving whichever view is open, since that might be halfway
+                // through editing a song
+                let mut commands = vec![
+                    Command::perform(ready(()), |_| ContentMessage::RefreshLibrary { report_changes: false }.into()),
+                    self.start_queued_downloads(),
+                ];
+
+                let toast_message = match result {
+                    Ok(outcome) => {
+                        // Count the final files, since the estimate might've been off
+                        let paths = if outcome.split_into.is_empty() { std::slice::from_ref(&outcome.path) } else { &outcome.split_into[..] };
+                        let size = paths.iter()
+                            .filter_map(|path| std::fs::metadata(path).ok())
+                            .map(|file_metadata| file_metadata.len())
+                            .sum::<u64>();
+                        if size > 0 {
+                            if let Err(e) = BandwidthUsage::record_download(size) {
+                                println!("[Download] Couldn't record bandwidth usage: {}", e);
+                            }
+                        }
+                        if let Err(e) = DownloadHistory::record(&dl.id, &title, true) {
+                            println!("[Download] Couldn't record download history: {}", e);
+                        }
+                        self.session_stats.record_success(size, outcome.audio_duration);
+                        if let Err(e) = DownloadStats::update_saved(|s| s.record_success(size, outcome.audio_duration)) {
+                            println!("[Download] Couldn't record download stats: {}", e);
+                        }
 
+                        if outcome.metadata_lookup_failed {
+                            commands.push(self.handle_metadata_lookup_failure(&outcome));
+                        }
+                        commands.extend(paths.iter().map(|path| self.run_post_download_command(path, &dl.id)));
+                        if outcome.replaced_existing {
+                            Message::ShowToast(tr_args(Key::ToastRedownloaded, &[&title]))
+                        } else if outcome.split_into.is_empty() {
+                            Message::ShowToast(tr_args(Key::ToastDownloaded, &[&title]))
+                        } else {
+                            Message::ShowToast(tr_count(Key::ToastDownloadedChapters, outcome.split_into.len(), &[&title]))
+                        }
+                    }
+                    Err(e) => {
+                        if let Err(e) = DownloadHistory::record(&dl.id, &title, false) {
+                            println!("[Download] Couldn't record download history: {}", e);
+                        }
+                        self.session_stats.record_failure();
+                        if let Err(e) = DownloadStats::update_saved(DownloadStats::record_failure) {
+                            println!("[Download] Couldn't record download stats: {}", e);
+                        }
+                        if let Some(path) = replacing {
+                            self.failed_redownloads.insert(dl.id.clone(), path);
+                        }
+                        commands.push(self.fail_download(title, dl, e));
+                        return Command::batch(commands)
+                    }
+                };
+                commands.push(Command::perform(ready(()), move |_| toast_message.clone()));
+
+                return Command::batch(commands)
+            },
+
+            DownloadMessage::PostDownloadCommandFinished(path, result) => {
+                let summary = match &result {
+                    Ok(output) => output.clone(),
+                    Err(e) => e.to_string(),
+                };
+
+                // The command might've moved the song somewhere else, like into another library,
+                // in which case there's nowhere left to record what it did
+                match self.library.read().unwrap().load_song(&path) {
+                    Ok(song) => activity_log::record(&song, Activity::PostDownloadCommandRan, summary),
+                    Err(_) => println!("[Hook] Post-download command finished for {}: {}", path.to_string_lossy(), summary),
+                }
+
+                // The download itself still succeeded, so this is only a warning
                 if let Err(e) = result {
-                    self.download_errors.push((dl, e));
+                    let name = path.file_name().unwrap_or_default().to_string_lossy().into_owned();
+                    let summary = tr_args(Key::ToastPostDownloadCommandFailed, &[&name]);
+                    let detail = e.to_string();
+                    return Command::perform(ready(()), move |_| Message::ShowErrorToast(summary.clone(), detail.clone()))
+                }
+            }
+
+            DownloadMessage::CancelQueued(dl) => {
+                self.download_queue.remove(&dl);
+                self.prefetched_info.remove(&dl.id);
+                self.save_queue();
+            }
+            // Only the queue is reordered, so running downloads carry on regardless
+            DownloadMessage::MoveQueuedUp(dl) => {
+                self.download_queue.move_by(&dl, -1);
+                self.save_queue();
+            }
+            DownloadMessage::MoveQueuedDown(dl) => {
+                self.download_queue.move_by(&dl, 1);
+                self.save_queue();
+            }
+            DownloadMessage::MoveQueuedToTop(dl) => {
+                self.download_queue.move_by(&dl, isize::MIN);
+                self.save_queue();
+            }
+            DownloadMessage::StartQueuedDownloads => return self.start_queued_downloads(),
+
+            DownloadMessage::ResumeInterruptedDownloads => {
+                let interrupted = std::mem::take(&mut self.interrupted_downloads);
+                let mut resumed = false;
+                if !interrupted.is_empty() {
+                    // List what they were, since the user might not remember
+                    const MAX_LISTED_DOWNLOADS: usize = 10;
+                    let mut links = interrupted.iter()
+                        .take(MAX_LISTED_DOWNLOADS)
+                        .map(|queued| queued.download().url())
+                        .collect::<Vec<_>>();
+                    if interrupted.len() > MAX_LISTED_DOWNLOADS {
+                        links.push(tr_args(Key::AndMore, &[&(interrupted.len() - MAX_LISTED_DOWNLOADS)]));
+                    }
+
+                    resumed = MessageDialog::new()
+                        .set_title(tr(Key::ResumeDownloadsTitle))
+                        .set_text(&tr_count(Key::ResumeDownloadsBody, interrupted.len(), &[&links.join("\n")]))
+                        .set_type(MessageType::Info)
+                        .show_confirm()
+                        .unwrap();
+
+                    // Put them back at the front, since they were started before anything queued
+                    if resumed {
+                        for (i, queued) in interrupted.into_iter().enumerate() {
+                            if !self.download_queue.contains(&queued.download()) {
+                                self.download_queue.downloads.insert(i.min(self.download_queue.downloads.len()), queued);
+                            }
+                        }
+                    }
+                }
+                self.save_queue();
+
+                let cleanup = self.offer_partial_file_cleanup();
+                return if resumed {
+                    Command::batch([cleanup, self.start_queued_downloads()])
+                } else {
+                    cleanup
+                }
+            },
+
+            DownloadMessage::ChooseCookiesFile => {
+                let path = match FileDialog::new()
+                    .add_filter(tr(Key::CookiesFileFilter), &["txt"])
+                    .show_open_single_file()
+                    .unwrap()
+                {
+                    Some(path) => path,
+                    None => return Command::none(),
+                };
+
+                let mut settings = self.settings.write().unwrap();
+                settings.cookies_file = Some(path);
+                settings.save();
+
+                return Command::perform(ready(()), |_| Message::ShowToast(tr(Key::ToastCookiesFileChosen).to_string()))
+            },
+
+            DownloadMessage::ClearCookiesFile => {
+                let mut settings = self.settings.write().unwrap();
+                settings.cookies_file = None;
+                settings.save();
+            },
+
+            DownloadMessage::CheckToolsAgain => {
+                // Forget the downloader looked for before, in case one has been installed since
+                self.downloader_detected_for = None;
+
+                let missing = preflight::check(&self.settings.read().unwrap());
+                if missing.is_empty() {
+                    return Command::batch([
+                        Command::perform(ready(()), |_| Message::ShowToast(tr(Key::ToastToolsFound).to_string())),
+                        Command::perform(ready(()), |_| DownloadMessage::CheckDownloaderVersion.into()),
+                    ])
+                }
+            },
+
+            DownloadMessage::CheckDownloaderVersion => {
+                let downloader = match self.downloader() {
+                    Some(downloader) => downloader,
+                    None => return Command::none(),
+                };
+
+                return Command::perform(
+                    tokio::task::spawn_blocking(move || {
+                        let version = downloader.version();
+                        (downloader, version)
+                    }),
+                    |r| {
+                        let (downloader, version) = r.expect("version check task failed");
+                        DownloadMessage::DownloaderVersionChecked(downloader, version).into()
+                    },
+                )
+            },
+
+            // A version which can't be parsed is given the benefit of the doubt, since it might be
+            // a build from source
+            DownloadMessage::DownloaderVersionChecked(downloader, version) =>
+                self.outdated_downloader = version
+                    .filter(DownloaderVersion::is_outdated)
+                    .map(|version| OutdatedDownloader { downloader, version, updating: false }),
+
+            DownloadMessage::OpenDownloaderUpdateInstructions =>
+                if let Some(outdated) = &self.outdated_downloader {
+                    return open_or_report(outdated.downloader.update_instructions_url());
+                },
+
+            DownloadMessage::UpdateDownloader =>
+                if let Some(outdated) = &mut self.outdated_downloader {
+                    outdated.updating = true;
+
+                    let downloader = outdated.downloader.clone();
+                    return Command::perform(
+                        tokio::task::spawn_blocking(move || downloader.self_update()),
+                        |r| DownloadMessage::DownloaderUpdated(r.expect("update task failed")).into(),
+                    )
+                },
+
+            DownloadMessage::DownloaderUpdated(result) => {
+                if let Some(outdated) = &mut self.outdated_downloader {
+                    outdated.updating = false;
                 }
 
-                return Command::perform(ready(()), |_| SongListMessage::RefreshSongList.into())
+                let toast = match result {
+                    Ok(output) => Message::ShowDetailedToast(tr(Key::ToastDownloaderUpdated).to_string(), output),
+                    Err(e) => Message::ShowErrorToast(tr(Key::ToastDownloaderUpdateFailed).to_string(), e.to_string()),
+                };
+
+                // Check again, which hides the warning if the update worked
+                return Command::batch([
+                    Command::perform(ready(()), move |_| toast.clone()),
+                    Command::perform(ready(()), |_| DownloadMessage::CheckDownloaderVersion.into()),
+                ])
             },
 
-            DownloadMessage::DismissErrors => self.download_errors.clear(),
+            DownloadMessage::DismissOutdatedDownloader => self.outdated_downloader = None,
+
+            DownloadMessage::RetryDownload(dl) => return self.retry(dl),
+            DownloadMessage::RetryAllDownloads => {
+                let failed = self.retryable_download_errors().map(|(dl, _)| dl.clone()).collect::<Vec<_>>();
+                return Command::batch(failed.into_iter().map(|dl| self.retry(dl)))
+            },
+
+            DownloadMessage::ToggleSessionStats => self.session_stats_expanded = !self.session_stats_expanded,
+            DownloadMessage::ResetSessionStats => {
+                self.session_stats = DownloadStats::default();
+                self.session_stats_expanded = false;
+            },
+
+            DownloadMessage::DismissErrors => {
+                self.download_errors.clear();
+                self.failed_playlists.clear();
+                self.failed_redownloads.clear();
+            },
         }
 
         Command::none()
     }
 
+    /// Options for the next download, like its artist and album, rather than have them found from
+    /// the video.
+    fn download_options_panel(&self) -> Element<Message> {
+        let extra_args_error = parse_extra_args(&self.extra_args_input).err().map(|e| describe_extra_args_error(&e));
+
+        Container::new(
+            Column::new()
+                .spacing(10)
+                .push(self.metadata_override_row())
+                .push(
+                    Row::new()
+                        .spacing(10)
+                        .align_items(iced::Alignment::Center)
+                        .push(Text::new(tr(Key::ExtraArgsLabel)))
+                        .push(
+                            TextInput::new(
+                                tr(Key::ExtraArgsPlaceholder),
+                                &self.extra_args_input,
+                                |s| DownloadMessage::ExtraArgsChange(s).into(),
+                            )
+                            .on_submit(DownloadMessage::StartDownload.into())
+                            .padding(5)
+                        )
+                )
+                .push_if_let(&extra_args_error, |error| Text::new(error.clone()).color([0.8, 0.0, 0.0]))
+        )
+            .padding(10)
+            .width(Length::Fill)
+            .style(ContainerStyleSheet(container::Style {
+                background: Some(Background::Color([0.9, 0.9, 0.9].into())),
+                ..Default::default()
+            }))
+            .into()
+    }
+
+    fn metadata_override_row(&self) -> Element<Message> {
+        Row::new()
+            .spacing(10)
+            .align_items(iced::Alignment::Center)
+            .push(Text::new(tr(Key::ArtistAndAlbumHint)))
+            .push(
+                TextInput::new(
+                    tr(Key::Artist),
+                    &self.artist_override_input,
+                    |s| DownloadMessage::ArtistOverrideChange(s).into(),
+                )
+                .on_submit(DownloadMessage::StartDownload.into())
+                .padding(5)
+            )
+            .push(
+                TextInput::new(
+                    tr(Key::Album),
+                    &self.album_override_input,
+                    |s| DownloadMessage::AlbumOverrideChange(s).into(),
+                )
+                .on_submit(DownloadMessage::StartDownload.into())
+                .padding(5)
+            )
+            .push(Button::new(Text::new(tr(Key::Cancel)))
+                .on_press(DownloadMessage::ToggleDownloadOptions.into()))
+            .into()
+    }
+
+    /// The artist and album typed in for the next download, if any.
+    fn metadata_override(&self) -> MetadataOverride {
+        if self.download_options_shown {
+            MetadataOverride::from_inputs(&self.artist_override_input, &self.album_override_input)
+        } else {
+            MetadataOverride::default()
+        }
+    }
+
+    /// The extra youtube-dl arguments typed in for the next download, if any.
+    fn extra_args(&self) -> Result<Vec<String>, ExtraArgsError> {
+        if self.download_options_shown {
+            parse_extra_args(&self.extra_args_input)
+        } else {
+            Ok(vec![])
+        }
+    }
+
+    fn hide_download_options(&mut self) {
+        self.download_options_shown = false;
+        self.artist_override_input.clear();
+        self.album_override_input.clear();
+        self.extra_args_input.clear();
+    }
+
+    /// Warns that the downloader is out of date, with ways to update it.
+    /// Explains that downloading is turned off because a program it needs is missing, with a
+    /// button to look again once it's been installed.
+    fn missing_tools_banner(&self, settings: &Settings) -> Element<Message> {
+        let mut missing = vec![];
+        if !preflight::downloader_found() {
+            missing.push(settings.downloader_binary.as_str());
+        }
+        if !preflight::ffmpeg_found() {
+            missing.push("ffmpeg");
+        }
+
+        Container::new(
+            Row::new()
+                .spacing(10)
+                .align_items(iced::Alignment::Center)
+                .push(Text::new(tr_args(Key::MissingToolsBanner, &[&missing.join(", ")])).width(Length::Fill))
+                .push(Button::new(Text::new(tr(Key::CheckAgain)))
+                    .on_press(DownloadMessage::CheckToolsAgain.into()))
+        )
+            .padding(10)
+            .width(Length::Fill)
+            .style(ContainerStyleSheet(container::Style {
+                background: Some(Background::Color([1.0, 0.85, 0.85].into())),
+                ..Default::default()
+            }))
+            .into()
+    }
+
+    fn outdated_downloader_banner(&self, outdated: &OutdatedDownloader) -> Element<Message> {
+        let program = outdated.downloader.program.file_name()
+            .unwrap_or(outdated.downloader.program.as_os_str())
+            .to_string_lossy();
+
+        Container::new(
+            Row::new()
+                .spacing(10)
+                .align_items(iced::Alignment::Center)
+                .push(Text::new(tr_args(Key::DownloaderOutdated, &[&program, &outdated.version])).width(Length::Fill))
+                .push(Button::new(Text::new(tr(Key::DownloaderUpdateHelp)))
+                    .on_press(DownloadMessage::OpenDownloaderUpdateInstructions.into()))
+                .push_if(outdated.downloader.is_yt_dlp(), ||
+                    Button::new(Text::new(tr(if outdated.updating { Key::DownloaderUpdating } else { Key::DownloaderUpdateNow })))
+                        .on_press_if(!outdated.updating, DownloadMessage::UpdateDownloader.into())
+                )
+                .push(Button::new(Text::new(tr(Key::Ok)))
+                    .on_press(DownloadMessage::DismissOutdatedDownloader.into()))
+        )
+            .padding(10)
+            .width(Length::Fill)
+            .style(ContainerStyleSheet(container::Style {
+                background: Some(Background::Color([1.0, 0.9, 0.7].into())),
+                ..Default::default()
+            }))
+            .into()
+    }
+
+    /// A row showing how far a running download has got. This is also shown by the song list, so
+    /// that downloads can be followed from further down it.
+    pub fn progress_row<'a>(dl: &MediaDownload, prog: &RwLock<YouTubeDownloadProgress>) -> Element<'a, Message> {
+        let prog = prog.read().unwrap();
+        let mut text = if let Some(metadata) = &prog.metadata {
+            tr_args(Key::DownloadTitleWithId, &[&metadata.title, &dl.id])
+        } else {
+            tr_args(Key::DownloadLookingUp, &[&dl.id])
+        };
+        if let Some((i, count)) = prog.splitting_chapter {
+            text.push_str(&tr_args(Key::DownloadSplittingChapter, &[&(i + 1), &count]));
+        }
+        if let Some(step) = prog.post_process_step {
+            text.push_str(&tr_args(Key::DownloadPostProcessing, &[&step]));
+        }
+        let size_text = match (prog.downloaded_size(), prog.estimated_size) {
+            (Some(downloaded), Some(estimated)) =>
+                tr_args(Key::DownloadSizeProgress, &[&format_size(downloaded), &format_size(estimated)]),
+            _ => "".to_string(),
+        };
+
+        // The steps after the download itself are already described in the text
+        let finishing = prog.splitting_chapter.is_some() || prog.post_process_step.is_some();
+
+        Row::new()
+            .align_items(iced::Alignment::Center)
+            .spacing(10)
+            .width(Length::Fill)
+            .push(
+                Column::new()
+                    .width(Length::FillPortion(2))
+                    .push(ProgressBar::new(0.0..=100.0, prog.progress))
+                    .push_if(!finishing, ||
+                        Text::new(describe_stage(prog.stage)).size(14).color([0.4, 0.4, 0.4])
+                    )
+            )
+            .push(Text::new(text).width(Length::FillPortion(3)))
+            .push(Text::new(size_text).width(Length::FillPortion(1)))
+            .into()
+    }
+
+    /// A summary of how downloads have gone this session, which can be expanded for more detail.
+    fn session_stats_panel(&self) -> Element<Message> {
+        let stats = &self.session_stats;
+        let summary = tr_args(Key::SessionStatsSummary, &[&stats.succeeded, &stats.failed]);
+
+        // Include the downloads which are still running
+        let downloading = Duration::from_secs(stats.downloading_secs)
+            + self.downloading_since.map_or(Duration::ZERO, |since| since.elapsed());
+
+        Column::new()
+            .padding([0, 10])
+            .spacing(5)
+            .push(
+                Button::new(Text::new(format!("{} {}", if self.session_stats_expanded { "▾" } else { "▸" }, summary)).size(16))
+                    .on_press(DownloadMessage::ToggleSessionStats.into())
+            )
+            .push_if(self.session_stats_expanded, ||
+                Row::new()
+                    .spacing(10)
+                    .align_items(iced::Alignment::Center)
+                    .push(Text::new(tr_args(Key::SessionStatsDetail, &[
+                        &format_size(stats.bytes),
+                        &format_duration(Duration::from_secs(stats.audio_secs)),
+                        &format_duration(downloading),
+                    ])).size(16).color([0.3, 0.3, 0.3]))
+                    .push(Button::new(Text::new(tr(Key::ResetSessionStats)).size(16))
+                        .on_press(DownloadMessage::ResetSessionStats.into()))
+            )
+            .into()
+    }
+
+    /// Shows what the video in the ID input is, or why it couldn't be looked up.
+    fn preview_panel(&self, preview: &PreviewState) -> Element<Message> {
+        let content: Element<Message> = match preview {
+            PreviewState::Loading =>
+                Text::new(tr(Key::PreviewLoading)).color([0.3, 0.3, 0.3]).into(),
+            // This is only a preview, so failing isn't a big deal - the video might still download
+            PreviewState::Failed(e) =>
+                Text::new(tr_args(Key::PreviewFailed, &[&describe_download_error(e)])).size(16).color([0.3, 0.3, 0.3]).into(),
+            PreviewState::Loaded { preview, thumbnail } => {
+                let details = preview.uploader.iter().cloned()
+                    .chain(preview.duration.map(format_duration))
+                    .chain(preview.estimated_size.map(|size| tr_args(Key::PreviewEstimatedSize, &[&format_size(size)])))
+                    .collect::<Vec<_>>();
+
+                Row::new()
+                    .spacing(10)
+                    .align_items(iced::Alignment::Center)
+                    .push_if_let(thumbnail, |handle| Image::new(handle.clone()).width(Length::Units(120)))
+                    .push(
+                        Column::new()
+                            .spacing(5)
+                            .push(Text::new(preview.title.clone()))
+                            .push_if(!details.is_empty(), ||
+                                Text::new(details.join(" · ")).size(16).color([0.3, 0.3, 0.3])
+                            )
+                            .push_if(preview.chapter_count > 1, || {
+                                let split = self.split_chapters_override
+                                    .unwrap_or_else(|| self.settings.read().unwrap().split_chapters);
+                                Checkbox::new(
+                                    split,
+                                    tr_count(Key::SplitIntoChapters, preview.chapter_count, &[]),
+                                    |v| DownloadMessage::ToggleSplitChapters(v).into(),
+                                ).size(16).text_size(16)
+                            })
+                            .push_if(preview.only_auto_subtitles && self.settings.read().unwrap().embed_lyrics, ||
+                                Checkbox::new(
+                                    self.skip_auto_subtitles,
+                                    tr(Key::SkipAutoSubtitles),
+                                    |v| DownloadMessage::ToggleSkipAutoSubtitles(v).into(),
+                                ).size(16).text_size(16)
+                            )
+                            .push_if(preview.duration.map_or(false, |d| d > LONG_VIDEO_DURATION), ||
+                                Text::new(tr(Key::PreviewLongVideo)).size(16).color([0.8, 0.4, 0.0])
+                            )
+                    )
+                    .push(Space::with_width(Length::Fill))
+                    .push(Button::new(Text::new(tr(Key::Download)))
+                        .on_press(DownloadMessage::StartDownload.into()))
+                    .into()
+            }
+        };
+
+        Container::new(content)
+            .padding(10)
+            .width(Length::Fill)
+            .style(ContainerStyleSheet(container::Style {
+                background: Some(Background::Color([0.9, 0.9, 0.9].into())),
+                ..Default::default()
+            }))
+            .into()
+    }
+
+    /// Forgets the current preview, and any which is still loading, since it's for a link which
+    /// is no longer in the ID input.
+    fn discard_preview(&mut self) {
+        self.preview = None;
+        self.preview_generation += 1;
+        self.preview_cancelled.store(true, Ordering::Relaxed);
+        self.preview_cancelled = Arc::new(AtomicBool::new(false));
+        self.split_chapters_override = None;
+        self.skip_auto_subtitles = false;
+    }
+
+    /// The downloader to use, looking for one if the downloader settings have changed since the
+    /// last time.
+    fn downloader(&mut self) -> Option<Downloader> {
+        let binary = self.settings.read().unwrap().downloader_binary.clone();
+
+        if self.downloader_detected_for.as_ref() != Some(&binary) {
+            self.downloader = Downloader::detect(&binary);
+
+            // A chosen executable might've been moved or uninstalled since, in which case one on
+            // the PATH is used instead
+            let is_path = Path::new(&binary).components().count() > 1;
+            if is_path && self.downloader.as_ref().map_or(false, |d| d.program != Path::new(&binary)) {
+                MessageDialog::new()
+                    .set_title(tr(Key::DownloaderPathMissingTitle))
+                    .set_text(&tr_args(Key::DownloaderPathMissingBody, &[&binary]))
+                    .set_type(MessageType::Warning)
+                    .show_alert()
+                    .unwrap();
+            }
+            self.downloader_detected_for = Some(binary);
+        }
+
+        self.downloader.clone()
+    }
+
+    /// Runs the user's post-download command on a song which has just been downloaded, if they've
+    /// set one and it's turned on.
+    fn run_post_download_command(&self, path: &Path, id: &str) -> Command<Message> {
+        let settings = self.settings.read().unwrap();
+        let command = match &settings.post_download_command {
+            Some(command) if settings.post_download_command_enabled => command.clone(),
+            _ => return Command::none(),
+        };
+
+        let path = path.to_path_buf();
+        let id = id.to_string();
+        Command::perform(
+            async move {
+                let result = run_post_download_command(&command, &path, &id).await;
+                (path, result)
+            },
+            |(path, result)| DownloadMessage::PostDownloadCommandFinished(path, result).into(),
+        )
+    }
+
+    /// Records that a download failed, and lets the user know with a toast.
+    fn fail_download(&mut self, title: String, dl: MediaDownload, error: DownloadError) -> Command<Message> {
+        let summary = tr_args(Key::ToastDownloadFailed, &[&title]);
+        let detail = error.to_string();
+        self.record_error(dl, error);
+
+        Command::perform(ready(()), move |_| Message::ShowErrorToast(summary.clone(), detail.clone()))
+    }
+
+    /// Adds a download to the error list, replacing any earlier error for the same ID, so that a
+    /// download which keeps failing is only listed once.
+    fn record_error(&mut self, dl: MediaDownload, error: DownloadError) {
+        self.download_errors.retain(|(this_dl, _)| *this_dl != dl);
+        self.download_errors.push((dl, error));
+    }
+
+    /// The failed downloads which are worth offering to retry.
+    fn retryable_download_errors(&self) -> impl Iterator<Item = &(MediaDownload, DownloadError)> {
+        self.download_errors.iter().filter(|(_, err)| err.is_retryable())
+    }
+
+    /// Removes a failed download or playlist from the error list, and tries it again.
+    fn retry(&mut self, dl: MediaDownload) -> Command<Message> {
+        if !self.check_ffmpeg() {
+            return Command::none();
+        }
+
+        self.download_errors.retain(|(this_dl, _)| *this_dl != dl);
+        let failed_playlist = self.failed_playlists.iter().position(|p| p.id == dl.id)
+            .map(|i| self.failed_playlists.remove(i));
+
+        let downloader = match self.downloader() {
+            Some(downloader) => downloader,
+            None => return self.fail_download(dl.id.clone(), dl, DownloadError::DownloaderNotFound),
+        };
+
+        let replacing = self.failed_redownloads.remove(&dl.id);
+        match failed_playlist {
+            Some(playlist) => self.list_playlist(downloader, playlist),
+            None => self.start_download(downloader, dl, DownloadChoices { replacing, ..Default::default() }),
+        }
+    }
+
+    /// Checks that ffmpeg is installed, since youtube-dl needs it to convert downloads. If it isn't,
+    /// and the user hasn't chosen to download without converting, explains why nothing can be
+    /// downloaded. Returns whether downloads can go ahead.
+    fn check_ffmpeg(&self) -> bool {
+        if self.settings.read().unwrap().missing_ffmpeg_action == MissingFfmpegAction::DownloadUnconverted
+            || ffmpeg_available() {
+            return true;
+        }
+
+        MessageDialog::new()
+            .set_title(tr(Key::FfmpegMissingTitle))
+            .set_text(tr(Key::FfmpegMissingBody))
+            .set_type(MessageType::Error)
+            .show_alert()
+            .unwrap();
+        false
+    }
+
+    /// Checks whether a video is already being downloaded or is already in the library, and if it's
+    /// in the library, asks the user whether to download it again over the existing song. Returns
+    /// whether the download should go ahead.
+    ///
+    /// Downloading again would destroy any changes made to the existing song. Songs with edited
+    /// metadata have a snapshot of their tags taken first, so the edits can be restored, but crops
+    /// can't be kept, so cropped songs are never downloaded again.
+    fn confirm_duplicate_download(&self, dl: &MediaDownload) -> bool {
+        let in_progress = self.downloads_in_progress.iter().find(|(this_dl, _)| this_dl == dl);
+        if in_progress.is_some() || self.download_queue.contains(dl) {
+            let title = in_progress
+                .and_then(|(_, prog)| prog.read().unwrap().metadata.as_ref().map(|m| m.title.clone()))
+                .unwrap_or_else(|| dl.id.clone());
+            MessageDialog::new()
+                .set_title(tr(Key::AlreadyDownloadingTitle))
+                .set_text(&tr_args(Key::AlreadyDownloadingBody, &[&title]))
+                .set_type(MessageType::Info)
+                .show_alert()
+                .unwrap();
+            return false;
+        }
+
+        let library = self.library.read().unwrap();
+        let existing = match library.songs().chain(library.staged_songs()).find(|s| dl.is_source_of(&s.metadata)) {
+            Some(song) => song.clone(),
+            None => return true,
+        };
+        drop(library);
+
+        if existing.metadata.is_cropped {
+            MessageDialog::new()
+                .set_title(tr(Key::AlreadyDownloadedTitle))
+                .set_text(&tr_args(Key::AlreadyDownloadedCroppedBody, &[&existing.metadata.title]))
+                .set_type(MessageType::Warning)
+                .show_alert()
+                .unwrap();
+            return false;
+        }
+
+        let mut text = tr_args(Key::AlreadyDownloadedBody, &[&existing.metadata.title]);
+        if existing.metadata.is_metadata_edited {
+            text.push_str("\n\n");
+            text.push_str(tr(Key::AlreadyDownloadedEditedNote));
+        }
+        let confirmation = MessageDialog::new()
+            .set_title(tr(Key::AlreadyDownloadedTitle))
+            .set_text(&text)
+            .set_type(MessageType::Warning)
+            .show_confirm()
+            .unwrap();
+        if !confirmation {
+            return false;
+        }
+
+        if existing.metadata.is_metadata_edited {
+            if let Err(e) = TagSnapshot::new(&[existing]).save() {
+                return confirm_without_snapshot(&e);
+            }
+        }
+
+        true
+    }
+
+    /// Downloads every link in some text, like a pasted list or a text file. Videos which are
+    /// already in the library or being downloaded are skipped, and anything which isn't a link is
+    /// reported along with what was started.
+    ///
+    /// Every video in the list, but not in any playlists in it, gets the same `choices`.
+    fn download_link_list(&mut self, downloader: Downloader, text: &str, choices: DownloadChoices) -> Command<Message> {
+        let list = parse_link_list(text);
+        if list.links.is_empty() && list.unparsed.is_empty() {
+            let toast = tr(Key::ToastLinkListEmpty).to_string();
+            return Command::perform(ready(()), move |_| Message::ShowToast(toast.clone()))
+        }
+
+        let mut commands = vec![];
+        let mut started = 0;
+        let mut skipped = 0;
+        let mut seen = HashSet::new();
+        for link in list.links {
+            if let Some(id) = extract_playlist_id(link) {
+                commands.push(self.list_playlist(downloader.clone(), YouTubePlaylist::new(id)));
+                started += 1;
+                continue;
+            }
+
+            // Unlike a single download, there's no asking about duplicates - in a long list,
+            // they're almost certainly things which were already downloaded from it
+            let dl = match MediaDownload::from_input(link) {
+                Ok(dl) => dl,
+                Err(_) => continue,
+            };
+            let already_downloading = self.downloads_in_progress.iter().any(|(this_dl, _)| *this_dl == dl)
+                || self.download_queue.contains(&dl);
+            let library = self.library.read().unwrap();
+            let already_downloaded = library.songs().chain(library.staged_songs()).any(|s| dl.is_source_of(&s.metadata));
+            drop(library);
+            if !seen.insert(dl.id.clone()) || already_downloading || already_downloaded {
+                skipped += 1;
+                continue;
+            }
+
+            commands.push(self.start_download(downloader.clone(), dl, choices.clone()));
+            started += 1;
+        }
+
+        let mut summary = tr_count(Key::ToastLinkListStarted, started, &[]);
+        if skipped > 0 {
+            summary.push_str(&tr_count(Key::LinkListSkippedSuffix, skipped, &[]));
+        }
+        if list.unparsed.is_empty() {
+            commands.push(Command::perform(ready(()), move |_| Message::ShowToast(summary.clone())));
+        } else {
+            summary.push_str(&tr_count(Key::LinkListUnparsedSuffix, list.unparsed.len(), &[]));
+            let detail = list.unparsed.iter()
+                .map(|(line, word)| tr_args(Key::LinkListUnparsedLine, &[line, word]))
+                .collect::<Vec<_>>()
+                .join("\n");
+            commands.push(Command::perform(ready(()), move |_| Message::ShowErrorToast(summary.clone(), detail.clone())));
+        }
+
+        Command::batch(commands)
+    }
+
+    fn show_downloader_not_found(&self) {
+        MessageDialog::new()
+            .set_title(tr(Key::DownloaderNotFoundTitle))
+            .set_text(&tr_args(Key::DownloaderNotFoundBody, &[&self.settings.read().unwrap().downloader_binary]))
+            .set_type(MessageType::Error)
+            .show_alert()
+            .unwrap();
+    }
+
+    /// Begins looking up the videos in a playlist, which are downloaded once they're known.
+    fn list_playlist(&mut self, downloader: Downloader, playlist: YouTubePlaylist) -> Command<Message> {
+        self.playlists_being_listed.push(playlist.clone());
+
+        let result_playlist = playlist.clone();
+        Command::perform(
+            async move { playlist.list_entries(&downloader).await },
+            move |r| DownloadMessage::PlaylistListed(result_playlist.clone(), r).into()
+        )
+    }
+
+    /// How many downloads can run at once. At least one must be allowed, or nothing would ever
+    /// leave the queue.
+    fn max_concurrent_downloads(&self) -> usize {
+        self.settings.read().unwrap().max_concurrent_downloads.max(1)
+    }
+
+    /// Whether any downloads are running or queued.
+    pub fn has_downloads(&self) -> bool {
+        !self.downloads_in_progress.is_empty() || !self.download_queue.is_empty()
+    }
+
+    /// Begins downloading a video, unless it's already being downloaded. If the maximum number of
+    /// downloads are already running, it's queued instead.
+    ///
+    /// Settings the user chose for this download in particular are taken from `choices`.
+    fn start_download(&mut self, downloader: Downloader, dl: MediaDownload, choices: DownloadChoices) -> Command<Message> {
+        // Downloads are told apart by their ID, so there can't be two of the same one at once
+        if self.downloads_in_progress.iter().any(|(this_dl, _)| *this_dl == dl)
+            || self.download_queue.contains(&dl) {
+            return Command::none();
+        }
+
+        let settings = self.settings.read().unwrap();
+        let queued = QueuedDownload {
+            id: dl.id,
+            format: settings.audio_format,
+            quality: settings.audio_quality,
+            split_chapters: choices.split_chapters.unwrap_or(settings.split_chapters),
+            skip_auto_subtitles: choices.skip_auto_subtitles,
+            metadata_override: choices.metadata_override,
+            replacing: choices.replacing,
+            extra_args: choices.extra_args,
+        };
+        drop(settings);
+
+        if self.downloads_in_progress.len() >= self.max_concurrent_downloads() {
+            self.download_queue.downloads.push(queued);
+            self.save_queue();
+            return Command::none();
+        }
+
+        self.spawn_download(downloader, queued)
+    }
+
+    /// Starts queued downloads until the maximum number are running, or the queue is empty.
+    fn start_queued_downloads(&mut self) -> Command<Message> {
+        if self.download_queue.is_empty() {
+            return Command::none();
+        }
+
+        // The downloader isn't saved with the queue, since it might not be the same one after a
+        // restart
+        let downloader = match self.downloader() {
+            Some(downloader) => downloader,
+            None => {
+                let failed = std::mem::take(&mut self.download_queue.downloads);
+                self.save_queue();
+                return Command::batch(failed.into_iter().map(|queued|
+                    self.fail_download(queued.id.clone(), queued.download(), DownloadError::DownloaderNotFound)
+                ))
+            }
+        };
+
+        let max = self.max_concurrent_downloads();
+        let mut commands = vec![];
+        while self.downloads_in_progress.len() < max && !self.download_queue.is_empty() {
+            let queued = self.download_queue.downloads.remove(0);
+            commands.push(self.spawn_download(downloader.clone(), queued));
+        }
+        self.save_queue();
+
+        Command::batch(commands)
+    }
+
+    /// Looks for files left behind by downloads which didn't finish, and offers to delete them.
+    /// Files belonging to downloads which are running or queued are left alone, since youtube-dl
+    /// will carry on from them.
+    fn offer_partial_file_cleanup(&self) -> Command<Message> {
+        let library_path = self.library.read().unwrap().path.clone();
+        let files = match partial_download_files(&library_path) {
+            Ok(files) => files,
+            Err(_) => return Command::none(),
+        };
+
+        let files = files.into_iter()
+            .filter(|path| {
+                let running = self.downloads_in_progress.iter().any(|(dl, _)| dl.owns_file(path));
+                let queued = self.download_queue.downloads.iter().any(|q| q.download().owns_file(path));
+                !running && !queued
+            })
+            .collect::<Vec<_>>();
+        if files.is_empty() {
+            return Command::none();
+        }
+
+        let confirmation = MessageDialog::new()
+            .set_title(tr(Key::PartialFilesTitle))
+            .set_text(&tr_count(Key::PartialFilesBody, files.len(), &[&library_path.to_string_lossy()]))
+            .set_type(MessageType::Info)
+            .show_confirm()
+            .unwrap();
+        if !confirmation {
+            return Command::none();
+        }
+
+        let mut deleted = 0;
+        for path in &files {
+            match std::fs::remove_file(path) {
+                Ok(()) => deleted += 1,
+                Err(e) => println!("[Download] Could not delete partial file {}: {}", path.to_string_lossy(), e),
+            }
+        }
+
+        let toast = tr_count(Key::ToastDeletedPartialFiles, deleted, &[]);
+        Command::perform(ready(()), move |_| Message::ShowToast(toast.clone()))
+    }
+
+    /// Saves the queue so that it can be picked up next time. Failing is only printed, since the
+    /// settings folder might be read-only, and downloading shouldn't stop because of it.
+    fn save_queue(&self) {
+        if let Err(e) = self.download_queue.save() {
+            println!("[Download] Could not save download queue: {}", e);
+        }
+    }
+
+    /// Saves the queue and the running downloads one last time before CrossPlay closes.
+    pub fn flush_queue(&self) {
+        self.save_queue();
+    }
+
+    /// Runs a download straight away, regardless of how many others are running.
+    fn spawn_download(&mut self, downloader: Downloader, queued: QueuedDownload) -> Command<Message> {
+        // A song being downloaded again is loaded now rather than when it was queued, so that
+        // any changes made to it while it waited are kept
+        let replacing = match queued.replacing.as_ref().map(|path| self.library.read().unwrap().load_song(path)) {
+            Some(Ok(song)) => Some(song),
+            Some(Err(e)) => {
+                let dl = queued.download();
+                return self.fail_download(dl.id.clone(), dl, DownloadError::ReplaceFailed(e))
+            }
+            None => None,
+        };
+
+        // Need two named copies for the two closures
+        let async_dl = queued.download();
+        let result_dl = async_dl.clone();
+        let progress = Arc::new(RwLock::new(YouTubeDownloadProgress::new()));
+        self.downloads_in_progress.push((result_dl.clone(), progress.clone()));
+        self.downloading_since.get_or_insert_with(Instant::now);
+
+        // Remember it's running, so that it can be resumed if CrossPlay is closed part-way through
+        self.download_queue.in_progress.push(queued.clone());
+        self.save_queue();
+
+        let settings = self.settings.read().unwrap();
+        let library = self.library.read().unwrap();
+        // Songs being downloaded again stay wherever they already are
+        let library_path = if settings.staging_enabled && replacing.is_none() {
+            library.staging_path()
+        } else {
+            library.path.clone()
+        };
+        let original_storage = library.original_storage;
+        drop(library);
+        let options = DownloadOptions {
+            format: queued.format,
+            quality: queued.quality,
+            pipeline: settings.post_process_pipeline.clone(),
+            loudness_target: settings.loudness_target,
+            allow_without_ffmpeg: settings.missing_ffmpeg_action == MissingFfmpegAction::DownloadUnconverted,
+            parse_artist_from_title: settings.parse_artist_from_title,
+            max_album_art_dimension: settings.album_art_size.max_dimension(),
+            split_chapters: queued.split_chapters,
+            prefetched_info: self.prefetched_info.remove(&queued.id),
+            rate_limit: settings.download_rate_limit.clone(),
+            cookies_file: settings.cookies_file.clone(),
+            fetch_lyrics: settings.embed_lyrics,
+            auto_subtitles: !queued.skip_auto_subtitles,
+            metadata_override: queued.metadata_override.clone(),
+            extra_args: settings.extra_downloader_args.iter().chain(&queued.extra_args).cloned().collect(),
+            progress_notifier: self.progress_notifier.clone(),
+        };
+        drop(settings);
+        Command::perform(
+            async move {
+                match replacing {
+                    Some(song) => async_dl.redownload(song, &downloader, &options, &library_path, original_storage, progress).await,
+                    None => match std::fs::create_dir_all(&library_path) {
+                        Ok(()) => async_dl.download(&downloader, &options, &library_path, original_storage, progress).await,
+                        Err(e) => Err(e.into()),
+                    },
+                }
+            },
+            move |r| DownloadMessage::DownloadComplete(result_dl.clone(), r).into()
+        )
+    }
+
+    /// Carries out the user's chosen action for a downloaded song which is missing its metadata.
+    fn handle_metadata_lookup_failure(&self, outcome: &DownloadOutcome) -> Command<Message> {
+        let mut song = match self.library.read().unwrap().load_song(&outcome.path) {
+            Ok(song) => song,
+            Err(e) => return library_error_toast(&e),
+        };
+
+        match self.settings.read().unwrap().metadata_lookup_failure_action {
+            MetadataLookupFailureAction::KeepFallback => Command::none(),
+            MetadataLookupFailureAction::OpenEditor =>
+                Command::perform(ready(()), move |_| ContentMessage::OpenEditMetadata(song.clone()).into()),
+            MetadataLookupFailureAction::FlagNeedsMetadata => match song.flag_needs_metadata() {
+                Ok(()) => Command::none(),
+                Err(e) => library_error_toast(&e),
+            }
+        }
+    }
+
     pub fn subscription(&self) -> Subscription<Message> {
-        // If a download is in progress, poke the UI to refresh occasionally to keep metadata and
-        // progress up-to-date
-        if !self.downloads_in_progress.is_empty() {
-            time::every(Duration::from_millis(500)).map(|_| Message::None)
+        // Ctrl+L focuses the ID input, like a browser's address bar. Ctrl+V pastes and downloads,
+        // but only when it hasn't already pasted into a focused text input
+        let shortcuts = subscription::events_with(|event, status| match (event, status) {
+            (Event::Keyboard(keyboard::Event::KeyPressed { key_code: KeyCode::L, modifiers }), _) if modifiers.command() =>
+                Some(DownloadMessage::FocusIdInput.into()),
+            (Event::Keyboard(keyboard::Event::KeyPressed { key_code: KeyCode::V, modifiers }), event::Status::Ignored) if modifiers.command() =>
+                Some(DownloadMessage::PasteAndDownload.into()),
+            _ => None,
+        });
+
+        // While anything is downloading, redraw whenever a download reports new progress or
+        // metadata, so that it's shown as soon as youtube-dl says so
+        let refresh = if !self.downloads_in_progress.is_empty() {
+            subscription::unfold("download progress", self.progress_changes.clone(), |mut changes| async move {
+                // The notifier lives as long as this view, but if it's ever gone, there's nothing
+                // more to wait for
+                if changes.changed().await.is_err() {
+                    std::future::pending::<()>().await;
+                }
+                (Some(Message::None), changes)
+            })
         } else {
             Subscription::none()
-        }
+        };
+
+        Subscription::batch([shortcuts, refresh])
     }
 }
+
+fn describe_stage(stage: DownloadStage) -> &'static str {
+    tr(match stage {
+        DownloadStage::FetchingMetadata => Key::DownloadStageFetchingMetadata,
+        DownloadStage::DownloadingAudio => Key::DownloadStageDownloadingAudio,
+        DownloadStage::ExtractingAudio => Key::DownloadStageExtractingAudio,
+        DownloadStage::ProcessingThumbnail => Key::DownloadStageProcessingThumbnail,
+        DownloadStage::WritingTags => Key::DownloadStageWritingTags,
+    })
+}
@@ -1,30 +1,142 @@
-use std::future::ready;
+use std::{future::ready, time::SystemTime};
 
-use iced::{Command, pure::{widget::{TextInput, Button, Column, Text, Row}, Element}, Length, Alignment, Image, image::Handle};
+use iced::{Command, pure::{widget::{TextInput, Button, Column, Text, Row, Scrollable}, Element}, Length, Alignment, Image, image::Handle, Space};
+use native_dialog::FileDialog;
 
-use crate::{library::Song, Message, ui_util::ElementContainerExtensions};
+use crate::{library::{Song, SongFileChange, AlbumArt}, Message, ui_util::{ElementContainerExtensions, ButtonExtensions, library_error_toast, describe_library_error, format_unix_time}, i18n::{Key, tr, tr_args}, musicbrainz::{self, RecordingMatch, LookupError}, post_process::square_album_art, youtube::cover_picture, activity_log::{self, Activity, ActivityEntry}, assets};
 
-use super::content::ContentMessage;
+use super::content::{ContentMessage, check_song_unchanged};
 
 #[derive(Debug, Clone)]
 pub enum EditMetadataMessage {
     TitleChange(String),
     ArtistChange(String),
     AlbumChange(String),
+    GenreChange(String),
+    YearChange(String),
+    TrackChange(String),
+    LyricsLineChange(usize, String),
+    /// Starts a new, empty line of lyrics at the given index.
+    InsertLyricsLine(usize),
+    RemoveLyricsLine(usize),
+    ClearLyrics,
+    SquareAlbumArt,
+    RemoveBrokenAlbumArt,
+    /// Picks an image file to replace the song's album art with.
+    ChangeCover,
     ApplyMetadataEdit,
+
+    LookUpMusicBrainz,
+    MusicBrainzResults(Result<Vec<RecordingMatch>, LookupError>),
+    ApplyMusicBrainzMatch(RecordingMatch),
 }
 
 impl From<EditMetadataMessage> for Message {
     fn from(emm: EditMetadataMessage) -> Self { Message::ContentMessage(ContentMessage::EditMetadataMessage(emm)) }
 }
 
+/// The progress of looking up the song on MusicBrainz.
+enum MusicBrainzLookup {
+    Searching,
+    Found(Vec<RecordingMatch>),
+    Failed(LookupError),
+}
+
+/// Covers chosen from a file are scaled down to fit within this size, since photos and scans can be
+/// far larger than any player shows them.
+const MAX_COVER_DIMENSION: u32 = 1000;
+
 pub struct EditMetadataView {
     song: Song,
+
+    /// When the song's file was last modified as of opening this view, to detect whether something
+    /// else changes it while we're editing. If it couldn't be read, the song is treated as changed
+    /// when the edit is applied.
+    opened_modified_time: Option<SystemTime>,
+    changed_on_disk: bool,
+
+    /// The contents of the numeric fields, which are only written into the song's metadata when
+    /// they parse.
+    year_input: String,
+    track_input: String,
+
+    /// The song's lyrics, edited a line at a time since text inputs can only hold one. There's
+    /// always at least one line, so that there's somewhere to start typing.
+    lyrics_lines: Vec<String>,
+
+    musicbrainz_lookup: Option<MusicBrainzLookup>,
+
+    /// The song's album art, ready to display. This is `None` if the song has no art, or if its
+    /// art couldn't be decoded - in which case `album_art_broken` is set.
+    album_art: Option<Handle>,
+    album_art_broken: bool,
+
+    /// Why the song's album art couldn't be read from its file, if it couldn't. The other tags can
+    /// still be edited, but the art has to be read again before they can be saved.
+    album_art_error: Option<String>,
+
+    /// What's been done to this song, newest first, from its library's activity log.
+    history: Vec<ActivityEntry>,
 }
 
 impl EditMetadataView {
-    pub fn new(song: Song) -> Self {
-        Self { song }
+    pub fn new(mut song: Song) -> Self {
+        let album_art_error = song.metadata.load_album_art(&song.path).err().map(|e| describe_library_error(&e));
+        let opened_modified_time = song.modified_time().ok();
+        let year_input = song.metadata.year.map(|y| y.to_string()).unwrap_or_default();
+        let track_input = song.metadata.track.map(|t| t.to_string()).unwrap_or_default();
+        let lyrics_lines = match &song.metadata.lyrics {
+            Some(lyrics) => lyrics.lines().map(Into::into).collect(),
+            None => vec![String::new()],
+        };
+        let history = activity_log::entries_for(&song);
+        let mut view = Self {
+            song, opened_modified_time, changed_on_disk: false, year_input, track_input, lyrics_lines, musicbrainz_lookup: None,
+            album_art: None, album_art_broken: false, album_art_error, history,
+        };
+        view.refresh_album_art();
+        view
+    }
+
+    /// Checks whether the song's album art can be decoded, and keeps a handle to it if so. This is
+    /// done whenever the art changes, rather than leaving the renderer to fail on every frame.
+    fn refresh_album_art(&mut self) {
+        self.album_art = None;
+        self.album_art_broken = false;
+
+        // Once there's new art, or none, the art which couldn't be read doesn't matter any more
+        if !matches!(self.song.metadata.album_art, Some(AlbumArt::NotLoaded)) {
+            self.album_art_error = None;
+        }
+
+        if let Some(art) = self.song.metadata.loaded_album_art() {
+            match image::load_from_memory(&art.data) {
+                Ok(_) => self.album_art = Some(Handle::from_memory(art.data.clone())),
+                Err(e) => {
+                    println!("[Thumbnail] Album art of {} can't be decoded: {}", self.song.path.to_string_lossy(), e);
+                    self.album_art_broken = true;
+                }
+            }
+        }
+    }
+
+    /// Writes the lines of the lyrics editor into the song's metadata. Lyrics with nothing but
+    /// blank lines are removed entirely.
+    fn sync_lyrics(&mut self) {
+        let lyrics = self.lyrics_lines.join("\n");
+        self.song.metadata.lyrics = if lyrics.trim().is_empty() { None } else { Some(lyrics) };
+    }
+
+    pub fn song(&self) -> &Song {
+        &self.song
+    }
+
+    /// Called when the library is reloaded while this view is open, to check whether the song
+    /// changed underneath us. If its file was only renamed, the edit carries on with the new one.
+    pub fn library_reloaded(&mut self, moved: &[(Song, Song)]) {
+        self.song.follow_move(moved);
+        self.changed_on_disk = self.song.change_since(self.opened_modified_time) != SongFileChange::Unchanged;
+        self.history = activity_log::entries_for(&self.song);
     }
 
     pub fn update(&mut self, message: EditMetadataMessage) -> Command<Message> {
@@ -32,10 +144,140 @@ impl EditMetadataView {
             EditMetadataMessage::TitleChange(v) => self.song.metadata.title = v,
             EditMetadataMessage::ArtistChange(v) => self.song.metadata.artist = v,
             EditMetadataMessage::AlbumChange(v) => self.song.metadata.album = v,
+            EditMetadataMessage::GenreChange(v) =>
+                self.song.metadata.genre = if v.trim().is_empty() { None } else { Some(v) },
+            // Anything other than digits is ignored, so these always parse unless they're empty
+            EditMetadataMessage::YearChange(v) => {
+                self.year_input = v.chars().filter(char::is_ascii_digit).take(4).collect();
+                self.song.metadata.year = self.year_input.parse().ok();
+            }
+            EditMetadataMessage::TrackChange(v) => {
+                self.track_input = v.chars().filter(char::is_ascii_digit).take(4).collect();
+                self.song.metadata.track = self.track_input.parse().ok();
+            }
+
+            EditMetadataMessage::LyricsLineChange(i, v) => {
+                self.lyrics_lines[i] = v;
+                self.sync_lyrics();
+            }
+            EditMetadataMessage::InsertLyricsLine(i) => {
+                self.lyrics_lines.insert(i, String::new());
+                self.sync_lyrics();
+            }
+            EditMetadataMessage::RemoveLyricsLine(i) => {
+                self.lyrics_lines.remove(i);
+                if self.lyrics_lines.is_empty() {
+                    self.lyrics_lines.push(String::new());
+                }
+                self.sync_lyrics();
+            }
+            EditMetadataMessage::ClearLyrics => {
+                self.lyrics_lines = vec![String::new()];
+                self.sync_lyrics();
+            }
+
+            // Like the other fields, this isn't saved until the edit is applied
+            EditMetadataMessage::SquareAlbumArt => {
+                if let Err(e) = square_album_art(&self.song.path, &mut self.song.metadata) {
+                    return library_error_toast(&e);
+                }
+                self.refresh_album_art();
+            }
+
+            // Like the other fields, this isn't saved until the edit is applied
+            EditMetadataMessage::ChangeCover => {
+                let path = match FileDialog::new()
+                    .add_filter(tr(Key::ImageFilter), &["jpg", "jpeg", "png", "webp", "gif", "bmp"])
+                    .show_open_single_file()
+                    .unwrap()
+                {
+                    Some(path) => path,
+                    None => return Command::none(),
+                };
+
+                let picture = image::open(&path).and_then(|image| {
+                    let image = if image.width() > MAX_COVER_DIMENSION || image.height() > MAX_COVER_DIMENSION {
+                        image.thumbnail(MAX_COVER_DIMENSION, MAX_COVER_DIMENSION)
+                    } else {
+                        image
+                    };
+                    cover_picture(&image)
+                });
+                match picture {
+                    Ok(picture) => {
+                        self.song.metadata.album_art = Some(AlbumArt::Loaded(picture));
+                        self.refresh_album_art();
+                    }
+                    Err(e) => {
+                        let summary = tr_args(Key::ToastCoverUnreadable, &[&path.to_string_lossy()]);
+                        let detail = e.to_string();
+                        return Command::perform(ready(()), move |_| Message::ShowErrorToast(summary.clone(), detail.clone()))
+                    }
+                }
+            }
+
+            // Unlike the other changes, this is saved straight away, since there's nothing to undo
+            EditMetadataMessage::RemoveBrokenAlbumArt => {
+                if let Some(command) = check_song_unchanged(&self.song, self.opened_modified_time, ContentMessage::OpenEditMetadata) {
+                    return command;
+                }
+
+                if let Err(e) = self.song.remove_album_art() {
+                    return library_error_toast(&e);
+                }
+                self.opened_modified_time = self.song.modified_time().ok();
+                self.history = activity_log::entries_for(&self.song);
+                self.refresh_album_art();
+
+                let toast = tr_args(Key::ToastRemovedBrokenAlbumArt, &[&self.song.metadata.title]);
+                return Command::perform(ready(()), move |_| Message::ShowToast(toast.clone()))
+            }
 
             EditMetadataMessage::ApplyMetadataEdit => {
-                self.song.user_edit_metadata().unwrap();
-                return Command::perform(ready(()), |_| ContentMessage::OpenSongList.into())
+                if let Some(command) = check_song_unchanged(&self.song, self.opened_modified_time, ContentMessage::OpenEditMetadata) {
+                    return command;
+                }
+
+                // Stay here on failure, so the edit isn't lost and can be retried
+                if let Err(e) = self.song.user_edit_metadata() {
+                    return library_error_toast(&e);
+                }
+
+                let toast = tr_args(Key::ToastEdited, &[&self.song.metadata.title]);
+                return Command::batch([
+                    Command::perform(ready(()), |_| ContentMessage::OpenSongList.into()),
+                    Command::perform(ready(()), move |_| Message::ShowToast(toast.clone())),
+                ])
+            }
+
+            EditMetadataMessage::LookUpMusicBrainz => {
+                self.musicbrainz_lookup = Some(MusicBrainzLookup::Searching);
+
+                let (title, artist) = (self.song.metadata.title.clone(), self.song.metadata.artist.clone());
+                return Command::perform(
+                    tokio::task::spawn_blocking(move || musicbrainz::search_recordings(&title, &artist)),
+                    |result| EditMetadataMessage::MusicBrainzResults(result.expect("lookup task failed")).into(),
+                )
+            }
+
+            EditMetadataMessage::MusicBrainzResults(result) =>
+                self.musicbrainz_lookup = Some(match result {
+                    Ok(matches) => MusicBrainzLookup::Found(matches),
+                    Err(e) => MusicBrainzLookup::Failed(e),
+                }),
+
+            // Only fills in the fields - nothing is saved until the edit is applied
+            EditMetadataMessage::ApplyMusicBrainzMatch(recording) => {
+                self.song.metadata.title = recording.title;
+                self.song.metadata.artist = recording.artist;
+                if let Some(album) = recording.album {
+                    self.song.metadata.album = album;
+                }
+                if let Some(year) = recording.year {
+                    self.song.metadata.year = Some(year);
+                    self.year_input = year.to_string();
+                }
+                self.musicbrainz_lookup = None;
             }
         }
 
@@ -46,36 +288,190 @@ impl EditMetadataView {
         Column::new()
             .padding(10)
             .spacing(10)
-            .push(Text::new("Edit Metadata").size(28))
+            .push(Text::new(tr(Key::EditMetadataTitle)).size(28))
+            .push_if(self.changed_on_disk, ||
+                Text::new(tr(Key::ChangedOnDisk)).color([0.8, 0.4, 0.0])
+            )
             .push(
                 Row::new()
                     .spacing(10)
                     .align_items(Alignment::Center)
-                    .push_if_let(&self.song.metadata.album_art, |art|
-                        Image::new(Handle::from_memory(art.data.clone()))
+                    .push_if_let(&self.album_art, |art|
+                        Column::new()
+                            .spacing(10)
+                            .align_items(Alignment::Center)
+                            .width(Length::FillPortion(1))
+                            .push(Image::new(art.clone()).width(Length::Fill))
+                            .push(Button::new(Text::new(tr(Key::SquareAlbumArt)))
+                                .on_press(EditMetadataMessage::SquareAlbumArt.into()))
+                            .push(Button::new(Text::new(tr(Key::ChangeCover)))
+                                .on_press(EditMetadataMessage::ChangeCover.into()))
+                    )
+                    .push_if(self.album_art_broken, ||
+                        Column::new()
+                            .spacing(10)
+                            .align_items(Alignment::Center)
+                            .width(Length::FillPortion(1))
+                            .push(Image::new(assets::NO_ALBUM_ART).width(Length::Fill))
+                            .push(Text::new(tr(Key::BrokenAlbumArt)).color([0.8, 0.4, 0.0]))
+                            .push(Button::new(Text::new(tr(Key::RemoveBrokenAlbumArt)))
+                                .on_press(EditMetadataMessage::RemoveBrokenAlbumArt.into()))
+                            .push(Button::new(Text::new(tr(Key::ChangeCover)))
+                                .on_press(EditMetadataMessage::ChangeCover.into()))
+                    )
+                    .push_if_let(&self.album_art_error, |error|
+                        Column::new()
+                            .spacing(10)
+                            .align_items(Alignment::Center)
+                            .width(Length::FillPortion(1))
+                            .push(Image::new(assets::NO_ALBUM_ART).width(Length::Fill))
+                            .push(Text::new(tr_args(Key::AlbumArtUnreadable, &[error])).color([0.8, 0.0, 0.0]))
+                            .push(Button::new(Text::new(tr(Key::ChangeCover)))
+                                .on_press(EditMetadataMessage::ChangeCover.into()))
+                    )
+                    .push_if(self.album_art.is_none() && !self.album_art_broken && self.album_art_error.is_none(), ||
+                        Column::new()
+                            .spacing(10)
+                            .align_items(Alignment::Center)
                             .width(Length::FillPortion(1))
+                            .push(Image::new(assets::NO_ALBUM_ART).width(Length::Fill))
+                            .push(Button::new(Text::new(tr(Key::ChangeCover)))
+                                .on_press(EditMetadataMessage::ChangeCover.into()))
                     )
                     .push(
                         Column::new()
                             .spacing(10)
-                            .push(self.field("Title", &self.song.metadata.title, |v| EditMetadataMessage::TitleChange(v).into()))
-                            .push(self.field("Artist", &self.song.metadata.artist, |v| EditMetadataMessage::ArtistChange(v).into()))
-                            .push(self.field("Album", &self.song.metadata.album, |v| EditMetadataMessage::AlbumChange(v).into()))
+                            .push(Self::field(tr(Key::Title), &self.song.metadata.title, |v| EditMetadataMessage::TitleChange(v).into()))
+                            .push(Self::field(tr(Key::Artist), &self.song.metadata.artist, |v| EditMetadataMessage::ArtistChange(v).into()))
+                            .push(Self::field(tr(Key::Album), &self.song.metadata.album, |v| EditMetadataMessage::AlbumChange(v).into()))
+                            .push(Self::field(tr(Key::Genre), self.song.metadata.genre.as_deref().unwrap_or(""), |v| EditMetadataMessage::GenreChange(v).into()))
+                            .push(Self::field(tr(Key::Year), &self.year_input, |v| EditMetadataMessage::YearChange(v).into()))
+                            .push(Self::field(tr(Key::Track), &self.track_input, |v| EditMetadataMessage::TrackChange(v).into()))
+                            // The channel records where the song came from, so it can't be edited
+                            .push_if_let(&self.song.metadata.uploader, |uploader|
+                                Text::new(tr_args(Key::SongFromUploader, &[uploader])).color([0.3, 0.3, 0.3])
+                            )
+                            // As is the video's title, in case the song's title was worked out wrongly
+                            .push_if_let(&self.song.metadata.video_title, |video_title|
+                                Text::new(tr_args(Key::SongFromVideoTitle, &[video_title])).color([0.3, 0.3, 0.3])
+                            )
+                            .push(self.musicbrainz_lookup())
                             .push(
                                 Row::new()
                                     .spacing(10)
-                                    .push(Button::new(Text::new("Cancel"))
+                                    .push(Button::new(Text::new(tr(Key::Cancel)))
                                         .on_press(ContentMessage::OpenSongList.into()))
-                                    .push(Button::new(Text::new("Apply and save"))
+                                    .push(Button::new(Text::new(tr(Key::ApplyAndSave)))
                                         .on_press(EditMetadataMessage::ApplyMetadataEdit.into()))
                             )
                             .width(Length::FillPortion(2))
                     )
             )
+            .push(self.lyrics_editor())
+            .push(self.history())
+            .into()
+    }
+
+    /// The song's lyrics, with an input for each line. Pressing Enter in a line starts a new one
+    /// after it.
+    fn lyrics_editor(&self) -> Element<Message> {
+        Column::new()
+            .spacing(5)
+            .push(
+                Row::new()
+                    .spacing(10)
+                    .align_items(Alignment::Center)
+                    .push(Text::new(tr(Key::LyricsHeading)).size(22))
+                    .push(Space::with_width(Length::Fill))
+                    .push(Button::new(Text::new(tr(Key::ClearLyrics)).size(16))
+                        .on_press_if(self.song.metadata.lyrics.is_some(), EditMetadataMessage::ClearLyrics.into()))
+            )
+            .push(
+                Scrollable::new(Column::with_children(
+                    self.lyrics_lines.iter().enumerate().map(|(i, line)| {
+                        Row::new()
+                            .spacing(5)
+                            .align_items(Alignment::Center)
+                            .push(
+                                TextInput::new("", line, move |v| EditMetadataMessage::LyricsLineChange(i, v).into())
+                                    .on_submit(EditMetadataMessage::InsertLyricsLine(i + 1).into())
+                                    .padding(3)
+                                    .size(16)
+                            )
+                            .push(Button::new(Text::new("✕").size(16))
+                                .on_press(EditMetadataMessage::RemoveLyricsLine(i).into()))
+                            .into()
+                    }).collect()
+                ).spacing(2))
+                    .height(Length::Units(200))
+            )
+            .push(Text::new(tr(Key::LyricsHint)).size(14).color([0.5, 0.5, 0.5]))
+            .into()
+    }
+
+    /// The entries in the activity log for this song, to explain how it got to be the way it is.
+    fn history(&self) -> Element<Message> {
+        Column::new()
+            .spacing(5)
+            .push(Text::new(tr(Key::ActivityHeading)).size(22))
+            .push_if(self.history.is_empty(), ||
+                Text::new(tr(Key::ActivityNone)).color([0.3, 0.3, 0.3])
+            )
+            .push(Column::with_children(
+                self.history.iter().map(|entry| {
+                    Row::new()
+                        .spacing(10)
+                        .push(Text::new(format_unix_time(entry.unix_time)).size(16).color([0.3, 0.3, 0.3]))
+                        .push(Text::new(describe_activity(entry.activity)).size(16))
+                        .push(Text::new(&entry.summary).size(16).color([0.3, 0.3, 0.3]))
+                        .into()
+                }).collect()
+            ).spacing(5))
+            .into()
+    }
+
+    /// A button to look the song up on MusicBrainz, and the matches found, which can be used to
+    /// fill in the fields.
+    fn musicbrainz_lookup(&self) -> Element<Message> {
+        let searching = matches!(self.musicbrainz_lookup, Some(MusicBrainzLookup::Searching));
+
+        Column::new()
+            .spacing(5)
+            .push(Button::new(Text::new(tr(Key::MusicBrainzLookUp)))
+                .on_press_if(!searching && !self.song.metadata.title.trim().is_empty(), EditMetadataMessage::LookUpMusicBrainz.into()))
+            .push(match &self.musicbrainz_lookup {
+                None => Column::new(),
+                Some(MusicBrainzLookup::Searching) =>
+                    Column::new().push(Text::new(tr(Key::MusicBrainzSearching)).color([0.3, 0.3, 0.3])),
+                Some(MusicBrainzLookup::Failed(e)) =>
+                    Column::new().push(Text::new(describe_lookup_error(e)).color([0.8, 0.0, 0.0])),
+                Some(MusicBrainzLookup::Found(matches)) if matches.is_empty() =>
+                    Column::new().push(Text::new(tr(Key::MusicBrainzNoMatches)).color([0.3, 0.3, 0.3])),
+                Some(MusicBrainzLookup::Found(matches)) =>
+                    Column::with_children(matches.iter().map(|recording| {
+                        let mut details = vec![];
+                        details.extend(recording.album.clone());
+                        details.extend(recording.year.map(|y| y.to_string()));
+
+                        let mut text = tr_args(Key::MusicBrainzMatch, &[&recording.title, &recording.artist]);
+                        if !details.is_empty() {
+                            text.push_str(&format!(" ({})", details.join(", ")));
+                        }
+
+                        Row::new()
+                            .spacing(10)
+                            .align_items(Alignment::Center)
+                            .push(Text::new(text).size(16).width(Length::Fill))
+                            .push(Button::new(Text::new(tr(Key::MusicBrainzUse)).size(16))
+                                .on_press(EditMetadataMessage::ApplyMusicBrainzMatch(recording.clone()).into()))
+                            .into()
+                    }).collect())
+                        .spacing(5),
+            })
             .into()
     }
 
-    pub fn field<'a>(&'a self, label: &str, value: &str, func: impl Fn(String) -> Message + 'a) -> Element<Message> {
+    pub fn field<'a>(label: &str, value: &str, func: impl Fn(String) -> Message + 'a) -> Element<'a, Message> {
         Row::new()
             .spacing(10)
             .align_items(Alignment::Center)
@@ -84,3 +480,28 @@ impl EditMetadataView {
             .into()
     }
 }
+
+fn describe_activity(activity: Activity) -> &'static str {
+    tr(match activity {
+        Activity::Downloaded => Key::ActivityDownloaded,
+        Activity::Cropped => Key::ActivityCropped,
+        Activity::MetadataEdited => Key::ActivityMetadataEdited,
+        Activity::PostProcessed => Key::ActivityPostProcessed,
+        Activity::Restored => Key::ActivityRestored,
+        Activity::Hidden => Key::ActivityHidden,
+        Activity::Unhidden => Key::ActivityUnhidden,
+        Activity::Imported => Key::ActivityImported,
+        Activity::Redownloaded => Key::ActivityRedownloaded,
+        Activity::Approved => Key::ActivityApproved,
+        Activity::PostDownloadCommandRan => Key::ActivityPostDownloadCommandRan,
+    })
+}
+
+/// Describes why a MusicBrainz lookup failed, in terms of what the user can do about it.
+fn describe_lookup_error(error: &LookupError) -> String {
+    match error {
+        LookupError::Network(_) => tr(Key::MusicBrainzErrorNetwork).to_string(),
+        LookupError::RateLimited => tr(Key::MusicBrainzErrorRateLimited).to_string(),
+        LookupError::BadResponse(_) => tr(Key::MusicBrainzErrorBadResponse).to_string(),
+    }
+}
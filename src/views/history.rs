@@ -0,0 +1,72 @@
+use iced::{pure::{Element, widget::{Column, Text, Button, Row, Scrollable, Rule}}, Length, Alignment};
+
+use crate::{Message, download_history::{DownloadHistory, HistoryEntry}, ui_util::{ElementContainerExtensions, format_unix_time}, i18n::{Key, tr, tr_args}};
+
+use super::{content::ContentMessage, download::DownloadMessage};
+
+/// Lists every download made from CrossPlay, newest first, so that songs which have since been
+/// deleted can be downloaded again.
+pub struct HistoryView {
+    history: DownloadHistory,
+
+    /// Why the history couldn't be loaded, if it couldn't. The view is then left empty.
+    load_error: Option<String>,
+}
+
+impl HistoryView {
+    pub fn new() -> Self {
+        match DownloadHistory::load() {
+            Ok(history) => Self { history, load_error: None },
+            Err(e) => Self { history: DownloadHistory::default(), load_error: Some(e.to_string()) },
+        }
+    }
+
+    pub fn view(&self) -> Element<Message> {
+        Scrollable::new(
+            Column::new()
+                .padding(10)
+                .spacing(10)
+                .push(Text::new(tr(Key::HistoryTitle)).size(28))
+                .push_if_let(&self.load_error, |e|
+                    Text::new(tr_args(Key::HistoryLoadFailed, &[e])).color([0.8, 0.0, 0.0])
+                )
+                .push_if(self.history.entries.is_empty() && self.load_error.is_none(), ||
+                    Text::new(tr(Key::HistoryEmpty)).color([0.3, 0.3, 0.3])
+                )
+                .push(Column::with_children(
+                    self.history.entries.iter()
+                        .rev()
+                        .map(|entry| Some(Self::entry_row(entry)))
+                        .intersperse_with(|| None)
+                        .map(|row| row.unwrap_or_else(|| Rule::horizontal(10).into()))
+                        .collect()
+                ))
+                .push(Button::new(Text::new(tr(Key::Back)))
+                    .on_press(ContentMessage::OpenSongList.into()))
+        ).into()
+    }
+
+    fn entry_row(entry: &HistoryEntry) -> Element<Message> {
+        Row::new()
+            .spacing(10)
+            .align_items(Alignment::Center)
+            .push(
+                Column::new()
+                    .width(Length::Fill)
+                    .push(Text::new(entry.title.clone()))
+                    .push(Text::new(entry.id.clone()).size(16).color([0.3, 0.3, 0.3]))
+            )
+            .push(Text::new(format_unix_time(entry.unix_time)).color([0.3, 0.3, 0.3]))
+            .push(
+                if entry.succeeded {
+                    Text::new(tr(Key::HistoryDownloaded))
+                } else {
+                    Text::new(tr(Key::HistoryFailed)).color([0.8, 0.0, 0.0])
+                }
+                    .width(Length::Units(120))
+            )
+            .push(Button::new(Text::new(tr(Key::DownloadAgain)))
+                .on_press(DownloadMessage::DownloadAgain(entry.id.clone()).into()))
+            .into()
+    }
+}
@@ -4,3 +4,7 @@ pub mod content;
 pub mod song_list;
 pub mod crop;
 pub mod edit_metadata;
+pub mod batch_edit_metadata;
+pub mod settings;
+pub mod now_playing;
+pub mod history;
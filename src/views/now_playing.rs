@@ -0,0 +1,138 @@
+use std::time::Duration;
+
+use iced::{Command, Subscription, time, pure::{Element, widget::{Column, Button, Text, Row, Container}}, Alignment, Length, container, Background, Space};
+use iced_video_player::VideoPlayer;
+use url::Url;
+
+use crate::{library::Song, Message, preflight, ui_util::{ButtonExtensions, ElementContainerExtensions, ContainerStyleSheet, format_millis}, i18n::{Key, tr, tr_args}};
+
+use super::content::ContentMessage;
+
+#[derive(Debug, Clone)]
+pub enum NowPlayingMessage {
+    PlayPause,
+    Stop,
+    TickPlayer,
+}
+
+impl From<NowPlayingMessage> for Message {
+    fn from(npm: NowPlayingMessage) -> Self { ContentMessage::NowPlayingMessage(npm).into() }
+}
+
+/// A bar along the bottom of the window, which plays a song from the library without leaving
+/// CrossPlay.
+pub struct NowPlayingView {
+    song: Song,
+
+    /// The player for the song, unless it couldn't be opened.
+    player: Option<VideoPlayer>,
+
+    /// Why playback went wrong, shown in the bar in place of the controls which don't work.
+    error: Option<String>,
+}
+
+impl NowPlayingView {
+    /// Starts playing a song from the beginning. If the song can't be opened, the bar shows why
+    /// instead of playing anything.
+    pub fn new(song: Song) -> Self {
+        let player = VideoPlayer::new(
+            &Url::from_file_path(song.path.clone()).unwrap(),
+            false,
+        );
+
+        match player {
+            Ok(player) => Self { song, player: Some(player), error: None },
+            Err(e) => Self { song, player: None, error: Some(tr_args(Key::PlaybackFailed, &[&e])) },
+        }
+    }
+
+    pub fn song(&self) -> &Song {
+        &self.song
+    }
+
+    pub fn position(&self) -> Duration {
+        self.player.as_ref().map_or(Duration::ZERO, VideoPlayer::position)
+    }
+
+    pub fn duration(&self) -> Duration {
+        self.player.as_ref().map_or(Duration::ZERO, VideoPlayer::duration)
+    }
+
+    pub fn set_paused(&mut self, paused: bool) {
+        if let Some(player) = &mut self.player {
+            player.set_paused(paused);
+        }
+    }
+
+    /// Moves playback to a position. If that fails, playback carries on from wherever it was, and
+    /// the bar shows why.
+    pub fn seek(&mut self, position: Duration) {
+        if let Some(player) = &mut self.player {
+            if let Err(e) = player.seek(position) {
+                self.error = Some(tr_args(Key::SeekFailed, &[&e]));
+            }
+        }
+    }
+
+    pub fn update(&mut self, message: NowPlayingMessage) -> Command<Message> {
+        match message {
+            NowPlayingMessage::PlayPause => if let Some(player) = &mut self.player {
+                player.set_paused(!player.paused());
+            },
+
+            // Nothing to do, this just causes a redraw so that the position updates
+            NowPlayingMessage::TickPlayer => (),
+
+            // Handled by the content view, which owns this
+            NowPlayingMessage::Stop => unreachable!(),
+        }
+
+        Command::none()
+    }
+
+    pub fn view(&self) -> Element<Message> {
+        Container::new(
+            Row::new()
+                .padding(10)
+                .spacing(10)
+                .align_items(Alignment::Center)
+                .push_if_let(&self.player, |player| player.frame_view()) // Actually invisible
+                .push(
+                    Column::new()
+                        .push(Text::new(self.song.metadata.title.clone()))
+                        .push(Text::new(self.song.metadata.artist.clone()).color([0.3, 0.3, 0.3]))
+                )
+                .push(Space::with_width(Length::Fill))
+                .push_if_let(&self.error, |error| Text::new(error).color([0.8, 0.0, 0.0]))
+                .push_if_let(&self.player, |player| Text::new(format!(
+                    "{} / {}",
+                    format_millis(player.position().as_millis() as f64),
+                    format_millis(player.duration().as_millis() as f64),
+                )))
+                .push_if_let(&self.player, |player|
+                    Button::new(Text::new(tr(if player.paused() { Key::Play } else { Key::Pause })))
+                        .on_press(NowPlayingMessage::PlayPause.into())
+                )
+                .push(Button::new(Text::new(tr(Key::Edit)))
+                    .on_press(ContentMessage::OpenEditMetadata(self.song.clone()).into()))
+                .push(Button::new(Text::new(tr(Key::Crop)))
+                    .on_press_if(!self.song.metadata.is_cropped && preflight::ffmpeg_found(), ContentMessage::OpenCrop(self.song.clone()).into()))
+                .push(Button::new(Text::new(tr(Key::Stop)))
+                    .on_press(NowPlayingMessage::Stop.into()))
+        )
+            .width(Length::Fill)
+            .style(ContainerStyleSheet(container::Style {
+                background: Some(Background::Color([0.9, 0.9, 0.9].into())),
+                ..Default::default()
+            }))
+            .into()
+    }
+
+    pub fn subscription(&self) -> Subscription<Message> {
+        if self.player.as_ref().map_or(true, VideoPlayer::paused) {
+            Subscription::none()
+        } else {
+            time::every(Duration::from_millis(250)).map(|_| NowPlayingMessage::TickPlayer.into())
+        }
+    }
+}
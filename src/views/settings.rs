@@ -0,0 +1,976 @@
+use std::{sync::{Arc, RwLock}, time::Duration};
+
+use iced::{Command, pure::{Element, widget::{Column, Text, Button, Row, TextInput, Scrollable, Checkbox, PickList}}, Length, Alignment};
+use native_dialog::{FileDialog, MessageDialog, MessageType};
+
+use crate::{Message, settings::{Settings, CropTemplate, RowAction, SplitLayout, MetadataLookupFailureAction, LibraryCheckInterval, AudioFormat, AudioQuality, AlbumArtSize, MissingFfmpegAction, ReviewRule, PostProcessStepKind}, ui_util::{ElementContainerExtensions, ButtonExtensions, format_size, format_duration, format_millis, describe_extra_args_error, describe_library_error}, library::{Library, OriginalStorage}, i18n::{self, Language, Key, tr, tr_args, tr_count}, bandwidth::BandwidthUsage, download_stats::DownloadStats, youtube::{Downloader, is_valid_rate_limit, parse_extra_args, split_args, join_extra_args}, preflight, hooks::{run_post_download_command, HookError, POST_DOWNLOAD_COMMAND_TIMEOUT}};
+
+use super::content::ContentMessage;
+
+#[derive(Debug, Clone)]
+pub enum SettingsMessage {
+    ChangeLanguage(Language),
+
+    CropTemplateNameChange(usize, String),
+    RenameCropTemplate(usize),
+    DeleteCropTemplate(usize),
+    ChangeCropNudge(u64),
+
+    ToggleDeduplicateOriginals(bool),
+    ToggleStaging(bool),
+    ToggleScanRecursively(bool),
+    ChangeLibraryCheckInterval(LibraryCheckInterval),
+
+    ChangeMetadataLookupFailureAction(MetadataLookupFailureAction),
+    ToggleParseArtistFromTitle(bool),
+    ChangeAudioFormat(AudioFormat),
+    ChangeAudioQuality(AudioQuality),
+    ToggleSplitChapters(bool),
+    ToggleEmbedLyrics(bool),
+    ChangeAlbumArtSize(AlbumArtSize),
+    ChangeMissingFfmpegAction(MissingFfmpegAction),
+    ChangeSplitLayout(SplitLayout),
+    ChangeMaxConcurrentDownloads(usize),
+    DownloaderBinaryChange(String),
+    ApplyDownloaderBinary,
+    ChooseDownloaderBinary,
+    RateLimitChange(String),
+    ApplyRateLimit,
+    ExtraArgsChange(String),
+    ApplyExtraArgs,
+    TogglePostProcessStep(PostProcessStepKind, bool),
+    /// Moves a step of the post-processing pipeline one place earlier, or later if `earlier` is
+    /// false.
+    MovePostProcessStep(PostProcessStepKind, bool),
+    ChangeLoudnessTarget(i32),
+    TogglePostDownloadCommand(bool),
+    PostDownloadCommandChange(String),
+    ApplyPostDownloadCommand,
+    /// Runs the post-download command as it's currently entered on the most recently downloaded
+    /// song, to check that it works.
+    TestPostDownloadCommand,
+    PostDownloadCommandTested(Result<String, HookError>),
+
+    ChangeRowDoubleClickAction(RowAction),
+    ChangeRowMiddleClickAction(RowAction),
+    ToggleReviewRule(ReviewRule, bool),
+
+    ResetBandwidthUsage,
+}
+
+impl From<SettingsMessage> for Message {
+    fn from(sm: SettingsMessage) -> Self { ContentMessage::SettingsMessage(sm).into() }
+}
+
+/// The choices offered for how many downloads can run at once.
+const MAX_CONCURRENT_DOWNLOADS_CHOICES: [usize; 6] = [1, 2, 3, 4, 6, 8];
+
+/// The choices offered for how far the arrow keys move the playhead in the crop view, in
+/// milliseconds.
+const CROP_NUDGE_CHOICES: [u64; 6] = [10, 50, 100, 250, 500, 1000];
+
+/// The choices offered for the loudness songs are normalized to, in LUFS. -14 is what most
+/// streaming services aim for, and -23 is the broadcast standard.
+const LOUDNESS_TARGET_CHOICES: [i32; 5] = [-23, -18, -16, -14, -11];
+
+/// A full-window view for settings which don't fit into the settings pick list.
+pub struct SettingsView {
+    library: Arc<RwLock<Library>>,
+    settings: Arc<RwLock<Settings>>,
+
+    /// The name currently entered for each crop template, which may not have been applied yet.
+    crop_template_names: Vec<String>,
+
+    /// The downloader binary currently entered, which may not have been applied yet.
+    downloader_binary: String,
+
+    /// The download rate limit currently entered, which may not have been applied yet. Empty for
+    /// no limit.
+    rate_limit: String,
+
+    /// The extra downloader arguments currently entered, which may not have been applied yet.
+    extra_args: String,
+
+    /// The post-download command currently entered, which may not have been applied yet.
+    post_download_command: String,
+
+    /// Whether the post-download command is being test-run.
+    testing_post_download_command: bool,
+
+    bandwidth_usage: BandwidthUsage,
+    download_stats: DownloadStats,
+}
+
+impl SettingsView {
+    pub fn new(library: Arc<RwLock<Library>>, settings: Arc<RwLock<Settings>>) -> Self {
+        let downloader_binary = settings.read().unwrap().downloader_binary.clone();
+        let rate_limit = settings.read().unwrap().download_rate_limit.clone().unwrap_or_default();
+        let extra_args = join_extra_args(&settings.read().unwrap().extra_downloader_args);
+        let post_download_command = settings.read().unwrap().post_download_command.clone().unwrap_or_default();
+
+        let mut result = Self {
+            library,
+            settings,
+            crop_template_names: vec![],
+            downloader_binary,
+            rate_limit,
+            extra_args,
+            post_download_command,
+            testing_post_download_command: false,
+            bandwidth_usage: BandwidthUsage::load().unwrap_or_else(|e| {
+                println!("[Bandwidth] Couldn't load usage, showing none: {}", e);
+                BandwidthUsage::empty()
+            }),
+            download_stats: DownloadStats::load().unwrap_or_else(|e| {
+                println!("[Stats] Couldn't load download stats, showing none: {}", e);
+                DownloadStats::default()
+            }),
+        };
+        result.reload_crop_template_names();
+        result
+    }
+
+    pub fn update(&mut self, message: SettingsMessage) -> Command<Message> {
+        match message {
+            SettingsMessage::ChangeLanguage(language) => {
+                let mut settings = self.settings.write().unwrap();
+                settings.language = language;
+                settings.save();
+
+                i18n::set_language(language);
+            }
+
+            SettingsMessage::CropTemplateNameChange(i, name) => self.crop_template_names[i] = name,
+
+            SettingsMessage::RenameCropTemplate(i) => {
+                let mut settings = self.settings.write().unwrap();
+                let new_name = self.crop_template_names[i].trim().to_string();
+
+                if settings.crop_templates.iter().enumerate().any(|(j, t)| i != j && t.name == new_name) {
+                    MessageDialog::new()
+                        .set_title(tr(Key::CropTemplateNameTakenTitle))
+                        .set_text(&tr_args(Key::CropTemplateNameTakenBody, &[&new_name]))
+                        .set_type(MessageType::Error)
+                        .show_alert()
+                        .unwrap();
+                    return Command::none();
+                }
+
+                settings.crop_templates[i].name = new_name;
+                settings.save();
+                drop(settings);
+
+                self.reload_crop_template_names();
+            }
+
+            SettingsMessage::DeleteCropTemplate(i) => {
+                let mut settings = self.settings.write().unwrap();
+                let confirmation = MessageDialog::new()
+                    .set_title(tr(Key::DeleteCropTemplateTitle))
+                    .set_text(&tr_args(Key::DeleteCropTemplateBody, &[&settings.crop_templates[i].name]))
+                    .set_type(MessageType::Warning)
+                    .show_confirm()
+                    .unwrap();
+
+                if confirmation {
+                    settings.crop_templates.remove(i);
+                    settings.save();
+                    drop(settings);
+
+                    self.reload_crop_template_names();
+                }
+            }
+
+            SettingsMessage::ToggleDeduplicateOriginals(enabled) => {
+                let mut settings = self.settings.write().unwrap();
+                settings.deduplicate_originals = enabled;
+                settings.save();
+
+                // Takes effect for songs loaded from now on, which includes the reload when leaving
+                // this view
+                self.library.write().unwrap().original_storage = if enabled {
+                    OriginalStorage::Deduplicated
+                } else {
+                    OriginalStorage::Sidecar
+                };
+            }
+
+            SettingsMessage::ToggleStaging(enabled) => {
+                let mut settings = self.settings.write().unwrap();
+                settings.staging_enabled = enabled;
+                settings.save();
+                drop(settings);
+
+                // Songs left in staging would be stuck there without the section to approve them
+                // from, so offer to let them all in now
+                if !enabled {
+                    let staged = self.library.read().unwrap().staged_songs().cloned().collect::<Vec<_>>();
+                    if staged.is_empty() {
+                        return Command::none();
+                    }
+
+                    let confirmation = MessageDialog::new()
+                        .set_title(tr(Key::ApproveStagedTitle))
+                        .set_text(&tr_count(Key::ApproveStagedBody, staged.len(), &[]))
+                        .set_type(MessageType::Info)
+                        .show_confirm()
+                        .unwrap();
+                    if !confirmation {
+                        return Command::none();
+                    }
+
+                    let mut failures = vec![];
+                    for song in staged {
+                        let title = song.metadata.title.clone();
+                        if let Err(e) = song.approve() {
+                            failures.push((title, e));
+                        }
+                    }
+                    // The song list picks up where they went with the reload when leaving this view
+
+                    if !failures.is_empty() {
+                        let detail = failures.iter()
+                            .map(|(title, e)| tr_args(Key::ApproveStagedFailure, &[title, &describe_library_error(e)]))
+                            .collect::<Vec<_>>()
+                            .join("\n");
+                        MessageDialog::new()
+                            .set_title(tr(Key::ApproveStagedFailedTitle))
+                            .set_text(&tr_args(Key::ApproveStagedFailedBody, &[&detail]))
+                            .set_type(MessageType::Error)
+                            .show_alert()
+                            .unwrap();
+                    }
+                }
+            }
+
+            SettingsMessage::ToggleScanRecursively(enabled) => {
+                let mut settings = self.settings.write().unwrap();
+                settings.scan_recursively = enabled;
+                settings.save();
+
+                // Like storing originals, this takes effect with the reload when leaving this view
+                self.library.write().unwrap().scan_recursively = enabled;
+            }
+
+            SettingsMessage::ChangeLibraryCheckInterval(interval) => {
+                let mut settings = self.settings.write().unwrap();
+                settings.library_check_interval = interval;
+                settings.save();
+            }
+
+            SettingsMessage::ChangeMetadataLookupFailureAction(action) => {
+                let mut settings = self.settings.write().unwrap();
+                settings.metadata_lookup_failure_action = action;
+                settings.save();
+            }
+
+            SettingsMessage::ToggleParseArtistFromTitle(enabled) => {
+                let mut settings = self.settings.write().unwrap();
+                settings.parse_artist_from_title = enabled;
+                settings.save();
+            }
+
+            SettingsMessage::ChangeAudioFormat(format) => {
+                let mut settings = self.settings.write().unwrap();
+                settings.audio_format = format;
+                settings.save();
+            }
+
+            SettingsMessage::ChangeAudioQuality(quality) => {
+                let mut settings = self.settings.write().unwrap();
+                settings.audio_quality = quality;
+                settings.save();
+            }
+
+            SettingsMessage::ToggleSplitChapters(enabled) => {
+                let mut settings = self.settings.write().unwrap();
+                settings.split_chapters = enabled;
+                settings.save();
+            }
+
+            SettingsMessage::ToggleEmbedLyrics(enabled) => {
+                let mut settings = self.settings.write().unwrap();
+                settings.embed_lyrics = enabled;
+                settings.save();
+            }
+
+            SettingsMessage::ChangeAlbumArtSize(size) => {
+                let mut settings = self.settings.write().unwrap();
+                settings.album_art_size = size;
+                settings.save();
+            }
+
+            SettingsMessage::ChangeMissingFfmpegAction(action) => {
+                let mut settings = self.settings.write().unwrap();
+                settings.missing_ffmpeg_action = action;
+                settings.save();
+            }
+
+            SettingsMessage::ChangeSplitLayout(layout) => {
+                let mut settings = self.settings.write().unwrap();
+                settings.split_layout = layout;
+                settings.save();
+            }
+
+            SettingsMessage::ChangeCropNudge(millis) => {
+                let mut settings = self.settings.write().unwrap();
+                settings.crop_nudge_millis = millis;
+                settings.save();
+            }
+
+            SettingsMessage::ChangeMaxConcurrentDownloads(max) => {
+                let mut settings = self.settings.write().unwrap();
+                settings.max_concurrent_downloads = max;
+                settings.save();
+            }
+
+            SettingsMessage::ChangeLoudnessTarget(target) => {
+                let mut settings = self.settings.write().unwrap();
+                settings.loudness_target = target;
+                settings.save();
+            }
+
+            SettingsMessage::DownloaderBinaryChange(binary) => self.downloader_binary = binary,
+
+            SettingsMessage::ApplyDownloaderBinary => {
+                if self.downloader_binary.trim().is_empty() {
+                    return Command::none();
+                }
+
+                let mut settings = self.settings.write().unwrap();
+                settings.downloader_binary = self.downloader_binary.trim().to_string();
+                settings.save();
+                self.downloader_binary = settings.downloader_binary.clone();
+            }
+
+            SettingsMessage::ChooseDownloaderBinary => {
+                let path = match FileDialog::new().show_open_single_file().unwrap() {
+                    Some(path) => path,
+                    None => return Command::none(),
+                };
+
+                if Downloader::at_path(&path).is_none() {
+                    MessageDialog::new()
+                        .set_title(tr(Key::DownloaderUnusableTitle))
+                        .set_text(&tr_args(Key::DownloaderUnusableBody, &[&path.to_string_lossy()]))
+                        .set_type(MessageType::Error)
+                        .show_alert()
+                        .unwrap();
+                    return Command::none();
+                }
+
+                let mut settings = self.settings.write().unwrap();
+                settings.downloader_binary = path.to_string_lossy().into_owned();
+                settings.save();
+                preflight::check(&settings);
+                self.downloader_binary = settings.downloader_binary.clone();
+            }
+
+            SettingsMessage::RateLimitChange(limit) => self.rate_limit = limit,
+
+            SettingsMessage::ApplyRateLimit => {
+                let limit = self.rate_limit.trim();
+                if !limit.is_empty() && !is_valid_rate_limit(limit) {
+                    return Command::none();
+                }
+
+                let mut settings = self.settings.write().unwrap();
+                settings.download_rate_limit = Some(limit.to_uppercase()).filter(|l| !l.is_empty());
+                settings.save();
+                self.rate_limit = settings.download_rate_limit.clone().unwrap_or_default();
+            }
+
+            SettingsMessage::ExtraArgsChange(args) => self.extra_args = args,
+
+            SettingsMessage::ApplyExtraArgs => {
+                let args = match parse_extra_args(&self.extra_args) {
+                    Ok(args) => args,
+                    Err(_) => return Command::none(),
+                };
+
+                let mut settings = self.settings.write().unwrap();
+                settings.extra_downloader_args = args;
+                settings.save();
+                self.extra_args = join_extra_args(&settings.extra_downloader_args);
+            }
+
+            SettingsMessage::TogglePostDownloadCommand(enabled) => {
+                let mut settings = self.settings.write().unwrap();
+                settings.post_download_command_enabled = enabled;
+                settings.save();
+            }
+
+            SettingsMessage::PostDownloadCommandChange(command) => self.post_download_command = command,
+
+            SettingsMessage::ApplyPostDownloadCommand => {
+                if split_args(&self.post_download_command).is_err() {
+                    return Command::none();
+                }
+
+                let mut settings = self.settings.write().unwrap();
+                settings.post_download_command = Some(self.post_download_command.trim().to_string()).filter(|c| !c.is_empty());
+                settings.save();
+                self.post_download_command = settings.post_download_command.clone().unwrap_or_default();
+            }
+
+            SettingsMessage::TestPostDownloadCommand => {
+                let newest = self.library.read().unwrap().songs()
+                    .max_by_key(|s| s.metadata.download_unix_time)
+                    .map(|s| (s.metadata.title.clone(), s.path.clone(), s.metadata.youtube_id.clone()));
+                let (title, path, id) = match newest {
+                    Some(newest) => newest,
+                    None => return Command::none(),
+                };
+
+                // The command does whatever it would do for real, so make sure that's expected
+                let confirmation = MessageDialog::new()
+                    .set_title(tr(Key::TestCommandTitle))
+                    .set_text(&tr_args(Key::TestCommandBody, &[&title]))
+                    .set_type(MessageType::Info)
+                    .show_confirm()
+                    .unwrap();
+                if !confirmation {
+                    return Command::none();
+                }
+
+                self.testing_post_download_command = true;
+                let command = self.post_download_command.trim().to_string();
+                return Command::perform(
+                    async move { run_post_download_command(&command, &path, &id).await },
+                    |r| SettingsMessage::PostDownloadCommandTested(r).into(),
+                )
+            }
+
+            SettingsMessage::PostDownloadCommandTested(result) => {
+                self.testing_post_download_command = false;
+
+                let (title, text, kind) = match result {
+                    Ok(output) if output.is_empty() =>
+                        (tr(Key::CommandSucceededTitle), tr(Key::CommandSucceededSilent).to_string(), MessageType::Info),
+                    Ok(output) =>
+                        (tr(Key::CommandSucceededTitle), tr_args(Key::CommandSucceededOutput, &[&output]), MessageType::Info),
+                    Err(e) =>
+                        (tr(Key::CommandFailedTitle), tr_args(Key::CommandFailedBody, &[&e]), MessageType::Error),
+                };
+                MessageDialog::new()
+                    .set_title(title)
+                    .set_text(&text)
+                    .set_type(kind)
+                    .show_alert()
+                    .unwrap();
+            }
+
+            SettingsMessage::ChangeRowDoubleClickAction(action) => {
+                let mut settings = self.settings.write().unwrap();
+                settings.row_double_click_action = action;
+                settings.save();
+            }
+
+            SettingsMessage::ChangeRowMiddleClickAction(action) => {
+                let mut settings = self.settings.write().unwrap();
+                settings.row_middle_click_action = action;
+                settings.save();
+            }
+
+            SettingsMessage::TogglePostProcessStep(step, enabled) => {
+                let mut settings = self.settings.write().unwrap();
+                settings.post_process_pipeline.retain(|s| *s != step);
+                if enabled {
+                    settings.post_process_pipeline.push(step);
+                }
+                settings.save();
+            }
+
+            SettingsMessage::MovePostProcessStep(step, earlier) => {
+                let mut settings = self.settings.write().unwrap();
+                let pipeline = &mut settings.post_process_pipeline;
+                if let Some(index) = pipeline.iter().position(|s| *s == step) {
+                    let other_index = if earlier { index.checked_sub(1) } else { Some(index + 1) };
+                    if let Some(other_index) = other_index.filter(|i| *i < pipeline.len()) {
+                        pipeline.swap(index, other_index);
+                    }
+                }
+                settings.save();
+            }
+
+            SettingsMessage::ToggleReviewRule(rule, enabled) => {
+                let mut settings = self.settings.write().unwrap();
+                settings.review_rules.retain(|r| *r != rule);
+                if enabled {
+                    settings.review_rules.push(rule);
+                }
+                settings.save();
+            }
+
+            SettingsMessage::ResetBandwidthUsage => {
+                let confirmation = MessageDialog::new()
+                    .set_title(tr(Key::ResetBandwidthTitle))
+                    .set_text(tr(Key::ResetBandwidthBody))
+                    .set_type(MessageType::Warning)
+                    .show_confirm()
+                    .unwrap();
+
+                if confirmation {
+                    match BandwidthUsage::reset() {
+                        Ok(()) => self.bandwidth_usage = BandwidthUsage::empty(),
+                        Err(e) => {
+                            MessageDialog::new()
+                                .set_title(tr(Key::ResetBandwidthFailedTitle))
+                                .set_text(&tr_args(Key::ResetBandwidthFailedBody, &[&e]))
+                                .set_type(MessageType::Error)
+                                .show_alert()
+                                .unwrap();
+                        }
+                    }
+                }
+            }
+        }
+
+        Command::none()
+    }
+
+    pub fn view(&self) -> Element<Message> {
+        Scrollable::new(
+            Column::new()
+                .padding(10)
+                .spacing(10)
+                .push(Text::new(tr(Key::SettingsMenu)).size(28))
+                .push(self.labelled(
+                    tr(Key::LanguageLabel),
+                    PickList::new(
+                        &Language::ALL[..],
+                        Some(self.settings.read().unwrap().language),
+                        |l| SettingsMessage::ChangeLanguage(l).into(),
+                    ),
+                ))
+                .push(self.crop_templates())
+                .push(self.row_actions())
+                .push(self.downloads())
+                .push(self.storage())
+                .push(self.statistics())
+                .push(Button::new(Text::new(tr(Key::Back)))
+                    .on_press(ContentMessage::OpenSongList.into()))
+        ).into()
+    }
+
+    fn crop_templates(&self) -> Element<Message> {
+        let settings = self.settings.read().unwrap();
+
+        Column::new()
+            .spacing(10)
+            .push(Text::new(tr(Key::CropTemplatesHeading)).size(22))
+            .push_if(settings.crop_templates.is_empty(), ||
+                Text::new(tr(Key::CropTemplatesEmpty))
+                    .color([0.3, 0.3, 0.3])
+            )
+            .push(Column::with_children(
+                settings.crop_templates.iter().zip(&self.crop_template_names).enumerate().map(|(i, (template, name))| {
+                    Row::new()
+                        .spacing(10)
+                        .align_items(Alignment::Center)
+                        .push(
+                            TextInput::new(tr(Key::TemplateNamePlaceholder), name, move |v| SettingsMessage::CropTemplateNameChange(i, v).into())
+                                .padding(5)
+                                .width(Length::FillPortion(2))
+                        )
+                        .push(Text::new(Self::describe_crop_template(template)).width(Length::FillPortion(2)))
+                        .push(Button::new(Text::new(tr(Key::Rename)))
+                            .on_press_if(
+                                !name.trim().is_empty() && name.trim() != template.name,
+                                SettingsMessage::RenameCropTemplate(i).into(),
+                            ))
+                        .push(Button::new(Text::new(tr(Key::Delete)))
+                            .on_press(SettingsMessage::DeleteCropTemplate(i).into()))
+                        .into()
+                }).collect()
+            ).spacing(10))
+            .push(self.labelled(
+                tr(Key::CropNudgeLabel),
+                PickList::new(
+                    &CROP_NUDGE_CHOICES[..],
+                    Some(settings.crop_nudge_millis),
+                    |m| SettingsMessage::ChangeCropNudge(m).into(),
+                ),
+            ))
+            .into()
+    }
+
+    fn row_actions(&self) -> Element<Message> {
+        let settings = self.settings.read().unwrap();
+
+        Column::new()
+            .spacing(10)
+            .push(Text::new(tr(Key::SongListHeading)).size(22))
+            .push(self.labelled(
+                tr(Key::DoubleClickLabel),
+                PickList::new(
+                    &RowAction::ALL[..],
+                    Some(settings.row_double_click_action),
+                    |a| SettingsMessage::ChangeRowDoubleClickAction(a).into(),
+                ),
+            ))
+            .push(self.labelled(
+                tr(Key::MiddleClickLabel),
+                PickList::new(
+                    &RowAction::ALL[..],
+                    Some(settings.row_middle_click_action),
+                    |a| SettingsMessage::ChangeRowMiddleClickAction(a).into(),
+                ),
+            ))
+            .push(self.labelled(
+                tr(Key::SplitLayoutLabel),
+                PickList::new(
+                    &SplitLayout::ALL[..],
+                    Some(settings.split_layout),
+                    |l| SettingsMessage::ChangeSplitLayout(l).into(),
+                ),
+            ))
+            .push(Text::new(tr(Key::ReviewRulesLabel)))
+            .push(Column::with_children(
+                ReviewRule::ALL.iter().map(|rule| {
+                    let rule = *rule;
+                    Checkbox::new(
+                        settings.review_rules.contains(&rule),
+                        rule.to_string(),
+                        move |v| SettingsMessage::ToggleReviewRule(rule, v).into(),
+                    ).into()
+                }).collect()
+            ).spacing(5))
+            .into()
+    }
+
+    fn downloads(&self) -> Element<Message> {
+        let settings = self.settings.read().unwrap();
+        let rate_limit_valid = self.rate_limit.trim().is_empty() || is_valid_rate_limit(self.rate_limit.trim());
+        let extra_args = parse_extra_args(&self.extra_args);
+        let post_download_command = split_args(&self.post_download_command);
+        let has_songs = self.library.read().unwrap().songs().next().is_some();
+
+        Column::new()
+            .spacing(10)
+            .push(Text::new(tr(Key::DownloadsHeading)).size(22))
+            .push(self.labelled(
+                tr(Key::AudioFormatLabel),
+                PickList::new(
+                    &AudioFormat::ALL[..],
+                    Some(settings.audio_format),
+                    |f| SettingsMessage::ChangeAudioFormat(f).into(),
+                ),
+            ))
+            .push_if(!settings.audio_format.supports_embedded_art(), ||
+                Text::new(tr(Key::OpusNoAlbumArtHint))
+                    .color([0.3, 0.3, 0.3])
+            )
+            .push(self.labelled(
+                tr(Key::AudioQualityLabel),
+                PickList::new(
+                    &AudioQuality::ALL[..],
+                    Some(settings.audio_quality),
+                    |q| SettingsMessage::ChangeAudioQuality(q).into(),
+                ),
+            ))
+            .push_if(settings.audio_format.is_lossless(), ||
+                Text::new(tr(Key::LosslessQualityHint))
+                    .color([0.3, 0.3, 0.3])
+            )
+            .push(self.labelled(
+                tr(Key::AlbumArtSizeLabel),
+                PickList::new(
+                    &AlbumArtSize::ALL[..],
+                    Some(settings.album_art_size),
+                    |s| SettingsMessage::ChangeAlbumArtSize(s).into(),
+                ),
+            ))
+            .push(Checkbox::new(
+                settings.split_chapters,
+                tr(Key::SplitChaptersSetting),
+                |v| SettingsMessage::ToggleSplitChapters(v).into(),
+            ))
+            .push(Checkbox::new(
+                settings.embed_lyrics,
+                tr(Key::EmbedLyricsSetting),
+                |v| SettingsMessage::ToggleEmbedLyrics(v).into(),
+            ))
+            .push(self.labelled(
+                tr(Key::MissingFfmpegLabel),
+                PickList::new(
+                    &MissingFfmpegAction::ALL[..],
+                    Some(settings.missing_ffmpeg_action),
+                    |a| SettingsMessage::ChangeMissingFfmpegAction(a).into(),
+                ),
+            ))
+            .push_if(settings.missing_ffmpeg_action == MissingFfmpegAction::DownloadUnconverted, ||
+                Text::new(tr(Key::DownloadUnconvertedHint))
+                    .color([0.3, 0.3, 0.3])
+            )
+            .push(self.labelled(
+                tr(Key::MaxConcurrentDownloadsLabel),
+                PickList::new(
+                    &MAX_CONCURRENT_DOWNLOADS_CHOICES[..],
+                    Some(settings.max_concurrent_downloads),
+                    |m| SettingsMessage::ChangeMaxConcurrentDownloads(m).into(),
+                ),
+            ))
+            .push(self.labelled(
+                tr(Key::MetadataFailureLabel),
+                PickList::new(
+                    &MetadataLookupFailureAction::ALL[..],
+                    Some(settings.metadata_lookup_failure_action),
+                    |a| SettingsMessage::ChangeMetadataLookupFailureAction(a).into(),
+                ),
+            ))
+            .push(Checkbox::new(
+                settings.parse_artist_from_title,
+                tr(Key::ParseArtistSetting),
+                |v| SettingsMessage::ToggleParseArtistFromTitle(v).into(),
+            ))
+            .push(self.labelled(
+                tr(Key::DownloaderProgramLabel),
+                Row::new()
+                    .spacing(10)
+                    .push(
+                        TextInput::new("yt-dlp", &self.downloader_binary, |v| SettingsMessage::DownloaderBinaryChange(v).into())
+                            .padding(5)
+                            .width(Length::Units(250))
+                            .on_submit(SettingsMessage::ApplyDownloaderBinary.into())
+                    )
+                    .push(Button::new(Text::new(tr(Key::Apply)))
+                        .on_press_if(
+                            !self.downloader_binary.trim().is_empty() && self.downloader_binary.trim() != settings.downloader_binary,
+                            SettingsMessage::ApplyDownloaderBinary.into(),
+                        ))
+                    .push(Button::new(Text::new(tr(Key::ChooseDownloaderButton)))
+                        .on_press(SettingsMessage::ChooseDownloaderBinary.into())),
+            ))
+            .push(
+                Text::new(tr(Key::DownloaderBinaryHint))
+                    .color([0.3, 0.3, 0.3])
+            )
+            .push(self.labelled(
+                tr(Key::RateLimitLabel),
+                Row::new()
+                    .spacing(10)
+                    .align_items(Alignment::Center)
+                    .push(
+                        TextInput::new(tr(Key::RateLimitPlaceholder), &self.rate_limit, |v| SettingsMessage::RateLimitChange(v).into())
+                            .padding(5)
+                            .width(Length::Units(100))
+                            .on_submit(SettingsMessage::ApplyRateLimit.into())
+                    )
+                    .push(Text::new(tr(Key::PerSecond)))
+                    .push(Button::new(Text::new(tr(Key::Apply)))
+                        .on_press_if(
+                            rate_limit_valid && self.rate_limit.trim() != settings.download_rate_limit.as_deref().unwrap_or(""),
+                            SettingsMessage::ApplyRateLimit.into(),
+                        )),
+            ))
+            .push(
+                Text::new(tr(if rate_limit_valid { Key::RateLimitHint } else { Key::RateLimitInvalid }))
+                    .color(if rate_limit_valid { [0.3, 0.3, 0.3] } else { [0.8, 0.0, 0.0] })
+            )
+            .push(self.labelled(
+                tr(Key::ExtraArgsLabel),
+                Row::new()
+                    .spacing(10)
+                    .align_items(Alignment::Center)
+                    .push(
+                        TextInput::new(tr(Key::ExtraArgsPlaceholder), &self.extra_args, |v| SettingsMessage::ExtraArgsChange(v).into())
+                            .padding(5)
+                            .width(Length::Units(300))
+                            .on_submit(SettingsMessage::ApplyExtraArgs.into())
+                    )
+                    .push(Button::new(Text::new(tr(Key::Apply)))
+                        .on_press_if(
+                            extra_args.as_ref().map_or(false, |args| *args != settings.extra_downloader_args),
+                            SettingsMessage::ApplyExtraArgs.into(),
+                        )),
+            ))
+            .push(
+                match &extra_args {
+                    Ok(_) => Text::new(tr(Key::ExtraArgsHint))
+                        .color([0.3, 0.3, 0.3]),
+                    Err(e) => Text::new(describe_extra_args_error(e))
+                        .color([0.8, 0.0, 0.0]),
+                }
+            )
+            .push(Text::new(tr(Key::PipelineLabel)))
+            .push(self.post_process_pipeline(&settings.post_process_pipeline))
+            .push_if(settings.post_process_pipeline.contains(&PostProcessStepKind::NormalizeLoudness), ||
+                self.labelled(
+                    tr(Key::LoudnessTargetLabel),
+                    PickList::new(
+                        &LOUDNESS_TARGET_CHOICES[..],
+                        Some(settings.loudness_target),
+                        |t| SettingsMessage::ChangeLoudnessTarget(t).into(),
+                    ),
+                )
+            )
+            .push(
+                Text::new(tr(Key::PipelineHint))
+                    .color([0.3, 0.3, 0.3])
+            )
+            .push(Checkbox::new(
+                settings.post_download_command_enabled,
+                tr(Key::PostDownloadCommandSetting),
+                |v| SettingsMessage::TogglePostDownloadCommand(v).into(),
+            ))
+            .push(
+                Row::new()
+                    .spacing(10)
+                    .align_items(Alignment::Center)
+                    .push(
+                        TextInput::new("beet import -q {path}", &self.post_download_command, |v| SettingsMessage::PostDownloadCommandChange(v).into())
+                            .padding(5)
+                            .width(Length::Units(400))
+                            .on_submit(SettingsMessage::ApplyPostDownloadCommand.into())
+                    )
+                    .push(Button::new(Text::new(tr(Key::Apply)))
+                        .on_press_if(
+                            post_download_command.is_ok() && self.post_download_command.trim() != settings.post_download_command.as_deref().unwrap_or(""),
+                            SettingsMessage::ApplyPostDownloadCommand.into(),
+                        ))
+                    .push(Button::new(Text::new(tr(if self.testing_post_download_command { Key::TestRunning } else { Key::TestRun })))
+                        .on_press_if(
+                            post_download_command.as_ref().map_or(false, |args| !args.is_empty()) && has_songs && !self.testing_post_download_command,
+                            SettingsMessage::TestPostDownloadCommand.into(),
+                        ))
+            )
+            .push(
+                match &post_download_command {
+                    Ok(_) => Text::new(tr_count(Key::PostDownloadCommandHint, POST_DOWNLOAD_COMMAND_TIMEOUT.as_secs() as usize / 60, &[]))
+                        .color([0.3, 0.3, 0.3]),
+                    // Splitting the command only fails if a quote isn't closed
+                    Err(_) => Text::new(tr(Key::PostDownloadCommandUnclosedQuote))
+                        .color([0.8, 0.0, 0.0]),
+                }
+            )
+            .into()
+    }
+
+    /// A checklist of post-processing steps, with the enabled steps first in the order they run.
+    fn post_process_pipeline<'a>(&self, pipeline: &[PostProcessStepKind]) -> Element<'a, Message> {
+        let disabled = PostProcessStepKind::ALL.into_iter().filter(|s| !pipeline.contains(s));
+
+        Column::with_children(
+            pipeline.iter().copied().chain(disabled).map(|step| {
+                let index = pipeline.iter().position(|s| *s == step);
+
+                Row::new()
+                    .spacing(10)
+                    .align_items(Alignment::Center)
+                    .push(
+                        Checkbox::new(
+                            index.is_some(),
+                            step.to_string(),
+                            move |v| SettingsMessage::TogglePostProcessStep(step, v).into(),
+                        )
+                            .width(Length::Units(300))
+                    )
+                    .push_if(index.is_some(), ||
+                        Button::new(Text::new(tr(Key::Earlier)))
+                            .on_press_if(index > Some(0), SettingsMessage::MovePostProcessStep(step, true).into())
+                    )
+                    .push_if(index.is_some(), ||
+                        Button::new(Text::new(tr(Key::Later)))
+                            .on_press_if(index < Some(pipeline.len() - 1), SettingsMessage::MovePostProcessStep(step, false).into())
+                    )
+                    .into()
+            }).collect()
+        )
+            .spacing(5)
+            .into()
+    }
+
+    fn labelled<'a>(&self, label: &str, control: impl Into<Element<'a, Message>>) -> Element<'a, Message> {
+        Row::new()
+            .spacing(10)
+            .align_items(Alignment::Center)
+            .push(Text::new(label).width(Length::Units(250)))
+            .push(control)
+            .into()
+    }
+
+    fn storage(&self) -> Element<Message> {
+        let settings = self.settings.read().unwrap();
+
+        Column::new()
+            .spacing(10)
+            .push(Text::new(tr(Key::StorageHeading)).size(22))
+            .push(Checkbox::new(
+                settings.deduplicate_originals,
+                tr(Key::DeduplicateOriginalsSetting),
+                |v| SettingsMessage::ToggleDeduplicateOriginals(v).into(),
+            ))
+            .push(
+                Text::new(tr(Key::DeduplicateOriginalsHint))
+                    .color([0.3, 0.3, 0.3])
+            )
+            .push(Checkbox::new(
+                settings.scan_recursively,
+                tr(Key::ScanRecursivelySetting),
+                |v| SettingsMessage::ToggleScanRecursively(v).into(),
+            ))
+            .push(Checkbox::new(
+                settings.staging_enabled,
+                tr(Key::StagingSetting),
+                |v| SettingsMessage::ToggleStaging(v).into(),
+            ))
+            .push(
+                Text::new(tr(Key::StagingHint))
+                    .color([0.3, 0.3, 0.3])
+            )
+            .push(self.labelled(
+                tr(Key::LibraryCheckLabel),
+                PickList::new(
+                    &LibraryCheckInterval::ALL[..],
+                    Some(settings.library_check_interval),
+                    |i| SettingsMessage::ChangeLibraryCheckInterval(i).into(),
+                ),
+            ))
+            .into()
+    }
+
+    fn statistics(&self) -> Element<Message> {
+        Column::new()
+            .spacing(10)
+            .push(Text::new(tr(Key::StatisticsHeading)).size(22))
+            .push(
+                Row::new()
+                    .spacing(10)
+                    .align_items(Alignment::Center)
+                    .push(Text::new(tr_args(
+                        Key::BandwidthThisMonth,
+                        &[&self.bandwidth_usage.month, &format_size(self.bandwidth_usage.bytes)],
+                    )))
+                    .push(Button::new(Text::new(tr(Key::Reset)))
+                        .on_press_if(self.bandwidth_usage.bytes > 0, SettingsMessage::ResetBandwidthUsage.into()))
+            )
+            .push(Text::new(tr_args(
+                Key::DownloadStatsAllTime,
+                &[
+                    &self.download_stats.succeeded,
+                    &self.download_stats.failed,
+                    &format_duration(Duration::from_secs(self.download_stats.audio_secs)),
+                    &format_size(self.download_stats.bytes),
+                    &format_duration(Duration::from_secs(self.download_stats.downloading_secs)),
+                ],
+            )))
+            .into()
+    }
+
+    fn describe_crop_template(template: &CropTemplate) -> String {
+        let end = match template.end_millis {
+            Some(end) => format_millis(end as f64),
+            None => tr(Key::CropTemplateRangeEnd).to_string(),
+        };
+        tr_args(Key::CropTemplateRange, &[&format_millis(template.start_millis as f64), &end])
+    }
+
+    fn reload_crop_template_names(&mut self) {
+        self.crop_template_names = self.settings.read().unwrap()
+            .crop_templates
+            .iter()
+            .map(|t| t.name.clone())
+            .collect();
+    }
+}
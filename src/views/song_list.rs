@@ -1,237 +1,1954 @@
-use std::{sync::{Arc, RwLock}, future::ready};
+use std::{sync::{Arc, RwLock, atomic::{AtomicUsize, Ordering}}, future::ready, collections::{HashSet, HashMap}, fmt::Display, path::{PathBuf, Path}, time::{Instant, Duration}, io::Cursor};
 
-use iced::{Command, pure::{Element, widget::{Column, Text, Button, Rule, Row, Image, Scrollable}}, image::Handle, Space, Length, Alignment};
-use native_dialog::{MessageDialog, MessageType};
-use crate::{library::{Library, Song}, Message, ui_util::{ElementContainerExtensions, ButtonExtensions}, settings::{Settings, SortBy, SortDirection}, assets};
+use anyhow::Result;
 
-use super::content::ContentMessage;
+use iced::{Command, pure::{Element, widget::{Column, Text, Button, Rule, Row, Image, Scrollable, PickList, TextInput, Container, Checkbox}}, image::Handle, Space, Length, Alignment, button, container, Background, Color, Subscription, time, alignment::Horizontal};
+use iced_native::{subscription, Event, keyboard::{self, KeyCode}, mouse, event};
+use native_dialog::{MessageDialog, MessageType, FileDialog};
+use feruca::Collator;
+use crate::{library::{Library, Song, SongMetadata, ReviewItem, LibraryError, TrashedFile, has_extension, probe_duration}, Message, ui_util::{ElementContainerExtensions, ButtonExtensions, ButtonStyleSheet, ContainerStyleSheet, MouseArea, library_error_toast, open_or_report, bulk_result_toast, snapshot_before_bulk_operation, snapshot_error_toast, display_order, format_duration}, settings::{Settings, SortBy, SortDirection, RowAction, ReviewRule, PostProcessStepKind}, assets, preflight, youtube::{MediaDownload, YouTubeDownloadProgress}, snapshot::TagSnapshot, i18n::{Key, tr, tr_args, tr_count}};
+
+use super::{content::ContentMessage, download::{DownloadView, DownloadMessage}};
 
 #[derive(Debug, Clone)]
 pub enum SongListMessage {
     RefreshSongList,
-    ChangeSort(SortBy),
+    RestoreTagSnapshot,
+    /// Saves the library as a playlist file, for opening in other players.
+    ExportPlaylist,
+    /// Copies MP3s from elsewhere into the library.
+    ImportFiles,
+    ChangeSort(SortBy, SortDirection),
+    /// Reverses the direction of the active sort.
     ToggleSortReverse,
+    /// Groups the song list by the active sort, or stops grouping it if false.
+    SetGrouping(bool),
+    /// Collapses the group with the given name, or expands it if it's collapsed.
+    ToggleGroup(String),
+    /// Shows or hides the details of running downloads above the song list.
+    ToggleDownloadsStrip,
+    /// Puts back the songs changed by the most recent deletion or original restore.
+    Undo,
+
+    ToggleFilter(SongFilter),
+    /// Shows only songs from the given channel, replacing any channel filter already active.
+    FilterUploader(UploaderListItem),
+
+    /// Checks songs against the review rules in the background, if the "Needs attention" filter
+    /// is active.
+    ReviewSongs,
+    ReviewLoaded(Vec<ReviewItem>),
+    MarkReviewed(Song),
+
+    /// Loads the thumbnails of the rows around the scroll position which haven't been already.
+    LoadThumbnails,
+    ThumbnailLoaded(PathBuf, Option<Thumbnail>),
+    /// The song list was scrolled, to the given offset between 0 (the top) and 1 (the bottom).
+    Scrolled(f32),
+
+    OpenLibraryFolder,
+    DeleteConflictedCopies,
 
     RestoreOriginal(Song),
+    /// Downloads the song again from its video, replacing its file but keeping its tags.
+    Redownload(Song),
+    /// Moves a staged song into the library - see [`Song::approve`].
+    ApproveStaged(Song),
+    ApproveAllStaged,
     Delete(Song),
     ToggleHide(Song),
+    TogglePin(Song),
+    CopyToClipboard(String),
+    ApplyCropTemplate(Song, String),
+    PostProcess(Song, PostProcessStepKind),
+
+    ToggleSelect(Song),
+    SelectAlbum(Song),
+    ClearSelection,
+    HideSelected,
+    DeleteSelected,
+
+    /// Hides, or unhides if false, every song matching the active filters, in the background.
+    ChangeVisibilityOfShown(bool),
+    TickVisibilityChange,
+    /// A bulk visibility change finished. Carries whether songs were being hidden, how many were
+    /// changed, and the title of each song which couldn't be, with why.
+    VisibilityChanged(bool, usize, Vec<(String, LibraryError)>),
+
+    ClickRow(Song),
+    MiddleClickRow(Song),
+
+    ClickInlineEditable(Song, InlineEditField),
+    InlineEditChange(String),
+    CommitInlineEdit,
+    CancelInlineEdit,
 }
 
 impl From<SongListMessage> for Message {
     fn from(slm: SongListMessage) -> Self { ContentMessage::SongListMessage(slm).into() }
 }
 
+/// A filter which can be toggled on to restrict which songs are shown in the song list. When
+/// multiple filters are active, a song must match all of them to be shown.
+#[derive(PartialEq, Eq, Hash, Debug, Clone)]
+pub enum SongFilter {
+    Hidden,
+    Pinned,
+    NeedsMetadata,
+    NeedsAttention,
+    Uploader(String),
+}
+
+impl SongFilter {
+    /// All filters which can always be toggled, in the order their chips are displayed. Channel
+    /// filters are picked from a list instead.
+    const ALL: [SongFilter; 4] = [SongFilter::Hidden, SongFilter::Pinned, SongFilter::NeedsMetadata, SongFilter::NeedsAttention];
+
+    /// Whether the given song should be shown while this filter is active. `review` is the result
+    /// of the last review, if there's been one.
+    pub fn matches(&self, song: &Song, review: Option<&HashMap<PathBuf, Vec<ReviewRule>>>) -> bool {
+        match self {
+            SongFilter::Hidden => song.is_hidden(),
+            SongFilter::Pinned => song.metadata.is_pinned,
+            SongFilter::NeedsMetadata => song.metadata.needs_metadata,
+            SongFilter::NeedsAttention => review.map(|r| r.contains_key(&song.path)).unwrap_or(false),
+            SongFilter::Uploader(uploader) => song.metadata.uploader.as_ref() == Some(uploader),
+        }
+    }
+}
+
+impl Display for SongFilter {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            SongFilter::Hidden => f.write_str(tr(Key::FilterHidden)),
+            SongFilter::Pinned => f.write_str(tr(Key::FilterPinned)),
+            SongFilter::NeedsMetadata => f.write_str(tr(Key::FilterNeedsMetadata)),
+            SongFilter::NeedsAttention => f.write_str(tr(Key::FilterNeedsAttention)),
+            SongFilter::Uploader(uploader) => f.write_str(&tr_args(Key::FilterUploader, &[uploader])),
+        }
+    }
+}
+
+#[derive(PartialEq, Eq, Debug, Clone)]
+pub enum UploaderListItem {
+    TopLevel,
+    Uploader(String),
+}
+
+impl Display for UploaderListItem {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            UploaderListItem::TopLevel => f.write_str(tr(Key::FilterByUploader)),
+            UploaderListItem::Uploader(uploader) => f.write_str(uploader),
+        }
+    }
+}
+
+/// A field of a song which can be edited directly in the song list, by double-clicking it.
+#[derive(PartialEq, Eq, Debug, Clone, Copy)]
+pub enum InlineEditField {
+    Title,
+    Artist,
+}
+
+/// An in-progress edit of a song's field, made directly in the song list.
+#[derive(Debug, Clone)]
+pub struct InlineEdit {
+    path: PathBuf,
+    field: InlineEditField,
+    value: String,
+}
+
+/// A bulk hide or unhide running in the background.
+struct VisibilityChange {
+    hiding: bool,
+    total: usize,
+
+    /// How many songs have been dealt with so far, whether or not that succeeded.
+    done: Arc<AtomicUsize>,
+}
+
+/// The width at which album art is shown in the song list.
+const THUMBNAIL_WIDTH: u32 = 100;
+
+/// How many rows either side of the song list's scroll position have their thumbnails loaded.
+/// This is more than fit on screen, so that thumbnails are usually ready by the time their rows
+/// are scrolled to.
+const THUMBNAIL_ROWS_AROUND_VIEW: usize = 30;
+
+/// The maximum time between two clicks for them to count as a double-click.
+const DOUBLE_CLICK_TIME: Duration = Duration::from_millis(500);
+
+/// How many deletions and restores can be undone. Once there are more, the oldest is made permanent.
+const MAX_UNDO_ENTRIES: usize = 10;
+
+/// A deletion or original restore, which can be undone by putting its files back.
+struct UndoEntry {
+    /// What was done, like "Deleted 'Song'", to say what's being undone.
+    summary: String,
+    files: Vec<TrashedFile>,
+}
+
 pub struct SongListView {
     library: Arc<RwLock<Library>>,
     settings: Arc<RwLock<Settings>>,
 
     song_views: Vec<(Song, SongView)>,
+
+    /// Songs waiting in the library's staging folder, which are listed separately above the rest.
+    /// These aren't filtered, sorted or selectable, since there are only meant to be a few.
+    staged_views: Vec<(Song, SongView)>,
+
+    filters: HashSet<SongFilter>,
+
+    /// Songs which bulk actions will apply to.
+    selected: HashSet<PathBuf>,
+
+    /// The problems found with each song by the last review, for the "Needs attention" filter.
+    /// This is `None` until the first review finishes.
+    review: Option<HashMap<PathBuf, Vec<ReviewRule>>>,
+
+    /// Only one field, in one row, may be edited inline at a time.
+    inline_edit: Option<InlineEdit>,
+    last_click: Option<(PathBuf, InlineEditField, Instant)>,
+    last_row_click: Option<(PathBuf, Instant)>,
+
+    visibility_change: Option<VisibilityChange>,
+
+    /// The names of the groups whose songs are hidden, while the song list is grouped. These are
+    /// forgotten when the sort changes, since the groups change with it.
+    collapsed_groups: HashSet<String>,
+
+    /// Whether each running download is listed above the song list, rather than just a summary.
+    downloads_strip_expanded: bool,
+
+    /// Deletions and restores which can be undone, oldest first.
+    undo_stack: Vec<UndoEntry>,
+
+    /// Whether the library is being loaded for the first time, so there's nothing to list yet.
+    loading: bool,
+
+    /// How far down the song list is scrolled, between 0 and 1, to find which rows are in view.
+    scroll_offset: f32,
+
+    /// Songs whose thumbnails are loading in the background, so that they aren't asked for again
+    /// in the meantime.
+    loading_thumbnails: HashSet<PathBuf>,
 }
 
 impl SongListView {
     pub fn new(library: Arc<RwLock<Library>>, settings: Arc<RwLock<Settings>>) -> Self {
-        let mut result = Self { library, settings, song_views: vec![] };
+        let loading = !library.read().unwrap().is_loaded();
+        let mut result = Self {
+            library,
+            settings,
+            song_views: vec![],
+            staged_views: vec![],
+            filters: HashSet::new(),
+            selected: HashSet::new(),
+            review: None,
+            inline_edit: None,
+            last_click: None,
+            last_row_click: None,
+            visibility_change: None,
+            collapsed_groups: HashSet::new(),
+            downloads_strip_expanded: false,
+            undo_stack: vec![],
+            loading,
+            scroll_offset: 0.0,
+            loading_thumbnails: HashSet::new(),
+        };
         result.rebuild_song_views();
         result
     }
 
-    pub fn view(&self) -> Element<Message> {
+    /// `downloads` are the downloads currently running, which are summarised above the list, so
+    /// that they can be followed without scrolling back up.
+    pub fn view<'a>(&'a self, downloads: &'a [(MediaDownload, Arc<RwLock<YouTubeDownloadProgress>>)]) -> Element<'a, Message> {
+        Column::new()
+            .push_if(!downloads.is_empty(), || self.downloads_strip(downloads))
+            .push(if self.loading {
+                Container::new(Text::new(tr(Key::LoadingLibrary)).color([0.3, 0.3, 0.3]))
+                    .padding(20)
+                    .width(Length::Fill)
+                    .center_x()
+                    .into()
+            } else {
+                self.song_list()
+            })
+            .into()
+    }
+
+    /// Shows a placeholder instead of the songs, while the library is loaded for the first time.
+    pub fn set_loading(&mut self, loading: bool) {
+        self.loading = loading;
+    }
+
+    /// A one-line summary of the running downloads, which can be clicked to list them.
+    fn downloads_strip<'a>(&'a self, downloads: &'a [(MediaDownload, Arc<RwLock<YouTubeDownloadProgress>>)]) -> Element<'a, Message> {
+        let progress = downloads.iter()
+            .map(|(_, prog)| prog.read().unwrap().progress)
+            .sum::<f32>() / downloads.len() as f32;
+        let summary = tr_count(Key::DownloadsStripSummary, downloads.len(), &[&(progress.round() as u32)]);
+
+        Container::new(
+            Column::new()
+                .spacing(5)
+                .push(
+                    Button::new(Text::new(format!("{} {}", if self.downloads_strip_expanded { "▾" } else { "▸" }, summary)).size(16))
+                        .padding(0)
+                        .style(ButtonStyleSheet(button::Style {
+                            background: None,
+                            border_width: 0.0,
+                            ..Default::default()
+                        }))
+                        .on_press(SongListMessage::ToggleDownloadsStrip.into())
+                )
+                .push_if(self.downloads_strip_expanded, ||
+                    Column::with_children(
+                        downloads.iter().map(|(dl, prog)| DownloadView::progress_row(dl, prog)).collect()
+                    )
+                        .spacing(5)
+                )
+        )
+            .padding([5, 10])
+            .width(Length::Fill)
+            .style(ContainerStyleSheet(container::Style {
+                background: Some(Background::Color([0.9, 0.9, 0.9].into())),
+                ..Default::default()
+            }))
+            .into()
+    }
+
+    fn song_list(&self) -> Element<Message> {
         Scrollable::new(
             Column::new()
                 .align_items(Alignment::Center)
                 .spacing(10)
+                .push_if(self.library.read().unwrap().conflicted_copies().next().is_some(), ||
+                    self.conflicted_copies_banner()
+                )
+                .push_if(!self.staged_views.is_empty(), || self.staging_section())
+                .push(self.filter_chips())
+                .push_if(!self.filters.is_empty() || self.visibility_change.is_some(), || self.shown_songs_bar())
+                .push_if(!self.selected.is_empty(), || self.selection_bar())
+                .push_if_let(&self.undo_stack.last(), |entry| self.undo_bar(entry))
+                .push_if(self.filters.contains(&SongFilter::NeedsAttention), || self.review_status())
                 .push(Column::with_children(
-                    self.song_views
-                        .iter()
-                        .map(Some)
-                        .intersperse_with(|| None)
-                        .map(|view|
-                            if let Some((_, view)) = view {
-                                view.view()
-                            } else {
-                                Rule::horizontal(10).into()
-                            }
+                    match self.grouping() {
+                        Some(sort) => self.grouped_song_rows(sort),
+                        None => self.song_rows(self.shown_song_views()),
+                    }
+                ))
+        )
+            .on_scroll(|offset| SongListMessage::Scrolled(offset).into())
+            .into()
+    }
+
+    /// The songs which have rows in the list, in the order they're shown - staged songs first,
+    /// then the rest, leaving out those in collapsed groups.
+    fn listed_song_views(&self) -> Vec<&(Song, SongView)> {
+        let grouping = self.grouping();
+        self.staged_views
+            .iter()
+            .chain(self.shown_song_views().filter(|(song, _)| match grouping {
+                Some(sort) => !self.collapsed_groups.contains(Self::group_name(sort, song).unwrap_or_default()),
+                None => true,
+            }))
+            .collect()
+    }
+
+    /// Starts loading the thumbnails of the rows around the scroll position, which aren't loaded
+    /// or loading already. Reading a song's art is slow, so rows further away wait until they're
+    /// scrolled towards rather than all being read whenever the list is refreshed.
+    fn load_visible_thumbnails(&mut self) -> Command<Message> {
+        let listed = self.listed_song_views();
+
+        // Rows aren't all the same height, so this is only roughly where the rows in view are
+        let centre = (self.scroll_offset * listed.len() as f32) as usize;
+        let start = centre.saturating_sub(THUMBNAIL_ROWS_AROUND_VIEW);
+        let end = (centre + THUMBNAIL_ROWS_AROUND_VIEW).min(listed.len());
+
+        let paths = listed[start..end]
+            .iter()
+            .filter(|(song, view)| song.metadata.album_art.is_some() && view.thumbnail.is_none())
+            .map(|(song, _)| song.path.clone())
+            .filter(|path| !self.loading_thumbnails.contains(path))
+            .collect::<Vec<_>>();
+        self.loading_thumbnails.extend(paths.iter().cloned());
+
+        Command::batch(
+            paths.into_iter()
+                .map(|path| Command::perform(
+                    tokio::task::spawn_blocking(move || {
+                        // Unreadable art is shown as broken, rather than asked for again every
+                        // time the list scrolls
+                        let thumbnail = Self::load_thumbnail(&path).unwrap_or(Some(Thumbnail::Broken));
+                        (path, thumbnail)
+                    }),
+                    |result| {
+                        let (path, thumbnail) = result.expect("thumbnail task failed");
+                        SongListMessage::ThumbnailLoaded(path, thumbnail).into()
+                    },
+                ))
+                .collect::<Vec<_>>()
+        )
+    }
+
+    /// The rows for the given songs, with a divider between each.
+    fn song_rows<'a>(&'a self, songs: impl Iterator<Item = &'a (Song, SongView)>) -> Vec<Element<'a, Message>> {
+        songs
+            .map(Some)
+            .intersperse_with(|| None)
+            .map(|view|
+                if let Some((song, view)) = view {
+                    let row = view.view(
+                        self.inline_edit.as_ref().filter(|e| e.path == song.path),
+                        self.selected.contains(&song.path),
+                    );
+
+                    match self.review.as_ref().and_then(|r| r.get(&song.path)) {
+                        Some(problems) if self.filters.contains(&SongFilter::NeedsAttention) =>
+                            Column::new().push(row).push(Self::review_problems(song, problems)).into(),
+                        _ => row,
+                    }
+                } else {
+                    Rule::horizontal(10).into()
+                }
+            )
+            .collect()
+    }
+
+    /// The sort which the song list is grouped by, if it's grouped at all.
+    fn grouping(&self) -> Option<SortBy> {
+        let settings = self.settings.read().unwrap();
+        Some(settings.sort_by).filter(|sort| settings.group_songs && matches!(sort, SortBy::Artist | SortBy::Album))
+    }
+
+    /// The name of the group which the song falls under when grouping by the given sort, or `None`
+    /// if that sort doesn't group songs.
+    fn group_name(sort: SortBy, song: &Song) -> Option<&str> {
+        match sort {
+            SortBy::Artist => Some(&song.metadata.artist),
+            SortBy::Album => Some(&song.metadata.album),
+            SortBy::Title | SortBy::Downloaded => None,
+        }
+    }
+
+    /// The rows for the shown songs, under a header for each group. Songs are already sorted by the
+    /// same field they're grouped by, so each group is one run of songs.
+    fn grouped_song_rows(&self, sort: SortBy) -> Vec<Element<Message>> {
+        let shown = self.shown_song_views().collect::<Vec<_>>();
+        let mut rows = vec![];
+
+        let mut start = 0;
+        while start < shown.len() {
+            let name = Self::group_name(sort, &shown[start].0);
+            let end = shown[start..].iter()
+                .position(|(song, _)| Self::group_name(sort, song) != name)
+                .map_or(shown.len(), |len| start + len);
+
+            let name = name.unwrap_or_default();
+            let collapsed = self.collapsed_groups.contains(name);
+            rows.push(self.group_header(sort, name, end - start, collapsed));
+            if !collapsed {
+                rows.extend(self.song_rows(shown[start..end].iter().copied()));
+            }
+
+            start = end;
+        }
+
+        rows
+    }
+
+    fn group_header(&self, sort: SortBy, name: &str, count: usize, collapsed: bool) -> Element<Message> {
+        let display_name = if name.is_empty() {
+            tr(if sort == SortBy::Album { Key::GroupNoAlbum } else { Key::GroupNoArtist }).to_string()
+        } else {
+            name.to_string()
+        };
+
+        Button::new(
+            Row::new()
+                .spacing(10)
+                .align_items(Alignment::Center)
+                .push(Text::new(if collapsed { "▸" } else { "▾" }).size(22))
+                .push(Text::new(display_name).size(22))
+                .push(Text::new(tr_count(Key::GroupSongCount, count, &[])).color([0.3, 0.3, 0.3]))
+        )
+            .width(Length::Fill)
+            .padding([10, 10, 0, 10])
+            .style(ButtonStyleSheet(button::Style {
+                background: None,
+                border_width: 0.0,
+                ..Default::default()
+            }))
+            .on_press(SongListMessage::ToggleGroup(name.to_string()).into())
+            .into()
+    }
+
+    fn conflicted_copies_banner(&self) -> Element<Message> {
+        let count = self.library.read().unwrap().conflicted_copies().count();
+
+        Row::new()
+            .spacing(10)
+            .padding([10, 10, 0, 10])
+            .align_items(Alignment::Center)
+            .width(Length::Fill)
+            .push(
+                Text::new(tr_count(Key::ConflictedCopiesBanner, count, &[]))
+                    .color([0.8, 0.4, 0.0])
+                    .width(Length::Fill)
+            )
+            .push(Button::new(Text::new(tr(Key::OpenFolder)))
+                .on_press(SongListMessage::OpenLibraryFolder.into()))
+            .push(Button::new(Text::new(tr(Key::Delete)))
+                .on_press(SongListMessage::DeleteConflictedCopies.into()))
+            .into()
+    }
+
+    /// The songs waiting in staging, each with a button to approve it into the library.
+    fn staging_section(&self) -> Element<Message> {
+        Container::new(
+            Column::new()
+                .spacing(10)
+                .push(
+                    Row::new()
+                        .spacing(10)
+                        .align_items(Alignment::Center)
+                        .push(Text::new(tr_args(Key::StagingTitle, &[&self.staged_views.len()])).size(22).width(Length::Fill))
+                        .push(Button::new(Text::new(tr(Key::ApproveAll)))
+                            .on_press(SongListMessage::ApproveAllStaged.into()))
+                )
+                .push(Column::with_children(
+                    self.staged_views.iter()
+                        .map(|(song, view)|
+                            Row::new()
+                                .spacing(10)
+                                .align_items(Alignment::Center)
+                                .push(
+                                    Container::new(view.view(self.inline_edit.as_ref().filter(|e| e.path == song.path), false))
+                                        .width(Length::Fill)
+                                )
+                                .push(Button::new(Text::new(tr(Key::Approve)))
+                                    .on_press(SongListMessage::ApproveStaged(song.clone()).into()))
+                                .into()
                         )
                         .collect()
-                ))
-        ).into()
+                ).spacing(5))
+        )
+            .padding(10)
+            .width(Length::Fill)
+            .style(ContainerStyleSheet(container::Style {
+                background: Some(Background::Color([0.9, 0.9, 0.9].into())),
+                ..Default::default()
+            }))
+            .into()
+    }
+
+    /// The songs which match every active filter, and their views, in list order.
+    fn shown_song_views(&self) -> impl Iterator<Item = &(Song, SongView)> {
+        self.song_views
+            .iter()
+            .filter(|(song, _)| self.filters.iter().all(|f| f.matches(song, self.review.as_ref())))
+    }
+
+    /// Shown while any filter is active, to act on every song it matches at once.
+    fn shown_songs_bar(&self) -> Element<Message> {
+        let shown_count = self.shown_song_views().count();
+        let hidden_count = self.shown_song_views().filter(|(song, _)| song.is_hidden()).count();
+        let idle = self.visibility_change.is_none();
+
+        let status = match &self.visibility_change {
+            Some(change) => tr_args(
+                if change.hiding { Key::HidingShownProgress } else { Key::UnhidingShownProgress },
+                &[&change.done.load(Ordering::Relaxed), &change.total],
+            ),
+            None => tr_count(Key::ShownCount, shown_count, &[]),
+        };
+
+        Row::new()
+            .spacing(10)
+            .padding([10, 10, 0, 10])
+            .align_items(Alignment::Center)
+            .width(Length::Fill)
+            .push(Text::new(status).width(Length::Fill))
+            .push(Button::new(Text::new(tr(Key::HideAllShown)))
+                .on_press_if(idle && hidden_count < shown_count, SongListMessage::ChangeVisibilityOfShown(true).into()))
+            .push(Button::new(Text::new(tr(Key::UnhideAllShown)))
+                .on_press_if(idle && hidden_count > 0, SongListMessage::ChangeVisibilityOfShown(false).into()))
+            .into()
+    }
+
+    fn selection_bar(&self) -> Element<Message> {
+        Row::new()
+            .spacing(10)
+            .padding([10, 10, 0, 10])
+            .align_items(Alignment::Center)
+            .width(Length::Fill)
+            .push(
+                Text::new(tr_count(Key::SelectionCount, self.selected.len(), &[]))
+                    .width(Length::Fill)
+            )
+            .push(Button::new(Text::new(tr(Key::EditSelected)))
+                .on_press(ContentMessage::OpenBatchEditMetadata(self.selected_songs()).into()))
+            .push(Button::new(Text::new(tr(Key::Hide)))
+                .on_press(SongListMessage::HideSelected.into()))
+            .push(Button::new(Text::new(tr(Key::Delete)))
+                .on_press(SongListMessage::DeleteSelected.into()))
+            .push(Button::new(Text::new(tr(Key::ClearSelection)))
+                .on_press(SongListMessage::ClearSelection.into()))
+            .into()
+    }
+
+    /// Offers to undo the most recent deletion or restore.
+    fn undo_bar(&self, entry: &UndoEntry) -> Element<Message> {
+        Row::new()
+            .spacing(10)
+            .padding([10, 10, 0, 10])
+            .align_items(Alignment::Center)
+            .width(Length::Fill)
+            .push(Text::new(entry.summary.clone()).color([0.3, 0.3, 0.3]).width(Length::Fill))
+            .push(Button::new(Text::new(tr(Key::Undo)))
+                .on_press(SongListMessage::Undo.into()))
+            .into()
+    }
+
+    /// Remembers something which can be undone, making the oldest permanent if there are too many.
+    fn push_undo(&mut self, summary: String, files: Vec<TrashedFile>) {
+        if files.is_empty() {
+            return;
+        }
+
+        self.undo_stack.push(UndoEntry { summary, files });
+        if self.undo_stack.len() > MAX_UNDO_ENTRIES {
+            for file in self.undo_stack.remove(0).files {
+                if let Err(e) = file.discard() {
+                    println!("[Undo] Could not discard {}: {}", file.path.to_string_lossy(), e);
+                }
+            }
+        }
+    }
+
+    /// The selected songs, in list order.
+    fn selected_songs(&self) -> Vec<Song> {
+        self.song_views
+            .iter()
+            .filter(|(song, _)| self.selected.contains(&song.path))
+            .map(|(song, _)| song.clone())
+            .collect()
+    }
+
+    /// Shown above the song list while the "Needs attention" filter is active, if there's nothing
+    /// to list yet.
+    fn review_status(&self) -> Element<Message> {
+        let text = match &self.review {
+            None => tr(Key::ReviewChecking),
+            Some(review) if review.is_empty() => tr(Key::ReviewNothingFound),
+            Some(_) => "",
+        };
+
+        Text::new(text)
+            .color([0.3, 0.3, 0.3])
+            .into()
+    }
+
+    /// The problems found with a song by the review, and buttons to deal with them.
+    fn review_problems<'a>(song: &Song, problems: &[ReviewRule]) -> Element<'a, Message> {
+        let descriptions = problems.iter()
+            .map(|problem| tr(match problem {
+                ReviewRule::TitleIsVideoId => Key::ReviewRuleTitleIsVideoId,
+                ReviewRule::MissingAlbumArt => Key::ReviewRuleMissingAlbumArt,
+                ReviewRule::UnknownArtist => Key::ReviewRuleUnknownArtist,
+                ReviewRule::TooShort => Key::ReviewRuleTooShort,
+                ReviewRule::TooLong => Key::ReviewRuleTooLong,
+            }))
+            .collect::<Vec<_>>();
+
+        Row::new()
+            .spacing(10)
+            .padding([0, 10, 10, 10])
+            .align_items(Alignment::Center)
+            .push(
+                Text::new(descriptions.join(" · "))
+                    .size(16)
+                    .color([0.8, 0.4, 0.0])
+                    .width(Length::Fill)
+            )
+            .push(Button::new(Text::new(tr(Key::Edit)).size(16))
+                .on_press(ContentMessage::OpenEditMetadata(song.clone()).into()))
+            .push(Button::new(Text::new(tr(Key::ReviewMarkFine)).size(16))
+                .on_press(SongListMessage::MarkReviewed(song.clone()).into()))
+            .into()
+    }
+
+    fn filter_chips(&self) -> Element<Message> {
+        // An active channel filter gets a chip too, so that it can be turned off
+        let active_uploader_filters = self.filters.iter().filter(|f| matches!(f, SongFilter::Uploader(_)));
+
+        let mut uploaders = self.song_views.iter()
+            .filter_map(|(song, _)| song.metadata.uploader.clone())
+            .collect::<HashSet<_>>()
+            .into_iter()
+            .collect::<Vec<_>>();
+        let mut collator = Collator::default();
+        uploaders.sort_by(|a, b| collator.collate(a, b));
+        let uploader_items = std::iter::once(UploaderListItem::TopLevel)
+            .chain(uploaders.into_iter().map(UploaderListItem::Uploader))
+            .collect::<Vec<_>>();
+
+        Row::with_children(
+            SongFilter::ALL.iter().chain(active_uploader_filters).map(|filter| {
+                let active = self.filters.contains(filter);
+                let (background, text_color) = if active {
+                    ([0.2, 0.4, 0.8], Color::WHITE)
+                } else {
+                    ([0.9, 0.9, 0.9], Color::BLACK)
+                };
+
+                Button::new(Text::new(filter.to_string()).size(16))
+                    .on_press(SongListMessage::ToggleFilter(filter.clone()).into())
+                    .padding([3, 10])
+                    .style(ButtonStyleSheet(button::Style {
+                        background: Some(Background::Color(background.into())),
+                        border_radius: 10.0,
+                        text_color,
+                        ..Default::default()
+                    }))
+                    .into()
+            }).collect()
+        )
+            .push_if(uploader_items.len() > 1, ||
+                PickList::new(
+                    uploader_items,
+                    Some(UploaderListItem::TopLevel),
+                    |item| SongListMessage::FilterUploader(item).into(),
+                )
+                    .text_size(16)
+            )
+            .spacing(10)
+            .padding([10, 10, 0, 10])
+            .align_items(Alignment::Center)
+            .width(Length::Fill)
+            .into()
     }
 
     pub fn update(&mut self, message: SongListMessage) -> Command<Message> {
+        // Doing anything else abandons an inline edit - but thumbnails load in the background, so
+        // they shouldn't count
+        if !matches!(
+            message,
+            SongListMessage::ClickInlineEditable(..) | SongListMessage::InlineEditChange(_) | SongListMessage::CommitInlineEdit
+                | SongListMessage::LoadThumbnails | SongListMessage::ThumbnailLoaded(..) | SongListMessage::Scrolled(_)
+                | SongListMessage::ReviewSongs | SongListMessage::ReviewLoaded(_)
+                | SongListMessage::TickVisibilityChange
+        ) {
+            self.inline_edit = None;
+        }
+
+        // Nearly anything can change which rows are in view, so look for thumbnails to load after
+        // every message
+        let command = self.handle_message(message);
+        Command::batch([command, self.load_visible_thumbnails()])
+    }
+
+    fn handle_message(&mut self, message: SongListMessage) -> Command<Message> {
         match message {
             SongListMessage::RefreshSongList => {
                 // The content view does this for us!
                 Command::perform(ready(()), |_| ContentMessage::OpenSongList.into())
             }
 
-            SongListMessage::ChangeSort(sort) => {
+            SongListMessage::ExportPlaylist => {
+                let path = match FileDialog::new()
+                    .set_filename("CrossPlay.m3u")
+                    .add_filter(tr(Key::M3uPlaylistFilter), &["m3u", "m3u8"])
+                    .add_filter(tr(Key::PlsPlaylistFilter), &["pls"])
+                    .show_save_single_file()
+                    .unwrap()
+                {
+                    Some(path) => path,
+                    None => return Command::none(),
+                };
+
+                let library = self.library.read().unwrap();
+                let hidden_count = library.songs().filter(|s| s.is_hidden()).count();
+                let include_hidden = hidden_count > 0 && MessageDialog::new()
+                    .set_title(tr(Key::ExportHiddenTitle))
+                    .set_text(&tr_count(Key::ExportHiddenBody, hidden_count, &[]))
+                    .set_type(MessageType::Info)
+                    .show_confirm()
+                    .unwrap();
+
+                // Whichever format the file is named as is the one it's written in
+                let result = if has_extension(&path, "pls") {
+                    library.export_pls(&path, include_hidden)
+                } else {
+                    library.export_m3u(&path, include_hidden)
+                };
+                drop(library);
+
+                return match result {
+                    Ok(count) => {
+                        let toast = tr_count(Key::ToastPlaylistExported, count, &[&path.to_string_lossy()]);
+                        Command::perform(ready(()), move |_| Message::ShowToast(toast.clone()))
+                    }
+                    Err(e) => library_error_toast(&e),
+                }
+            },
+
+            SongListMessage::ImportFiles => {
+                let paths = FileDialog::new()
+                    .add_filter(tr(Key::Mp3Filter), &["mp3"])
+                    .show_open_multiple_file()
+                    .unwrap();
+                if paths.is_empty() {
+                    return Command::none();
+                }
+
+                // Keep going past failures, so that one bad file doesn't stop the rest
+                let library = self.library.read().unwrap();
+                let mut imported = vec![];
+                let mut failures = vec![];
+                for path in paths {
+                    match library.import_file(&path) {
+                        Ok(song) => imported.push(song),
+                        Err(e) => failures.push((path.file_name().unwrap_or_default().to_string_lossy().into_owned(), e)),
+                    }
+                }
+                drop(library);
+
+                // Importing doesn't change any songs already in the library, so the snapshot is of
+                // the imported songs' tags as they arrived. They're already imported by now, so
+                // there's nothing to ask about if it can't be saved
+                let snapshot_command = match TagSnapshot::take_before_bulk_operation(&imported) {
+                    Ok(()) => Command::none(),
+                    Err(e) => {
+                        println!("[Snapshot] Couldn't take snapshot: {}", e);
+                        snapshot_error_toast(&e)
+                    }
+                };
+
+                return Command::batch([
+                    snapshot_command,
+                    Command::perform(ready(()), |_| SongListMessage::RefreshSongList.into()),
+                    bulk_result_toast(Key::ToastImportedFiles, Key::ToastImportFilesFailed, imported.len(), &failures),
+                ])
+            },
+
+            SongListMessage::RestoreTagSnapshot => {
+                let _ = std::fs::create_dir_all(TagSnapshot::snapshots_dir());
+                let path = match FileDialog::new()
+                    .set_location(&TagSnapshot::snapshots_dir())
+                    .add_filter(tr(Key::TagSnapshotFilter), &["json"])
+                    .show_open_single_file()
+                    .unwrap()
+                {
+                    Some(path) => path,
+                    None => return Command::none(),
+                };
+
+                let snapshot = match TagSnapshot::load(&path) {
+                    Ok(snapshot) => snapshot,
+                    Err(e) => {
+                        MessageDialog::new()
+                            .set_title(tr(Key::SnapshotUnreadableTitle))
+                            .set_text(&tr_args(Key::SnapshotUnreadableBody, &[&e]))
+                            .set_type(MessageType::Error)
+                            .show_alert()
+                            .unwrap();
+                        return Command::none();
+                    }
+                };
+
+                let songs = self.library.read().unwrap().songs().cloned().collect::<Vec<_>>();
+                let plan = snapshot.plan_restore(&songs);
+                if plan.is_empty() {
+                    MessageDialog::new()
+                        .set_title(tr(Key::NothingToRestoreTitle))
+                        .set_text(tr(Key::NothingToRestoreBody))
+                        .show_alert()
+                        .unwrap();
+                    return Command::none();
+                }
+
+                const MAX_LISTED_CHANGES: usize = 10;
+                let mut changes = plan.iter()
+                    .take(MAX_LISTED_CHANGES)
+                    .map(|(song, entry)| tr_args(Key::TagChange, &[
+                        &song.metadata.title, &song.metadata.artist, &song.metadata.album,
+                        &entry.title, &entry.artist, &entry.album,
+                    ]))
+                    .collect::<Vec<_>>();
+                if plan.len() > MAX_LISTED_CHANGES {
+                    changes.push(tr_args(Key::AndMore, &[&(plan.len() - MAX_LISTED_CHANGES)]));
+                }
+
+                let confirmation = MessageDialog::new()
+                    .set_title(tr(Key::RestoreTagsTitle))
+                    .set_text(&tr_count(Key::RestoreTagsBody, plan.len(), &[&changes.join("\n")]))
+                    .set_type(MessageType::Warning)
+                    .show_confirm()
+                    .unwrap();
+                if !confirmation {
+                    return Command::none();
+                }
+
+                // Restoring the wrong snapshot is itself something to be undone
+                let affected_songs = plan.iter().map(|(song, _)| (*song).clone()).collect::<Vec<_>>();
+                let snapshot_command = match snapshot_before_bulk_operation(&affected_songs) {
+                    Some(command) => command,
+                    None => return Command::none(),
+                };
+
+                let mut failures = vec![];
+                for (song, entry) in &plan {
+                    let mut song = (*song).clone();
+                    if let Err(e) = entry.restore(&mut song) {
+                        failures.push(format!("{}: {}", song.path.to_string_lossy(), e));
+                    }
+                }
+
+                if !failures.is_empty() {
+                    MessageDialog::new()
+                        .set_title(tr(Key::RestoreTagsFailedTitle))
+                        .set_text(&tr_args(Key::RestoreTagsFailedBody, &[&failures.join("\n")]))
+                        .set_type(MessageType::Error)
+                        .show_alert()
+                        .unwrap();
+                }
+
+                let toast = tr_count(Key::ToastRestoredTags, plan.len() - failures.len(), &[]);
+                Command::batch([
+                    snapshot_command,
+                    Command::perform(ready(()), |_| SongListMessage::RefreshSongList.into()),
+                    Command::perform(ready(()), move |_| Message::ShowToast(toast.clone())),
+                ])
+            }
+
+            SongListMessage::ChangeSort(sort, direction) => {
                 let mut settings = self.settings.write().unwrap();
-                settings.sort_by = sort;
-                settings.save().expect("failed to save settings");
+                let sort_changed = settings.sort_by != sort;
+                settings.set_sort(sort, direction);
+                settings.save();
                 drop(settings);
 
-                self.sort_song_views();
+                // Only the order changes if it's the same sort in the other direction, so the
+                // groups stay the same
+                if sort_changed {
+                    self.collapsed_groups.clear();
+                }
+                self.sort_song_views();
+
+                Command::perform(ready(()), |_| SongListMessage::RefreshSongList.into())
+            }
+
+            SongListMessage::ToggleSortReverse => {
+                let mut settings = self.settings.write().unwrap();
+                settings.reverse_sort_direction();
+                settings.save();
+                drop(settings);
+
+                self.sort_song_views();
+
+                Command::perform(ready(()), |_| SongListMessage::RefreshSongList.into())
+            }
+
+            SongListMessage::SetGrouping(grouped) => {
+                let mut settings = self.settings.write().unwrap();
+                settings.group_songs = grouped;
+                settings.save();
+                drop(settings);
+
+                self.collapsed_groups.clear();
+                self.sort_song_views();
+
+                Command::perform(ready(()), |_| SongListMessage::RefreshSongList.into())
+            }
+
+            SongListMessage::Undo => {
+                let entry = match self.undo_stack.pop() {
+                    Some(entry) => entry,
+                    None => return Command::none(),
+                };
+
+                // Put back as much as possible, even if some of it fails
+                let mut error = None;
+                for file in entry.files.iter().rev() {
+                    if let Err(e) = file.restore() {
+                        error.get_or_insert(e);
+                    }
+                }
+
+                let toast = match error {
+                    Some(e) => library_error_toast(&e),
+                    None => {
+                        let toast = tr_args(Key::ToastUndone, &[&entry.summary]);
+                        Command::perform(ready(()), move |_| Message::ShowToast(toast.clone()))
+                    }
+                };
+                Command::batch([
+                    Command::perform(ready(()), |_| SongListMessage::RefreshSongList.into()),
+                    toast,
+                ])
+            }
+
+            SongListMessage::ToggleDownloadsStrip => {
+                self.downloads_strip_expanded = !self.downloads_strip_expanded;
+                Command::none()
+            }
+
+            SongListMessage::ToggleGroup(name) => {
+                if !self.collapsed_groups.remove(&name) {
+                    self.collapsed_groups.insert(name);
+                }
+                Command::none()
+            }
+
+            SongListMessage::ToggleFilter(filter) => {
+                let review = filter == SongFilter::NeedsAttention;
+                if !self.filters.remove(&filter) {
+                    self.filters.insert(filter);
+                }
+
+                if review {
+                    Command::perform(ready(()), |_| SongListMessage::ReviewSongs.into())
+                } else {
+                    Command::none()
+                }
+            }
+
+            SongListMessage::FilterUploader(item) => {
+                if let UploaderListItem::Uploader(uploader) = item {
+                    self.filters.retain(|f| !matches!(f, SongFilter::Uploader(_)));
+                    self.filters.insert(SongFilter::Uploader(uploader));
+                }
+                Command::none()
+            }
+
+            SongListMessage::ReviewSongs => {
+                if !self.filters.contains(&SongFilter::NeedsAttention) {
+                    return Command::none();
+                }
+
+                // Copy the songs out, so the library isn't locked while durations are probed
+                let songs = self.library.read().unwrap().songs().cloned().collect::<Vec<_>>();
+                let rules = self.settings.read().unwrap().review_rules.clone();
+                Command::perform(
+                    tokio::task::spawn_blocking(move || Library::review_items(&songs, &rules)),
+                    |result| SongListMessage::ReviewLoaded(result.expect("review task failed")).into(),
+                )
+            }
+
+            SongListMessage::ReviewLoaded(items) => {
+                self.review = Some(
+                    items.into_iter()
+                        .map(|item| (item.song.path, item.problems))
+                        .collect()
+                );
+
+                Command::none()
+            }
+
+            SongListMessage::MarkReviewed(mut song) => {
+                match song.mark_reviewed() {
+                    Ok(()) => {
+                        if let Some(review) = &mut self.review {
+                            review.remove(&song.path);
+                        }
+                        Command::perform(ready(()), |_| SongListMessage::RefreshSongList.into())
+                    }
+                    Err(e) => library_error_toast(&e),
+                }
+            }
+
+            // The thumbnails are loaded once this returns, as after every message
+            SongListMessage::LoadThumbnails => Command::none(),
+
+            SongListMessage::Scrolled(offset) => {
+                self.scroll_offset = offset;
+                Command::none()
+            }
+
+            SongListMessage::ThumbnailLoaded(path, thumbnail) => {
+                self.loading_thumbnails.remove(&path);
+                if let Some((_, view)) = self.song_views.iter_mut().chain(&mut self.staged_views).find(|(song, _)| song.path == path) {
+                    view.thumbnail = thumbnail;
+                }
 
-                Command::perform(ready(()), |_| SongListMessage::RefreshSongList.into())
+                Command::none()
             }
 
-            SongListMessage::ToggleSortReverse => {
-                let mut settings = self.settings.write().unwrap();
-                settings.sort_direction = settings.sort_direction.reverse();
-                settings.save().expect("failed to save settings");
-                drop(settings);
+            SongListMessage::OpenLibraryFolder => open_or_report(&self.library.read().unwrap().path),
 
-                self.sort_song_views();
+            SongListMessage::DeleteConflictedCopies => {
+                let copies = self.library.read().unwrap().conflicted_copies().cloned().collect::<Vec<_>>();
+                let confirmation = MessageDialog::new()
+                    .set_title(tr(Key::DeleteConflictedTitle))
+                    .set_text(&tr_args(
+                        Key::DeleteConflictedBody,
+                        &[&copies.iter().map(|p| p.to_string_lossy()).collect::<Vec<_>>().join("\n")],
+                    ))
+                    .set_type(MessageType::Warning)
+                    .show_confirm()
+                    .unwrap();
 
-                Command::perform(ready(()), |_| SongListMessage::RefreshSongList.into())
+                if confirmation {
+                    let failures = copies.iter()
+                        .filter_map(|copy| std::fs::remove_file(copy).err().map(|e| (copy, e)))
+                        .map(|(copy, e)| tr_args(Key::DeleteConflictedFailure, &[&copy.to_string_lossy(), &e]))
+                        .collect::<Vec<_>>();
+                    if !failures.is_empty() {
+                        MessageDialog::new()
+                            .set_title(tr(Key::DeleteConflictedFailedTitle))
+                            .set_text(&tr_args(Key::DeleteConflictedFailedBody, &[&failures.join("\n")]))
+                            .set_type(MessageType::Error)
+                            .show_alert()
+                            .unwrap();
+                    }
+
+                    // Some may have been deleted even if others failed
+                    Command::perform(ready(()), |_| SongListMessage::RefreshSongList.into())
+                } else {
+                    Command::none()
+                }
             }
 
             SongListMessage::RestoreOriginal(song) => {
+                if !song.has_original_copy() {
+                    MessageDialog::new()
+                        .set_title(tr(Key::RestoreOriginalTitle))
+                        .set_text(&tr_args(Key::RestoreOriginalMissing, &[&song.metadata.title]))
+                        .set_type(MessageType::Error)
+                        .show_alert()
+                        .unwrap();
+                    return Command::none();
+                }
+
+                // Probing only takes a moment, so it's simplest to wait for it before asking
+                let current_duration = song.metadata.duration.or_else(|| probe_duration(&song.path).ok());
+                let original_duration = song.probe_original_duration();
                 let confirmation = MessageDialog::new()
-                    .set_title("Restore original?")
-                    .set_text(&format!(
-                        "This will undo any metadata modifications, and remove the crop if applied. Are you sure you would like to restore '{}'?",
-                        song.metadata.title,
-                    ))
+                    .set_title(tr(Key::RestoreOriginalTitle))
+                    .set_text(&restore_confirmation_text(&song.metadata, current_duration, &original_duration))
                     .set_type(MessageType::Warning)
                     .show_confirm()
                     .unwrap();
 
                 if confirmation {
-                    song.restore_original_copy().unwrap();
-                    Command::perform(ready(()), |_| SongListMessage::RefreshSongList.into())
+                    // Keep the working copy, so that the restore can be undone
+                    let stashed = match song.stash_working_copy() {
+                        Ok(file) => file,
+                        Err(e) => return library_error_toast(&e),
+                    };
+                    if let Err(e) = song.restore_original_copy() {
+                        if let Err(discard_error) = stashed.discard() {
+                            println!("[Undo] Could not discard {}: {}", stashed.path.to_string_lossy(), discard_error);
+                        }
+                        return library_error_toast(&e);
+                    }
+
+                    let toast = tr_args(Key::ToastRestoredOriginal, &[&song.metadata.title]);
+                    self.push_undo(toast.clone(), vec![stashed]);
+                    Command::batch([
+                        Command::perform(ready(()), |_| SongListMessage::RefreshSongList.into()),
+                        Command::perform(ready(()), move |_| Message::ShowToast(toast.clone())),
+                    ])
                 } else {
                     Command::none()
                 }
             }
 
+            SongListMessage::ApproveStaged(song) => {
+                let title = song.metadata.title.clone();
+                match song.approve() {
+                    Ok(()) => {
+                        let toast = tr_args(Key::ToastApproved, &[&title]);
+                        Command::batch([
+                            Command::perform(ready(()), |_| SongListMessage::RefreshSongList.into()),
+                            Command::perform(ready(()), move |_| Message::ShowToast(toast.clone())),
+                        ])
+                    }
+                    Err(e) => library_error_toast(&e),
+                }
+            }
+
+            SongListMessage::ApproveAllStaged => {
+                let songs = self.staged_views.iter().map(|(song, _)| song.clone()).collect::<Vec<_>>();
+                let snapshot_command = match snapshot_before_bulk_operation(&songs) {
+                    Some(command) => command,
+                    None => return Command::none(),
+                };
+
+                // Keep going past failures, so that one song which can't be moved doesn't hold
+                // back the rest
+                let mut approved = 0;
+                let mut failures = vec![];
+                for song in &songs {
+                    match song.clone().approve() {
+                        Ok(()) => approved += 1,
+                        Err(e) => failures.push((song.metadata.title.clone(), e)),
+                    }
+                }
+
+                Command::batch([
+                    snapshot_command,
+                    Command::perform(ready(()), |_| SongListMessage::RefreshSongList.into()),
+                    bulk_result_toast(Key::ToastApprovedAll, Key::ToastApproveFailed, approved, &failures),
+                ])
+            }
+
+            SongListMessage::Redownload(song) => {
+                // Crops and post-processing were done to the old download, so they can't be kept
+                if song.metadata.is_cropped || song.metadata.is_post_processed {
+                    let confirmation = MessageDialog::new()
+                        .set_title(tr(Key::RedownloadTitle))
+                        .set_text(&tr_args(Key::RedownloadModifiedBody, &[&song.metadata.title]))
+                        .set_type(MessageType::Warning)
+                        .show_confirm()
+                        .unwrap();
+                    if !confirmation {
+                        return Command::none();
+                    }
+                }
+
+                Command::perform(ready(()), move |_| DownloadMessage::Redownload(song.clone()).into())
+            }
+
             SongListMessage::Delete(mut song) => {
                 let confirmation = MessageDialog::new()
-                    .set_title("Delete song?")
-                    .set_text(&format!(
-                        "This will permanently delete the song and any modifications made to it. Are you sure you would like to delete '{}'?",
-                        song.metadata.title,
-                    ))
+                    .set_title(tr(Key::DeleteSongTitle))
+                    .set_text(&tr_args(Key::DeleteSongBody, &[&song.metadata.title]))
                     .set_type(MessageType::Warning)
                     .show_confirm()
                     .unwrap();
 
                 if confirmation {
-                    song.delete().expect("delete failed");
-                    Command::perform(ready(()), |_| SongListMessage::RefreshSongList.into())
+                    let trashed = match song.delete() {
+                        Ok(trashed) => trashed,
+                        Err(e) => return library_error_toast(&e),
+                    };
+
+                    let toast = tr_args(Key::ToastDeleted, &[&song.metadata.title]);
+                    self.push_undo(toast.clone(), trashed);
+                    Command::batch([
+                        Command::perform(ready(()), |_| SongListMessage::RefreshSongList.into()),
+                        Command::perform(ready(()), move |_| Message::ShowToast(toast.clone())),
+                    ])
                 } else {
                     Command::none()
                 }
             }
 
             SongListMessage::ToggleHide(song) => {
-                let mut need_refresh = false;
+                let mut toast = None;
 
                 if song.is_hidden() {
                     let confirmation = MessageDialog::new()
-                        .set_title("Unhide song?")
-                        .set_text(&format!(
-                            "The song '{}' will re-appear in media players.",
-                            song.metadata.title,
-                        ))
+                        .set_title(tr(Key::UnhideSongTitle))
+                        .set_text(&tr_args(Key::UnhideSongBody, &[&song.metadata.title]))
                         .set_type(MessageType::Warning)
                         .show_confirm()
                         .unwrap();
 
                     if confirmation {
-                        song.unhide().expect("unhide failed");
-                        need_refresh = true;
+                        toast = Some(tr_args(Key::ToastUnhid, &[&song.metadata.title]));
+                        if let Err(e) = song.unhide() {
+                            return library_error_toast(&e);
+                        }
                     }
                 } else {
                     let confirmation = MessageDialog::new()
-                        .set_title("Hide song?")
-                        .set_text(&format!(
-                            "The song '{}' will remain downloaded and visible in CrossPlay, but will stop showing in media players.",
-                            song.metadata.title,
-                        ))
+                        .set_title(tr(Key::HideSongTitle))
+                        .set_text(&tr_args(Key::HideSongBody, &[&song.metadata.title]))
                         .set_type(MessageType::Warning)
                         .show_confirm()
                         .unwrap();
 
                     if confirmation {
-                        song.hide().expect("hide failed");
-                        need_refresh = true;
+                        toast = Some(tr_args(Key::ToastHid, &[&song.metadata.title]));
+                        if let Err(e) = song.hide() {
+                            return library_error_toast(&e);
+                        }
                     }
                 }
 
-                if need_refresh {
-                    Command::perform(ready(()), |_| SongListMessage::RefreshSongList.into())
+                if let Some(toast) = toast {
+                    Command::batch([
+                        Command::perform(ready(()), |_| SongListMessage::RefreshSongList.into()),
+                        Command::perform(ready(()), move |_| Message::ShowToast(toast.clone())),
+                    ])
+                } else {
+                    Command::none()
+                }
+            }
+
+            SongListMessage::TogglePin(mut song) => {
+                match song.set_pinned(!song.metadata.is_pinned) {
+                    Ok(()) => Command::perform(ready(()), |_| SongListMessage::RefreshSongList.into()),
+                    Err(e) => library_error_toast(&e),
+                }
+            }
+
+            SongListMessage::CopyToClipboard(text) => {
+                let toast = tr_args(Key::ToastCopied, &[&text]);
+                Command::batch([
+                    iced::clipboard::write(text),
+                    Command::perform(ready(()), move |_| Message::ShowToast(toast.clone())),
+                ])
+            }
+
+            SongListMessage::ApplyCropTemplate(mut song, name) => {
+                // The template could've been deleted from settings since the menu was opened
+                let template = self.settings.read().unwrap()
+                    .crop_templates
+                    .iter()
+                    .find(|t| t.name == name)
+                    .cloned();
+                let template = match template {
+                    Some(template) => template,
+                    None => {
+                        MessageDialog::new()
+                            .set_title(tr(Key::TemplateMissingTitle))
+                            .set_text(&tr_args(Key::TemplateMissingBody, &[&name]))
+                            .set_type(MessageType::Error)
+                            .show_alert()
+                            .unwrap();
+                        return Command::none();
+                    }
+                };
+
+                let duration = match song.duration() {
+                    Ok(duration) => duration,
+                    Err(e) => return library_error_toast(&e),
+                };
+                if let Some((start, end)) = template.range_for(duration) {
+                    if let Err(e) = song.crop(&[(start, end)], Duration::ZERO, Duration::ZERO) {
+                        return library_error_toast(&e);
+                    }
+
+                    let toast = tr_args(Key::ToastCroppedWithTemplate, &[&song.metadata.title, &template.name]);
+                    Command::batch([
+                        Command::perform(ready(()), |_| SongListMessage::RefreshSongList.into()),
+                        Command::perform(ready(()), move |_| Message::ShowToast(toast.clone())),
+                    ])
                 } else {
+                    MessageDialog::new()
+                        .set_title(tr(Key::TemplateDoesntFitTitle))
+                        .set_text(&tr_args(Key::TemplateDoesntFitBody, &[&template.name, &song.metadata.title]))
+                        .set_type(MessageType::Error)
+                        .show_alert()
+                        .unwrap();
+
                     Command::none()
                 }
             }
+
+            SongListMessage::PostProcess(mut song, step) => {
+                let loudness_target = self.settings.read().unwrap().loudness_target;
+                if let Err(e) = song.post_process(step, loudness_target) {
+                    return library_error_toast(&e);
+                }
+
+                let toast = tr_args(Key::ToastPostProcessed, &[&song.metadata.title, &step]);
+                Command::batch([
+                    Command::perform(ready(()), |_| SongListMessage::RefreshSongList.into()),
+                    Command::perform(ready(()), move |_| Message::ShowToast(toast.clone())),
+                ])
+            }
+
+            SongListMessage::ToggleSelect(song) => {
+                if !self.selected.remove(&song.path) {
+                    self.selected.insert(song.path);
+                }
+
+                Command::none()
+            }
+
+            SongListMessage::SelectAlbum(song) => {
+                let library = self.library.read().unwrap();
+                self.selected.extend(library.songs_in_album_of(&song).map(|s| s.path.clone()));
+
+                Command::none()
+            }
+
+            SongListMessage::ClearSelection => {
+                self.selected.clear();
+                Command::none()
+            }
+
+            SongListMessage::HideSelected => {
+                // Hiding a hidden song does nothing, so leave those out of the count
+                let songs = self.selected_songs().into_iter().filter(|s| !s.is_hidden()).collect::<Vec<_>>();
+                if songs.is_empty() {
+                    return Command::none();
+                }
+
+                let confirmation = MessageDialog::new()
+                    .set_title(tr(Key::HideSelectedTitle))
+                    .set_text(&tr_count(Key::HideSelectedBody, songs.len(), &[]))
+                    .set_type(MessageType::Warning)
+                    .show_confirm()
+                    .unwrap();
+                if !confirmation {
+                    return Command::none();
+                }
+
+                let snapshot_command = match snapshot_before_bulk_operation(&songs) {
+                    Some(command) => command,
+                    None => return Command::none(),
+                };
+
+                // Keep going past failures, so that one bad song doesn't stop the rest
+                self.selected.clear();
+                let mut hidden = 0;
+                let mut failures = vec![];
+                for song in songs {
+                    let title = song.metadata.title.clone();
+                    match song.hide() {
+                        Ok(()) => hidden += 1,
+                        Err(e) => failures.push((title, e)),
+                    }
+                }
+
+                Command::batch([
+                    snapshot_command,
+                    Command::perform(ready(()), |_| SongListMessage::RefreshSongList.into()),
+                    bulk_result_toast(Key::ToastHidSelected, Key::ToastVisibilityChangeFailed, hidden, &failures),
+                ])
+            }
+
+            SongListMessage::ChangeVisibilityOfShown(hiding) => {
+                if self.visibility_change.is_some() {
+                    return Command::none();
+                }
+
+                // Songs which already look the way they're being changed to are left alone, and
+                // aren't counted
+                let songs = self.shown_song_views()
+                    .map(|(song, _)| song.clone())
+                    .filter(|song| song.is_hidden() != hiding)
+                    .collect::<Vec<_>>();
+                if songs.is_empty() {
+                    return Command::none();
+                }
+
+                let (title, body) = if hiding {
+                    (tr(Key::HideSelectedTitle), tr_count(Key::HideSelectedBody, songs.len(), &[]))
+                } else {
+                    (tr(Key::UnhideShownTitle), tr_count(Key::UnhideShownBody, songs.len(), &[]))
+                };
+                let confirmation = MessageDialog::new()
+                    .set_title(title)
+                    .set_text(&body)
+                    .set_type(MessageType::Warning)
+                    .show_confirm()
+                    .unwrap();
+                if !confirmation {
+                    return Command::none();
+                }
+
+                let snapshot_command = match snapshot_before_bulk_operation(&songs) {
+                    Some(command) => command,
+                    None => return Command::none(),
+                };
+
+                // Renaming songs breaks the paths of any which are selected
+                self.selected.clear();
+
+                let done = Arc::new(AtomicUsize::new(0));
+                self.visibility_change = Some(VisibilityChange { hiding, total: songs.len(), done: done.clone() });
+
+                let change_command = Command::perform(
+                    tokio::task::spawn_blocking(move || {
+                        let mut changed = 0;
+                        let mut failures = vec![];
+                        for song in songs {
+                            let title = song.metadata.title.clone();
+                            let result = if hiding { song.hide() } else { song.unhide() };
+                            match result {
+                                Ok(()) => changed += 1,
+                                Err(e) => failures.push((title, e)),
+                            }
+                            done.fetch_add(1, Ordering::Relaxed);
+                        }
+                        (changed, failures)
+                    }),
+                    move |result| {
+                        let (changed, failures) = result.expect("visibility change task failed");
+                        SongListMessage::VisibilityChanged(hiding, changed, failures).into()
+                    },
+                );
+                Command::batch([snapshot_command, change_command])
+            }
+
+            SongListMessage::TickVisibilityChange => {
+                // Don't need to do anything - the message being sent is enough to redraw the
+                // progress
+                Command::none()
+            }
+
+            SongListMessage::VisibilityChanged(hiding, changed, failures) => {
+                self.visibility_change = None;
+
+                let succeeded_key = if hiding { Key::ToastHidSelected } else { Key::ToastUnhidShown };
+                Command::batch([
+                    Command::perform(ready(()), |_| SongListMessage::RefreshSongList.into()),
+                    bulk_result_toast(succeeded_key, Key::ToastVisibilityChangeFailed, changed, &failures),
+                ])
+            }
+
+            SongListMessage::DeleteSelected => {
+                let songs = self.selected_songs();
+                if songs.is_empty() {
+                    return Command::none();
+                }
+
+                let confirmation = MessageDialog::new()
+                    .set_title(tr(Key::DeleteSelectedTitle))
+                    .set_text(&tr_count(Key::DeleteSelectedBody, songs.len(), &[]))
+                    .set_type(MessageType::Warning)
+                    .show_confirm()
+                    .unwrap();
+                if !confirmation {
+                    return Command::none();
+                }
+
+                let snapshot_command = match snapshot_before_bulk_operation(&songs) {
+                    Some(command) => command,
+                    None => return Command::none(),
+                };
+
+                // Keep going past failures, so that one bad song doesn't stop the rest
+                self.selected.clear();
+                let mut deleted = 0;
+                let mut failures = vec![];
+                let mut trashed = vec![];
+                for mut song in songs {
+                    match song.delete() {
+                        Ok(files) => {
+                            deleted += 1;
+                            trashed.extend(files);
+                        }
+                        Err(e) => failures.push((song.metadata.title.clone(), e)),
+                    }
+                }
+                self.push_undo(tr_count(Key::ToastDeletedSelected, deleted, &[]), trashed);
+
+                Command::batch([
+                    snapshot_command,
+                    Command::perform(ready(()), |_| SongListMessage::RefreshSongList.into()),
+                    bulk_result_toast(Key::ToastDeletedSelected, Key::ToastDeleteSelectedFailed, deleted, &failures),
+                ])
+            }
+
+            SongListMessage::ClickRow(song) => {
+                let now = Instant::now();
+                let is_double_click = matches!(
+                    &self.last_row_click,
+                    Some((path, at)) if *path == song.path && now - *at <= DOUBLE_CLICK_TIME
+                );
+
+                if is_double_click {
+                    self.last_row_click = None;
+                    let action = self.settings.read().unwrap().row_double_click_action;
+                    self.run_row_action(song, action)
+                } else {
+                    self.last_row_click = Some((song.path.clone(), now));
+                    Command::perform(ready(()), move |_| ContentMessage::SelectSong(song.clone()).into())
+                }
+            }
+
+            SongListMessage::MiddleClickRow(song) => {
+                let action = self.settings.read().unwrap().row_middle_click_action;
+                self.run_row_action(song, action)
+            }
+
+            SongListMessage::ClickInlineEditable(song, field) => {
+                let now = Instant::now();
+                let is_double_click = matches!(
+                    &self.last_click,
+                    Some((path, last_field, at)) if *path == song.path && *last_field == field && now - *at <= DOUBLE_CLICK_TIME
+                );
+
+                if is_double_click {
+                    self.last_click = None;
+                    self.inline_edit = Some(InlineEdit {
+                        value: match field {
+                            InlineEditField::Title => song.metadata.title.clone(),
+                            InlineEditField::Artist => song.metadata.artist.clone(),
+                        },
+                        path: song.path,
+                        field,
+                    });
+                } else {
+                    self.last_click = Some((song.path, field, now));
+                }
+
+                Command::none()
+            }
+
+            SongListMessage::InlineEditChange(value) => {
+                if let Some(edit) = &mut self.inline_edit {
+                    edit.value = value;
+                }
+
+                Command::none()
+            }
+
+            SongListMessage::CommitInlineEdit => {
+                let edit = match self.inline_edit.take() {
+                    Some(edit) => edit,
+                    None => return Command::none(),
+                };
+                let song = match self.song_views.iter().chain(&self.staged_views).find(|(s, _)| s.path == edit.path) {
+                    Some((song, _)) => song,
+                    None => return Command::none(),
+                };
+
+                // The song could've changed on disk since the list was loaded, in which case we'd
+                // clobber those changes (or crash, if it's gone completely)
+                let mut current_song = match song.reload() {
+                    Ok(current_song) if current_song.metadata == song.metadata => current_song,
+                    _ => {
+                        let toast = tr_args(Key::ToastInlineEditConflict, &[&song.metadata.title]);
+                        return Command::batch([
+                            Command::perform(ready(()), |_| SongListMessage::RefreshSongList.into()),
+                            Command::perform(ready(()), move |_| Message::ShowToast(toast.clone())),
+                        ])
+                    }
+                };
+
+                let value = edit.value.trim().to_string();
+                let field = match edit.field {
+                    InlineEditField::Title => &mut current_song.metadata.title,
+                    InlineEditField::Artist => &mut current_song.metadata.artist,
+                };
+                if value.is_empty() || *field == value {
+                    return Command::none();
+                }
+                *field = value;
+
+                match current_song.user_edit_metadata() {
+                    Ok(()) => Command::perform(ready(()), |_| SongListMessage::RefreshSongList.into()),
+                    Err(e) => library_error_toast(&e),
+                }
+            }
+
+            SongListMessage::CancelInlineEdit => Command::none(),
+        }
+    }
+
+    fn run_row_action(&mut self, song: Song, action: RowAction) -> Command<Message> {
+        match action {
+            RowAction::Play => open_or_report(&song.path),
+            RowAction::PlayInCrossPlay =>
+                Command::perform(ready(()), move |_| ContentMessage::Play(song.clone()).into()),
+            RowAction::EditMetadata =>
+                Command::perform(ready(()), move |_| ContentMessage::OpenEditMetadata(song.clone()).into()),
+            RowAction::Crop if !song.metadata.is_cropped && preflight::ffmpeg_found() =>
+                Command::perform(ready(()), move |_| ContentMessage::OpenCrop(song.clone()).into()),
+            RowAction::Crop => Command::none(),
+            RowAction::OpenOnYouTube => open_or_report(MediaDownload::for_song(&song.metadata).url()),
+            RowAction::Nothing => Command::none(),
         }
     }
 
-    fn rebuild_song_views(&mut self) {
-        self.song_views.clear();
+    pub fn subscription(&self) -> Subscription<Message> {
+        // Keep redrawing the progress of a bulk hide or unhide until it's done
+        let visibility_change = if self.visibility_change.is_some() {
+            time::every(Duration::from_millis(100)).map(|_| SongListMessage::TickVisibilityChange.into())
+        } else {
+            Subscription::none()
+        };
 
-        let library_reader = self.library.read().unwrap();
-        let songs = library_reader.songs();
+        // Ctrl+Z undoes, unless something like a text input wanted it
+        let undo = if self.undo_stack.is_empty() {
+            Subscription::none()
+        } else {
+            subscription::events_with(|event, status| match (event, status) {
+                (Event::Keyboard(keyboard::Event::KeyPressed { key_code: KeyCode::Z, modifiers }), event::Status::Ignored)
+                    if modifiers.command() => Some(SongListMessage::Undo.into()),
+                _ => None,
+            })
+        };
+
+        Subscription::batch([visibility_change, self.inline_edit_subscription(), undo])
+    }
+
+    fn inline_edit_subscription(&self) -> Subscription<Message> {
+        // While editing inline, pressing Escape or clicking anywhere else cancels the edit
+        if self.inline_edit.is_some() {
+            subscription::events_with(|event, status| match (event, status) {
+                (Event::Keyboard(keyboard::Event::KeyPressed { key_code: KeyCode::Escape, .. }), _)
+                | (Event::Mouse(mouse::Event::ButtonPressed(_)), event::Status::Ignored) =>
+                    Some(SongListMessage::CancelInlineEdit.into()),
+                _ => None,
+            })
+        } else {
+            Subscription::none()
+        }
+    }
+
+    /// Reads a song's album art and scales it down to [`THUMBNAIL_WIDTH`], re-encoding it as a
+    /// small JPEG so that keeping thumbnails for the whole library in memory is cheap.
+    ///
+    /// Art which can't be decoded is found here, once, rather than by the renderer on every frame.
+    fn load_thumbnail(path: &Path) -> Result<Option<Thumbnail>> {
+        let picture = match Library::album_art_for(path)? {
+            Some(picture) => picture,
+            None => return Ok(None),
+        };
+
+        let image = match image::load_from_memory(&picture.data) {
+            Ok(image) => image.thumbnail(THUMBNAIL_WIDTH, u32::MAX),
+            Err(e) => {
+                println!("[Thumbnail] Album art of {} can't be decoded: {}", path.to_string_lossy(), e);
+                return Ok(Some(Thumbnail::Broken));
+            }
+        };
+        let mut jpeg_bytes = Cursor::new(vec![]);
+        image.write_to(&mut jpeg_bytes, image::ImageFormat::Jpeg)?;
+
+        Ok(Some(Thumbnail::Loaded(Handle::from_memory(jpeg_bytes.into_inner()))))
+    }
+
+    /// Carries over everything kept about songs whose files were renamed, which is kept by their
+    /// path, to their new paths. This needs to happen before the song views are rebuilt, which
+    /// forgets anything kept for paths which no longer exist.
+    pub fn follow_moves(&mut self, moved: &[(Song, Song)]) {
+        // Moving the song views along means the rebuild keeps them, thumbnails and all
+        for (song, view) in self.song_views.iter_mut().chain(&mut self.staged_views) {
+            song.follow_move(moved);
+            view.song.follow_move(moved);
+        }
 
-        for song in songs {
-            self.song_views.push((song.clone(), SongView::new(self.library.clone(), song.clone())))
+        for (old, new) in moved {
+            if self.selected.remove(&old.path) {
+                self.selected.insert(new.path.clone());
+            }
+            if let Some(review) = &mut self.review {
+                if let Some(problems) = review.remove(&old.path) {
+                    review.insert(new.path.clone(), problems);
+                }
+            }
+            if let Some(edit) = self.inline_edit.as_mut().filter(|edit| edit.path == old.path) {
+                edit.path = new.path.clone();
+            }
         }
+    }
 
+    /// Updates the song views to match the library's loaded songs. Views for songs which haven't
+    /// changed are kept, so that this is cheap after a reload which only changed a few songs.
+    pub fn rebuild_song_views(&mut self) {
+        let mut old_views = std::mem::take(&mut self.song_views)
+            .into_iter()
+            .chain(std::mem::take(&mut self.staged_views))
+            .map(|(song, view)| (song.path.clone(), (song, view)))
+            .collect::<HashMap<_, _>>();
+
+        let (library, settings) = (self.library.clone(), self.settings.clone());
+        let mut view_for = |song: &Song| match old_views.remove(&song.path) {
+            Some((old_song, view)) if old_song == *song => (old_song, view),
+            _ => (song.clone(), SongView::new(library.clone(), settings.clone(), song.clone())),
+        };
+
+        let library_reader = self.library.read().unwrap();
+        self.song_views = library_reader.songs().map(&mut view_for).collect();
+        self.staged_views = library_reader.staged_songs().map(&mut view_for).collect();
         drop(library_reader);
 
+        // Newest first, so that what was just downloaded is at the top
+        self.staged_views.sort_by_key(|(s, _)| u64::MAX - s.metadata.download_unix_time);
+
+        // Don't hold onto selections for songs which aren't in the library any more
+        let song_views = &self.song_views;
+        self.selected.retain(|path| song_views.iter().any(|(song, _)| &song.path == path));
+
         self.sort_song_views();
     }
 
     fn sort_song_views(&mut self) {
         let settings = self.settings.read().unwrap();
-        
+
+        // Text is sorted with the Unicode Collation Algorithm, so that accented letters sort with
+        // their base letter, and other scripts are kept in a sensible order rather than by code point
+        let mut collator = Collator::default();
         match settings.sort_by {
-            SortBy::Title => self.song_views.sort_by_key(|(s, _)| s.metadata.title.clone().to_lowercase()),
-            SortBy::Artist => self.song_views.sort_by_key(|(s, _)| s.metadata.artist.clone().to_lowercase()),
-            SortBy::Album => self.song_views.sort_by_key(|(s, _)| s.metadata.album.clone().to_lowercase()),
+            SortBy::Title => self.song_views.sort_by(|(a, _), (b, _)| collator.collate(&a.metadata.title, &b.metadata.title)),
+            SortBy::Artist => self.song_views.sort_by(|(a, _), (b, _)| collator.collate(&a.metadata.artist, &b.metadata.artist)),
+            SortBy::Album => self.song_views.sort_by(|(a, _), (b, _)| collator.collate(&a.metadata.album, &b.metadata.album)),
             
             // It makes sense for the default order of download time to go from newest to oldest,
             // so "invert" the u64 by subtracting it from the largest possible
             SortBy::Downloaded => self.song_views.sort_by_key(|(s, _)| u64::MAX - s.metadata.download_unix_time),
         }
 
-        match settings.sort_direction {
+        match settings.sort_direction() {
             SortDirection::Normal => (),
             SortDirection::Reverse => self.song_views.reverse(),
         }
+
+        drop(settings);
+
+        // Pinned songs always go first, regardless of sort - this is a stable sort, so both groups
+        // keep the order from the active sort. When the list is grouped, that's first within their
+        // group instead, so that each group stays in one piece
+        match self.grouping() {
+            Some(sort) => {
+                let mut start = 0;
+                while start < self.song_views.len() {
+                    let name = Self::group_name(sort, &self.song_views[start].0);
+                    let end = self.song_views[start..].iter()
+                        .position(|(song, _)| Self::group_name(sort, song) != name)
+                        .map_or(self.song_views.len(), |len| start + len);
+                    self.song_views[start..end].sort_by_key(|(s, _)| !s.metadata.is_pinned);
+                    start = end;
+                }
+            }
+            None => self.song_views.sort_by_key(|(s, _)| !s.metadata.is_pinned),
+        }
+    }
+}
+
+/// The confirmation shown before restoring a song's original, saying what will be lost: how much
+/// the crop removed, going by the two copies' durations, and whether edits will be undone.
+fn restore_confirmation_text(metadata: &SongMetadata, current_duration: Option<Duration>, original_duration: &std::result::Result<Duration, LibraryError>) -> String {
+    let mut paragraphs = vec![tr_args(Key::RestoreOriginalBody, &[&metadata.title])];
+
+    match (current_duration, original_duration) {
+        (Some(current), Ok(original)) => {
+            let mut comparison = tr_args(Key::RestoreDurations, &[&format_duration(current), &format_duration(*original)]);
+            // Less than a second either way is just the two files being encoded differently
+            if original.saturating_sub(current) >= Duration::from_secs(1) {
+                comparison.push(' ');
+                comparison.push_str(&tr_args(Key::RestoreDurationRegained, &[&format_duration(*original - current)]));
+            }
+            paragraphs.push(comparison);
+        }
+        (None, Ok(original)) => paragraphs.push(tr_args(Key::RestoreOriginalDuration, &[&format_duration(*original)])),
+        (_, Err(e)) => paragraphs.push(tr_args(Key::RestoreOriginalUnreadable, &[&e.to_string()])),
+    }
+
+    if metadata.is_metadata_edited {
+        paragraphs.push(tr(Key::RestoreRevertsMetadata).to_string());
+    } else {
+        paragraphs.push(tr(Key::RestoreKeepsMetadata).to_string());
+    }
+    if metadata.is_post_processed {
+        paragraphs.push(tr(Key::RestoreRevertsPostProcessing).to_string());
+    }
+
+    paragraphs.join("\n\n")
+}
+
+/// The actions in a song's context menu.
+#[derive(PartialEq, Eq, Debug, Clone)]
+pub enum SongMenuItem {
+    TopLevel,
+    Pin,
+    Unpin,
+    CopyYouTubeId,
+    CopyYouTubeUrl,
+    CopyFilePath,
+    Redownload,
+    /// Selects every song from the named album.
+    SelectAlbum(String),
+    /// Stands in for [`SongMenuItem::SelectAlbum`] on songs with no album, explaining why it can't be
+    /// used - songs with no album would select a huge pile of unrelated songs. Choosing this does
+    /// nothing.
+    SelectAlbumUnavailable,
+    ApplyCropTemplate(String),
+    PostProcess(PostProcessStepKind),
+}
+
+impl Display for SongMenuItem {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.write_str(match self {
+            SongMenuItem::TopLevel => tr(Key::MenuMore),
+            SongMenuItem::Pin => tr(Key::MenuPin),
+            SongMenuItem::Unpin => tr(Key::MenuUnpin),
+            SongMenuItem::CopyYouTubeId => tr(Key::MenuCopyYouTubeId),
+            SongMenuItem::CopyYouTubeUrl => tr(Key::MenuCopyYouTubeUrl),
+            SongMenuItem::CopyFilePath => tr(Key::MenuCopyFilePath),
+            SongMenuItem::Redownload => tr(Key::MenuRedownload),
+            SongMenuItem::SelectAlbum(album) => return f.write_str(&tr_args(Key::MenuSelectAlbum, &[album])),
+            SongMenuItem::SelectAlbumUnavailable => tr(Key::MenuSelectAlbumUnavailable),
+            SongMenuItem::ApplyCropTemplate(name) => return f.write_str(&tr_args(Key::MenuCropWith, &[name])),
+            SongMenuItem::PostProcess(step) => return step.fmt(f),
+        })
     }
 }
 
+/// A song's album art, as shown in the song list.
+#[derive(Debug, Clone)]
+pub enum Thumbnail {
+    Loaded(Handle),
+
+    /// The song has album art, but it couldn't be decoded.
+    Broken,
+}
+
 #[allow(unused)]
 struct SongView {
     library: Arc<RwLock<Library>>,
+    settings: Arc<RwLock<Settings>>,
     song: Song,
+
+    /// The song's album art, scaled down to the size it's displayed at. This is loaded in the
+    /// background once the row is near the scroll position, since reading every song's art is
+    /// slow, and is kept until the song changes and the view is rebuilt.
+    thumbnail: Option<Thumbnail>,
 }
 
 impl SongView {
-    pub fn new(library: Arc<RwLock<Library>>, song: Song) -> Self {
+    pub fn new(library: Arc<RwLock<Library>>, settings: Arc<RwLock<Settings>>, song: Song) -> Self {
         Self {
             library,
+            settings,
             song,
+            thumbnail: None,
         }
     }
 
-    pub fn view(&self) -> Element<Message> {
-        Row::new()
+    /// Songs can only be cropped once, and only if ffmpeg is installed to do it.
+    fn can_crop(&self) -> bool {
+        !self.song.metadata.is_cropped && preflight::ffmpeg_found()
+    }
+
+    pub fn view(&self, inline_edit: Option<&InlineEdit>, selected: bool) -> Element<Message> {
+        let row = Row::new()
             .padding(10)
             .spacing(10)
             .align_items(Alignment::Center)
-            .push_if_let(&self.song.metadata.album_art, |art|
-                Image::new(Handle::from_memory(art.data.clone()))
-                    .width(Length::Units(100))
+            .push({
+                let song = self.song.clone();
+                Checkbox::new(selected, "", move |_| SongListMessage::ToggleSelect(song.clone()).into())
+            })
+            .push(self.thumbnail_image())
+            .push_if(self.song.metadata.is_pinned, ||
+                Text::new(tr(Key::PinnedBadge)).size(12).color([0.2, 0.4, 0.8])
+            )
+            .push_if(self.song.metadata.needs_metadata, ||
+                Text::new(tr(Key::NeedsMetadataBadge)).size(12).color([0.8, 0.4, 0.0])
             )
             .push(
                 Column::new()
-                    .push(Text::new(self.song.metadata.title.clone()))
-                    .push(Text::new(self.song.metadata.artist.clone()).color([0.3, 0.3, 0.3]))
+                    .push(self.inline_editable(InlineEditField::Title, inline_edit, Text::new(display_order(&self.song.metadata.title))))
+                    .push(self.inline_editable(InlineEditField::Artist, inline_edit, Text::new(display_order(&self.song.metadata.artist)).color([0.3, 0.3, 0.3])))
+                    // The artist is the channel until it's edited, so there's no need to show both
+                    .push_if_let(&self.song.metadata.uploader.as_ref().filter(|u| **u != self.song.metadata.artist), |uploader|
+                        Text::new(tr_args(Key::SongFromUploader, &[uploader])).size(14).color([0.5, 0.5, 0.5])
+                    )
             )
             .push(Space::with_width(Length::Fill))
+            .push(
+                // Songs downloaded before lengths were recorded don't have one
+                Text::new(self.song.metadata.duration.map_or_else(|| "-".to_string(), format_duration))
+                    .color([0.3, 0.3, 0.3])
+                    .width(Length::Units(60))
+                    .horizontal_alignment(Horizontal::Right)
+            )
             // TODO: these buttons aren't responsive at all!
             // Too long a title will cause these to go tiny
             .push(
@@ -240,8 +1957,8 @@ impl SongView {
                     .width(Length::Units(40))
             )
             .push(
-                Button::new(Image::new(if self.song.metadata.is_cropped { assets::CROP_DISABLED } else { assets::CROP }))
-                    .on_press_if(!self.song.metadata.is_cropped, ContentMessage::OpenCrop(self.song.clone()).into())
+                Button::new(Image::new(if self.can_crop() { assets::CROP } else { assets::CROP_DISABLED }))
+                    .on_press_if(self.can_crop(), ContentMessage::OpenCrop(self.song.clone()).into())
                     .width(Length::Units(40))
             )
             .push(
@@ -259,6 +1976,166 @@ impl SongView {
                     .on_press(SongListMessage::Delete(self.song.clone()).into())
                     .width(Length::Units(40))
             )
+            .push(self.context_menu());
+
+        let row = MouseArea::new(row)
+            .on_left_press(SongListMessage::ClickRow(self.song.clone()).into())
+            .on_middle_press(SongListMessage::MiddleClickRow(self.song.clone()).into());
+
+        Container::new(row)
+            .style(ContainerStyleSheet(container::Style {
+                background: if selected { Some(Background::Color([0.85, 0.9, 1.0].into())) } else { None },
+                ..Default::default()
+            }))
+            .into()
+    }
+
+    /// The song's album art, or a placeholder if it has none or it's broken, so that every row
+    /// lines up.
+    fn thumbnail_image(&self) -> Element<Message> {
+        let width = Length::Units(THUMBNAIL_WIDTH as u16);
+        match (&self.song.metadata.album_art, &self.thumbnail) {
+            (Some(_), Some(Thumbnail::Loaded(thumbnail))) => Image::new(thumbnail.clone()).width(width).into(),
+
+            // Leave a gap while the thumbnail is loading, so the row doesn't jump around
+            (Some(_), None) => Space::new(width, width).into(),
+
+            _ => Image::new(assets::NO_ALBUM_ART).width(width).into(),
+        }
+    }
+
+    /// Wraps the text for a field so that double-clicking it begins an inline edit, or replaces it
+    /// with a text input if this field is already being edited.
+    fn inline_editable<'a>(&self, field: InlineEditField, inline_edit: Option<&InlineEdit>, text: Text) -> Element<'a, Message> {
+        match inline_edit {
+            Some(edit) if edit.field == field =>
+                TextInput::new("", &edit.value, |v| SongListMessage::InlineEditChange(v).into())
+                    .on_submit(SongListMessage::CommitInlineEdit.into())
+                    .padding(2)
+                    .into(),
+
+            _ =>
+                Button::new(text)
+                    .on_press(SongListMessage::ClickInlineEditable(self.song.clone(), field).into())
+                    .padding(0)
+                    .style(ButtonStyleSheet(button::Style {
+                        background: None,
+                        border_width: 0.0,
+                        ..Default::default()
+                    }))
+                    .into(),
+        }
+    }
+
+    fn context_menu(&self) -> Element<Message> {
+        let song = self.song.clone();
+
+        let mut items = vec![
+            if self.song.metadata.is_pinned { SongMenuItem::Unpin } else { SongMenuItem::Pin },
+        ];
+
+        // Songs which didn't come from YouTube have no ID to copy
+        if !self.song.metadata.youtube_id.is_empty() {
+            if self.song.metadata.source_url.is_none() {
+                items.push(SongMenuItem::CopyYouTubeId);
+            }
+            items.push(SongMenuItem::CopyYouTubeUrl);
+        }
+        items.push(SongMenuItem::CopyFilePath);
+
+        // Only whole videos can be downloaded again, since a chapter would need splitting out
+        if !self.song.metadata.youtube_id.is_empty() && self.song.metadata.chapter.is_none() {
+            items.push(SongMenuItem::Redownload);
+        }
+
+        items.push(if self.song.has_known_album() {
+            SongMenuItem::SelectAlbum(self.song.metadata.album.clone())
+        } else {
+            SongMenuItem::SelectAlbumUnavailable
+        });
+
+        // Like the crop button, templates can only be applied to songs which aren't cropped yet
+        if self.can_crop() {
+            items.extend(
+                self.settings.read().unwrap()
+                    .crop_templates
+                    .iter()
+                    .map(|t| SongMenuItem::ApplyCropTemplate(t.name.clone()))
+            );
+        }
+        items.extend(PostProcessStepKind::ALL.into_iter().map(SongMenuItem::PostProcess));
+
+        PickList::new(
+            items,
+            Some(SongMenuItem::TopLevel),
+            move |i| match i {
+                SongMenuItem::TopLevel => unreachable!(),
+                SongMenuItem::Pin | SongMenuItem::Unpin => SongListMessage::TogglePin(song.clone()).into(),
+                SongMenuItem::CopyYouTubeId => SongListMessage::CopyToClipboard(song.metadata.youtube_id.clone()).into(),
+                SongMenuItem::CopyYouTubeUrl =>
+                    SongListMessage::CopyToClipboard(MediaDownload::for_song(&song.metadata).url()).into(),
+                SongMenuItem::CopyFilePath => SongListMessage::CopyToClipboard(song.path.to_string_lossy().into_owned()).into(),
+                SongMenuItem::Redownload => SongListMessage::Redownload(song.clone()).into(),
+                SongMenuItem::SelectAlbum(_) => SongListMessage::SelectAlbum(song.clone()).into(),
+                SongMenuItem::SelectAlbumUnavailable => Message::None,
+                SongMenuItem::ApplyCropTemplate(name) => SongListMessage::ApplyCropTemplate(song.clone(), name).into(),
+                SongMenuItem::PostProcess(step) => SongListMessage::PostProcess(song.clone(), step).into(),
+            },
+        )
+            .padding(10)
             .into()
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::library::tests::metadata;
+
+    #[test]
+    fn test_restore_confirmation_compares_durations() {
+        let text = restore_confirmation_text(&metadata("abc"), Some(Duration::from_secs(150)), &Ok(Duration::from_secs(215)));
+
+        assert!(text.contains("This copy is 2:30 long, and the original is 3:35."), "{}", text);
+        assert!(text.contains("Restoring brings back 1:05 which the crop removed."), "{}", text);
+        assert!(text.contains("The metadata hasn't been edited"), "{}", text);
+        assert!(!text.contains("Post-processing"), "{}", text);
+    }
+
+    #[test]
+    fn test_restore_confirmation_ignores_tiny_differences() {
+        let text = restore_confirmation_text(&metadata("abc"), Some(Duration::from_millis(215_000)), &Ok(Duration::from_millis(215_400)));
+
+        assert!(text.contains("This copy is 3:35 long, and the original is 3:35."), "{}", text);
+        assert!(!text.contains("brings back"), "{}", text);
+    }
+
+    #[test]
+    fn test_restore_confirmation_without_current_duration() {
+        let text = restore_confirmation_text(&metadata("abc"), None, &Ok(Duration::from_secs(215)));
+
+        assert!(text.contains("The original is 3:35 long."), "{}", text);
+        assert!(!text.contains("This copy"), "{}", text);
+    }
+
+    #[test]
+    fn test_restore_confirmation_with_unreadable_original() {
+        let error = Err(LibraryError::FfmpegFailed { stderr: "no audio".into() });
+        let text = restore_confirmation_text(&metadata("abc"), Some(Duration::from_secs(150)), &error);
+
+        assert!(text.contains("The original's length couldn't be read: ffmpeg failed: no audio"), "{}", text);
+        assert!(!text.contains("2:30"), "no durations should be compared: {}", text);
+    }
+
+    #[test]
+    fn test_restore_confirmation_lists_reverted_changes() {
+        let mut metadata = metadata("abc");
+        metadata.is_metadata_edited = true;
+        metadata.is_post_processed = true;
+        let text = restore_confirmation_text(&metadata, Some(Duration::from_secs(150)), &Ok(Duration::from_secs(150)));
+
+        assert!(text.contains("Metadata edits will also be undone."), "{}", text);
+        assert!(text.contains("Post-processing will also be undone."), "{}", text);
+        assert!(!text.contains("hasn't been edited"), "{}", text);
+    }
+}
@@ -1,28 +1,190 @@
-use std::{sync::{Arc, RwLock}, io::{Cursor, BufReader}, path::{PathBuf, Path}, fs::File, time::{SystemTime, UNIX_EPOCH}};
+use std::{sync::{Arc, RwLock, atomic::{AtomicBool, Ordering}}, io::{self, Cursor, BufReader, Read}, path::{PathBuf, Path}, ffi::OsString, borrow::Cow, fs::File, time::{SystemTime, UNIX_EPOCH, Duration, Instant}, fmt::Display};
 
-use anyhow::{Result, anyhow};
 use async_process::{Command, Stdio};
+use async_io::Timer;
 use id3::frame::Picture;
-use image::ImageFormat;
+use image::{ImageFormat, DynamicImage};
 use regex::Regex;
+use sha2::{Sha256, Digest};
+use serde::{Serialize, Deserialize};
 use serde_json::Value;
-use iced::futures::{io::BufReader as AsyncBufReader, AsyncBufReadExt, StreamExt};
+use url::Url;
+use tokio::sync::watch;
+use iced::futures::{io::BufReader as AsyncBufReader, AsyncBufReadExt, AsyncReadExt, StreamExt, future::join};
 
-use crate::library::SongMetadata;
+use crate::settings::{AudioFormat, AudioQuality, PostProcessStepKind};
+use crate::ffmpeg_tags::ffmpeg_available;
+use crate::lyrics::subtitles_to_lyrics;
+use crate::activity_log::{self, Activity};
+use crate::library::{has_extension, probe_duration, library_root_of, Song, OriginalStorage, SongMetadata, AlbumArt, Chapter, LibraryError, UNKNOWN_ARTIST, UNKNOWN_ALBUM, REDOWNLOAD_DIR};
 
+/// A program which downloads videos from YouTube. yt-dlp is a maintained fork of youtube-dl, with
+/// the same command-line interface, so either can be used.
 #[derive(PartialEq, Eq, Debug, Clone)]
-pub struct YouTubeDownload {
+pub struct Downloader {
+    /// The name of the downloader's executable on the PATH, or a full path to it.
+    pub program: PathBuf,
+}
+
+impl Downloader {
+    /// Downloaders to fall back to if the configured one can't be run, most preferred first.
+    pub const FALLBACK_BINARIES: [&'static str; 2] = ["yt-dlp", "youtube-dl"];
+
+    /// Finds a downloader on the PATH which can be run, trying the `preferred` binary before the
+    /// fallbacks.
+    pub fn detect(preferred: &str) -> Option<Downloader> {
+        std::iter::once(preferred)
+            .chain(Self::FALLBACK_BINARIES)
+            .find(|binary| Self::can_run(binary.as_ref()))
+            .map(|binary| Downloader { program: binary.into() })
+    }
+
+    /// Uses the downloader executable at the given path, if it can be run.
+    pub fn at_path(path: &Path) -> Option<Downloader> {
+        if Self::can_run(path) {
+            Some(Downloader { program: path.to_path_buf() })
+        } else {
+            None
+        }
+    }
+
+    /// Asks the downloader which version it is. This blocks until it answers, so it should be run
+    /// in the background.
+    pub fn version(&self) -> Option<DownloaderVersion> {
+        let output = std::process::Command::new(&self.program)
+            .arg("--version")
+            .output()
+            .ok()?;
+        DownloaderVersion::parse(&String::from_utf8_lossy(&output.stdout))
+    }
+
+    /// Whether this is yt-dlp rather than youtube-dl, judging by the name of its executable. Only
+    /// yt-dlp can update itself reliably, since youtube-dl can't when installed with pip.
+    pub fn is_yt_dlp(&self) -> bool {
+        self.program.file_stem()
+            .map(|stem| stem.to_string_lossy().to_lowercase().starts_with("yt-dlp"))
+            .unwrap_or(false)
+    }
+
+    /// Where to find out how to update this downloader.
+    pub fn update_instructions_url(&self) -> &'static str {
+        if self.is_yt_dlp() {
+            "https://github.com/yt-dlp/yt-dlp#update"
+        } else {
+            "https://github.com/ytdl-org/youtube-dl#how-do-i-update-youtube-dl"
+        }
+    }
+
+    /// Runs yt-dlp's self-update, returning what it printed. This blocks until the update has
+    /// finished, so it should be run in the background.
+    pub fn self_update(&self) -> Result<String, DownloadError> {
+        let output = std::process::Command::new(&self.program)
+            .arg("-U")
+            .output()
+            .map_err(|e| DownloadError::SpawnFailed(Arc::new(e)))?;
+
+        let printed = format!("{}{}", String::from_utf8_lossy(&output.stdout), String::from_utf8_lossy(&output.stderr));
+        if output.status.success() {
+            Ok(printed.trim().to_string())
+        } else {
+            eprint!("{}", printed);
+            Err(DownloadError::non_zero_exit(output.status.code(), &String::from_utf8_lossy(&output.stderr)))
+        }
+    }
+
+    /// Whether the given program exists and runs successfully.
+    fn can_run(program: &Path) -> bool {
+        std::process::Command::new(program)
+            .arg("--version")
+            .output()
+            .map(|output| output.status.success())
+            .unwrap_or(false)
+    }
+}
+
+/// The version of a downloader. youtube-dl and yt-dlp both name their releases by date, like
+/// "2021.12.17", and yt-dlp sometimes adds a fourth part for extra releases on the same day, which
+/// is ignored.
+#[derive(PartialEq, Eq, PartialOrd, Ord, Debug, Clone, Copy)]
+pub struct DownloaderVersion {
+    pub year: u32,
+    pub month: u32,
+    pub day: u32,
+}
+
+impl DownloaderVersion {
+    /// The oldest version which is known to still work with YouTube. This is youtube-dl's last
+    /// release, so that it isn't always reported as outdated - but anything older than it
+    /// certainly is.
+    pub const MINIMUM: DownloaderVersion = DownloaderVersion { year: 2021, month: 12, day: 17 };
+
+    /// Parses a version as printed by `--version`, like "2021.12.17" or "2022.07.18.1".
+    pub fn parse(version: &str) -> Option<DownloaderVersion> {
+        let mut parts = version.trim().split('.').map(|part| part.parse::<u32>().ok());
+        Some(DownloaderVersion {
+            year: parts.next()??,
+            month: parts.next()??,
+            day: parts.next()??,
+        })
+    }
+
+    pub fn is_outdated(&self) -> bool {
+        *self < Self::MINIMUM
+    }
+}
+
+impl Display for DownloaderVersion {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{:04}.{:02}.{:02}", self.year, self.month, self.day)
+    }
+}
+
+/// A video to download the audio of. This is usually on YouTube, but can be on any site which
+/// youtube-dl supports, like SoundCloud or Bandcamp.
+#[derive(PartialEq, Eq, Debug, Clone)]
+pub struct MediaDownload {
+    /// The video's ID if it's on YouTube, or otherwise the URL it was downloaded from, exactly as
+    /// it was given. This is what the download is known by until it's finished - once it is, the
+    /// song gets a stable ID from youtube-dl's info JSON instead, in [`SongMetadata::youtube_id`].
     pub id: String,
 }
 
+/// What a download is busy with, from looking the video up to saving the finished song.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum DownloadStage {
+    FetchingMetadata,
+    DownloadingAudio,
+
+    /// youtube-dl is having ffmpeg convert the download into the chosen format.
+    ExtractingAudio,
+    ProcessingThumbnail,
+    WritingTags,
+}
+
 pub struct YouTubeDownloadProgress {
     pub progress: f32,
+    pub stage: DownloadStage,
     pub metadata: Option<SongMetadata>,
+
+    /// The approximate size of the download in bytes, if youtube-dl knew it.
+    pub estimated_size: Option<u64>,
+
+    /// The post-processing step being run, once the download itself has finished.
+    pub post_process_step: Option<PostProcessStepKind>,
+
+    /// While the download is being split by its chapters, the index of the chapter being cut and
+    /// how many chapters there are.
+    pub splitting_chapter: Option<(usize, usize)>,
 }
 
 impl YouTubeDownloadProgress {
     pub fn new() -> Self {
-        Self { progress: 0.0, metadata: None }
+        Self { progress: 0.0, stage: DownloadStage::FetchingMetadata, metadata: None, estimated_size: None, post_process_step: None, splitting_chapter: None }
+    }
+
+    /// Approximately how many bytes have been downloaded so far, if the size is known.
+    pub fn downloaded_size(&self) -> Option<u64> {
+        self.estimated_size.map(|size| (size as f64 * self.progress as f64 / 100.0) as u64)
     }
 }
 
@@ -30,209 +192,1765 @@ impl Default for YouTubeDownloadProgress {
     fn default() -> Self { Self::new() }
 }
 
-impl YouTubeDownload {
+/// How a download should be carried out, according to the user's settings.
+#[derive(Debug, Clone)]
+pub struct DownloadOptions {
+    pub format: AudioFormat,
+    pub quality: AudioQuality,
+
+    /// The post-processing steps to run on the song once it's downloaded, in order.
+    pub pipeline: Vec<PostProcessStepKind>,
+
+    /// The loudness which [`PostProcessStepKind::NormalizeLoudness`] brings songs to, in LUFS.
+    pub loudness_target: i32,
+
+    /// If ffmpeg isn't installed, youtube-dl can't convert the audio. If this is true, the best
+    /// audio which YouTube already has in a format the library understands is downloaded instead,
+    /// and `format`, `quality` and `pipeline` are ignored.
+    pub allow_without_ffmpeg: bool,
+
+    /// Whether to split the artist out of video titles like "Artist - Song", and trim noise like
+    /// "(Official Video)" off them - see [`artist_and_title`].
+    pub parse_artist_from_title: bool,
+
+    /// If set, album art larger than this in either dimension is scaled down to fit.
+    pub max_album_art_dimension: Option<u32>,
+
+    /// Whether to split videos with chapters into one song per chapter - see
+    /// [`Song::split_into_chapters`].
+    pub split_chapters: bool,
+
+    /// The video's info, if it was already looked up for its preview, so that youtube-dl doesn't
+    /// need to look it up again.
+    pub prefetched_info: Option<PrefetchedInfo>,
+
+    /// If set, the fastest youtube-dl may download at, like "500K" - see [`is_valid_rate_limit`].
+    pub rate_limit: Option<String>,
+
+    /// A cookies file for youtube-dl to sign in with, for videos which need an account.
+    pub cookies_file: Option<PathBuf>,
+
+    /// Whether to fetch the video's English subtitles and embed them as the song's lyrics - see
+    /// [`subtitles_to_lyrics`].
+    pub fetch_lyrics: bool,
+
+    /// Whether subtitles which YouTube generated itself will do, if the uploader didn't write any.
+    /// These are often nonsense for music.
+    pub auto_subtitles: bool,
+
+    /// Arguments passed to youtube-dl as they are, for options CrossPlay doesn't know about -
+    /// see [`parse_extra_args`].
+    pub extra_args: Vec<String>,
+
+    /// Metadata chosen before the download started, which replaces what's found from the video.
+    pub metadata_override: MetadataOverride,
+
+    /// Told whenever the download's progress changes, so that it's shown straight away.
+    pub progress_notifier: ProgressNotifier,
+}
+
+/// Wakes whatever is showing downloads' progress whenever any of it changes, so that it's redrawn
+/// as soon as youtube-dl reports something new, rather than on a timer. Every download shares the
+/// same one.
+#[derive(Debug, Clone)]
+pub struct ProgressNotifier(Arc<watch::Sender<()>>);
+
+impl ProgressNotifier {
+    /// Creates a notifier, and the receiver which it wakes.
+    pub fn channel() -> (Self, watch::Receiver<()>) {
+        let (sender, receiver) = watch::channel(());
+        (Self(Arc::new(sender)), receiver)
+    }
+
+    pub fn notify(&self) {
+        // This only fails if nothing is listening, in which case there's nobody to tell
+        let _ = self.0.send(());
+    }
+}
+
+/// Changes a download's progress, then wakes the UI to show it. The lock is released before this
+/// returns, so it's never held across an `await`.
+fn update_progress(progress: &RwLock<YouTubeDownloadProgress>, options: &DownloadOptions, update: impl FnOnce(&mut YouTubeDownloadProgress)) {
+    update(&mut progress.write().unwrap());
+    options.progress_notifier.notify();
+}
+
+/// Metadata for a download chosen before it starts, for when the user already knows better than
+/// the video's uploader. Fields which aren't set are found from the video as usual.
+#[derive(Debug, Clone, PartialEq, Eq, Default, Serialize, Deserialize)]
+pub struct MetadataOverride {
+    pub artist: Option<String>,
+    pub album: Option<String>,
+}
+
+impl MetadataOverride {
+    /// Builds an override from what was typed in, leaving out any fields which were left blank.
+    pub fn from_inputs(artist: &str, album: &str) -> Self {
+        let non_blank = |s: &str| Some(s.trim().to_string()).filter(|s| !s.is_empty());
+        Self { artist: non_blank(artist), album: non_blank(album) }
+    }
+
+    pub fn apply(&self, metadata: &mut SongMetadata) {
+        if let Some(artist) = &self.artist {
+            metadata.artist = artist.clone();
+        }
+        if let Some(album) = &self.album {
+            metadata.album = album.clone();
+        }
+    }
+}
+
+/// youtube-dl's info JSON for a video, as found when it was previewed.
+#[derive(Debug, Clone)]
+pub struct PrefetchedInfo {
+    json: String,
+    fetched_at: Instant,
+}
+
+impl PrefetchedInfo {
+    /// Whether this was fetched recently enough to download from. The links youtube-dl finds to
+    /// download from expire after a few hours, so older info has to be looked up again.
+    pub fn is_fresh(&self) -> bool {
+        self.fetched_at.elapsed() < PREFETCHED_INFO_MAX_AGE
+    }
+}
+
+/// How long [`PrefetchedInfo`] can be used for after it's fetched. This is well within how long
+/// YouTube's links last, to leave room for the download itself.
+const PREFETCHED_INFO_MAX_AGE: Duration = Duration::from_secs(60 * 60);
+
+/// What a video is, looked up without downloading it, so that the user can check they've got the
+/// right link before downloading.
+#[derive(Debug, Clone)]
+pub struct VideoPreview {
+    pub title: String,
+    pub uploader: Option<String>,
+    pub duration: Option<Duration>,
+
+    /// How many chapters the video's uploader has marked, if any, which it could be split by.
+    pub chapter_count: usize,
+
+    /// Whether the only English subtitles the video has are ones YouTube generated itself, so that
+    /// the download can be told to skip them.
+    pub only_auto_subtitles: bool,
+
+    /// The video's thumbnail, scaled down and re-encoded as a JPEG, if it could be fetched.
+    pub thumbnail: Option<Vec<u8>>,
+
+    /// Roughly how much the download will use, if YouTube says how big the video's audio is.
+    pub estimated_size: Option<u64>,
+
+    /// Everything youtube-dl found, so that downloading the video can skip looking it up again.
+    pub info: PrefetchedInfo,
+}
+
+/// The width which [`VideoPreview`] thumbnails are scaled down to.
+const PREVIEW_THUMBNAIL_WIDTH: u32 = 120;
+
+/// How long to wait for a preview's thumbnail before going without it.
+const PREVIEW_THUMBNAIL_TIMEOUT: Duration = Duration::from_secs(10);
+
+/// How often a preview checks whether it's been cancelled while youtube-dl is running.
+const PREVIEW_CANCEL_POLL_INTERVAL: Duration = Duration::from_millis(50);
+
+/// How long to wait for the largest thumbnail to download before using the one youtube-dl wrote
+/// instead.
+const LARGEST_THUMBNAIL_TIMEOUT: Duration = Duration::from_secs(30);
+
+/// The extensions which youtube-dl might give thumbnails.
+const THUMBNAIL_EXTENSIONS: [&str; 4] = ["jpg", "jpeg", "webp", "png"];
+
+/// The extensions of the subtitle formats asked for when fetching lyrics.
+const SUBTITLE_EXTENSIONS: [&str; 2] = ["vtt", "srt"];
+
+/// How long to wait for youtube-dl's info JSON to appear after it says it's been written, before
+/// going without metadata.
+const INFO_JSON_TIMEOUT: Duration = Duration::from_secs(5);
+
+/// How often to check whether the info JSON has appeared.
+const INFO_JSON_POLL_INTERVAL: Duration = Duration::from_millis(50);
+
+/// The result of a successful download.
+#[derive(Debug, Clone)]
+pub struct DownloadOutcome {
+    pub path: PathBuf,
+
+    /// If the video was split by its chapters, the songs it was split into, in order. `path` is
+    /// the first of these, since the whole video is gone.
+    pub split_into: Vec<PathBuf>,
+
+    /// How long the downloaded audio is, across every song if it was split, if ffprobe could
+    /// tell.
+    pub audio_duration: Option<Duration>,
+
+    /// True if youtube-dl never gave us the video's info, so the song was saved with placeholder
+    /// metadata.
+    pub metadata_lookup_failed: bool,
+
+    /// True if this replaced a song already in the library, rather than adding a new one - see
+    /// [`MediaDownload::redownload`].
+    pub replaced_existing: bool,
+}
+
+/// Something which went wrong while downloading a song.
+#[derive(Debug, Clone)]
+pub enum DownloadError {
+    /// Neither yt-dlp nor youtube-dl is installed.
+    DownloaderNotFound,
+
+    /// The downloader couldn't be started.
+    SpawnFailed(Arc<io::Error>),
+
+    /// youtube-dl ran, but reported failure. The last few lines it printed to stderr are kept,
+    /// since that's where it explains why.
+    NonZeroExit { code: Option<i32>, stderr_tail: String },
+
+    /// youtube-dl claimed to succeed, but didn't leave an audio file behind.
+    AudioNotFound,
+
+    ThumbnailNotFound,
+    ThumbnailConversionFailed(String),
+
+    /// The song downloaded, but its metadata couldn't be written into it.
+    MetadataWriteFailed(LibraryError),
+
+    /// The song downloaded, but one of the post-processing steps failed on it. Steps before it
+    /// have already been applied, and steps after it weren't run.
+    PostProcessFailed { step: PostProcessStepKind, error: LibraryError },
+
+    /// The song downloaded, but couldn't be split by its chapters, so it was kept whole.
+    ChapterSplitFailed(LibraryError),
+
+    /// The song was downloaded again, but couldn't replace the copy in the library, which was
+    /// left as it was.
+    ReplaceFailed(LibraryError),
+
+    /// A video in a playlist was private, deleted, or couldn't be understood, so it was skipped.
+    PlaylistEntryUnavailable,
+
+    /// youtube-dl printed info about a video which couldn't be understood.
+    InfoUnreadable,
+
+    /// youtube-dl needed ffmpeg to convert the download, but it isn't installed.
+    FfmpegNotFound,
+
+    /// The cookies file chosen in settings doesn't exist any more. Its path isn't kept, since the
+    /// file holds the user's credentials.
+    CookiesFileMissing,
+
+    Io(Arc<io::Error>),
+}
+
+impl DownloadError {
+    /// Whether trying the download again has a reasonable chance of succeeding. youtube-dl fails
+    /// for all sorts of reasons, but network trouble and throttling are the most common.
+    ///
+    /// Some failures youtube-dl explains, like the video being private, won't go away by trying
+    /// again, so those aren't counted.
+    pub fn is_retryable(&self) -> bool {
+        match self {
+            DownloadError::NonZeroExit { .. } =>
+                matches!(self.exit_reason(), None | Some(ExitReason::RateLimited | ExitReason::Network)),
+            DownloadError::Io(_) => !self.is_disk_full(),
+            _ => false,
+        }
+    }
+
+    /// Whether the download failed because there's no space left on the disk, which the user
+    /// needs to sort out before trying again.
+    pub fn is_disk_full(&self) -> bool {
+        match self {
+            DownloadError::NonZeroExit { .. } => self.exit_reason() == Some(ExitReason::DiskFull),
+            // ENOSPC on Unix, and ERROR_HANDLE_DISK_FULL or ERROR_DISK_FULL on Windows
+            DownloadError::Io(e) if cfg!(windows) => matches!(e.raw_os_error(), Some(39 | 112)),
+            DownloadError::Io(e) => e.raw_os_error() == Some(28),
+            _ => false,
+        }
+    }
+
+    /// If youtube-dl failed and said why, the reason it gave.
+    pub fn exit_reason(&self) -> Option<ExitReason> {
+        match self {
+            DownloadError::NonZeroExit { stderr_tail, .. } => ExitReason::from_stderr(stderr_tail),
+            _ => None,
+        }
+    }
+
+    /// Builds a [`DownloadError::NonZeroExit`], keeping the end of whatever youtube-dl printed to
+    /// stderr.
+    fn non_zero_exit(code: Option<i32>, stderr: &str) -> Self {
+        let lines = stderr.lines()
+            .map(str::trim)
+            .filter(|line| !line.is_empty())
+            .collect::<Vec<_>>();
+        let stderr_tail = lines[lines.len().saturating_sub(STDERR_TAIL_LINES)..].join("\n");
+
+        DownloadError::NonZeroExit { code, stderr_tail }
+    }
+}
+
+/// How many lines of youtube-dl's stderr are kept when it fails.
+const STDERR_TAIL_LINES: usize = 3;
+
+/// A reason for failing which youtube-dl commonly gives, and which is worth explaining to the user
+/// rather than just showing them youtube-dl's output.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ExitReason {
+    /// The video has been deleted, or never existed.
+    Unavailable,
+    Private,
+
+    /// YouTube wants the user to sign in to prove their age.
+    AgeRestricted,
+
+    /// The video can't be watched from the user's country.
+    RegionBlocked,
+
+    /// The site is refusing requests because too many have been made.
+    RateLimited,
+
+    /// There's no space left on the disk the download was being saved to.
+    DiskFull,
+
+    /// ffmpeg failed while youtube-dl was using it to convert the download.
+    ConversionFailed,
+
+    /// The site couldn't be reached, or the connection to it dropped.
+    Network,
+}
+
+impl ExitReason {
+    /// Looks for a known reason in youtube-dl's stderr. More specific messages are checked first,
+    /// since YouTube often says a video is unavailable as well as why.
+    fn from_stderr(stderr: &str) -> Option<Self> {
+        let stderr = stderr.to_lowercase();
+        if stderr.contains("private video") {
+            Some(ExitReason::Private)
+        } else if stderr.contains("confirm your age") || stderr.contains("age-restricted") {
+            Some(ExitReason::AgeRestricted)
+        } else if stderr.contains("not available in your country") || stderr.contains("geo restriction") {
+            Some(ExitReason::RegionBlocked)
+        } else if stderr.contains("http error 429") || stderr.contains("too many requests") {
+            Some(ExitReason::RateLimited)
+        } else if stderr.contains("video unavailable") || stderr.contains("has been removed") {
+            Some(ExitReason::Unavailable)
+        } else if stderr.contains("no space left on device") || stderr.contains("not enough space on the disk") {
+            Some(ExitReason::DiskFull)
+        } else if stderr.contains("postprocessing:") || stderr.contains("conversion failed") {
+            Some(ExitReason::ConversionFailed)
+        } else if ["unable to download webpage", "unable to download video data", "urlopen error", "timed out", "connection reset", "name resolution", "getaddrinfo failed", "network is unreachable"]
+            .iter()
+            .any(|message| stderr.contains(message)) {
+            Some(ExitReason::Network)
+        } else {
+            None
+        }
+    }
+}
+
+impl Display for DownloadError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            DownloadError::DownloaderNotFound => write!(f, "neither yt-dlp nor youtube-dl could be found"),
+            DownloadError::SpawnFailed(e) => write!(f, "could not run downloader: {}", e),
+            DownloadError::NonZeroExit { code, stderr_tail } => {
+                match code {
+                    Some(code) => write!(f, "youtube-dl exited with code {}", code)?,
+                    None => write!(f, "youtube-dl was terminated")?,
+                }
+                if !stderr_tail.is_empty() {
+                    write!(f, ":\n{}", stderr_tail)?;
+                }
+                Ok(())
+            },
+            DownloadError::AudioNotFound => write!(f, "downloaded audio file could not be located"),
+            DownloadError::ThumbnailNotFound => write!(f, "downloaded thumbnail could not be located"),
+            DownloadError::ThumbnailConversionFailed(e) => write!(f, "could not convert thumbnail: {}", e),
+            DownloadError::MetadataWriteFailed(e) => write!(f, "could not write metadata: {}", e),
+            DownloadError::PostProcessFailed { step, error } => write!(f, "post-processing step '{}' failed: {}", step, error),
+            DownloadError::ChapterSplitFailed(e) => write!(f, "could not split into chapters: {}", e),
+            DownloadError::ReplaceFailed(e) => write!(f, "could not replace the existing song: {}", e),
+            DownloadError::PlaylistEntryUnavailable => write!(f, "playlist entry is unavailable"),
+            DownloadError::InfoUnreadable => write!(f, "video info from youtube-dl could not be parsed"),
+            DownloadError::FfmpegNotFound => write!(f, "youtube-dl could not find ffmpeg"),
+            DownloadError::CookiesFileMissing => write!(f, "the chosen cookies file does not exist"),
+            DownloadError::Io(e) => write!(f, "{}", e),
+        }
+    }
+}
+
+impl std::error::Error for DownloadError {}
+
+impl From<io::Error> for DownloadError {
+    fn from(e: io::Error) -> Self { DownloadError::Io(Arc::new(e)) }
+}
+
+impl From<image::ImageError> for DownloadError {
+    fn from(e: image::ImageError) -> Self { DownloadError::ThumbnailConversionFailed(e.to_string()) }
+}
+
+impl MediaDownload {
     pub fn new(id: impl Into<String>) -> Self {
         Self { id: id.into() }
     }
 
+    /// The video the given song was downloaded from.
+    pub fn for_song(metadata: &SongMetadata) -> Self {
+        Self::new(metadata.source_url.clone().unwrap_or_else(|| metadata.youtube_id.clone()))
+    }
+
+    /// Creates a download from whatever the user typed, which could be a link to a YouTube video,
+    /// a bare YouTube video ID, or a link to a video on any other site.
+    pub fn from_input(input: &str) -> Result<Self, NotAVideoLink> {
+        match extract_video_id(input)? {
+            VideoSource::YouTube(id) => Ok(Self::new(id)),
+            VideoSource::Url(url) => Ok(Self::new(url)),
+        }
+    }
+
+    /// Whether this is a YouTube video, rather than one from another site.
+    pub fn is_youtube(&self) -> bool {
+        matches!(extract_video_id(&self.id), Ok(VideoSource::YouTube(_)))
+    }
+
     pub fn url(&self) -> String {
-        format!("https://youtube.com/watch?v={}", self.id)
+        if self.is_youtube() {
+            format!("https://youtube.com/watch?v={}", self.id)
+        } else {
+            self.id.clone()
+        }
+    }
+
+    /// The URL to record in the song's tags, for videos which aren't on YouTube. YouTube videos
+    /// are recorded by their ID alone.
+    pub fn source_url(&self) -> Option<String> {
+        if self.is_youtube() { None } else { Some(self.id.clone()) }
+    }
+
+    /// Whether the given song was downloaded from this video.
+    pub fn is_source_of(&self, metadata: &SongMetadata) -> bool {
+        match &metadata.source_url {
+            Some(url) => *url == self.id,
+            None => metadata.youtube_id == self.id,
+        }
+    }
+
+    /// Whether youtube-dl wrote the given file for this video.
+    pub fn owns_file(&self, path: &Path) -> bool {
+        path.file_name()
+            .and_then(|n| n.to_str())
+            .map_or(false, |name| name.starts_with(&format!("{}.", self.file_stem())))
+    }
+
+    /// The name, without an extension, of the files youtube-dl writes for this video. YouTube IDs
+    /// are already safe to use in file names, but URLs aren't, so those are hashed instead.
+    fn file_stem(&self) -> String {
+        if self.is_youtube() {
+            self.id.clone()
+        } else {
+            let hash = format!("{:x}", Sha256::digest(self.id.as_bytes()));
+            format!("media-{}", &hash[..16])
+        }
     }
 
-    pub async fn download(&self, library_path: &Path, progress: Arc<RwLock<YouTubeDownloadProgress>>) -> Result<()> {
+    pub async fn download(&self, downloader: &Downloader, options: &DownloadOptions, library_path: &Path, original_storage: OriginalStorage, progress: Arc<RwLock<YouTubeDownloadProgress>>) -> Result<DownloadOutcome, DownloadError> {
         println!("[Download] Starting...");
 
         // Set up initial progress, just in case we were passed a dirty object
-        // Note: The blocks dispersed throughout this function around usages of `progress`, like
-        // this one, are to stop the compiler getting angry about passing RwLocks across thread
-        // boundaries (even though we aren't because of `drop`s)
-        {
-            let mut progress_writer = progress.write().unwrap();
-            *progress_writer = YouTubeDownloadProgress::new();
-            drop(progress_writer);
-        }
+        update_progress(&progress, options, |p| *p = YouTubeDownloadProgress::new());
 
-        let download_path = library_path.join(format!("{}.%(ext)s", self.id));
+        let download_path = library_path.join(format!("{}.%(ext)s", self.file_stem()));
         
+        let convert = !options.allow_without_ffmpeg || ffmpeg_available();
+        if !convert {
+            println!("[Download] ffmpeg not found, downloading without converting");
+        }
+
+        if let Some(cookies_file) = &options.cookies_file {
+            if !cookies_file.is_file() {
+                return Err(DownloadError::CookiesFileMissing);
+            }
+        }
+
+        // If the video was looked up for its preview, hand youtube-dl what it found, rather than
+        // having it look the video up again
+        let info_json_path = match options.prefetched_info.as_ref().filter(|info| info.is_fresh()) {
+            Some(info) => {
+                let path = std::env::temp_dir().join(format!("crossplay-{}.info.json", self.file_stem()));
+                std::fs::write(&path, &info.json)?;
+                println!("[Download] Using info from preview");
+                Some(path)
+            }
+            None => None,
+        };
+
         // Ask youtube-dl to download this video
-        let mut process = Command::new("youtube-dl")
-            .arg("--write-info-json")
-            .arg("--extract-audio")
-            .arg("--write-thumbnail")
-            .arg("--newline")
-            .arg("--audio-format")
-            .arg("mp3")
-            .arg("--output")
-            .arg(download_path.clone())
-            .arg(self.url())
+        // The arguments are collected before being passed, so that they can be logged
+        let mut args: Vec<OsString> = vec![
+            "--write-info-json".into(),
+            "--write-thumbnail".into(),
+            "--newline".into(),
+            // This is the default, but it's what lets an interrupted download carry on from where
+            // it got to, so make sure of it
+            "--continue".into(),
+        ];
+        if let Some(rate_limit) = &options.rate_limit {
+            args.extend(["--limit-rate".into(), rate_limit.into()]);
+        }
+        if let Some(cookies_file) = &options.cookies_file {
+            args.extend(["--cookies".into(), cookies_file.into()]);
+        }
+        if options.fetch_lyrics {
+            args.extend([
+                "--write-sub".into(),
+                "--sub-lang".into(),
+                "en,en-US,en-GB".into(),
+                "--sub-format".into(),
+                "vtt/srt/best".into(),
+            ]);
+            // youtube-dl prefers the uploader's subtitles when there are both
+            if options.auto_subtitles {
+                args.push("--write-auto-sub".into());
+            }
+        }
+        if convert {
+            args.extend([
+                "--extract-audio".into(),
+                "--audio-format".into(),
+                options.format.extension().into(),
+                "--audio-quality".into(),
+                options.quality.youtube_dl_arg().into(),
+            ]);
+        } else {
+            args.extend([
+                "--format".into(),
+                "bestaudio[ext=m4a]/bestaudio[ext=mp3]".into(),
+            ]);
+        }
+        args.extend(options.extra_args.iter().map(OsString::from));
+        args.extend(["--output".into(), download_path.clone().into()]);
+        match &info_json_path {
+            Some(path) => args.extend(["--load-info-json".into(), path.into()]),
+            None => args.push(self.url().into()),
+        };
+
+        // Keep where the user's credentials are out of the log, like with errors
+        let logged_args = args.iter()
+            .map(|arg| match &options.cookies_file {
+                Some(cookies_file) if arg == cookies_file.as_os_str() => "<cookies file>".to_string(),
+                _ => quote_arg(&arg.to_string_lossy()),
+            })
+            .collect::<Vec<_>>();
+        println!("[Download] Running {} {}", downloader.program.to_string_lossy(), logged_args.join(" "));
+
+        let mut process = Command::new(&downloader.program)
+            .args(&args)
             .stdout(Stdio::piped())
-            .spawn()?;
+            .stderr(Stdio::piped())
+            .spawn()
+            .map_err(|e| DownloadError::SpawnFailed(Arc::new(e)))?;
 
         let mut line_reader = AsyncBufReader::new(process.stdout.take().unwrap()).lines();
-        let json_file_regex = Regex::new("Writing video description metadata as JSON to: (.+)$").unwrap();
-        let progress_regex = Regex::new(r"\[download\]\s*(\d+\.\d+)%").unwrap();
-        while let Some(line) = line_reader.next().await {
-            let line = line?;
-
-            // Look for the line which tells us where our metadata file is
-            if let Some(captures) = json_file_regex.captures(&line) {
-                // youtube-dl says it written the file, but that's not a guarantee, sometimes it
-                // can take a little while (presumably due to disk flusing)
-                // Wait for it to exist
-                // TODO: delay between checks, maybe with timeout
-                let json_file = captures.get(1).unwrap().as_str();
-                while !PathBuf::from(json_file).exists() {}
-
-                let contents = std::fs::read_to_string(json_file)?;
-                
-                // Convert into metadata
-                {
-                    let mut progress_writer = progress.write().unwrap();
-                    progress_writer.metadata = Self::youtube_dl_output_to_metadata(contents);
-                    drop(progress_writer);
+        // yt-dlp says "video metadata" rather than "video description metadata", and prints 100%
+        // without a decimal point
+        let json_file_regex = Regex::new("Writing video (?:description )?metadata as JSON to: (.+)$").unwrap();
+        let progress_regex = Regex::new(r"\[download\]\s*(\d+(?:\.\d+)?)%").unwrap();
+        let mut largest_thumbnail_url = None;
+        let mut chapters = vec![];
+        let read_stdout = async {
+            while let Some(line) = line_reader.next().await {
+                let line = line?;
+
+                // Look for the line which tells us where our metadata file is
+                if let Some(captures) = json_file_regex.captures(&line) {
+                    // youtube-dl says it written the file, but that's not a guarantee, sometimes it
+                    // can take a little while (presumably due to disk flusing)
+                    // If it never turns up, carry on without metadata rather than waiting forever
+                    let json_file = Path::new(captures.get(1).unwrap().as_str());
+                    if wait_for_file(json_file, INFO_JSON_TIMEOUT).await {
+                        let contents = std::fs::read_to_string(json_file)?;
+
+                        // Convert into metadata
+                        let metadata = match Self::youtube_dl_output_to_metadata(&contents, options.parse_artist_from_title) {
+                            Ok(mut metadata) => {
+                                metadata.source_url = self.source_url();
+                                Some(metadata)
+                            }
+                            Err(reason) => {
+                                println!("[Download] Could not read metadata from info JSON ({}), using placeholders", reason);
+                                None
+                            }
+                        };
+                        update_progress(&progress, options, |p| {
+                            p.metadata = metadata;
+                            p.estimated_size = Self::youtube_dl_output_to_estimated_size(&contents);
+                        });
+                        largest_thumbnail_url = Self::youtube_dl_output_to_largest_thumbnail(&contents);
+                        chapters = Self::youtube_dl_output_to_chapters(&contents);
+
+                        // Delete file - we've got what we need
+                        std::fs::remove_file(json_file)?;
+                    } else {
+                        println!("[Download] Info JSON did not appear within {:?}, using placeholders", INFO_JSON_TIMEOUT);
+                    }
                 }
 
-                // Delete file - we've got what we need
-                std::fs::remove_file(json_file)?;
-            }
+                // Also look for progress updates
+                if let Some(captures) = progress_regex.captures(&line) {
+                    let percentage = captures.get(1).unwrap().as_str();
 
-            // Also look for progress updates
-            if let Some(captures) = progress_regex.captures(&line) {
-                let percentage = captures.get(1).unwrap().as_str();
+                    update_progress(&progress, options, |p| {
+                        p.progress = percentage.parse().unwrap();
+                        p.stage = DownloadStage::DownloadingAudio;
+                    });
+                }
 
-                {
-                    let mut progress_writer = progress.write().unwrap();
-                    progress_writer.progress = percentage.parse().unwrap();
-                    drop(progress_writer);
+                // Once the download has finished, youtube-dl hands it to ffmpeg to convert, which
+                // yt-dlp reports as "[ExtractAudio]" instead
+                if line.starts_with("[ffmpeg]") || line.starts_with("[ExtractAudio]") {
+                    update_progress(&progress, options, |p| p.stage = DownloadStage::ExtractingAudio);
                 }
             }
+
+            Ok::<(), DownloadError>(())
+        };
+
+        // stderr is read alongside stdout, so that neither pipe can fill up and stall youtube-dl
+        let mut stderr_pipe = process.stderr.take().unwrap();
+        let read_stderr = async move {
+            let mut stderr = String::new();
+            let _ = stderr_pipe.read_to_string(&mut stderr).await;
+            stderr
+        };
+
+        let (stdout_result, stderr) = join(read_stdout, read_stderr).await;
+        if let Some(path) = &info_json_path {
+            let _ = std::fs::remove_file(path);
         }
+        // Still show youtube-dl's errors in the console, like before they were captured
+        eprint!("{}", stderr);
+        stdout_result?;
 
         // If we never got any metadata, initialise it
         let mut metadata;
+        let metadata_lookup_failed;
         {
             let progress_reader = progress.read().unwrap();
+            metadata_lookup_failed = progress_reader.metadata.is_none();
             metadata = progress_reader.metadata.clone().unwrap_or_else(||
                 SongMetadata {
                     title: self.id.clone(),
-                    artist: "Unknown Artist".into(),
-                    album: "Unknown Album".into(),
+                    artist: UNKNOWN_ARTIST.into(),
+                    album: UNKNOWN_ALBUM.into(),
+                    genre: None,
+                    year: None,
+                    track: None,
                     youtube_id: self.id.clone(),
+                    source_url: self.source_url(),
                     album_art: None,
                     is_cropped: false,
                     is_metadata_edited: false,
+                    is_post_processed: false,
+                    is_pinned: false,
+                    needs_metadata: false,
+                    is_reviewed: false,
+                    original_hash: None,
                     download_unix_time: unix_time_now(),
+                    uploader: None,
+                    video_title: None,
+                    chapter: None,
+                    duration: None,
+                    lyrics: None,
                 }
             );
             drop(progress_reader);
-            drop(progress);
         }
 
         // Check success
         let status = process.status().await?;
-        status.exit_ok()?;
+        if !status.success() {
+            return Err(
+                if mentions_missing_ffmpeg(&stderr) {
+                    DownloadError::FfmpegNotFound
+                } else {
+                    // youtube-dl may mention the cookies file if it can't read it, but the error
+                    // is shown and logged, so keep where the user's credentials are out of it
+                    let stderr = match &options.cookies_file {
+                        Some(cookies_file) => stderr.replace(&*cookies_file.to_string_lossy(), "<cookies file>"),
+                        None => stderr,
+                    };
+                    DownloadError::non_zero_exit(status.code(), &stderr)
+                }
+            );
+        }
 
         println!("[Download] Command has zero exit status");
 
         // The download path we were working with up to this point is templated for youtube-dl with
-        // an unknown extension. Make sure we actually downloaded an MP3
-        let download_path = library_path.join(format!("{}.mp3", self.id));
-        if !download_path.exists() {
-            return Err(anyhow!("Downloaded MP3 could not be located."));
+        // an unknown extension. Make sure we actually downloaded the format we asked for - or, if
+        // it wasn't converted, any format the library understands
+        let extensions = if convert {
+            vec![options.format.extension()]
+        } else {
+            AudioFormat::ALL.iter().map(|f| f.extension()).collect()
+        };
+        let download_path = self.find_download_file(library_path, &extensions)?
+            .ok_or_else(|| if mentions_missing_ffmpeg(&stderr) { DownloadError::FfmpegNotFound } else { DownloadError::AudioNotFound })?;
+
+        update_progress(&progress, options, |p| p.stage = DownloadStage::ProcessingThumbnail);
+
+        // youtube-dl writes whichever thumbnail it prefers, which is often a small one, so try to
+        // fetch the largest listed in the info JSON instead
+        let largest_thumbnail = match largest_thumbnail_url {
+            Some(url) => tokio::task::spawn_blocking(move || fetch_image(&url, LARGEST_THUMBNAIL_TIMEOUT)).await.ok().flatten(),
+            None => None,
+        };
+        let loaded_thumbnail = match largest_thumbnail {
+            Some(image) => Ok(image),
+            None => self.load_written_thumbnail(library_path),
+        };
+
+        // Delete thumbnail files, since they're either encoded into ID3 or not needed
+        self.remove_thumbnail_files(library_path)?;
+        let mut loaded_thumbnail = loaded_thumbnail?;
+
+        // Subtitles are only worth having as lyrics, so these are removed too. Lyrics are a nicety,
+        // so the download carries on without them if they can't be read
+        let subtitle_files = self.subtitle_files(library_path)?;
+        if let Some(path) = subtitle_files.first() {
+            match std::fs::read_to_string(path) {
+                Ok(subtitles) => metadata.lyrics = subtitles_to_lyrics(&subtitles),
+                Err(e) => println!("[Download] Could not read subtitles, going without lyrics: {}", e),
+            }
+        }
+        for path in subtitle_files {
+            std::fs::remove_file(path)?;
         }
 
-        // We should've downloaded a thumbnail too, figure out where that is
-        let thumbnail_possible_extensions = ["jpg", "jpeg", "webp", "png"];
-        let thumbnail_path = thumbnail_possible_extensions
-            .iter()
-            .find_map(|ext| {
-                let path = library_path.join(format!("{}.{}", self.id, ext));
-                if path.exists() {
-                    Some(path)
-                } else {
-                    None
+        if let Some(max) = options.max_album_art_dimension {
+            if loaded_thumbnail.width() > max || loaded_thumbnail.height() > max {
+                loaded_thumbnail = loaded_thumbnail.thumbnail(max, max);
+            }
+        }
+
+        // Assign thumbnail
+        metadata.album_art = Some(AlbumArt::Loaded(cover_picture(&loaded_thumbnail)?));
+
+        println!("[Download] Build metadata object");
+
+        options.metadata_override.apply(&mut metadata);
+        update_progress(&progress, options, |p| p.stage = DownloadStage::WritingTags);
+
+        // Write metadata into file, along with the song's length. This needs ffprobe, so songs
+        // downloaded without ffmpeg go without it
+        metadata.duration = probe_duration(&download_path).ok();
+        metadata.write_into_file(&download_path).map_err(DownloadError::MetadataWriteFailed)?;
+
+        println!("[Download] Written to file");
+
+        // Splitting by chapters needs ffmpeg too, and a video with only one chapter isn't worth it
+        let song = Song::new(download_path.clone(), metadata, false, original_storage, library_root_of(library_path));
+        let mut songs = if options.split_chapters && convert && chapters.len() > 1 {
+            let chapter_count = chapters.len();
+            let split = song.clone().split_into_chapters(&chapters, |i|
+                update_progress(&progress, options, |p| p.splitting_chapter = Some((i, chapter_count)))
+            );
+            update_progress(&progress, options, |p| p.splitting_chapter = None);
+
+            match split {
+                Ok(songs) => songs,
+                Err(e) => {
+                    // The whole song is still there, so it's kept rather than thrown away
+                    activity_log::record(&song, Activity::Downloaded, self.url());
+                    return Err(DownloadError::ChapterSplitFailed(e));
                 }
-            })
-            .ok_or_else(|| anyhow!("Downloaded thumbnail could not be located."))?;
+            }
+        } else {
+            vec![song]
+        };
+        let split_into = if songs.len() > 1 { songs.iter().map(|s| s.path.clone()).collect() } else { vec![] };
+
+        // Run the user's post-processing steps on each song, stopping at the first which fails.
+        // These need ffmpeg, so they're skipped if the song couldn't be converted
+        let pipeline: &[PostProcessStepKind] = if convert { &options.pipeline } else { &[] };
+        for song in &mut songs {
+            activity_log::record(song, Activity::Downloaded, self.url());
+            for step in pipeline {
+                update_progress(&progress, options, |p| p.post_process_step = Some(*step));
+
+                song.post_process(*step, options.loudness_target).map_err(|error| DownloadError::PostProcessFailed { step: *step, error })?;
+            }
+        }
+
+        let audio_duration = songs.iter().map(|s| s.duration().ok()).sum();
+
+        Ok(DownloadOutcome { path: songs[0].path.clone(), split_into, audio_duration, metadata_lookup_failed, replaced_existing: false })
+    }
+
+    /// Downloads this video again to replace `song`, keeping its tags - see
+    /// [`Song::replace_with_download`]. The new copy is downloaded into the library's
+    /// [`REDOWNLOAD_DIR`] first, so the song is left alone if the download fails.
+    ///
+    /// The point is to start again from a clean copy, so the video isn't split by its chapters,
+    /// and none of the post-processing in `options` is done.
+    pub async fn redownload(&self, mut song: Song, downloader: &Downloader, options: &DownloadOptions, library_path: &Path, original_storage: OriginalStorage, progress: Arc<RwLock<YouTubeDownloadProgress>>) -> Result<DownloadOutcome, DownloadError> {
+        let staging_path = library_path.join(REDOWNLOAD_DIR);
+        std::fs::create_dir_all(&staging_path)?;
+
+        let options = DownloadOptions {
+            pipeline: vec![],
+            split_chapters: false,
+            metadata_override: MetadataOverride::default(),
+            ..options.clone()
+        };
+        let outcome = self.download(downloader, &options, &staging_path, original_storage, progress).await?;
+
+        println!("[Download] Replacing {}", song.path.to_string_lossy());
+        if let Err(e) = song.replace_with_download(&outcome.path) {
+            let _ = std::fs::remove_file(&outcome.path);
+            return Err(DownloadError::ReplaceFailed(e));
+        }
+
+        Ok(DownloadOutcome {
+            path: song.path,
+            split_into: vec![],
+            audio_duration: outcome.audio_duration,
+            // The song's own tags were kept, so it doesn't matter whether the video's were found
+            metadata_lookup_failed: false,
+            replaced_existing: true,
+        })
+    }
+
+    /// Asks youtube-dl about this video without downloading it, and fetches its thumbnail.
+    ///
+    /// This blocks until both have finished, so it should be run in the background. Failing to
+    /// fetch the thumbnail isn't an error, since the rest of the preview is still useful.
+    ///
+    /// If `cancelled` is set while this is running, youtube-dl is killed and an error is returned,
+    /// which should be ignored.
+    pub fn preview(&self, downloader: &Downloader, cancelled: &AtomicBool) -> Result<VideoPreview, DownloadError> {
+        let mut process = std::process::Command::new(&downloader.program)
+            .arg("--dump-json")
+            .arg("--skip-download")
+            .arg(self.url())
+            .stdout(std::process::Stdio::piped())
+            .stderr(std::process::Stdio::piped())
+            .spawn()
+            .map_err(|e| DownloadError::SpawnFailed(Arc::new(e)))?;
+
+        // The pipes are read on their own threads, so that neither can fill up and stall
+        // youtube-dl while this is waiting to see if it's been cancelled
+        let read_pipe = |mut pipe: Box<dyn Read + Send>| std::thread::spawn(move || {
+            let mut contents = vec![];
+            let _ = pipe.read_to_end(&mut contents);
+            contents
+        });
+        let stdout = read_pipe(Box::new(process.stdout.take().unwrap()));
+        let stderr = read_pipe(Box::new(process.stderr.take().unwrap()));
+
+        let status = loop {
+            if cancelled.load(Ordering::Relaxed) {
+                let _ = process.kill();
+            }
+            if let Some(status) = process.try_wait()? {
+                break status;
+            }
+            std::thread::sleep(PREVIEW_CANCEL_POLL_INTERVAL);
+        };
+        let stdout = stdout.join().unwrap_or_default();
+        let stderr = stderr.join().unwrap_or_default();
+        if !status.success() {
+            return Err(DownloadError::non_zero_exit(status.code(), &String::from_utf8_lossy(&stderr)));
+        }
+
+        let json: Value = serde_json::from_slice(&stdout).map_err(|_| DownloadError::InfoUnreadable)?;
+        let title = json["title"].as_str().ok_or(DownloadError::InfoUnreadable)?.to_string();
+
+        // The thumbnail isn't worth waiting for if nobody's going to see it
+        let thumbnail = if cancelled.load(Ordering::Relaxed) {
+            None
+        } else {
+            json["thumbnail"].as_str().and_then(Self::fetch_preview_thumbnail)
+        };
+
+        Ok(VideoPreview {
+            title,
+            uploader: json["uploader"].as_str().or_else(|| json["channel"].as_str()).map(Into::into),
+            duration: json["duration"].as_f64().map(Duration::from_secs_f64),
+            chapter_count: json["chapters"].as_array().map_or(0, Vec::len),
+            only_auto_subtitles: !has_english_subtitles(&json["subtitles"]) && has_english_subtitles(&json["automatic_captions"]),
+            thumbnail,
+            estimated_size: Self::info_json_to_estimated_audio_size(&json),
+            info: PrefetchedInfo { json: String::from_utf8_lossy(&stdout).into_owned(), fetched_at: Instant::now() },
+        })
+    }
+
+    fn fetch_preview_thumbnail(url: &str) -> Option<Vec<u8>> {
+        let image = fetch_image(url, PREVIEW_THUMBNAIL_TIMEOUT)?.thumbnail(PREVIEW_THUMBNAIL_WIDTH, u32::MAX);
+        let mut jpeg_bytes = Cursor::new(vec![]);
+        image.write_to(&mut jpeg_bytes, ImageFormat::Jpeg).ok()?;
+
+        Some(jpeg_bytes.into_inner())
+    }
+
+    /// Loads the thumbnail which youtube-dl wrote alongside the download.
+    fn load_written_thumbnail(&self, library_path: &Path) -> Result<DynamicImage, DownloadError> {
+        let thumbnail_path = self.find_download_file(library_path, &THUMBNAIL_EXTENSIONS)?
+            .ok_or(DownloadError::ThumbnailNotFound)?;
 
-        // Convert to JPEG
         // Originally, this tried to be clever and only convert if the image was a WEBP - but
         // YouTube sometimes lies and sends us WEBPs with a .jpg extension
         // https://github.com/ytdl-org/youtube-dl/issues/29754 
         // Using image::io::Reader rather than image::open lets us use `with_guessed_format`, which
         // guesses using content instead of path, circumventing this
         let reader = BufReader::new(File::open(&thumbnail_path)?);
-        let loaded_file = image::io::Reader::new(reader)
+        Ok(image::io::Reader::new(reader)
             .with_guessed_format()?
-            .decode()?;
-        let mut jpeg_bytes = Cursor::new(vec![]);
-        loaded_file.write_to(&mut jpeg_bytes, ImageFormat::Jpeg)?;
-        let thumbnail_data = jpeg_bytes.into_inner();
-
-        // Convert thumbnail into an ID3 picture
-        let thumbnail_picture = Picture {
-            mime_type: "image/jpeg".to_string(),
-            picture_type: id3::frame::PictureType::CoverFront,
-            description: "Cover".to_string(),
-            data: thumbnail_data,
-        };
+            .decode()?)
+    }
 
-        // Delete thumbnail file, since it's now encoded into ID3
-        std::fs::remove_file(thumbnail_path)?;
-            
-        // Assign thumbnail
-        metadata.album_art = Some(thumbnail_picture); 
+    /// Deletes every thumbnail youtube-dl wrote for this video. Depending on the downloader and its
+    /// configuration, these can be named like `ID.jpg`, `ID_0.jpg` or `ID.0.jpg`.
+    fn remove_thumbnail_files(&self, library_path: &Path) -> Result<(), DownloadError> {
+        for entry in std::fs::read_dir(library_path)? {
+            let path = entry?.path();
+            let stem = match path.file_stem().and_then(|s| s.to_str()) {
+                Some(stem) => stem,
+                None => continue,
+            };
 
-        println!("[Download] Build metadata object");
+            let own_stem = self.file_stem();
+            let belongs_to_video = stem == own_stem
+                || stem.strip_prefix(own_stem.as_str()).map_or(false, |rest| rest.starts_with(['_', '.']));
+            if belongs_to_video && THUMBNAIL_EXTENSIONS.iter().any(|ext| has_extension(&path, ext)) {
+                std::fs::remove_file(&path)?;
+            }
+        }
 
-        // Write metadata into file
-        metadata.write_into_file(&download_path)?;
+        Ok(())
+    }
 
-        println!("[Download] Written to file");
+    /// Finds the subtitles youtube-dl wrote for this video, which are named with their language too,
+    /// like `ID.en.vtt`.
+    fn subtitle_files(&self, library_path: &Path) -> Result<Vec<PathBuf>, DownloadError> {
+        let prefix = format!("{}.", self.file_stem());
+        let mut files = vec![];
+        for entry in std::fs::read_dir(library_path)? {
+            let path = entry?.path();
+            let belongs_to_video = path.file_name().and_then(|n| n.to_str()).map_or(false, |n| n.starts_with(&prefix));
+            if belongs_to_video && SUBTITLE_EXTENSIONS.iter().any(|ext| has_extension(&path, ext)) {
+                files.push(path);
+            }
+        }
 
-        Ok(())
+        Ok(files)
     }
 
-    fn youtube_dl_output_to_metadata(string: String) -> Option<SongMetadata> {
-        let stdout_json: Value = serde_json::from_str(&string).ok()?;
+    /// Finds a file which youtube-dl wrote for this video, named by its ID with one of the given
+    /// extensions, in any case.
+    fn find_download_file(&self, library_path: &Path, extensions: &[&str]) -> Result<Option<PathBuf>, DownloadError> {
+        for entry in std::fs::read_dir(library_path)? {
+            let path = entry?.path();
+            if path.file_stem() == Some(self.file_stem().as_ref()) && extensions.iter().any(|ext| has_extension(&path, ext)) {
+                return Ok(Some(path))
+            }
+        }
+
+        Ok(None)
+    }
+
+    /// Builds metadata from youtube-dl's info JSON, or describes what was missing from it.
+    fn youtube_dl_output_to_metadata(string: &str, parse_artist_from_title: bool) -> Result<SongMetadata, String> {
+        let stdout_json: Value = serde_json::from_str(string).map_err(|e| format!("invalid JSON: {}", e))?;
         
-        Some(SongMetadata {
-            title: stdout_json["title"].as_str()?.into(),
-            artist: stdout_json["uploader"].as_str()?.into(),
-            album: "Unknown Album".into(),
-            youtube_id: stdout_json["id"].as_str()?.into(),
+        let video_title = stdout_json["title"].as_str().ok_or("no title")?;
+        let uploader = stdout_json["uploader"].as_str().ok_or("no uploader")?;
+        let (artist, title) = artist_and_title(uploader, video_title, parse_artist_from_title);
+
+        // Videos on other sites could share an ID with a YouTube video, or each other, so they're
+        // told apart by which of youtube-dl's extractors found them
+        let id = stdout_json["id"].as_str().ok_or("no ID")?;
+        let stable_id = match stdout_json["extractor"].as_str().map(str::to_lowercase) {
+            Some(extractor) if extractor != "youtube" => format!("{}:{}", extractor, id),
+            _ => id.to_string(),
+        };
+
+        Ok(SongMetadata {
+            title,
+            artist,
+            album: UNKNOWN_ALBUM.into(),
+            genre: None,
+            // Dates are given like 20220131
+            year: stdout_json["upload_date"].as_str().and_then(|d| d.get(..4)).and_then(|y| y.parse().ok()),
+            track: None,
+            youtube_id: stable_id,
+            // Filled in by the download, which knows where it came from
+            source_url: None,
             album_art: None,
             is_cropped: false,
             is_metadata_edited: false,
+            is_post_processed: false,
+            is_pinned: false,
+            needs_metadata: false,
+            is_reviewed: false,
+            original_hash: None,
             download_unix_time: unix_time_now(),
+            uploader: stdout_json["uploader"].as_str().or_else(|| stdout_json["channel"].as_str()).map(Into::into),
+            video_title: Some(video_title.into()),
+            chapter: None,
+            duration: None,
+            // Filled in from the subtitles, if they were asked for
+            lyrics: None,
         })
     }
+
+    /// Finds the URL of the largest thumbnail in youtube-dl's info JSON. Thumbnails without a known
+    /// size are only considered if none have one, in which case youtube-dl's own pick is used.
+    fn youtube_dl_output_to_largest_thumbnail(string: &str) -> Option<String> {
+        let stdout_json: Value = serde_json::from_str(string).ok()?;
+
+        let largest = stdout_json["thumbnails"].as_array()
+            .into_iter()
+            .flatten()
+            .filter_map(|t| Some((t["url"].as_str()?, t["width"].as_u64()? * t["height"].as_u64()?)))
+            .max_by_key(|(_, area)| *area)
+            .map(|(url, _)| url);
+
+        largest.or_else(|| stdout_json["thumbnail"].as_str()).map(Into::into)
+    }
+
+    /// Reads the chapters the uploader marked from youtube-dl's info JSON, in order. Chapters with no
+    /// title are numbered instead, and any which can't be understood are left out.
+    fn youtube_dl_output_to_chapters(string: &str) -> Vec<Chapter> {
+        let stdout_json: Value = match serde_json::from_str(string) {
+            Ok(json) => json,
+            Err(_) => return vec![],
+        };
+
+        stdout_json["chapters"].as_array()
+            .into_iter()
+            .flatten()
+            .enumerate()
+            .filter_map(|(i, c)| {
+                let start = c["start_time"].as_f64()?;
+                let end = c["end_time"].as_f64()?;
+                let title = c["title"].as_str()
+                    .map(str::trim)
+                    .filter(|t| !t.is_empty())
+                    .map_or_else(|| format!("Chapter {}", i + 1), Into::into);
+                (end > start).then(|| Chapter { title, start: Duration::from_secs_f64(start), end: Duration::from_secs_f64(end) })
+            })
+            .collect()
+    }
+
+    /// Reads the size of the format youtube-dl picked from its info JSON. Some formats only have an
+    /// estimate, which is used if an exact size isn't available.
+    fn youtube_dl_output_to_estimated_size(string: &str) -> Option<u64> {
+        let stdout_json: Value = serde_json::from_str(string).ok()?;
+
+        ["filesize", "filesize_approx"]
+            .iter()
+            .find_map(|key| stdout_json[key].as_f64())
+            .map(|size| size as u64)
+    }
+
+    /// Estimates the size of the audio a download will fetch, from info JSON which youtube-dl
+    /// wasn't told which format to use for. The top-level size is then of the whole video, so the
+    /// largest audio-only format is looked at instead, falling back to the top-level size.
+    fn info_json_to_estimated_audio_size(json: &Value) -> Option<u64> {
+        let size = |format: &Value| ["filesize", "filesize_approx"]
+            .iter()
+            .find_map(|key| format[key].as_f64())
+            .map(|size| size as u64);
+
+        json["formats"]
+            .as_array()
+            .and_then(|formats| formats.iter()
+                .filter(|format| format["vcodec"].as_str() == Some("none"))
+                .filter_map(size)
+                .max())
+            .or_else(|| size(json))
+    }
+}
+
+/// A YouTube playlist, whose videos can be downloaded all at once.
+#[derive(PartialEq, Eq, Debug, Clone)]
+pub struct YouTubePlaylist {
+    pub id: String,
+}
+
+/// A video listed in a [`YouTubePlaylist`].
+#[derive(Debug, Clone)]
+pub enum PlaylistEntry {
+    Video(MediaDownload),
+
+    /// An entry which can't be downloaded. Entries without a usable ID are identified by their
+    /// position in the playlist instead.
+    Unavailable(MediaDownload, DownloadError),
+}
+
+impl YouTubePlaylist {
+    pub fn new(id: impl Into<String>) -> Self {
+        Self { id: id.into() }
+    }
+
+    pub fn url(&self) -> String {
+        format!("https://youtube.com/playlist?list={}", self.id)
+    }
+
+    /// Asks youtube-dl which videos are in this playlist, without downloading any of them.
+    pub async fn list_entries(&self, downloader: &Downloader) -> Result<Vec<PlaylistEntry>, DownloadError> {
+        let output = Command::new(&downloader.program)
+            .arg("--flat-playlist")
+            .arg("--dump-json")
+            .arg(self.url())
+            .output()
+            .await
+            .map_err(|e| DownloadError::SpawnFailed(Arc::new(e)))?;
+
+        // youtube-dl reports failure if any entries are unavailable, but it still lists the rest,
+        // so only give up if it didn't list anything
+        let stdout = String::from_utf8_lossy(&output.stdout);
+        if !output.status.success() && stdout.trim().is_empty() {
+            return Err(DownloadError::non_zero_exit(output.status.code(), &String::from_utf8_lossy(&output.stderr)));
+        }
+
+        Ok(
+            stdout
+                .lines()
+                .filter(|line| !line.trim().is_empty())
+                .enumerate()
+                .map(|(i, line)| self.parse_entry(i, line))
+                .collect()
+        )
+    }
+
+    fn parse_entry(&self, index: usize, line: &str) -> PlaylistEntry {
+        let json: Value = match serde_json::from_str(line) {
+            Ok(json) => json,
+            Err(_) => return self.unavailable_entry_at(index),
+        };
+        let id = match json["id"].as_str() {
+            Some(id) => id,
+            None => return self.unavailable_entry_at(index),
+        };
+
+        // Private and deleted videos are still listed, but only with a placeholder title
+        let download = MediaDownload::new(id);
+        match json["title"].as_str() {
+            Some("[Private video]" | "[Deleted video]") =>
+                PlaylistEntry::Unavailable(download, DownloadError::PlaylistEntryUnavailable),
+            _ => PlaylistEntry::Video(download),
+        }
+    }
+
+    fn unavailable_entry_at(&self, index: usize) -> PlaylistEntry {
+        PlaylistEntry::Unavailable(
+            MediaDownload::new(format!("{} #{}", self.id, index + 1)),
+            DownloadError::PlaylistEntryUnavailable,
+        )
+    }
+}
+
+/// Downloads an image, or returns `None` if it couldn't be fetched or decoded. YouTube's
+/// thumbnails might be WEBPs whatever their extension says, so the format is guessed from the
+/// content.
+///
+/// This blocks until the download has finished, so it should be run in the background.
+fn fetch_image(url: &str, timeout: Duration) -> Option<DynamicImage> {
+    let bytes = reqwest::blocking::Client::builder()
+        .timeout(timeout)
+        .build().ok()?
+        .get(url)
+        .send().ok()?
+        .error_for_status().ok()?
+        .bytes().ok()?;
+
+    image::load_from_memory(&bytes).ok()
+}
+
+/// Waits until a file exists, checking every [`INFO_JSON_POLL_INTERVAL`] without blocking. Returns
+/// whether it appeared before `timeout` was up.
+async fn wait_for_file(path: &Path, timeout: Duration) -> bool {
+    let started = Instant::now();
+    while !path.exists() {
+        if started.elapsed() >= timeout {
+            return false;
+        }
+        Timer::after(INFO_JSON_POLL_INTERVAL).await;
+    }
+    true
+}
+
+/// Suffixes which channels add to an artist's name, but which aren't part of it - like
+/// "Artist - Topic" for YouTube's auto-generated channels, or "ArtistVEVO".
+const ARTIST_NOISE_SUFFIXES: [&str; 3] = [" - Topic", "VEVO", " Official"];
+
+/// Dashes which separate the artist from the song in video titles like "Artist - Song".
+const TITLE_SEPARATORS: [&str; 3] = [" - ", " \u{2013} ", " \u{2014} "];
+
+/// Trailing bits of video titles which describe the video rather than the song, like
+/// "(Official Video)", "[Official Audio]" or "(Lyrics)".
+const TITLE_NOISE_PATTERN: &str = r"(?i)\s*[(\[]\s*(?:official\s+)?(?:hd\s+|4k\s+)?(?:music\s+|lyric\s+)?(?:video|audio|lyrics|visuali[sz]er)(?:\s+hd)?\s*[)\]]\s*$";
+
+/// Works out a song's artist and title from the channel and title of the video it came from.
+///
+/// Noise like "- Topic" is trimmed off the end of the channel's name. If `parse_title` is true,
+/// noise like "(Official Video)" is trimmed off the end of the video's title, and then if it looks
+/// like "Artist - Song", it's split at the first dash. The artist from the title is used rather
+/// than the channel, since channels often belong to labels rather than artists.
+fn artist_and_title(uploader: &str, video_title: &str, parse_title: bool) -> (String, String) {
+    if !parse_title {
+        return (strip_artist_noise(uploader).to_string(), video_title.trim().to_string());
+    }
+
+    // There can be more than one, like "(Official Video) [HD Audio]"
+    let noise_regex = Regex::new(TITLE_NOISE_PATTERN).unwrap();
+    let mut video_title = video_title.trim();
+    while let Some(noise) = noise_regex.find(video_title) {
+        if noise.start() == 0 {
+            break;
+        }
+        video_title = &video_title[..noise.start()];
+    }
+
+    let split = TITLE_SEPARATORS.iter()
+        .filter_map(|sep| video_title.find(sep).map(|i| (i, sep.len())))
+        .min();
+
+    if let Some((i, len)) = split {
+        let (artist, title) = (video_title[..i].trim(), video_title[i + len..].trim());
+        if !artist.is_empty() && !title.is_empty() {
+            return (strip_artist_noise(artist).to_string(), title.to_string());
+        }
+    }
+
+    (strip_artist_noise(uploader).to_string(), video_title.trim().to_string())
+}
+
+/// Trims any [`ARTIST_NOISE_SUFFIXES`] off the end of an artist's name, unless that would leave
+/// nothing.
+fn strip_artist_noise(artist: &str) -> &str {
+    let mut artist = artist.trim();
+    for suffix in ARTIST_NOISE_SUFFIXES {
+        // Channels write "VEVO" and "Vevo", so compare case-insensitively
+        let split_at = artist.len().saturating_sub(suffix.len());
+        let matches = artist.get(split_at..).map_or(false, |tail| tail.eq_ignore_ascii_case(suffix));
+        if matches && split_at > 0 {
+            artist = artist[..split_at].trim_end();
+        }
+    }
+    artist
+}
+
+/// Whether youtube-dl's error output says that it couldn't find ffmpeg. youtube-dl and yt-dlp word
+/// this differently, and yt-dlp only warns if it could download something without it.
+fn mentions_missing_ffmpeg(stderr: &str) -> bool {
+    let regex = Regex::new(r"ffmpeg(?:/avconv)? not found").unwrap();
+    stderr.lines().any(|line| regex.is_match(line))
+}
+
+/// Whether a map of subtitles from youtube-dl's info JSON, keyed by language, has any in English.
+/// These can be regional, like "en-GB".
+fn has_english_subtitles(subtitles: &Value) -> bool {
+    subtitles.as_object().map_or(false, |languages| languages.keys().any(|lang| lang == "en" || lang.starts_with("en-")))
+}
+
+/// If the given string is a link to a YouTube playlist, extracts the playlist's ID. Links to a
+/// video which happen to be played from within a playlist aren't counted.
+pub fn extract_playlist_id(string: &str) -> Option<&str> {
+    let playlist_url_regex = Regex::new(r"youtube.com/playlist\?(?:.*&)?list=([^&]+)&?").unwrap();
+
+    playlist_url_regex.captures(string).map(|c| c.get(1).unwrap().as_str())
+}
+
+/// Whether youtube-dl left this file behind part-way through a download. These are named like
+/// `ID.webm.part`, `ID.f251.webm.part-Frag3` or `ID.webm.ytdl`.
+pub fn is_partial_download_file(path: &Path) -> bool {
+    match path.file_name().and_then(|n| n.to_str()) {
+        Some(name) => name.ends_with(".part") || name.ends_with(".ytdl") || name.contains(".part-Frag"),
+        None => false,
+    }
+}
+
+/// Lists the files in the library folder which youtube-dl left behind part-way through downloads.
+pub fn partial_download_files(library_path: &Path) -> io::Result<Vec<PathBuf>> {
+    let mut files = vec![];
+    for entry in std::fs::read_dir(library_path)? {
+        let path = entry?.path();
+        if is_partial_download_file(&path) {
+            files.push(path);
+        }
+    }
+    Ok(files)
+}
+
+/// Hosts which serve YouTube's own pages, where a video's ID is somewhere in the link's path or
+/// query.
+const YOUTUBE_HOSTS: &[&str] = &[
+    "youtube.com", "www.youtube.com", "m.youtube.com", "music.youtube.com",
+    "youtube-nocookie.com", "www.youtube-nocookie.com",
+];
+
+/// The host of YouTube's short share links, where the video's ID is the whole path.
+const SHORT_LINK_HOST: &str = "youtu.be";
+
+/// Where a video to download is, as worked out by [`extract_video_id`].
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum VideoSource {
+    /// A YouTube video, by its ID.
+    YouTube(String),
+
+    /// A link to a video on any other site, which is passed to youtube-dl as-is.
+    Url(String),
 }
 
-/// Attempts to extract a YouTube video ID from the given string. This is done by looking for the
-/// following URL patterns:
-///   - youtube.com/watch?v=...
+/// Returned by [`extract_video_id`] for something which is neither a link nor a YouTube video ID,
+/// or for a YouTube link which doesn't point at a video.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct NotAVideoLink;
+
+impl Display for NotAVideoLink {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "not a YouTube link or video ID")
+    }
+}
+
+impl std::error::Error for NotAVideoLink {}
+
+/// Whether the given string has the shape of a YouTube video ID - eleven letters, digits, `-`s or
+/// `_`s.
+pub fn is_video_id(string: &str) -> bool {
+    string.len() == 11 && string.chars().all(|c| c.is_ascii_alphanumeric() || c == '-' || c == '_')
+}
+
+/// Attempts to extract a YouTube video ID from the given string, which can be a bare ID or any of
+/// the links YouTube gives out when sharing a video:
+///   - youtube.com/watch?v=..., also on m.youtube.com and music.youtube.com
+///   - youtube.com/shorts/..., /embed/..., /live/... or /v/...
 ///   - youtu.be/...
-/// If neither of these match, then the original string will be returned. As such, there is no
-/// guarantee that the video ID will be valid or in the correct format.
-pub fn extract_video_id(string: &str) -> &str {
-    let long_url_regex = Regex::new(r"youtube.com/watch\?v=([^&]+)&?").unwrap();
-    let short_url_regex = Regex::new(r"youtu.be/([^&]+)&?").unwrap();
+/// The rest of the link, like `si=` tracking or a `t=` timestamp, is dropped. Links to any other
+/// site are passed through as a [`VideoSource::Url`], in case youtube-dl supports the site it's on.
+pub fn extract_video_id(string: &str) -> Result<VideoSource, NotAVideoLink> {
+    let trimmed = string.trim();
+    if is_video_id(trimmed) {
+        return Ok(VideoSource::YouTube(trimmed.to_string()));
+    }
 
-    if let Some(c) = long_url_regex.captures(string) {
-        return c.get(1).unwrap().as_str();
+    // YouTube links are often copied without their scheme, but they won't parse without one
+    let lowercase = trimmed.to_ascii_lowercase();
+    let link = if lowercase.starts_with("http://") || lowercase.starts_with("https://") {
+        Cow::Borrowed(trimmed)
+    } else if YOUTUBE_HOSTS.iter().chain(&[SHORT_LINK_HOST]).any(|host| lowercase.starts_with(&format!("{}/", host))) {
+        Cow::Owned(format!("https://{}", trimmed))
+    } else {
+        return Err(NotAVideoLink);
+    };
+    let url = Url::parse(&link).map_err(|_| NotAVideoLink)?;
+    let host = url.host_str().unwrap_or("");
+
+    let id = if host == SHORT_LINK_HOST {
+        url.path_segments().and_then(|mut segments| segments.next()).map(str::to_string)
+    } else if YOUTUBE_HOSTS.contains(&host) {
+        let segments: Vec<&str> = url.path_segments().map_or(vec![], |segments| segments.collect());
+        match segments.as_slice() {
+            ["watch"] => url.query_pairs().find(|(key, _)| key == "v").map(|(_, id)| id.into_owned()),
+            ["shorts" | "embed" | "live" | "v", id, ..] => Some(id.to_string()),
+            _ => None,
+        }
+    } else {
+        return Ok(VideoSource::Url(trimmed.to_string()));
+    };
+
+    id.filter(|id| is_video_id(id)).map(VideoSource::YouTube).ok_or(NotAVideoLink)
+}
+
+/// Whether the given string is a link which could be downloaded - a YouTube video or playlist, or
+/// a page on another site. Unlike [`extract_video_id`], bare IDs aren't counted, since a stray
+/// word could pass for one.
+pub fn looks_like_link(string: &str) -> bool {
+    if extract_playlist_id(string).is_some() {
+        return true;
     }
 
-    if let Some(c) = short_url_regex.captures(string) {
-        return c.get(1).unwrap().as_str();
+    extract_video_id(string).is_ok() && !is_video_id(string.trim())
+}
+
+/// Converts an image to a JPEG, as an ID3 picture which can be used as a song's album art.
+pub fn cover_picture(image: &DynamicImage) -> Result<Picture, image::ImageError> {
+    let mut jpeg_bytes = Cursor::new(vec![]);
+    image.write_to(&mut jpeg_bytes, ImageFormat::Jpeg)?;
+
+    Ok(Picture {
+        mime_type: "image/jpeg".to_string(),
+        picture_type: id3::frame::PictureType::CoverFront,
+        description: "Cover".to_string(),
+        data: jpeg_bytes.into_inner(),
+    })
+}
+
+/// Whether the given string is a download rate which youtube-dl's `--limit-rate` understands - a
+/// number of bytes per second, optionally with a K, M or G suffix, like "500K" or "1.5M".
+pub fn is_valid_rate_limit(string: &str) -> bool {
+    let regex = Regex::new(r"^(?i)\d+(?:\.\d+)?[kmg]?$").unwrap();
+    regex.is_match(string)
+        // A limit of zero would never finish
+        && string.trim_end_matches(|c: char| c.is_ascii_alphabetic()).parse::<f64>().map_or(false, |n| n > 0.0)
+}
+
+/// Arguments which can't be passed to youtube-dl as extra arguments, since CrossPlay needs to
+/// control where downloads are saved to find them afterwards, and shouldn't run other programs.
+const FORBIDDEN_EXTRA_ARGS: [&str; 8] = ["-o", "--output", "-P", "--paths", "-a", "--batch-file", "--exec", "--exec-before-download"];
+
+/// Why arguments typed by the user couldn't be used - see [`parse_extra_args`] and [`split_args`].
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum ExtraArgsError {
+    /// A quote was opened, but never closed.
+    UnclosedQuote,
+
+    /// One of the [`FORBIDDEN_EXTRA_ARGS`] was given.
+    Forbidden(String),
+}
+
+impl Display for ExtraArgsError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            ExtraArgsError::UnclosedQuote => write!(f, "a quote is never closed"),
+            ExtraArgsError::Forbidden(arg) => write!(f, "'{}' can't be used, since CrossPlay needs to control where downloads are saved", arg),
+        }
+    }
+}
+
+/// Splits extra arguments for youtube-dl, as typed by the user, with [`split_args`].
+///
+/// Arguments which would stop CrossPlay finding the download are rejected.
+pub fn parse_extra_args(text: &str) -> Result<Vec<String>, ExtraArgsError> {
+    let args = split_args(text)?;
+    for arg in &args {
+        let forbidden = FORBIDDEN_EXTRA_ARGS.iter().any(|flag| {
+            // Options can be given as "--output=x", and short options as "-ox"
+            let attached = if flag.starts_with("--") { format!("{}=", flag) } else { flag.to_string() };
+            arg == flag || arg.starts_with(&attached)
+        });
+        if forbidden {
+            return Err(ExtraArgsError::Forbidden(arg.clone()));
+        }
+    }
+
+    Ok(args)
+}
+
+/// Splits arguments typed by the user like a shell would - on whitespace, except within single or
+/// double quotes. A backslash outside of single quotes takes the next character literally.
+pub fn split_args(text: &str) -> Result<Vec<String>, ExtraArgsError> {
+    let mut args = vec![];
+    let mut current: Option<String> = None;
+    let mut quote = None;
+    let mut chars = text.chars();
+    while let Some(c) = chars.next() {
+        match (quote, c) {
+            (Some(q), c) if c == q => quote = None,
+            (Some('"') | None, '\\') => {
+                let escaped = chars.next().unwrap_or('\\');
+                current.get_or_insert_with(String::new).push(escaped);
+            }
+            (Some(_), c) => current.get_or_insert_with(String::new).push(c),
+            (None, '"' | '\'') => {
+                quote = Some(c);
+                // Empty quotes are still an argument
+                current.get_or_insert_with(String::new);
+            }
+            (None, c) if c.is_whitespace() => args.extend(current.take()),
+            (None, c) => current.get_or_insert_with(String::new).push(c),
+        }
     }
+    if quote.is_some() {
+        return Err(ExtraArgsError::UnclosedQuote);
+    }
+    args.extend(current);
+
+    Ok(args)
+}
+
+/// Joins arguments back into text which [`parse_extra_args`] gives them back from, quoting those
+/// which need it.
+pub fn join_extra_args(args: &[String]) -> String {
+    args.iter().map(|arg| quote_arg(arg)).collect::<Vec<_>>().join(" ")
+}
+
+/// Quotes an argument if it's empty or has characters a shell would treat specially.
+fn quote_arg(arg: &str) -> String {
+    if !arg.is_empty() && !arg.chars().any(|c| c.is_whitespace() || matches!(c, '"' | '\'' | '\\')) {
+        return arg.to_string();
+    }
+
+    format!("\"{}\"", arg.replace('\\', "\\\\").replace('"', "\\\""))
+}
+
+/// The links found in a list of them, like a text file with one on each line.
+#[derive(Debug, Clone, Default)]
+pub struct LinkList<'a> {
+    pub links: Vec<&'a str>,
+
+    /// Anything which wasn't a link, with the number of the line it was on, counting from 1.
+    pub unparsed: Vec<(usize, &'a str)>,
+}
+
+/// Finds the links in some text, separated by whitespace or new lines. Blank lines and lines
+/// starting with `#` are skipped, so that lists can have comments in them.
+///
+/// Like [`looks_like_link`], bare IDs aren't counted as links here, since a stray word in the list
+/// would pass for one.
+pub fn parse_link_list(text: &str) -> LinkList {
+    let mut list = LinkList::default();
+    for (i, line) in text.lines().enumerate() {
+        if line.trim_start().starts_with('#') {
+            continue;
+        }
 
-    string
+        for word in line.split_whitespace() {
+            if looks_like_link(word) {
+                list.links.push(word);
+            } else {
+                list.unparsed.push((i + 1, word));
+            }
+        }
+    }
+    list
 }
 
-fn unix_time_now() -> u64 {
+pub(crate) fn unix_time_now() -> u64 {
     SystemTime::now()
         .duration_since(UNIX_EPOCH)
         .expect("Time went backwards")
         .as_secs()
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+    use crate::library::tests::TestDir;
+
+    fn version(year: u32, month: u32, day: u32) -> DownloaderVersion {
+        DownloaderVersion { year, month, day }
+    }
+
+    #[test]
+    fn test_parse_downloader_version() {
+        assert_eq!(DownloaderVersion::parse("2021.12.17\n"), Some(version(2021, 12, 17)));
+        assert_eq!(DownloaderVersion::parse("2022.07.18.1"), Some(version(2022, 7, 18)));
+
+        assert_eq!(DownloaderVersion::parse("2021.12"), None);
+        assert_eq!(DownloaderVersion::parse("2021.12.x"), None);
+        assert_eq!(DownloaderVersion::parse(""), None);
+        assert_eq!(DownloaderVersion::parse("command not found"), None);
+    }
+
+    #[test]
+    fn test_downloader_version_is_outdated() {
+        assert!(version(2021, 6, 6).is_outdated());
+        assert!(version(2020, 12, 31).is_outdated());
+        assert!(!DownloaderVersion::MINIMUM.is_outdated());
+        assert!(!version(2022, 1, 1).is_outdated());
+    }
+
+    #[test]
+    fn test_downloader_version_display() {
+        assert_eq!(version(2022, 7, 8).to_string(), "2022.07.08");
+    }
+
+    #[test]
+    fn test_wait_for_file_times_out() {
+        let dir = TestDir::new("wait-times-out");
+        let path = dir.0.join("never.info.json");
+
+        let started = Instant::now();
+        assert!(!async_io::block_on(wait_for_file(&path, Duration::from_millis(200))));
+        assert!(started.elapsed() >= Duration::from_millis(200));
+        assert!(started.elapsed() < Duration::from_secs(2), "gave up too late");
+    }
+
+    #[test]
+    fn test_wait_for_file_finds_existing_file() {
+        let dir = TestDir::new("wait-finds-file");
+        let path = dir.0.join("video.info.json");
+        std::fs::write(&path, b"{}").unwrap();
+
+        assert!(async_io::block_on(wait_for_file(&path, Duration::from_secs(5))));
+    }
+
+    fn args(args: &[&str]) -> Vec<String> {
+        args.iter().map(|arg| arg.to_string()).collect()
+    }
+
+    #[test]
+    fn test_split_args() {
+        assert_eq!(split_args("  --limit-rate   1M "), Ok(args(&["--limit-rate", "1M"])));
+        assert_eq!(split_args(""), Ok(args(&[])));
+        assert_eq!(split_args(r#"--referer "https://a.com/b c" 'single "quoted"'"#), Ok(args(&["--referer", "https://a.com/b c", r#"single "quoted""#])));
+        assert_eq!(split_args(r#"a\ b "c\"d" 'e\f'"#), Ok(args(&["a b", r#"c"d"#, r"e\f"])));
+        assert_eq!(split_args(r#"--x "" ''"#), Ok(args(&["--x", "", ""])));
+        assert_eq!(split_args(r#"pre"quoted part"post"#), Ok(args(&["prequoted partpost"])));
+
+        assert_eq!(split_args(r#"--referer "https://a.com"#), Err(ExtraArgsError::UnclosedQuote));
+        assert_eq!(split_args("'unclosed"), Err(ExtraArgsError::UnclosedQuote));
+    }
+
+    #[test]
+    fn test_parse_extra_args_rejects_forbidden_args() {
+        for arg in ["-o", "--output=%(id)s", "-ofoo", "--output", "-P", "--exec", "--exec=rm", "--exec-before-download", "--batch-file"] {
+            let text = format!("--limit-rate 1M {} x", arg);
+            assert_eq!(parse_extra_args(&text), Err(ExtraArgsError::Forbidden(arg.to_string())), "{} was allowed", arg);
+        }
+
+        // Quoting doesn't sneak them through
+        assert_eq!(parse_extra_args(r#""--exec" x"#), Err(ExtraArgsError::Forbidden("--exec".into())));
+
+        // Options which only share a prefix are fine
+        assert_eq!(parse_extra_args("--output-na-placeholder x --audio-quality 0"), Ok(args(&["--output-na-placeholder", "x", "--audio-quality", "0"])));
+    }
+
+    #[test]
+    fn test_join_extra_args_round_trips() {
+        let cases = [
+            args(&[]),
+            args(&["--limit-rate", "1M"]),
+            args(&["--referer", "https://a.com/b c"]),
+            args(&["", r#"double "quotes""#, "'single'", r"back\slash", "tab\there"]),
+        ];
+        for case in cases {
+            assert_eq!(parse_extra_args(&join_extra_args(&case)), Ok(case.clone()), "{:?} didn't round trip", case);
+        }
+
+        assert_eq!(join_extra_args(&args(&["--limit-rate", "1M"])), "--limit-rate 1M");
+    }
+
+    #[test]
+    fn test_extract_video_id() {
+        let youtube = |id: &str| -> Result<VideoSource, NotAVideoLink> { Ok(VideoSource::YouTube(id.into())) };
+        let cases = [
+            ("dQw4w9WgXcQ", youtube("dQw4w9WgXcQ")),
+            ("  dQw4w9WgXcQ\n", youtube("dQw4w9WgXcQ")),
+            ("https://www.youtube.com/watch?v=dQw4w9WgXcQ", youtube("dQw4w9WgXcQ")),
+            ("https://www.youtube.com/watch?feature=share&v=dQw4w9WgXcQ&t=42s", youtube("dQw4w9WgXcQ")),
+            ("http://m.youtube.com/watch?v=dQw4w9WgXcQ", youtube("dQw4w9WgXcQ")),
+            ("https://music.youtube.com/watch?v=dQw4w9WgXcQ&si=tracking", youtube("dQw4w9WgXcQ")),
+            ("https://www.youtube.com/watch?v=dQw4w9WgXcQ&list=PL123", youtube("dQw4w9WgXcQ")),
+            ("https://youtube.com/shorts/dQw4w9WgXcQ?si=tracking", youtube("dQw4w9WgXcQ")),
+            ("https://www.youtube.com/embed/dQw4w9WgXcQ", youtube("dQw4w9WgXcQ")),
+            ("https://www.youtube-nocookie.com/embed/dQw4w9WgXcQ?start=10", youtube("dQw4w9WgXcQ")),
+            ("https://www.youtube.com/live/dQw4w9WgXcQ?feature=share", youtube("dQw4w9WgXcQ")),
+            ("https://youtu.be/dQw4w9WgXcQ", youtube("dQw4w9WgXcQ")),
+            ("https://youtu.be/dQw4w9WgXcQ?si=tracking&t=42", youtube("dQw4w9WgXcQ")),
+            ("HTTPS://YOUTU.BE/dQw4w9WgXcQ", youtube("dQw4w9WgXcQ")),
+
+            // Without a scheme
+            ("www.youtube.com/watch?v=dQw4w9WgXcQ", youtube("dQw4w9WgXcQ")),
+            ("youtube.com/shorts/dQw4w9WgXcQ", youtube("dQw4w9WgXcQ")),
+            ("youtu.be/dQw4w9WgXcQ?t=42", youtube("dQw4w9WgXcQ")),
+
+            // YouTube links which aren't to a video
+            ("https://www.youtube.com/watch?v=short", Err(NotAVideoLink)),
+            ("https://www.youtube.com/watch", Err(NotAVideoLink)),
+            ("https://www.youtube.com/@channel", Err(NotAVideoLink)),
+            ("https://youtu.be/", Err(NotAVideoLink)),
+
+            // Anything else
+            ("https://soundcloud.com/artist/song", Ok(VideoSource::Url("https://soundcloud.com/artist/song".into()))),
+            ("soundcloud.com/artist/song", Err(NotAVideoLink)),
+            ("never gonna give you up", Err(NotAVideoLink)),
+            ("", Err(NotAVideoLink)),
+        ];
+
+        for (input, expected) in cases {
+            assert_eq!(extract_video_id(input), expected, "for {:?}", input);
+        }
+    }
+
+    #[test]
+    fn test_strip_artist_noise() {
+        assert_eq!(strip_artist_noise("Rick Astley - Topic"), "Rick Astley");
+        assert_eq!(strip_artist_noise("RickAstleyVEVO"), "RickAstley");
+        assert_eq!(strip_artist_noise("RickAstleyVevo"), "RickAstley");
+        assert_eq!(strip_artist_noise("Rick Astley Official"), "Rick Astley");
+        assert_eq!(strip_artist_noise("  Rick Astley  "), "Rick Astley");
+
+        // Nothing would be left
+        assert_eq!(strip_artist_noise("VEVO"), "VEVO");
+    }
+
+    #[test]
+    fn test_artist_and_title_without_dash() {
+        assert_eq!(
+            artist_and_title("Rick Astley - Topic", "Never Gonna Give You Up", true),
+            ("Rick Astley".into(), "Never Gonna Give You Up".into()),
+        );
+
+        // Only dashes surrounded by spaces separate the artist
+        assert_eq!(
+            artist_and_title("Channel", "Jay-Z Song", true),
+            ("Channel".into(), "Jay-Z Song".into()),
+        );
+    }
+
+    #[test]
+    fn test_artist_and_title_splits_at_first_dash() {
+        assert_eq!(
+            artist_and_title("Label", "Artist - Song", true),
+            ("Artist".into(), "Song".into()),
+        );
+        assert_eq!(
+            artist_and_title("Label", "Artist - Song - Live", true),
+            ("Artist".into(), "Song - Live".into()),
+        );
+    }
+
+    #[test]
+    fn test_artist_and_title_splits_at_unicode_dashes() {
+        assert_eq!(
+            artist_and_title("Label", "Artist \u{2013} Song", true),
+            ("Artist".into(), "Song".into()),
+        );
+        assert_eq!(
+            artist_and_title("Label", "Artist \u{2014} Song", true),
+            ("Artist".into(), "Song".into()),
+        );
+        assert_eq!(
+            artist_and_title("Label", "Artist \u{2013} Song - Live", true),
+            ("Artist".into(), "Song - Live".into()),
+        );
+    }
+
+    #[test]
+    fn test_artist_and_title_trims_title_noise() {
+        assert_eq!(
+            artist_and_title("Label", "ArtistVEVO - Song (Official Video) [Lyrics]", true),
+            ("Artist".into(), "Song".into()),
+        );
+    }
+
+    #[test]
+    fn test_artist_and_title_without_parsing_title() {
+        assert_eq!(
+            artist_and_title("ArtistVEVO", " Artist - Song (Official Video) ", false),
+            ("Artist".into(), "Artist - Song (Official Video)".into()),
+        );
+    }
+
+    #[test]
+    fn test_estimated_audio_size_ignores_video_formats() {
+        let json = json!({
+            "filesize_approx": 90_000_000,
+            "formats": [
+                { "vcodec": "none", "filesize": 2_000_000 },
+                { "vcodec": "none", "filesize_approx": 4_000_000 },
+                { "vcodec": "avc1", "filesize": 80_000_000 },
+            ],
+        });
+        assert_eq!(MediaDownload::info_json_to_estimated_audio_size(&json), Some(4_000_000));
+
+        let json = json!({ "filesize": 3_000_000 });
+        assert_eq!(MediaDownload::info_json_to_estimated_audio_size(&json), Some(3_000_000));
+    }
+}